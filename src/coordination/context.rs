@@ -5,7 +5,7 @@
 
 // -------------------------------------------------------------------------------------------------
 
-use dharma::{DispatcherController, EventHandler, EventKind, Signaler, SignalId};
+use dharma::{DispatcherController, EventHandler, EventHandlerId, EventKind, Signaler, SignalId};
 
 use qualia::{Config, Settings, Perceptron, InputManager};
 
@@ -50,11 +50,17 @@ impl Context {
         self.signaler.emit(id, package);
     }
 
-    /// Add new event handler.
+    /// Add new event handler. Returns ID which can be used to remove the handler.
     pub fn add_event_handler(&mut self,
                              event_handler: Box<EventHandler + Send>,
-                             event_kind: EventKind) {
-        self.dispatcher.add_source(event_handler, event_kind);
+                             event_kind: EventKind)
+                             -> EventHandlerId {
+        self.dispatcher.add_source(event_handler, event_kind)
+    }
+
+    /// Remove event handler with given ID.
+    pub fn remove_event_handler(&mut self, id: EventHandlerId) {
+        self.dispatcher.delete_source(id);
     }
 
     /// Get global configuration.
@@ -9,20 +9,29 @@
 
 use std;
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
 use dharma;
 
-use qualia::{Position, Size, Vector, MemoryPoolId, MemoryViewId};
-use qualia::{Buffer, MappedMemory, MemoryPool, MemoryView};
+use qualia::{Area, Position, Region, Size, Vector, MemoryPoolId, MemoryViewId};
+use qualia::{Buffer, Illusion, MappedMemory, MemoryPool, MemoryView};
+use qualia::{Screenshot, ScreenshotId, ScreenshotRequest};
 use qualia::{perceptron, Perceptron};
-use qualia::{SurfaceContext, SurfaceId, SurfaceInfo};
+use qualia::{LayerSurfaceInfo, SizeHints, SurfaceContext, SurfaceId, SurfaceInfo};
 use qualia::{SurfaceManagement, SurfaceControl, SurfaceViewer};
 use qualia::{SurfaceAccess, SurfaceListing, SurfaceFocusing};
 use qualia::{AppearanceManagement, Emiter, MemoryManagement, Screenshooting};
 use qualia::{AestheticsCoordinationTrait, ExhibitorCoordinationTrait};
 use qualia::{show_reason, surface_state};
 
-use surfaces::Surface;
+use surfaces::{Mapping, Surface};
+use transaction::Transaction;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Time after which layout transaction is finished even if not all clients responded.
+const TRANSACTION_TIMEOUT_MS: u64 = 200;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -133,8 +142,14 @@ struct InnerCoordinator {
     /// Storage for all memory pools.
     memory_pools: MemoryPoolMap,
 
-    /// Screenshot buffer to be shared between threads.
-    screenshot_buffer: Option<Buffer>,
+    /// Results of screenshot requests waiting to be taken by requesters.
+    screenshots: std::collections::HashMap<ScreenshotId, Option<Screenshot>>,
+
+    /// Currently ongoing layout transaction.
+    transaction: Option<Transaction>,
+
+    /// Time after which layout transaction is finished even if not all clients responded.
+    transaction_timeout: Duration,
 
     /// Counter of surface IDs
     last_surface_id: SurfaceId,
@@ -156,13 +171,15 @@ struct InnerCoordinator {
 
 impl InnerCoordinator {
     /// `InnerCoordinator` constructor.
-    pub fn new(signaler: dharma::Signaler<Perceptron>) -> Self {
+    pub fn new(signaler: dharma::Signaler<Perceptron>, transaction_timeout: Duration) -> Self {
         InnerCoordinator {
             signaler: signaler,
             surfaces: SurfaceMap::new(),
             memory_views: MemoryViewMap::new(),
             memory_pools: MemoryPoolMap::new(),
-            screenshot_buffer: None,
+            screenshots: std::collections::HashMap::new(),
+            transaction: None,
+            transaction_timeout: transaction_timeout,
             last_surface_id: SurfaceId::invalid(),
             last_memory_view_id: MemoryViewId::initial(),
             last_memory_pool_id: MemoryPoolId::initial(),
@@ -191,25 +208,92 @@ impl InnerCoordinator {
         surface.attach(view.view.clone());
     }
 
-    /// Informs other parts of application the surface is now not visible.
+    /// Removes pending buffer of given surface. The surface will be unmapped on commit.
     pub fn detach_surface(&mut self, sid: SurfaceId) {
-        self.signaler.emit(perceptron::SURFACE_DESTROYED, Perceptron::SurfaceDestroyed(sid));
+        let surface = try_get_surface!(self, sid);
+        surface.detach();
+    }
+
+    /// Marks given area (in surface coordinates) of surface as damaged.
+    pub fn damage_surface(&mut self, sid: SurfaceId, area: Area) {
+        let surface = try_get_surface!(self, sid);
+        surface.damage(area);
+    }
+
+    /// Marks given area (in buffer coordinates) of surface as damaged.
+    pub fn damage_surface_buffer(&mut self, sid: SurfaceId, area: Area) {
+        let surface = try_get_surface!(self, sid);
+        surface.damage_buffer(area);
     }
 
-    /// Sets pending buffer of given surface as current. Corrects sizes adds `drawable` show reason.
+    /// Sets pending scale of surfaces buffer.
+    pub fn set_surface_buffer_scale(&mut self, sid: SurfaceId, scale: i32) {
+        let surface = try_get_surface!(self, sid);
+        surface.set_buffer_scale(scale);
+    }
+
+    /// Sets pending transformation of surfaces buffer.
+    pub fn set_surface_buffer_transform(&mut self, sid: SurfaceId, transform: i32) {
+        let surface = try_get_surface!(self, sid);
+        surface.set_buffer_transform(transform);
+    }
+
+    /// Sets pending state of given surface as current. Corrects sizes and adds or removes
+    /// `drawable` show reason.
+    ///
+    /// If the commit concludes surfaces part in layout transaction, the buffer will become current
+    /// after the transaction finishes. Later commits before the transaction finishes replace the
+    /// held state so that older state never overwrites newer one.
+    ///
+    /// State of synchronized subsurfaces is cached and applied when their parent is committed.
+    ///
+    /// Whenever committed state becomes current signal `surface state applied` is emitted.
     pub fn commit_surface(&mut self, sid: SurfaceId) {
+        if self.is_surface_synchronized(sid) {
+            let surface = try_get_surface!(self, sid);
+            surface.cache();
+            return;
+        }
+
+        let is_in_transaction = if let Some(ref mut transaction) = self.transaction {
+            transaction.commit(sid)
+        } else {
+            false
+        };
+
         if {
             let surface = try_get_surface!(self, sid);
-            surface.commit()
+            (is_in_transaction || surface.is_holding()) && surface.hold()
         } {
-            self.show_surface(sid, show_reason::DRAWABLE);
+            self.update_transaction();
+        } else {
+            let mapping = {
+                let surface = try_get_surface!(self, sid);
+                surface.commit()
+            };
+            self.on_state_applied(sid, mapping);
         }
+
+        self.apply_cached_satellites(sid);
     }
 
-    /// Detaches and forgets given surface.
+    /// Detaches and forgets given surface. Surfaces transient for destroyed surface are handed
+    /// over to its parent.
     pub fn destroy_surface(&mut self, sid: SurfaceId) {
-        self.detach_surface(sid);
-        self.surfaces.remove(&sid);
+        self.signaler.emit(perceptron::SURFACE_DESTROYED, Perceptron::SurfaceDestroyed(sid));
+        if let Some(ref mut transaction) = self.transaction {
+            transaction.remove(sid);
+        }
+        self.update_transaction();
+
+        if let Some(surface) = self.surfaces.remove(&sid) {
+            let transient_for_sid = surface.get_transient_for_sid();
+            for transient in self.surfaces.values_mut() {
+                if transient.get_transient_for_sid() == sid {
+                    transient.set_transient_for_sid(transient_for_sid);
+                }
+            }
+        }
     }
 
     /// Adds given show reason flag to set of surfaces show reason. If all reasons needed for
@@ -232,18 +316,42 @@ impl InnerCoordinator {
         }
     }
 
-    /// Sets position offset given surface.
+    /// Sets pending position offset given surface.
     pub fn set_surface_offset(&mut self, sid: SurfaceId, offset: Vector) {
         let surface = try_get_surface!(self, sid);
         surface.set_offset(offset)
     }
 
+    /// Sets pending input region of given surface.
+    pub fn set_surface_input_region(&mut self, sid: SurfaceId, region: Option<Region>) {
+        let surface = try_get_surface!(self, sid);
+        surface.set_input_region(region)
+    }
+
+    /// Sets pending opaque region of given surface.
+    pub fn set_surface_opaque_region(&mut self, sid: SurfaceId, region: Region) {
+        let surface = try_get_surface!(self, sid);
+        surface.set_opaque_region(region)
+    }
+
     /// Sets requested size for given surface.
     pub fn set_surface_requested_size(&mut self, sid: SurfaceId, size: Size) {
         let surface = try_get_surface!(self, sid);
         surface.set_requested_size(size)
     }
 
+    /// Sets minimal size requested by client for given surface.
+    pub fn set_surface_min_size(&mut self, sid: SurfaceId, size: Size) {
+        let surface = try_get_surface!(self, sid);
+        surface.set_min_size(size)
+    }
+
+    /// Sets maximal size requested by client for given surface.
+    pub fn set_surface_max_size(&mut self, sid: SurfaceId, size: Size) {
+        let surface = try_get_surface!(self, sid);
+        surface.set_max_size(size)
+    }
+
     /// Sets satellite surface position relative to its parent.
     pub fn set_surface_relative_position(&mut self, sid: SurfaceId, position: Position) {
         let surface = try_get_surface!(self, sid);
@@ -269,42 +377,139 @@ impl InnerCoordinator {
             let mut surface = try_get_surface!(self, sid);
             let parent_sid = surface.get_parent_sid();
             surface.set_parent_sid(SurfaceId::invalid());
+            surface.set_synchronized(false);
             parent_sid
         };
         let mut parent_surface = try_get_surface!(self, parent_sid);
         parent_surface.remove_satellite(sid);
     }
 
+    /// Sets if given subsurface is synchronized with its parent. When subsurface becomes
+    /// desynchronized its cached state is applied.
+    pub fn set_surface_synchronized(&mut self, sid: SurfaceId, synchronized: bool) {
+        {
+            let surface = try_get_surface!(self, sid);
+            surface.set_synchronized(synchronized);
+        }
+
+        if !self.is_surface_synchronized(sid) {
+            self.apply_cached_state(sid);
+        }
+    }
+
+    /// Places subsurface directly above its sibling or parent.
+    pub fn place_surface_above(&mut self, sid: SurfaceId, sibling_sid: SurfaceId) {
+        let parent_sid = try_get_surface!(self, sid).get_parent_sid();
+        let parent_surface = try_get_surface!(self, parent_sid);
+        if !parent_surface.place_satellite_above(sid, sibling_sid) {
+            log_warn2!("Surface {} can not be placed above {}", sid, sibling_sid);
+        }
+    }
+
+    /// Places subsurface directly below its sibling or parent.
+    pub fn place_surface_below(&mut self, sid: SurfaceId, sibling_sid: SurfaceId) {
+        let parent_sid = try_get_surface!(self, sid).get_parent_sid();
+        let parent_surface = try_get_surface!(self, parent_sid);
+        if !parent_surface.place_satellite_below(sid, sibling_sid) {
+            log_warn2!("Surface {} can not be placed below {}", sid, sibling_sid);
+        }
+    }
+
+    /// Informs that client acknowledged the most recent configuration of given surface.
+    pub fn acknowledge_surface_configuration(&mut self, sid: SurfaceId) {
+        if let Some(ref mut transaction) = self.transaction {
+            transaction.acknowledge(sid);
+        }
+    }
+
+    /// Marks surface as transient for other surface. Request is ignored if it would make the
+    /// surface transient for itself directly or through the chain of parents.
+    pub fn set_surface_transient_for(&mut self, sid: SurfaceId, parent_sid: SurfaceId) {
+        if self.is_surface_transient_for(parent_sid, sid) {
+            log_warn2!("Surface {} can not be transient for its transient {}", sid, parent_sid);
+            return;
+        }
+        let surface = try_get_surface!(self, sid);
+        surface.set_transient_for_sid(parent_sid);
+    }
+
     /// Returns information about surface.
     pub fn get_surface(&self, sid: SurfaceId) -> Option<SurfaceInfo> {
         let surface = try_get_surface_or_none!(self, sid);
         Some(surface.get_info())
     }
 
-    /// Reconfigure surface and send notification about this event.
+    /// Returns area of surface changed since last call for given display and clears it.
+    pub fn take_surface_damage(&mut self, sid: SurfaceId, display_id: i32) -> Region {
+        if let Some(surface) = self.surfaces.get_mut(&sid) {
+            surface.take_damage(display_id)
+        } else {
+            Region::new()
+        }
+    }
+
+    /// Reconfigure surface and send notification about this event. Surface is added to current
+    /// layout transaction. New transaction is started if needed. Returns `true` if new transaction
+    /// was started.
     pub fn reconfigure(&mut self,
                        sid: SurfaceId,
                        size: Size,
-                       state_flags: surface_state::SurfaceState) {
-        let surface = try_get_surface!(self, sid);
-        if (surface.get_desired_size() != size) || (surface.get_state_flags() != state_flags) {
-            surface.set_desired_size(size);
-            surface.set_state_flags(state_flags);
+                       state_flags: surface_state::SurfaceState)
+                       -> bool {
+        let mut is_started = false;
+        let is_changed = if let Some(surface) = self.surfaces.get_mut(&sid) {
+            if (surface.get_desired_size() != size) || (surface.get_state_flags() != state_flags) {
+                surface.set_desired_size(size);
+                surface.set_state_flags(state_flags);
+                true
+            } else {
+                false
+            }
+        } else {
+            log_warn2!("Surface {} not found!", sid);
+            false
+        };
+
+        if is_changed {
+            if self.transaction.is_none() {
+                self.transaction = Some(Transaction::new());
+                is_started = true;
+            }
+            if let Some(ref mut transaction) = self.transaction {
+                transaction.add(sid);
+            }
             self.signaler.emit(perceptron::SURFACE_RECONFIGURED,
                                Perceptron::SurfaceReconfigured(sid));
         }
+        is_started
+    }
+
+    /// Checks if layout transaction is in progress.
+    pub fn is_transaction_pending(&mut self) -> bool {
+        self.update_transaction()
+    }
+
+    /// Returns minimal and maximal size requested by client for given surface. Returns no limits
+    /// if surface does not exist.
+    pub fn get_size_hints(&self, sid: SurfaceId) -> SizeHints {
+        match self.surfaces.get(&sid) {
+            Some(surface) => surface.get_size_hints(),
+            None => SizeHints::default(),
+        }
     }
 
     /// Returns surface context.
     pub fn get_renderer_context(&self, sid: SurfaceId) -> Option<Vec<SurfaceContext>> {
         let surface = try_get_surface_or_none!(self, sid);
+        let position = surface.get_renderer_context().pos;
         let mut result = Vec::new();
         for child_sid in surface.get_satellites() {
             if *child_sid == sid {
                 result.push(surface.get_renderer_context());
             } else {
-                if let Some(ref mut array) = self.get_renderer_context(*child_sid) {
-                    result.append(array)
+                // Positions of satellites are relative to this surface.
+                if let Some(array) = self.get_renderer_context(*child_sid) {
+                    result.extend(array.iter().map(|c| c.moved(position)));
                 }
             }
         }
@@ -352,6 +557,21 @@ impl InnerCoordinator {
         self.signaler.emit(perceptron::NOTIFY, Perceptron::Notify);
     }
 
+    /// Informs other parts of application about request from client to change drag icon surface.
+    pub fn set_surface_as_drag_icon(&mut self, sid: SurfaceId) {
+        self.signaler.emit(perceptron::DRAG_ICON_SURFACE_CHANGE,
+                           Perceptron::DragIconSurfaceChange(sid));
+        self.signaler.emit(perceptron::NOTIFY, Perceptron::Notify);
+    }
+
+    /// Informs other parts of application about request from client to place surface in one of
+    /// the layers or to change its layer state.
+    pub fn set_surface_as_layer(&mut self, sid: SurfaceId, info: Option<LayerSurfaceInfo>) {
+        self.signaler.emit(perceptron::LAYER_SURFACE_CHANGE,
+                           Perceptron::LayerSurfaceChange(sid, info));
+        self.signaler.emit(perceptron::NOTIFY, Perceptron::Notify);
+    }
+
     /// Emits given signal.
     fn emit(&mut self, id: dharma::SignalId, package: Perceptron) {
         self.signaler.emit(id, package);
@@ -434,20 +654,32 @@ impl InnerCoordinator {
         }
     }
 
+    /// Copies contents of given buffer to memory shared with client through given view.
+    pub fn fill_memory_view(&mut self,
+                            mvid: MemoryViewId,
+                            buffer: &Buffer)
+                            -> Result<(), Illusion> {
+        if let Some(bundle) = self.memory_views.get(&mvid) {
+            unsafe { bundle.view.absorb(buffer) }
+        } else {
+            Err(Illusion::General(format!("No memory view with ID {:?}", mvid)))
+        }
+    }
+
     /// Makes screenshot request.
-    pub fn take_screenshot(&mut self, id: i32) {
-        self.signaler.emit(perceptron::TAKE_SCREENSHOT, Perceptron::TakeScreenshot(id));
+    pub fn take_screenshot(&mut self, request: ScreenshotRequest) {
+        self.signaler.emit(perceptron::TAKE_SCREENSHOT, Perceptron::TakeScreenshot(request));
     }
 
-    /// Sets given buffer as results of screenshot.
-    pub fn set_screenshot_buffer(&mut self, buffer: Buffer) {
-        self.screenshot_buffer = Some(buffer);
-        self.signaler.emit(perceptron::SCREENSHOT_DONE, Perceptron::ScreenshotDone);
+    /// Sets result of screenshot request. `None` means the screenshot could not be taken.
+    pub fn set_screenshot(&mut self, id: ScreenshotId, screenshot: Option<Screenshot>) {
+        self.screenshots.insert(id, screenshot);
+        self.signaler.emit(perceptron::SCREENSHOT_DONE, Perceptron::ScreenshotDone(id));
     }
 
-    /// Returns and forgets screenshot buffer.
-    pub fn take_screenshot_buffer(&mut self) -> Option<Buffer> {
-        self.screenshot_buffer.take()
+    /// Returns and forgets result of screenshot request.
+    pub fn take_screenshot_result(&mut self, id: ScreenshotId) -> Option<Screenshot> {
+        self.screenshots.remove(&id).and_then(|screenshot| screenshot)
     }
 }
 
@@ -455,6 +687,114 @@ impl InnerCoordinator {
 
 // Helper functions
 impl InnerCoordinator {
+    /// Finishes layout transaction if all its surfaces acknowledged and committed new
+    /// configuration or if it timed out. Buffers of all surfaces from finished transaction become
+    /// current at once. Returns `true` if transaction is still pending, `false` otherwise.
+    fn update_transaction(&mut self) -> bool {
+        let is_finished = if let Some(ref transaction) = self.transaction {
+            if transaction.is_complete() {
+                true
+            } else if transaction.is_timed_out(self.transaction_timeout) {
+                log_warn2!("Layout transaction timed out");
+                true
+            } else {
+                false
+            }
+        } else {
+            return false;
+        };
+
+        if is_finished {
+            if let Some(transaction) = self.transaction.take() {
+                for &sid in transaction.get_members() {
+                    let mapping = if let Some(surface) = self.surfaces.get_mut(&sid) {
+                        surface.release()
+                    } else {
+                        None
+                    };
+                    if let Some(mapping) = mapping {
+                        self.on_state_applied(sid, mapping);
+                    }
+                }
+            }
+            self.notify();
+        }
+        !is_finished
+    }
+
+    /// Checks if given surface or any of its ancestors is synchronized subsurface.
+    fn is_surface_synchronized(&self, sid: SurfaceId) -> bool {
+        let mut sid = sid;
+        while let Some(surface) = self.surfaces.get(&sid) {
+            if surface.is_synchronized() {
+                return true;
+            }
+            sid = surface.get_parent_sid();
+        }
+        false
+    }
+
+    /// Checks if surface `sid` is `ancestor_sid` or is transient for it directly or through the
+    /// chain of parents.
+    fn is_surface_transient_for(&self, sid: SurfaceId, ancestor_sid: SurfaceId) -> bool {
+        let mut sid = sid;
+        while sid.is_valid() {
+            if sid == ancestor_sid {
+                return true;
+            }
+            sid = match self.surfaces.get(&sid) {
+                Some(surface) => surface.get_transient_for_sid(),
+                None => break,
+            };
+        }
+        false
+    }
+
+    /// Applies state cached by given surface and then by its synchronized satellites.
+    fn apply_cached_state(&mut self, sid: SurfaceId) {
+        if let Some(mapping) = {
+            let surface = try_get_surface!(self, sid);
+            surface.apply_cached()
+        } {
+            self.on_state_applied(sid, mapping);
+        }
+        self.apply_cached_satellites(sid);
+    }
+
+    /// Informs frontend that state committed by given surface became current and updates its
+    /// `drawable` show reason.
+    fn on_state_applied(&mut self, sid: SurfaceId, mapping: Mapping) {
+        let serial = try_get_surface!(self, sid).get_current_serial();
+        self.signaler.emit(perceptron::SURFACE_STATE_APPLIED,
+                           Perceptron::SurfaceStateApplied(sid, serial));
+
+        match mapping {
+            Mapping::Mapped => self.show_surface(sid, show_reason::DRAWABLE),
+            Mapping::Unmapped => self.hide_surface(sid, show_reason::DRAWABLE),
+            Mapping::Unchanged => {}
+        }
+    }
+
+    /// Applies state cached by synchronized satellites of given surface.
+    fn apply_cached_satellites(&mut self, sid: SurfaceId) {
+        let satellites = {
+            let surface = try_get_surface!(self, sid);
+            surface.get_satellites().clone()
+        };
+
+        for satellite_sid in satellites {
+            let is_synchronized = if let Some(satellite) = self.surfaces.get(&satellite_sid) {
+                satellite.is_synchronized()
+            } else {
+                false
+            };
+
+            if (satellite_sid != sid) && is_synchronized {
+                self.apply_cached_state(satellite_sid);
+            }
+        }
+    }
+
     // FIXME: Finish implementation of Coordinator (counter)
     fn generate_next_surface_id(&mut self) -> SurfaceId {
         self.last_surface_id = SurfaceId::new(self.last_surface_id.as_number() as u64 + 1);
@@ -483,7 +823,29 @@ pub struct Coordinator {
 impl Coordinator {
     /// `Coordinator` constructor.
     pub fn new(signaler: dharma::Signaler<Perceptron>) -> Self {
-        Coordinator { inner: Arc::new(Mutex::new(InnerCoordinator::new(signaler))) }
+        Self::with_transaction_timeout(signaler, Duration::from_millis(TRANSACTION_TIMEOUT_MS))
+    }
+
+    /// `Coordinator` constructor with custom timeout of layout transactions.
+    pub fn with_transaction_timeout(signaler: dharma::Signaler<Perceptron>,
+                                    transaction_timeout: Duration)
+                                    -> Self {
+        let inner = InnerCoordinator::new(signaler, transaction_timeout);
+        Coordinator { inner: Arc::new(Mutex::new(inner)) }
+    }
+
+    /// Finishes layout transaction when it times out. Otherwise it would be finished only on next
+    /// client request or redraw and unresponsive client could block presenting new layout.
+    fn schedule_transaction_timeout(&self, timeout: Duration) {
+        let inner = self.inner.clone();
+        let result = thread::Builder::new().name("p:transaction".to_owned()).spawn(move || {
+            thread::sleep(timeout);
+            let mut mine = inner.lock().unwrap();
+            mine.update_transaction();
+        });
+        if let Err(err) = result {
+            log_warn1!("Failed to schedule layout transaction timeout: {}", err);
+        }
     }
 }
 
@@ -519,6 +881,30 @@ impl SurfaceManagement for Coordinator {
         mine.detach_surface(sid)
     }
 
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn damage_surface(&self, sid: SurfaceId, area: Area) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.damage_surface(sid, area)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn damage_surface_buffer(&self, sid: SurfaceId, area: Area) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.damage_surface_buffer(sid, area)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_buffer_scale(&self, sid: SurfaceId, scale: i32) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_buffer_scale(sid, scale)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_buffer_transform(&self, sid: SurfaceId, transform: i32) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_buffer_transform(sid, transform)
+    }
+
     /// Lock and call corresponding method from `InnerCoordinator`.
     fn commit_surface(&self, sid: SurfaceId) {
         let mut mine = self.inner.lock().unwrap();
@@ -554,12 +940,36 @@ impl SurfaceControl for Coordinator {
         mine.set_surface_offset(sid, offset)
     }
 
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_input_region(&self, sid: SurfaceId, region: Option<Region>) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_input_region(sid, region)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_opaque_region(&self, sid: SurfaceId, region: Region) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_opaque_region(sid, region)
+    }
+
     /// Lock and call corresponding method from `InnerCoordinator`.
     fn set_surface_requested_size(&self, sid: SurfaceId, size: Size) {
         let mut mine = self.inner.lock().unwrap();
         mine.set_surface_requested_size(sid, size)
     }
 
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_min_size(&self, sid: SurfaceId, size: Size) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_min_size(sid, size)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_max_size(&self, sid: SurfaceId, size: Size) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_max_size(sid, size)
+    }
+
     /// Lock and call corresponding method from `InnerCoordinator`.
     fn set_surface_relative_position(&self, sid: SurfaceId, offset: Vector) {
         let mut mine = self.inner.lock().unwrap();
@@ -577,6 +987,36 @@ impl SurfaceControl for Coordinator {
         let mut mine = self.inner.lock().unwrap();
         mine.unrelate_surface(sid)
     }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_transient_for(&self, sid: SurfaceId, parent_sid: SurfaceId) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_transient_for(sid, parent_sid)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn acknowledge_surface_configuration(&self, sid: SurfaceId) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.acknowledge_surface_configuration(sid)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_synchronized(&self, sid: SurfaceId, synchronized: bool) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_synchronized(sid, synchronized)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn place_surface_above(&self, sid: SurfaceId, sibling_sid: SurfaceId) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.place_surface_above(sid, sibling_sid)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn place_surface_below(&self, sid: SurfaceId, sibling_sid: SurfaceId) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.place_surface_below(sid, sibling_sid)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -587,6 +1027,12 @@ impl SurfaceViewer for Coordinator {
         let mine = self.inner.lock().unwrap();
         mine.get_surface(sid)
     }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn take_surface_damage(&self, sid: SurfaceId, display_id: i32) -> Region {
+        let mut mine = self.inner.lock().unwrap();
+        mine.take_surface_damage(sid, display_id)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -597,8 +1043,29 @@ impl SurfaceAccess for Coordinator {
                    sid: SurfaceId,
                    size: Size,
                    state_flags: surface_state::SurfaceState) {
+        let timeout = {
+            let mut mine = self.inner.lock().unwrap();
+            if mine.reconfigure(sid, size, state_flags) {
+                Some(mine.transaction_timeout)
+            } else {
+                None
+            }
+        };
+        if let Some(timeout) = timeout {
+            self.schedule_transaction_timeout(timeout);
+        }
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn get_size_hints(&self, sid: SurfaceId) -> SizeHints {
+        let mine = self.inner.lock().unwrap();
+        mine.get_size_hints(sid)
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn is_transaction_pending(&self) -> bool {
         let mut mine = self.inner.lock().unwrap();
-        mine.reconfigure(sid, size, state_flags);
+        mine.is_transaction_pending()
     }
 }
 
@@ -654,6 +1121,18 @@ impl AppearanceManagement for Coordinator {
         let mut mine = self.inner.lock().unwrap();
         mine.set_surface_as_background(sid);
     }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_as_drag_icon(&self, sid: SurfaceId) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_as_drag_icon(sid);
+    }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn set_surface_as_layer(&self, sid: SurfaceId, info: Option<LayerSurfaceInfo>) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.set_surface_as_layer(sid, info);
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -716,27 +1195,33 @@ impl MemoryManagement for Coordinator {
         let mut mine = self.inner.lock().unwrap();
         mine.destroy_memory_view(mpid);
     }
+
+    /// Lock and call corresponding method from `InnerCoordinator`.
+    fn fill_memory_view(&mut self, mvid: MemoryViewId, buffer: &Buffer) -> Result<(), Illusion> {
+        let mut mine = self.inner.lock().unwrap();
+        mine.fill_memory_view(mvid, buffer)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
 
 impl Screenshooting for Coordinator {
     /// Lock and call corresponding method from `InnerCoordinator`.
-    fn take_screenshot(&mut self, id: i32) {
+    fn take_screenshot(&mut self, request: ScreenshotRequest) {
         let mut mine = self.inner.lock().unwrap();
-        mine.take_screenshot(id);
+        mine.take_screenshot(request);
     }
 
     /// Lock and call corresponding method from `InnerCoordinator`.
-    fn set_screenshot_buffer(&mut self, buffer: Buffer) {
+    fn set_screenshot(&mut self, id: ScreenshotId, screenshot: Option<Screenshot>) {
         let mut mine = self.inner.lock().unwrap();
-        mine.set_screenshot_buffer(buffer);
+        mine.set_screenshot(id, screenshot);
     }
 
     /// Lock and call corresponding method from `InnerCoordinator`.
-    fn take_screenshot_buffer(&mut self) -> Option<Buffer> {
+    fn take_screenshot_result(&mut self, id: ScreenshotId) -> Option<Screenshot> {
         let mut mine = self.inner.lock().unwrap();
-        mine.take_screenshot_buffer()
+        mine.take_screenshot_result(id)
     }
 }
 
@@ -15,6 +15,7 @@ extern crate qualia;
 extern crate dharma;
 
 mod surfaces;
+mod transaction;
 
 pub mod coordinator;
 pub use coordinator::Coordinator;
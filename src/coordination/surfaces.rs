@@ -5,9 +5,127 @@
 
 // -------------------------------------------------------------------------------------------------
 
+use std::collections::HashMap;
+
 use qualia::{MemoryView, Pixmap};
-use qualia::{Position, Size, Vector};
-use qualia::{SurfaceContext, SurfaceId, SurfaceInfo, show_reason, surface_state};
+use qualia::{Area, Position, Region, Size, Vector};
+use qualia::{SizeHints, SurfaceContext, SurfaceId, SurfaceInfo};
+use qualia::{buffer_transform, show_reason, surface_state};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Double-buffered part of surface state. Client modifies pending state which is applied
+/// atomically on commit.
+#[derive(Clone)]
+pub struct BufferedState {
+    /// Data required for draw.
+    pub buffer: Option<MemoryView>,
+
+    /// Offset used to move coordinate system of surface.
+    pub offset: Vector,
+
+    /// Damaged areas in surface coordinates.
+    pub damage: Vec<Area>,
+
+    /// Damaged areas in buffer coordinates.
+    pub buffer_damage: Vec<Area>,
+
+    /// Region accepting input. `None` means the whole surface.
+    pub input_region: Option<Region>,
+
+    /// Region with fully opaque content.
+    pub opaque_region: Region,
+
+    /// Scale of buffer.
+    pub buffer_scale: i32,
+
+    /// Transformation of buffer as defined by `wl_output.transform`.
+    pub buffer_transform: i32,
+
+    /// Number of commits up to the one which latched this state. Reported to the frontend when the
+    /// state becomes current.
+    pub serial: u32,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl BufferedState {
+    /// Constructs new `BufferedState`.
+    pub fn new() -> Self {
+        BufferedState {
+            buffer: None,
+            offset: Vector::default(),
+            damage: Vec::new(),
+            buffer_damage: Vec::new(),
+            input_region: None,
+            opaque_region: Region::new(),
+            buffer_scale: 1,
+            buffer_transform: 0,
+            serial: 0,
+        }
+    }
+
+    /// Returns copy of the state to be applied and prepares the state for next commit. Damage is
+    /// accumulated only between commits, rest of the state is preserved.
+    pub fn latch(&mut self) -> Self {
+        self.serial = self.serial.wrapping_add(1);
+        let state = self.clone();
+        self.damage.clear();
+        self.buffer_damage.clear();
+        state
+    }
+
+    /// Takes over damage from `previous` state which is replaced by this one before being applied.
+    pub fn inherit_damage(&mut self, mut previous: BufferedState) {
+        previous.damage.append(&mut self.damage);
+        previous.buffer_damage.append(&mut self.buffer_damage);
+        self.damage = previous.damage;
+        self.buffer_damage = previous.buffer_damage;
+    }
+
+    /// Returns size of buffer in surface coordinates or `None` if there is no buffer.
+    pub fn get_logical_size(&self) -> Option<Size> {
+        if let Some(ref buffer) = self.buffer {
+            Some(buffer_transform::logical_size(buffer.get_size(),
+                                                self.buffer_scale,
+                                                self.buffer_transform))
+        } else {
+            None
+        }
+    }
+
+    /// Returns damage of this state converted to surface coordinates.
+    pub fn get_surface_damage(&self) -> Region {
+        let mut region = Region::new();
+        for area in self.damage.iter() {
+            region.add(area);
+        }
+        if let Some(ref buffer) = self.buffer {
+            for area in self.buffer_damage.iter() {
+                region.add(&buffer_transform::buffer_to_surface(*area,
+                                                                buffer.get_size(),
+                                                                self.buffer_scale,
+                                                                self.buffer_transform));
+            }
+        }
+        region
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Describes how commit changed visibility of surface.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mapping {
+    /// Surface was visible and still is or was not visible and still is not.
+    Unchanged,
+
+    /// Surface received buffer for the first time or after being unmapped.
+    Mapped,
+
+    /// Surface lost its buffer.
+    Unmapped,
+}
 
 // -------------------------------------------------------------------------------------------------
 
@@ -16,30 +134,50 @@ pub struct Surface {
     /// ID of the surface.
     id: SurfaceId,
 
-    /// Offset used to move coordinate system of surface.
-    offset: Vector,
-
     /// Size desired by compositor.
     desired_size: Size,
 
     /// Size requested by client.
     requested_size: Size,
 
+    /// Minimal and maximal size requested by client.
+    size_hints: SizeHints,
+
     /// ID of parent surface.
     parent_sid: SurfaceId,
 
-    /// List of IDs of satelliting surfaces.
+    /// ID of surface for which this surface is transient (e.g. parent of dialog).
+    transient_for_sid: SurfaceId,
+
+    /// List of IDs of satelliting surfaces in order of drawing. Contains also ID of this surface.
     satellites: Vec<SurfaceId>,
 
+    /// Order of satellites requested by client to be applied on next commit.
+    pending_satellites: Option<Vec<SurfaceId>>,
+
+    /// Tells if surface is synchronized subsurface. State committed by such surface is cached and
+    /// applied when its parent is committed.
+    synchronized: bool,
+
     /// Position requested by client relative to parent surface.
     /// For surfaces without parent this must be {0, 0}.
     relative_position: Position,
 
-    /// Data required for draw.
-    buffer: Option<MemoryView>,
+    /// State currently used for drawing.
+    current: BufferedState,
 
-    /// Data to be used after commit.
-    pending_buffer: Option<MemoryView>,
+    /// State to be used after commit.
+    pending: BufferedState,
+
+    /// State committed during layout transaction to be used after the transaction finishes.
+    held: Option<BufferedState>,
+
+    /// State committed by synchronized subsurface to be used after its parent is committed.
+    cached: Option<BufferedState>,
+
+    /// Area (in surface coordinates) changed since damage was last taken, kept separately for
+    /// every display which took it.
+    accumulated_damage: HashMap<i32, Region>,
 
     /// Flags describing logical state of surface
     state_flags: surface_state::SurfaceState,
@@ -55,23 +193,65 @@ impl Surface {
     pub fn new(id: &SurfaceId) -> Self {
         Surface {
             id: *id,
-            offset: Vector::default(),
             desired_size: Size::default(),
             requested_size: Size::default(),
+            size_hints: SizeHints::default(),
             parent_sid: SurfaceId::invalid(),
+            transient_for_sid: SurfaceId::invalid(),
             satellites: vec![*id],
+            pending_satellites: None,
+            synchronized: false,
             relative_position: Position::default(),
-            buffer: None,
-            pending_buffer: None,
+            current: BufferedState::new(),
+            pending: BufferedState::new(),
+            held: None,
+            cached: None,
+            accumulated_damage: HashMap::new(),
             show_reasons: show_reason::NONE,
             state_flags: surface_state::REGULAR,
         }
     }
 
-    /// Sets position offset.
+    /// Sets pending position offset.
     pub fn set_offset(&mut self, offset: Vector) {
-        self.offset.x = if offset.x > 0 { offset.x } else { 0 };
-        self.offset.y = if offset.y > 0 { offset.y } else { 0 };
+        self.pending.offset.x = if offset.x > 0 { offset.x } else { 0 };
+        self.pending.offset.y = if offset.y > 0 { offset.y } else { 0 };
+    }
+
+    /// Adds damaged area in surface coordinates to pending state.
+    #[inline]
+    pub fn damage(&mut self, area: Area) {
+        self.pending.damage.push(area)
+    }
+
+    /// Adds damaged area in buffer coordinates to pending state.
+    #[inline]
+    pub fn damage_buffer(&mut self, area: Area) {
+        self.pending.buffer_damage.push(area)
+    }
+
+    /// Sets pending input region.
+    #[inline]
+    pub fn set_input_region(&mut self, region: Option<Region>) {
+        self.pending.input_region = region
+    }
+
+    /// Sets pending opaque region.
+    #[inline]
+    pub fn set_opaque_region(&mut self, region: Region) {
+        self.pending.opaque_region = region
+    }
+
+    /// Sets pending buffer scale.
+    #[inline]
+    pub fn set_buffer_scale(&mut self, scale: i32) {
+        self.pending.buffer_scale = scale
+    }
+
+    /// Sets pending buffer transformation.
+    #[inline]
+    pub fn set_buffer_transform(&mut self, transform: i32) {
+        self.pending.buffer_transform = transform
     }
 
     /// Sets size requested by client.
@@ -80,6 +260,18 @@ impl Surface {
         self.requested_size = size
     }
 
+    /// Sets minimal size requested by client.
+    #[inline]
+    pub fn set_min_size(&mut self, size: Size) {
+        self.size_hints.min_size = size
+    }
+
+    /// Sets maximal size requested by client.
+    #[inline]
+    pub fn set_max_size(&mut self, size: Size) {
+        self.size_hints.max_size = size
+    }
+
     /// Sets size desired by compositor.
     #[inline]
     pub fn set_desired_size(&mut self, size: Size) {
@@ -92,6 +284,12 @@ impl Surface {
         self.parent_sid = sid
     }
 
+    /// Sets ID of surface for which this surface is transient.
+    #[inline]
+    pub fn set_transient_for_sid(&mut self, sid: SurfaceId) {
+        self.transient_for_sid = sid
+    }
+
     /// Adds satellite.
     #[inline]
     pub fn add_satellite(&mut self, sid: SurfaceId) {
@@ -103,6 +301,9 @@ impl Surface {
         }
         if !contains {
             self.satellites.push(sid);
+            if let Some(ref mut pending_satellites) = self.pending_satellites {
+                pending_satellites.push(sid);
+            }
         }
     }
 
@@ -119,6 +320,49 @@ impl Surface {
         if let Some(index) = index {
             self.satellites.remove(index);
         }
+        if let Some(ref mut pending_satellites) = self.pending_satellites {
+            pending_satellites.retain(|satellite| *satellite != sid);
+        }
+    }
+
+    /// Places satellite directly above its sibling. Change will be applied on next commit.
+    /// Returns `false` if any of given surfaces is not a satellite of this surface.
+    pub fn place_satellite_above(&mut self, sid: SurfaceId, sibling_sid: SurfaceId) -> bool {
+        self.place_satellite(sid, sibling_sid, 1)
+    }
+
+    /// Places satellite directly below its sibling. Change will be applied on next commit.
+    /// Returns `false` if any of given surfaces is not a satellite of this surface.
+    pub fn place_satellite_below(&mut self, sid: SurfaceId, sibling_sid: SurfaceId) -> bool {
+        self.place_satellite(sid, sibling_sid, 0)
+    }
+
+    /// Helper method for changing stacking order of satellites. Satellite is placed `shift`
+    /// positions after its sibling in pending order.
+    fn place_satellite(&mut self, sid: SurfaceId, sibling_sid: SurfaceId, shift: usize) -> bool {
+        if (sid == self.id) || (sid == sibling_sid) {
+            return false;
+        }
+
+        let mut satellites = if let Some(ref pending_satellites) = self.pending_satellites {
+            pending_satellites.clone()
+        } else {
+            self.satellites.clone()
+        };
+
+        if let Some(index) = satellites.iter().position(|satellite| *satellite == sid) {
+            satellites.remove(index);
+        } else {
+            return false;
+        }
+
+        if let Some(index) = satellites.iter().position(|satellite| *satellite == sibling_sid) {
+            satellites.insert(index + shift, sid);
+            self.pending_satellites = Some(satellites);
+            true
+        } else {
+            false
+        }
     }
 
     /// Sets relative position.
@@ -127,6 +371,12 @@ impl Surface {
         self.relative_position = position
     }
 
+    /// Sets if surface is synchronized subsurface.
+    #[inline]
+    pub fn set_synchronized(&mut self, synchronized: bool) {
+        self.synchronized = synchronized
+    }
+
     /// Sets state flags.
     #[inline]
     pub fn set_state_flags(&mut self, state_flags: surface_state::SurfaceState) {
@@ -162,51 +412,166 @@ impl Surface {
     /// Sets given buffer as pending.
     #[inline]
     pub fn attach(&mut self, buffer: MemoryView) {
-        self.pending_buffer = Some(buffer);
+        self.pending.buffer = Some(buffer);
     }
 
-    /// Sets pending buffer as current. If surface was committed for the first time and sizes are
-    /// not set, assign size of buffer as requested size. Return `true` if surface was committed for
-    /// the first time, `false` otherwise.
-    pub fn commit(&mut self) -> bool {
-        let is_first_time_committed = self.buffer.is_none();
-        self.buffer = self.pending_buffer.clone();
+    /// Removes pending buffer. Surface will be unmapped on commit.
+    #[inline]
+    pub fn detach(&mut self) {
+        self.pending.buffer = None;
+    }
 
-        if let Some(ref buffer) = self.buffer {
-            // If surface was just created...
-            if is_first_time_committed {
+    /// Sets pending state as current. If surface was committed for the first time and sizes are
+    /// not set, assign size of buffer as requested size. Returns information if surface was mapped
+    /// or unmapped by this commit.
+    pub fn commit(&mut self) -> Mapping {
+        let state = self.pending.latch();
+        self.apply(state)
+    }
+
+    /// Stores pending state of synchronized subsurface. It will be applied by `apply_cached`.
+    /// Damage from previously cached state which was not yet applied is preserved.
+    pub fn cache(&mut self) {
+        let mut state = self.pending.latch();
+        if let Some(cached) = self.cached.take() {
+            state.inherit_damage(cached);
+        }
+        self.cached = Some(state);
+    }
+
+    /// Applies state cached by synchronized subsurface. Returns `None` if there is no cached
+    /// state, otherwise returns information if surface was mapped or unmapped.
+    pub fn apply_cached(&mut self) -> Option<Mapping> {
+        if let Some(state) = self.cached.take() {
+            Some(self.apply(state))
+        } else {
+            None
+        }
+    }
+
+    /// Helper method for applying committed state. See `commit`.
+    fn apply(&mut self, state: BufferedState) -> Mapping {
+        if let Some(satellites) = self.pending_satellites.take() {
+            self.satellites = satellites;
+        }
+
+        let was_mapped = self.current.buffer.is_some();
+        let old_size = self.current.get_logical_size();
+        self.current = state;
+        let is_mapped = self.current.buffer.is_some();
+
+        // Newly mapped or resized surface is damaged as a whole
+        let new_size = self.current.get_logical_size();
+        if let Some(size) = new_size {
+            let damage = if old_size == new_size {
+                self.current.get_surface_damage()
+            } else {
+                Region::from_area(Area::new(Position::default(), size))
+            };
+            for region in self.accumulated_damage.values_mut() {
+                region.union(&damage);
+            }
+        }
+
+        if let Some(size) = new_size {
+            // If surface was just mapped...
+            if !was_mapped {
                 // ... size was not yet requested by surface ...
                 if (self.requested_size.width == 0) || (self.requested_size.height == 0) {
-                    // ... use its buffer size as requested size ...
-                    self.requested_size = buffer.get_size();
+                    // ... use its logical buffer size as requested size ...
+                    self.requested_size = size;
                 }
                 // ... and if it is subsurface ...
                 if self.parent_sid.is_valid() {
                     // ... set its desired size.
-                    self.desired_size = buffer.get_size();
+                    self.desired_size = size;
                 }
             }
         }
 
-        is_first_time_committed
+        match (was_mapped, is_mapped) {
+            (false, true) => Mapping::Mapped,
+            (true, false) => Mapping::Unmapped,
+            _ => Mapping::Unchanged,
+        }
+    }
+
+    /// Commits the surface during layout transaction. Pending state will become current after
+    /// call to `release`. If some state is already held it is replaced by the new one but its
+    /// damage is preserved. Surface committed for the first time can not be held and `false` is
+    /// returned in such case, `true` otherwise.
+    pub fn hold(&mut self) -> bool {
+        if self.held.is_some() || self.current.buffer.is_some() {
+            let mut state = self.pending.latch();
+            if let Some(held) = self.held.take() {
+                state.inherit_damage(held);
+            }
+            self.held = Some(state);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Checks if surface holds state committed during layout transaction.
+    pub fn is_holding(&self) -> bool {
+        self.held.is_some()
+    }
+
+    /// Sets state committed during layout transaction as current. Returns `None` if there is no
+    /// held state, otherwise returns information if surface was mapped or unmapped.
+    pub fn release(&mut self) -> Option<Mapping> {
+        if let Some(state) = self.held.take() {
+            Some(self.apply(state))
+        } else {
+            None
+        }
+    }
+
+    /// Returns serial of the commit which latched current state.
+    pub fn get_current_serial(&self) -> u32 {
+        self.current.serial
+    }
+
+    /// Returns area (in surface coordinates) changed since last call for given display and clears
+    /// it for that display. Display taking damage for the first time receives the whole surface.
+    pub fn take_damage(&mut self, display_id: i32) -> Region {
+        match self.accumulated_damage.insert(display_id, Region::new()) {
+            Some(damage) => damage,
+            None => {
+                if let Some(size) = self.current.get_logical_size() {
+                    Region::from_area(Area::new(Position::default(), size))
+                } else {
+                    Region::new()
+                }
+            }
+        }
     }
 
     /// Returns information about surface.
     pub fn get_info(&self) -> SurfaceInfo {
         SurfaceInfo {
             id: self.id,
-            offset: self.offset,
+            offset: self.current.offset,
             parent_sid: self.parent_sid,
+            transient_for_sid: self.transient_for_sid,
             desired_size: self.desired_size,
             requested_size: self.requested_size,
+            size_hints: self.size_hints,
             state_flags: self.state_flags,
-            buffer: self.buffer.clone(),
+            buffer: self.current.buffer.clone(),
+            damage: self.current.damage.clone(),
+            buffer_damage: self.current.buffer_damage.clone(),
+            input_region: self.current.input_region.clone(),
+            opaque_region: self.current.opaque_region.clone(),
+            buffer_scale: self.current.buffer_scale,
+            buffer_transform: self.current.buffer_transform,
         }
     }
 
     /// Returns surfaces buffer.
     pub fn get_buffer(&self) -> Option<MemoryView> {
-        self.buffer.clone()
+        self.current.buffer.clone()
     }
 
     /// Returns surfaces rendering context.
@@ -222,16 +587,31 @@ impl Surface {
         self.desired_size
     }
 
+    /// Returns minimal and maximal size requested by client.
+    pub fn get_size_hints(&self) -> SizeHints {
+        self.size_hints
+    }
+
     /// Returns flags describing state of the surface.
     pub fn get_state_flags(&self) -> surface_state::SurfaceState {
         self.state_flags
     }
 
+    /// Checks if surface is synchronized subsurface.
+    pub fn is_synchronized(&self) -> bool {
+        self.synchronized
+    }
+
     /// Returns ID of parent surface.
     pub fn get_parent_sid(&self) -> SurfaceId {
         self.parent_sid
     }
 
+    /// Returns ID of surface for which this surface is transient.
+    pub fn get_transient_for_sid(&self) -> SurfaceId {
+        self.transient_for_sid
+    }
+
     /// Returns vector of IDs of satelliting surfaces (pop-ups, subsurfaces).
     pub fn get_satellites(&self) -> &Vec<SurfaceId> {
         &self.satellites
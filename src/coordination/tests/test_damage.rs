@@ -0,0 +1,84 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Unit tests of tracking surface damage.
+
+extern crate dharma;
+extern crate qualia;
+extern crate coordination;
+
+use qualia::{Area, Buffer, MemoryPoolId, Region, SurfaceId};
+use qualia::{MemoryManagement, SurfaceManagement, SurfaceViewer};
+use coordination::Coordinator;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Size of buffers used in tests.
+const SIZE: usize = 10;
+
+/// IDs of displays taking damage.
+const DISPLAY1: i32 = 1;
+const DISPLAY2: i32 = 2;
+
+// -------------------------------------------------------------------------------------------------
+
+struct Environment {
+    coordinator: Coordinator,
+    mpid: MemoryPoolId,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Environment {
+    pub fn create() -> Self {
+        let mut coordinator = Coordinator::new(dharma::Signaler::new());
+        let stride = 4 * SIZE;
+        let buffer = Buffer::new(SIZE, SIZE, stride, vec![0; stride * SIZE]);
+        let mpid = coordinator.create_pool_from_buffer(buffer);
+        Environment {
+            coordinator: coordinator,
+            mpid: mpid,
+        }
+    }
+
+    /// Creates new surface mapped with buffer.
+    pub fn create_surface(&mut self) -> SurfaceId {
+        let sid = self.coordinator.create_surface();
+        let mvid = self.coordinator
+            .create_memory_view(self.mpid, 0, SIZE, SIZE, 4 * SIZE)
+            .expect("create memory view");
+        self.coordinator.attach_surface(mvid, sid);
+        self.coordinator.commit_surface(sid);
+        sid
+    }
+
+    /// Damages given area of surface and commits it.
+    pub fn damage(&mut self, sid: SurfaceId, area: Area) {
+        self.coordinator.damage_surface(sid, area);
+        self.coordinator.commit_surface(sid);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if damage taken by one display is still reported to other displays and if display taking
+/// damage for the first time receives the whole surface.
+#[test]
+fn test_taking_damage_by_many_displays() {
+    let mut e = Environment::create();
+    let sid = e.create_surface();
+    let whole = Region::from_area(Area::create(0, 0, SIZE, SIZE));
+    assert_eq!(e.coordinator.take_surface_damage(sid, DISPLAY1), whole);
+
+    let area = Area::create(1, 2, 3, 4);
+    e.damage(sid, area);
+    assert_eq!(e.coordinator.take_surface_damage(sid, DISPLAY1), Region::from_area(area));
+    assert_eq!(e.coordinator.take_surface_damage(sid, DISPLAY1), Region::new());
+    assert_eq!(e.coordinator.take_surface_damage(sid, DISPLAY2), whole);
+
+    e.damage(sid, area);
+    assert_eq!(e.coordinator.take_surface_damage(sid, DISPLAY2), Region::from_area(area));
+    assert_eq!(e.coordinator.take_surface_damage(sid, DISPLAY1), Region::from_area(area));
+}
+
+// -------------------------------------------------------------------------------------------------
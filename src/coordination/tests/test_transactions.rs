@@ -0,0 +1,233 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Unit tests of layout transactions.
+
+extern crate dharma;
+extern crate qualia;
+extern crate coordination;
+
+use std::time::Duration;
+
+use qualia::{perceptron, Perceptron};
+use qualia::{Buffer, MemoryPoolId, Pixmap, Size, SurfaceId};
+use qualia::{MemoryManagement, SurfaceAccess, SurfaceControl, SurfaceManagement, SurfaceViewer};
+use qualia::surface_state;
+use coordination::Coordinator;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Size of the largest buffer used in tests.
+const MAX_SIZE: usize = 30;
+
+// -------------------------------------------------------------------------------------------------
+
+struct Environment {
+    coordinator: Coordinator,
+    mpid: MemoryPoolId,
+    receiver: dharma::Receiver<Perceptron>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Environment {
+    /// Creates environment in which transactions never time out.
+    pub fn create() -> Self {
+        Self::create_with_timeout(Duration::from_secs(3600))
+    }
+
+    /// Creates environment with given transaction timeout.
+    pub fn create_with_timeout(timeout: Duration) -> Self {
+        let mut signaler = dharma::Signaler::new();
+        let receiver = dharma::Receiver::new();
+        signaler.subscribe(perceptron::SURFACE_STATE_APPLIED, &receiver);
+
+        let mut coordinator = Coordinator::with_transaction_timeout(signaler, timeout);
+        let stride = 4 * MAX_SIZE;
+        let buffer = Buffer::new(MAX_SIZE, MAX_SIZE, stride, vec![0; stride * MAX_SIZE]);
+        let mpid = coordinator.create_pool_from_buffer(buffer);
+        Environment {
+            coordinator: coordinator,
+            mpid: mpid,
+            receiver: receiver,
+        }
+    }
+
+    /// Creates new surface mapped with buffer of given size.
+    pub fn create_surface(&mut self, size: usize) -> SurfaceId {
+        let sid = self.coordinator.create_surface();
+        self.commit(sid, size);
+        sid
+    }
+
+    /// Attaches new square buffer of given size to surface and commits the surface.
+    pub fn commit(&mut self, sid: SurfaceId, size: usize) {
+        let mvid = self.coordinator
+            .create_memory_view(self.mpid, 0, size, size, 4 * MAX_SIZE)
+            .expect("create memory view");
+        self.coordinator.attach_surface(mvid, sid);
+        self.coordinator.commit_surface(sid);
+    }
+
+    /// Requests new size of surface. This starts or extends layout transaction.
+    pub fn reconfigure(&mut self, sid: SurfaceId, size: usize) {
+        self.coordinator.reconfigure(sid, Size::new(size, size), surface_state::MAXIMIZED);
+    }
+
+    /// Acknowledges the most recent configuration of surface.
+    pub fn acknowledge(&mut self, sid: SurfaceId) {
+        self.coordinator.acknowledge_surface_configuration(sid);
+    }
+
+    /// Returns size of the buffer currently presented by surface.
+    pub fn get_current_size(&self, sid: SurfaceId) -> usize {
+        let surface = self.coordinator.get_surface(sid).expect("get surface");
+        surface.buffer.expect("surface should have buffer").get_width()
+    }
+
+    /// Returns serials of states of given surface reported as applied since last call.
+    pub fn take_applied_serials(&mut self, sid: SurfaceId) -> Vec<u32> {
+        let mut serials = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                dharma::ReceiveResult::Defined(_, Perceptron::SurfaceStateApplied(s, serial)) => {
+                    if s == sid {
+                        serials.push(serial);
+                    }
+                }
+                dharma::ReceiveResult::Empty => break,
+                _ => {}
+            }
+        }
+        serials
+    }
+
+    /// Checks if layout transaction is still in progress.
+    pub fn is_pending(&self) -> bool {
+        self.coordinator.is_transaction_pending()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if state committed after acknowledging configuration is held until all surfaces in the
+/// transaction committed.
+#[test]
+fn test_holding_commit_until_transaction_is_complete() {
+    let mut e = Environment::create();
+    let sid1 = e.create_surface(10);
+    let sid2 = e.create_surface(10);
+
+    e.reconfigure(sid1, 20);
+    e.reconfigure(sid2, 20);
+    assert!(e.is_pending());
+
+    e.acknowledge(sid1);
+    e.commit(sid1, 20);
+    assert_eq!(e.get_current_size(sid1), 10);
+    assert!(e.is_pending());
+
+    e.acknowledge(sid2);
+    e.commit(sid2, 20);
+    assert_eq!(e.get_current_size(sid1), 20);
+    assert_eq!(e.get_current_size(sid2), 20);
+    assert!(!e.is_pending());
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if commits not preceded by acknowledgement of configuration are not held and if
+/// reconfigured surface has to acknowledge new configuration even if it acknowledged previous one.
+#[test]
+fn test_acknowledging_configuration() {
+    let mut e = Environment::create();
+    let sid1 = e.create_surface(10);
+    let sid2 = e.create_surface(10);
+
+    e.reconfigure(sid1, 20);
+    e.reconfigure(sid2, 20);
+    e.commit(sid1, 15);
+    assert_eq!(e.get_current_size(sid1), 15);
+
+    e.acknowledge(sid1);
+    e.reconfigure(sid1, 25);
+    e.commit(sid1, 20);
+    assert_eq!(e.get_current_size(sid1), 20);
+
+    e.acknowledge(sid1);
+    e.commit(sid1, 25);
+    assert_eq!(e.get_current_size(sid1), 20);
+    assert!(e.is_pending());
+
+    e.acknowledge(sid2);
+    e.commit(sid2, 20);
+    assert_eq!(e.get_current_size(sid1), 25);
+    assert!(!e.is_pending());
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if state committed while previous one is held replaces the held one and older state does
+/// not become current after the transaction finishes.
+#[test]
+fn test_committing_while_held() {
+    let mut e = Environment::create();
+    let sid1 = e.create_surface(10);
+    let sid2 = e.create_surface(10);
+
+    e.reconfigure(sid1, 20);
+    e.reconfigure(sid2, 20);
+    e.acknowledge(sid1);
+    e.commit(sid1, 20);
+    e.commit(sid1, 30);
+    assert_eq!(e.get_current_size(sid1), 10);
+
+    e.acknowledge(sid2);
+    e.commit(sid2, 20);
+    assert_eq!(e.get_current_size(sid1), 30);
+    assert!(!e.is_pending());
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if held states are applied when transaction times out even if not all surfaces
+/// committed. Transactions time out immediately in this test.
+#[test]
+fn test_finishing_timed_out_transaction() {
+    let mut e = Environment::create_with_timeout(Duration::from_millis(0));
+    let sid1 = e.create_surface(10);
+    let sid2 = e.create_surface(10);
+
+    e.reconfigure(sid1, 20);
+    e.reconfigure(sid2, 20);
+    e.acknowledge(sid1);
+    e.commit(sid1, 20);
+    assert!(!e.is_pending());
+    assert_eq!(e.get_current_size(sid1), 20);
+    assert_eq!(e.get_current_size(sid2), 10);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if applying of state is reported only when held state becomes current and if state
+/// replaced while being held is not reported.
+#[test]
+fn test_reporting_applied_state() {
+    let mut e = Environment::create();
+    let sid1 = e.create_surface(10);
+    let sid2 = e.create_surface(10);
+    assert_eq!(e.take_applied_serials(sid1), vec![1]);
+
+    e.reconfigure(sid1, 20);
+    e.reconfigure(sid2, 20);
+    e.acknowledge(sid1);
+    e.commit(sid1, 20);
+    e.commit(sid1, 30);
+    assert_eq!(e.take_applied_serials(sid1), Vec::<u32>::new());
+
+    e.acknowledge(sid2);
+    e.commit(sid2, 20);
+    assert_eq!(e.take_applied_serials(sid1), vec![3]);
+}
+
+// -------------------------------------------------------------------------------------------------
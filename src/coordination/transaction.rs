@@ -0,0 +1,91 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! This module contains functionality related to layout transactions.
+//!
+//! Layout transaction gathers all surfaces reconfigured during one change of layout. Changes are
+//! presented to user only after all clients acknowledged and committed new configuration (or when
+//! waiting for them timed out) so that all surfaces are displayed in their new state at once.
+
+// -------------------------------------------------------------------------------------------------
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use qualia::SurfaceId;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Structure describing state of layout transaction.
+pub struct Transaction {
+    /// Time of beginning of the transaction.
+    start: Instant,
+
+    /// IDs of all surfaces taking part in the transaction.
+    members: HashSet<SurfaceId>,
+
+    /// IDs of surfaces which configuration was not yet acknowledged by client.
+    awaiting_ack: HashSet<SurfaceId>,
+
+    /// IDs of surfaces which configuration was acknowledged but not yet committed by client.
+    awaiting_commit: HashSet<SurfaceId>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Transaction {
+    /// Constructs new `Transaction`.
+    pub fn new() -> Self {
+        Transaction {
+            start: Instant::now(),
+            members: HashSet::new(),
+            awaiting_ack: HashSet::new(),
+            awaiting_commit: HashSet::new(),
+        }
+    }
+
+    /// Adds surface to transaction. Surface reconfigured again has to acknowledge new
+    /// configuration even if it already acknowledged previous one.
+    pub fn add(&mut self, sid: SurfaceId) {
+        self.members.insert(sid);
+        self.awaiting_commit.remove(&sid);
+        self.awaiting_ack.insert(sid);
+    }
+
+    /// Removes surface from transaction (e.g. when it was destroyed).
+    pub fn remove(&mut self, sid: SurfaceId) {
+        self.members.remove(&sid);
+        self.awaiting_ack.remove(&sid);
+        self.awaiting_commit.remove(&sid);
+    }
+
+    /// Marks configuration of given surface as acknowledged.
+    pub fn acknowledge(&mut self, sid: SurfaceId) {
+        if self.awaiting_ack.remove(&sid) {
+            self.awaiting_commit.insert(sid);
+        }
+    }
+
+    /// Marks given surface as committed. Returns `true` if this commit concluded surfaces part in
+    /// the transaction, `false` if commit is not related to the transaction.
+    pub fn commit(&mut self, sid: SurfaceId) -> bool {
+        self.awaiting_commit.remove(&sid)
+    }
+
+    /// Checks if all surfaces acknowledged and committed their configuration.
+    pub fn is_complete(&self) -> bool {
+        self.awaiting_ack.is_empty() && self.awaiting_commit.is_empty()
+    }
+
+    /// Checks if transaction lasts at least given timeout.
+    pub fn is_timed_out(&self, timeout: Duration) -> bool {
+        self.start.elapsed() >= timeout
+    }
+
+    /// Returns IDs of all surfaces taking part in the transaction.
+    pub fn get_members(&self) -> &HashSet<SurfaceId> {
+        &self.members
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -0,0 +1,110 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Pointer acceleration.
+//!
+//! Motion is multiplied by factor depending on chosen profile. For `adaptive` profile the factor is
+//! `speed * (velocity / VELOCITY_THRESHOLD) ^ curve` limited to range from `MIN_FACTOR` to
+//! `MAX_FACTOR` of `speed`, where velocity is calculated from time stamps of events. Fractional
+//! parts of scaled motion are accumulated so slow moves of high-resolution devices are not lost.
+
+// -------------------------------------------------------------------------------------------------
+
+use std::cmp::{max, min};
+
+use qualia::AccelerationConfig;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Velocity in device units per millisecond for which adaptive factor is equal to `speed`.
+const VELOCITY_THRESHOLD: f32 = 1.0;
+
+/// Minimal adaptive factor relative to `speed`.
+const MIN_FACTOR: f32 = 0.3;
+
+/// Maximal adaptive factor relative to `speed`.
+const MAX_FACTOR: f32 = 4.0;
+
+/// Maximal time between events in milliseconds for which they are considered one move.
+const MAX_DELTA_TIME: u64 = 50;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Acceleration profile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Profile {
+    Flat,
+    Adaptive,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Applies acceleration to pointer motion.
+pub struct Accelerator {
+    profile: Profile,
+    speed: f32,
+    curve: f32,
+    last_time: Option<u64>,
+    remainder: (f32, f32),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Accelerator {
+    /// `Accelerator` constructor.
+    pub fn new(config: &AccelerationConfig) -> Self {
+        let profile = match config.profile.as_ref() {
+            "flat" => Profile::Flat,
+            "adaptive" => Profile::Adaptive,
+            _ => {
+                log_warn1!("Unknown acceleration profile '{}', using 'flat'", config.profile);
+                Profile::Flat
+            }
+        };
+
+        Accelerator {
+            profile: profile,
+            speed: config.speed,
+            curve: config.curve,
+            last_time: None,
+            remainder: (0.0, 0.0),
+        }
+    }
+
+    /// Accelerates motion which happened at given time (in milliseconds).
+    pub fn accelerate(&mut self, x: f32, y: f32, time: u64) -> (isize, isize) {
+        let factor = match self.profile {
+            Profile::Flat => self.speed,
+            Profile::Adaptive => self.speed * self.calculate_adaptive_factor(x, y, time),
+        };
+        self.last_time = Some(time);
+
+        let x = factor * x + self.remainder.0;
+        let y = factor * y + self.remainder.1;
+        self.remainder = (x.fract(), y.fract());
+        (x.trunc() as isize, y.trunc() as isize)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Helper methods.
+impl Accelerator {
+    /// Calculates factor of adaptive profile basing on velocity of the move.
+    fn calculate_adaptive_factor(&mut self, x: f32, y: f32, time: u64) -> f32 {
+        let delta = match self.last_time {
+            Some(last_time) if time >= last_time => max(min(time - last_time, MAX_DELTA_TIME), 1),
+            _ => MAX_DELTA_TIME,
+        };
+
+        if delta == MAX_DELTA_TIME {
+            // New move starts - forget about fractions of the previous one
+            self.remainder = (0.0, 0.0);
+        }
+
+        let velocity = (x * x + y * y).sqrt() / delta as f32;
+        (velocity / VELOCITY_THRESHOLD).powf(self.curve).max(MIN_FACTOR).min(MAX_FACTOR)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -6,8 +6,8 @@
 // -------------------------------------------------------------------------------------------------
 
 use std::cell::RefCell;
-use std::collections::BTreeSet;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::os::unix::io;
 
 use nix::{self, Errno};
@@ -24,10 +24,11 @@ use qualia::{Illusion, Ipc};
 /// to open it for him. Communication is done via `dbus`.
 ///
 /// `logind` allows to take device only once, and further tries will fail. `RestrictedOpener`
-/// handles IDs of open devices and releases them before next take.
+/// handles IDs of open devices and releases them before next take. Paths of taken devices are
+/// remembered so they can be released after device node was removed.
 pub struct RestrictedOpener {
     ipc: Ipc,
-    taken_devices: RefCell<BTreeSet<u64>>,
+    taken_devices: RefCell<BTreeMap<u64, PathBuf>>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -37,7 +38,7 @@ impl RestrictedOpener {
     pub fn new() -> Self {
         RestrictedOpener {
             ipc: Ipc::new(),
-            taken_devices: RefCell::new(BTreeSet::new()),
+            taken_devices: RefCell::new(BTreeMap::new()),
         }
     }
 
@@ -58,6 +59,22 @@ impl RestrictedOpener {
         }
     }
 
+    /// Releases device taken from `logind`. Does nothing if device was opened directly.
+    pub fn release(&self, path: &Path) {
+        let rdev = self.taken_devices
+            .borrow()
+            .iter()
+            .find(|&(_, taken_path)| taken_path == path)
+            .map(|(rdev, _)| *rdev);
+
+        if let Some(rdev) = rdev {
+            if let Err(err) = self.ipc.release_device(rdev) {
+                log_warn2!("Failed to release device {:?}: {:?}", path, err);
+            }
+            self.taken_devices.borrow_mut().remove(&rdev);
+        }
+    }
+
     /// Initialize connection to `logind`.
     pub fn initialize_ipc(&mut self) -> Result<(), Illusion> {
         self.ipc.initialize()
@@ -74,7 +91,7 @@ impl RestrictedOpener {
                 let rdev = st.st_rdev as u64;
 
                 // If device is taken - release it first
-                let contains = self.taken_devices.borrow().contains(&rdev);
+                let contains = self.taken_devices.borrow().contains_key(&rdev);
                 if contains {
                     if self.ipc.release_device(rdev).is_ok() {
                         self.taken_devices.borrow_mut().remove(&rdev);
@@ -84,7 +101,7 @@ impl RestrictedOpener {
                 // Take the device
                 let result = self.ipc.take_device(rdev);
                 if result.is_ok() {
-                    self.taken_devices.borrow_mut().insert(rdev);
+                    self.taken_devices.borrow_mut().insert(rdev, path.to_owned());
                 }
                 result
             }
@@ -6,33 +6,47 @@
 // -------------------------------------------------------------------------------------------------
 
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use dharma;
+use qualia::InputDeviceBundle;
 use coordination::Context;
 
 use evdev;
 use udev;
 use device_access::RestrictedOpener;
+use device_monitor::DeviceMonitor;
 use output_collector::OutputCollector;
-use input_gateway::InputGateway;
+use input_gateway::{InputGateway, InputState, InputStateRef};
 use drivers::InputDriver;
 use virtual_terminal;
 
 // -------------------------------------------------------------------------------------------------
 
+/// Input device handled by `DeviceManager`.
+struct InputDevice {
+    handler_id: dharma::EventHandlerId,
+    devnode: PathBuf,
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Device Manager manages searching input and output devices and monitoring them.
-pub struct DeviceManager<'a> {
-    udev: udev::Udev<'a>,
+pub struct DeviceManager {
+    udev: udev::Udev,
     restricted_opener: Rc<RefCell<RestrictedOpener>>,
     vt: Option<virtual_terminal::VirtualTerminal>,
     output_collector: OutputCollector,
+    input_state: InputStateRef,
+    input_devices: HashMap<PathBuf, InputDevice>,
     context: Context,
 }
 
 // -------------------------------------------------------------------------------------------------
 
-impl<'a> DeviceManager<'a> {
+impl DeviceManager {
     /// `DeviceManager` constructor.
     pub fn new(mut context: Context) -> Self {
         let restricted_opener = Self::prepare_restricted_opener();
@@ -43,6 +57,8 @@ impl<'a> DeviceManager<'a> {
             vt: None,
             output_collector: OutputCollector::new(context.get_dispatcher().clone(),
                                                    context.get_signaler().clone()),
+            input_state: InputState::new_ref(context.get_config().get_keyboard_config().clone()),
+            input_devices: HashMap::new(),
             context: context.clone(),
         };
 
@@ -100,26 +116,51 @@ impl<'a> DeviceManager<'a> {
 
     /// Iterate over input devices to find usable ones and initialize event handlers for them.
     fn initialize_input_devices(&mut self, context: &mut Context) {
-        self.udev.iterate_event_devices(|devnode, devkind, _| {
-            let config = context.get_config().get_input_config().clone();
-            let gateway = InputGateway::new(config.clone(),
-                                            context.get_input_manager().clone(),
-                                            context.get_signaler().clone(),
-                                            self.vt);
-            let r = evdev::Evdev::initialize_device(devnode,
-                                                    devkind,
-                                                    config,
-                                                    gateway,
-                                                    &self.restricted_opener.borrow());
-            match r {
-                Ok(driver) => {
-                    context.add_event_handler(driver, dharma::event_kind::READ);
-                }
-                Err(err) => {
-                    log_error!("Could not initialize input devices: {}", err);
+        let mut input_devices = Vec::new();
+        self.udev.iterate_event_devices(|_, _, device| {
+            if let Some(bundle) = udev::get_input_device_bundle(device) {
+                if let Some(input_device) = self.add_input_device(&bundle, context) {
+                    input_devices.push((bundle.syspath, input_device));
                 }
             }
         });
+        self.input_devices.extend(input_devices);
+    }
+
+    /// Initializes event handler for given input device. Effective configuration of the device is
+    /// resolved from device-specific config sections.
+    fn add_input_device(&self,
+                        bundle: &InputDeviceBundle,
+                        context: &mut Context)
+                        -> Option<InputDevice> {
+        let info = &bundle.info;
+        log_info2!("Input device: {:?}", info);
+        let config = context.get_config().get_input_config().get_device_config(info);
+        let keyboard = context.get_config().get_device_keyboard_config(info);
+        let gateway = InputGateway::new(config.clone(),
+                                        keyboard,
+                                        context.get_input_manager().clone(),
+                                        context.get_signaler().clone(),
+                                        self.vt,
+                                        bundle.output_name.clone(),
+                                        self.input_state.clone());
+        let r = evdev::Evdev::initialize_device(&bundle.devnode,
+                                                info.kind,
+                                                config,
+                                                gateway,
+                                                &self.restricted_opener.borrow());
+        match r {
+            Ok(driver) => {
+                Some(InputDevice {
+                    handler_id: context.add_event_handler(driver, dharma::event_kind::READ),
+                    devnode: bundle.devnode.clone(),
+                })
+            }
+            Err(err) => {
+                log_error!("Could not initialize input device: {}", err);
+                None
+            }
+        }
     }
 
     /// Find and initialize outputs.
@@ -137,7 +178,7 @@ impl<'a> DeviceManager<'a> {
 
     /// Initialize device monitoring.
     fn initialize_device_monitor(&mut self, context: &mut Context) {
-        match self.udev.start_device_monitor() {
+        match DeviceMonitor::new(context.get_signaler().clone()) {
             Ok(device_monitor) => {
                 context.add_event_handler(Box::new(device_monitor), dharma::event_kind::READ);
             }
@@ -151,16 +192,48 @@ impl<'a> DeviceManager<'a> {
 // -------------------------------------------------------------------------------------------------
 
 /// Event handlers
-impl<'a> DeviceManager<'a> {
+impl DeviceManager {
     pub fn on_suspend(&mut self) {
         // Nothing to do as for now...
     }
 
     pub fn on_wakeup(&mut self) {
         // Old event devices hung-up so devices must be reinitialized.
+        for (_, input_device) in self.input_devices.drain() {
+            self.context.remove_event_handler(input_device.handler_id);
+        }
         let mut context = self.context.clone();
         self.initialize_input_devices(&mut context);
     }
+
+    /// Handles adding of input device.
+    pub fn on_input_device_added(&mut self, bundle: &InputDeviceBundle) {
+        if self.input_devices.contains_key(&bundle.syspath) {
+            return;
+        }
+
+        let mut context = self.context.clone();
+        if let Some(input_device) = self.add_input_device(bundle, &mut context) {
+            self.input_devices.insert(bundle.syspath.clone(), input_device);
+        }
+    }
+
+    /// Handles removing of input device with given system path. Event handler of the device is
+    /// removed and the device is released.
+    pub fn on_input_device_removed(&mut self, syspath: &Path) {
+        if let Some(input_device) = self.input_devices.remove(syspath) {
+            self.context.remove_event_handler(input_device.handler_id);
+            self.restricted_opener.borrow().release(&input_device.devnode);
+        }
+    }
+
+    /// Handles change of output device with given device node. Outputs could be connected or
+    /// disconnected so the device is rescanned.
+    pub fn on_output_device_changed(&mut self, devnode: &Path) {
+        if let Err(err) = self.output_collector.rescan_device(devnode) {
+            log_error!("{}", err);
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -1,28 +1,107 @@
 // This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
 // the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
 
-/// `DeviceMonitor` implements `dharma::Dispatcher`'s `EventHandler`. It is used to process
-/// notifications from `udev` about adding and removing devices..
+//! `DeviceMonitor` implements `dharma::Dispatcher`'s `EventHandler`. It is used to process
+//! notifications from `udev` about adding and removing devices.
 
 // -------------------------------------------------------------------------------------------------
 
-use std::os::unix::io;
+use std::os::unix::io::{self, AsRawFd};
 
-use dharma::{EventHandler, EventKind};
+use libudev;
+
+use dharma::{EventHandler, EventKind, Signaler, SignalId, event_kind};
+use qualia::{Illusion, Perceptron, perceptron};
+
+use udev;
 
 // -------------------------------------------------------------------------------------------------
 
-/// `udev` device event handled.
+/// `udev` device event handler.
+///
+/// `libudev::MonitorSocket` borrows `libudev::Context` so both are kept here. Context is boxed so
+/// its address does not change when `DeviceMonitor` is moved. Socket is declared first so it is
+/// dropped before context.
 pub struct DeviceMonitor {
-    monitor_fd: io::RawFd,
+    socket: libudev::MonitorSocket<'static>,
+    _context: Box<libudev::Context>,
+    signaler: Signaler<Perceptron>,
+}
+
+/// `libudev` structures are not thread-safe, but `DeviceMonitor` is used only by one thread at a
+/// time: it is created in device manager and then passed to dispatcher.
+unsafe impl Send for DeviceMonitor {}
+
+// -------------------------------------------------------------------------------------------------
+
+impl DeviceMonitor {
+    /// `DeviceMonitor` constructor. Starts monitoring input and DRM devices.
+    pub fn new(signaler: Signaler<Perceptron>) -> Result<Self, Illusion> {
+        let context = Box::new(libudev::Context::new()?);
+
+        // Reference to context is valid as long as `DeviceMonitor` exists.
+        let context_ref: &'static libudev::Context =
+            unsafe { &*(context.as_ref() as *const libudev::Context) };
+
+        let mut monitor = libudev::Monitor::new(context_ref)?;
+        ensure!(monitor.match_subsystem("input"));
+        ensure!(monitor.match_subsystem("drm"));
+        let socket = monitor.listen()?;
+
+        Ok(DeviceMonitor {
+            socket: socket,
+            _context: context,
+            signaler: signaler,
+        })
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
 
+/// Helper methods.
 impl DeviceMonitor {
-    /// `DeviceMonitor` constructor.
-    pub fn new(fd: io::RawFd) -> Self {
-        DeviceMonitor { monitor_fd: fd }
+    /// Translates `udev` event to signal.
+    fn translate_event(event: &libudev::Event) -> Option<(SignalId, Perceptron)> {
+        match event.sysname().to_str() {
+            Some(sysname) if sysname.starts_with("event") => Self::translate_input_event(event),
+            Some(sysname) if sysname.starts_with("card") => Self::translate_output_event(event),
+            _ => None,
+        }
+    }
+
+    /// Translates event of input device. Only adding and removing of event devices is reported.
+    /// Data of added device is read from the event as the device may be gone before the signal is
+    /// handled.
+    fn translate_input_event(event: &libudev::Event) -> Option<(SignalId, Perceptron)> {
+        match event.event_type() {
+            libudev::EventType::Add => {
+                if let Some(bundle) = udev::get_input_device_bundle(event.device()) {
+                    log_info1!("Input device added: {:?}", bundle.devnode);
+                    Some((perceptron::INPUT_DEVICE_ADDED, Perceptron::InputDeviceAdded(bundle)))
+                } else {
+                    None
+                }
+            }
+            libudev::EventType::Remove => {
+                let syspath = event.syspath().to_owned();
+                log_info1!("Input device removed: {:?}", syspath);
+                Some((perceptron::INPUT_DEVICE_REMOVED, Perceptron::InputDeviceRemoved(syspath)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Translates event of DRM device. Connecting or disconnecting output is reported by kernel as
+    /// change of the whole device.
+    fn translate_output_event(event: &libudev::Event) -> Option<(SignalId, Perceptron)> {
+        match (event.event_type(), event.devnode()) {
+            (libudev::EventType::Change, Some(devnode)) => {
+                log_info1!("Output device changed: {:?}", devnode);
+                Some((perceptron::OUTPUT_DEVICE_CHANGED,
+                      Perceptron::OutputDeviceChanged(devnode.to_owned())))
+            }
+            _ => None,
+        }
     }
 }
 
@@ -31,11 +110,19 @@ impl DeviceMonitor {
 /// This code executes in main dispatchers thread.
 impl EventHandler for DeviceMonitor {
     fn get_fd(&self) -> io::RawFd {
-        self.monitor_fd
+        self.socket.as_raw_fd()
     }
 
-    fn process_event(&mut self, _: EventKind) {
-        // FIXME: Implement handling of device adding and removing.
+    fn process_event(&mut self, event_kind: EventKind) {
+        if event_kind.intersects(event_kind::READ) {
+            while let Some(event) = self.socket.receive_event() {
+                if let Some((id, package)) = Self::translate_event(&event) {
+                    self.signaler.emit(id, package);
+                }
+            }
+        } else if event_kind.intersects(event_kind::HANGUP) {
+            log_warn1!("Device monitor hung up");
+        }
     }
 }
 
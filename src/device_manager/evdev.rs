@@ -5,6 +5,7 @@
 
 // -------------------------------------------------------------------------------------------------
 
+use std::collections::BTreeMap;
 use std::mem;
 use std::os::unix::io;
 use std::path::Path;
@@ -12,14 +13,201 @@ use uinput_sys::{self, input_event};
 
 use nix::fcntl;
 use nix::sys::stat;
-use nix::unistd::read;
+use nix::unistd::{close, read};
 
-use qualia::{DeviceKind, Illusion, InputConfig};
+use qualia::{DeviceKind, Illusion, InputConfig, Slide, Vector};
 use dharma::{EventHandler, EventKind, event_kind};
 
 use drivers;
 use device_access::RestrictedOpener;
 use input_gateway::InputGateway;
+use touchpad::Touchpad;
+
+// -------------------------------------------------------------------------------------------------
+
+pub mod ioctl {
+    /// Information about absolute axis.
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct AbsInfo {
+        pub value: i32,
+        pub minimum: i32,
+        pub maximum: i32,
+        pub fuzz: i32,
+        pub flat: i32,
+        pub resolution: i32,
+    }
+
+    const EVDEV_MAGIC: u8 = b'E';
+    const GET_ABS_MT_POSITION_X: u8 = 0x40 + 0x35;
+    const GET_ABS_MT_POSITION_Y: u8 = 0x40 + 0x36;
+
+    ioctl!(read get_abs_mt_position_x with EVDEV_MAGIC, GET_ABS_MT_POSITION_X; AbsInfo);
+    ioctl!(read get_abs_mt_position_y with EVDEV_MAGIC, GET_ABS_MT_POSITION_Y; AbsInfo);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Code of high-resolution vertical wheel event.
+const REL_WHEEL_HI_RES: u16 = 0x0b;
+
+/// Code of high-resolution horizontal wheel event.
+const REL_HWHEEL_HI_RES: u16 = 0x0c;
+
+/// Value of high-resolution wheel event corresponding to one notch.
+const HI_RES_NOTCH: f32 = 120.0;
+
+/// Distance of scroll for one notch of the wheel.
+const WHEEL_STEP: f32 = 10.0;
+
+/// Mouse buttons passed to clients.
+const MOUSE_BUTTONS: [i32; 8] = [uinput_sys::BTN_LEFT,
+                                 uinput_sys::BTN_RIGHT,
+                                 uinput_sys::BTN_MIDDLE,
+                                 uinput_sys::BTN_SIDE,
+                                 uinput_sys::BTN_EXTRA,
+                                 uinput_sys::BTN_FORWARD,
+                                 uinput_sys::BTN_BACK,
+                                 uinput_sys::BTN_TASK];
+
+// -------------------------------------------------------------------------------------------------
+
+/// Change of touch point state reported in currently processed frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TouchChange {
+    None,
+    Down,
+    Motion,
+    Up,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// State of multitouch slot.
+#[derive(Clone, Copy, Debug)]
+struct TouchSlot {
+    x: i32,
+    y: i32,
+    active: bool,
+    change: TouchChange,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl TouchSlot {
+    /// Constructs inactive `TouchSlot`.
+    fn new() -> Self {
+        TouchSlot {
+            x: 0,
+            y: 0,
+            active: false,
+            change: TouchChange::None,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// State of touchscreen. Touch points are tracked using multitouch slots (protocol B). Changes are
+/// accumulated until `SYN_REPORT` and then emitted as one frame.
+struct Multitouch {
+    current_slot: i32,
+    slots: BTreeMap<i32, TouchSlot>,
+    x_range: ioctl::AbsInfo,
+    y_range: ioctl::AbsInfo,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Multitouch {
+    /// Constructs new `Multitouch` reading ranges of position axes from the device.
+    fn new(fd: io::RawFd) -> Self {
+        let mut x_range = ioctl::AbsInfo::default();
+        let mut y_range = ioctl::AbsInfo::default();
+        unsafe {
+            if let Err(err) = ioctl::get_abs_mt_position_x(fd, &mut x_range) {
+                log_warn1!("Failed to read touchscreen X range: {:?}", err);
+            }
+            if let Err(err) = ioctl::get_abs_mt_position_y(fd, &mut y_range) {
+                log_warn1!("Failed to read touchscreen Y range: {:?}", err);
+            }
+        }
+
+        Multitouch {
+            current_slot: 0,
+            slots: BTreeMap::new(),
+            x_range: x_range,
+            y_range: y_range,
+        }
+    }
+
+    /// Returns currently selected slot.
+    fn get_current_slot(&mut self) -> &mut TouchSlot {
+        self.slots.entry(self.current_slot).or_insert(TouchSlot::new())
+    }
+
+    /// Converts position in device units to fraction of device size.
+    fn normalize(value: i32, range: &ioctl::AbsInfo) -> f32 {
+        if range.maximum > range.minimum {
+            (value - range.minimum) as f32 / (range.maximum - range.minimum) as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// State of mouse. Relative events are accumulated until `SYN_REPORT` and then emitted together.
+struct Mouse {
+    /// Relative motion.
+    motion: (isize, isize),
+
+    /// Wheel motion in notches.
+    wheel: (isize, isize),
+
+    /// Wheel motion in high-resolution units.
+    wheel_hi_res: (isize, isize),
+
+    /// `true` if device reports high-resolution wheel events. In such case continuous scroll value
+    /// is calculated from them instead of number of notches.
+    hi_res: bool,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Mouse {
+    /// Constructs new `Mouse`.
+    fn new() -> Self {
+        Mouse {
+            motion: (0, 0),
+            wheel: (0, 0),
+            wheel_hi_res: (0, 0),
+            hi_res: false,
+        }
+    }
+
+    /// Returns wheel motion as discrete and continuous values and resets it.
+    fn take_wheel(&mut self) -> Option<(Vector, Slide)> {
+        let wheel = if self.hi_res {
+            if self.wheel_hi_res == (0, 0) {
+                return None;
+            }
+            Slide::new(WHEEL_STEP * self.wheel_hi_res.0 as f32 / HI_RES_NOTCH,
+                       WHEEL_STEP * self.wheel_hi_res.1 as f32 / HI_RES_NOTCH)
+        } else {
+            if self.wheel == (0, 0) {
+                return None;
+            }
+            Slide::new(WHEEL_STEP * self.wheel.0 as f32, WHEEL_STEP * self.wheel.1 as f32)
+        };
+
+        let discrete = Vector::new(self.wheel.0, self.wheel.1);
+        self.wheel = (0, 0);
+        self.wheel_hi_res = (0, 0);
+        Some((discrete, wheel))
+    }
+}
 
 // -------------------------------------------------------------------------------------------------
 
@@ -27,9 +215,10 @@ use input_gateway::InputGateway;
 pub struct Evdev {
     fd: io::RawFd,
     device_kind: DeviceKind,
-    config: InputConfig,
     gateway: InputGateway,
-    pressure: i32,
+    touchpad: Option<Touchpad>,
+    multitouch: Option<Multitouch>,
+    mouse: Option<Mouse>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -51,6 +240,16 @@ impl drivers::InputDriver for Evdev {
 
 // -------------------------------------------------------------------------------------------------
 
+impl Drop for Evdev {
+    fn drop(&mut self) {
+        if let Err(err) = close(self.fd) {
+            log_warn2!("Failed to close input device: {:?}", err);
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 impl EventHandler for Evdev {
     fn get_fd(&self) -> io::RawFd {
         self.fd
@@ -74,12 +273,31 @@ impl Evdev {
            config: InputConfig,
            gateway: InputGateway)
            -> Self {
+        let touchpad = if device_kind == DeviceKind::Touchpad {
+            Some(Touchpad::new(fd, config))
+        } else {
+            None
+        };
+
+        let multitouch = if device_kind == DeviceKind::Touchscreen {
+            Some(Multitouch::new(fd))
+        } else {
+            None
+        };
+
+        let mouse = if device_kind == DeviceKind::Mouse {
+            Some(Mouse::new())
+        } else {
+            None
+        };
+
         Evdev {
             fd: fd,
             device_kind: device_kind,
-            config: config,
             gateway: gateway,
-            pressure: 0,
+            touchpad: touchpad,
+            multitouch: multitouch,
+            mouse: mouse,
         }
     }
 
@@ -93,6 +311,7 @@ impl Evdev {
                     DeviceKind::Keyboard => self.process_keyboard_event(&ev),
                     DeviceKind::Mouse => self.process_mouse_event(&ev),
                     DeviceKind::Touchpad => self.process_touchpad_event(&ev),
+                    DeviceKind::Touchscreen => self.process_touchscreen_event(&ev),
                     DeviceKind::Unknown => panic!("Received event from device of unknown type"),
                 }
             }
@@ -109,27 +328,44 @@ impl Evdev {
 
     /// Helper method for processing mouse events.
     fn process_mouse_event(&mut self, ev: &input_event) {
+        let mouse = if let Some(ref mut mouse) = self.mouse {
+            mouse
+        } else {
+            return;
+        };
+
         if ev.kind == uinput_sys::EV_SYN as _ {
-            // Ignore sync
+            if ev.code == uinput_sys::SYN_REPORT as _ {
+                Self::emit_mouse_frame(ev, mouse, &mut self.gateway);
+            }
         } else if ev.kind == uinput_sys::EV_KEY as _ {
-            if (ev.code == uinput_sys::BTN_LEFT as _) || (ev.code == uinput_sys::BTN_MIDDLE as _) ||
-               (ev.code == uinput_sys::BTN_RIGHT as _) {
+            if MOUSE_BUTTONS.contains(&(ev.code as i32)) {
                 self.gateway.emit_button(ev.code, ev.value);
             } else {
                 log_nyimp!("Unhandled mouse key event (code: {}, value: {})", ev.code, ev.value);
             }
         } else if ev.kind == uinput_sys::EV_REL as _ {
-            if ev.code == uinput_sys::ABS_X as _ {
-                self.gateway.emit_motion(ev.value as isize, 0);
-            } else if ev.code == uinput_sys::ABS_Y as _ {
-                self.gateway.emit_motion(0, ev.value as isize);
+            if ev.code == uinput_sys::REL_X as _ {
+                mouse.motion.0 += ev.value as isize;
+            } else if ev.code == uinput_sys::REL_Y as _ {
+                mouse.motion.1 += ev.value as isize;
             } else if ev.code == uinput_sys::REL_WHEEL as _ {
-                self.gateway.emit_axis(0, ev.value as isize);
+                mouse.wheel.1 += ev.value as isize;
+            } else if ev.code == uinput_sys::REL_HWHEEL as _ {
+                mouse.wheel.0 += ev.value as isize;
+            } else if ev.code == REL_WHEEL_HI_RES {
+                mouse.hi_res = true;
+                mouse.wheel_hi_res.1 += ev.value as isize;
+            } else if ev.code == REL_HWHEEL_HI_RES {
+                mouse.hi_res = true;
+                mouse.wheel_hi_res.0 += ev.value as isize;
             } else {
                 log_nyimp!("Unhandled mouse relative event (code: {}, value: {})",
                            ev.code,
                            ev.value);
             }
+        } else if ev.kind == uinput_sys::EV_MSC as _ {
+            // Scan codes are not needed
         } else if ev.kind == uinput_sys::EV_ABS as _ {
             log_nyimp!("Unhandled mouse absolute event (code: {}, value: {})", ev.code, ev.value);
         } else {
@@ -142,44 +378,98 @@ impl Evdev {
 
     /// Helper method for processing touchpad events.
     fn process_touchpad_event(&mut self, ev: &input_event) {
+        if let Some(ref mut touchpad) = self.touchpad {
+            touchpad.process_event(ev, &mut self.gateway);
+        }
+    }
+
+    /// Helper method for processing touchscreen events.
+    fn process_touchscreen_event(&mut self, ev: &input_event) {
+        let multitouch = if let Some(ref mut multitouch) = self.multitouch {
+            multitouch
+        } else {
+            return;
+        };
+
         if ev.kind == uinput_sys::EV_SYN as _ {
-            // Ignore sync
-        } else if ev.kind == uinput_sys::EV_KEY as _ {
-            if (ev.code == uinput_sys::BTN_LEFT as _) || (ev.code == uinput_sys::BTN_MIDDLE as _) ||
-               (ev.code == uinput_sys::BTN_RIGHT as _) {
-                self.gateway.emit_button(ev.code, ev.value);
-            } else if (ev.code == uinput_sys::BTN_TOOL_FINGER as _) ||
-                      (ev.code == uinput_sys::BTN_TOUCH as _) {
-                self.gateway.emit_position_reset();
-            } else {
-                log_nyimp!("Unhandled touchpad key event (code: {}, value: {})", ev.code, ev.value);
+            if ev.code == uinput_sys::SYN_REPORT as _ {
+                Self::emit_touch_frame(multitouch, &mut self.gateway);
             }
-        } else if ev.kind == uinput_sys::EV_REL as _ {
-            log_nyimp!("Unhandled touchpad relative event (code: {}, value: {})",
-                       ev.code,
-                       ev.value);
+        } else if ev.kind == uinput_sys::EV_KEY as _ {
+            // Touch state is tracked using slots. `BTN_TOUCH` can be ignored.
         } else if ev.kind == uinput_sys::EV_ABS as _ {
-            if ev.code == uinput_sys::ABS_PRESSURE as _ {
-                log_info4!("Touchpad pressure: {:?}", ev.value);
-                self.pressure = ev.value;
+            if ev.code == uinput_sys::ABS_MT_SLOT as _ {
+                multitouch.current_slot = ev.value;
             } else if ev.code == uinput_sys::ABS_MT_TRACKING_ID as _ {
-                self.gateway.emit_position_reset();
-            } else if self.pressure > self.config.touchpad_pressure_threshold {
-                if (ev.code == uinput_sys::ABS_MT_POSITION_X as _) ||
-                   (ev.code == uinput_sys::ABS_X as _) {
-                    self.gateway.emit_position(Some(ev.value as isize), None);
-                } else if (ev.code == uinput_sys::ABS_MT_POSITION_Y as _) ||
-                          (ev.code == uinput_sys::ABS_Y as _) {
-                    self.gateway.emit_position(None, Some(ev.value as isize));
+                let slot = multitouch.get_current_slot();
+                if ev.value >= 0 {
+                    slot.active = true;
+                    slot.change = TouchChange::Down;
+                } else if slot.active {
+                    slot.change = TouchChange::Up;
+                }
+            } else if ev.code == uinput_sys::ABS_MT_POSITION_X as _ {
+                let slot = multitouch.get_current_slot();
+                slot.x = ev.value;
+                if slot.change == TouchChange::None {
+                    slot.change = TouchChange::Motion;
+                }
+            } else if ev.code == uinput_sys::ABS_MT_POSITION_Y as _ {
+                let slot = multitouch.get_current_slot();
+                slot.y = ev.value;
+                if slot.change == TouchChange::None {
+                    slot.change = TouchChange::Motion;
                 }
             }
         } else {
-            log_nyimp!("Unhandled touchpad event (type: {}, code: {}, value: {})",
+            log_nyimp!("Unhandled touchscreen event (type: {}, code: {}, value: {})",
                        ev.kind,
                        ev.code,
                        ev.value);
         }
     }
+
+    /// Emits mouse motion and wheel events accumulated since last frame. Motion from both axes is
+    /// emitted at once so acceleration sees real velocity.
+    fn emit_mouse_frame(ev: &input_event, mouse: &mut Mouse, gateway: &mut InputGateway) {
+        if mouse.motion != (0, 0) {
+            let time = ev.time.tv_sec as u64 * 1000 + ev.time.tv_usec as u64 / 1000;
+            gateway.emit_motion(mouse.motion.0, mouse.motion.1, time);
+            mouse.motion = (0, 0);
+        }
+
+        if let Some((discrete, continuous)) = mouse.take_wheel() {
+            gateway.emit_axis(discrete, continuous);
+        }
+    }
+
+    /// Emits changes of touch points accumulated since last frame.
+    fn emit_touch_frame(multitouch: &mut Multitouch, gateway: &mut InputGateway) {
+        let mut changed = false;
+        for (number, slot) in multitouch.slots.iter_mut() {
+            let x = Multitouch::normalize(slot.x, &multitouch.x_range);
+            let y = Multitouch::normalize(slot.y, &multitouch.y_range);
+            match slot.change {
+                TouchChange::None => continue,
+                TouchChange::Down => gateway.emit_touch_down(*number, x, y),
+                TouchChange::Motion => {
+                    if slot.active {
+                        gateway.emit_touch_motion(*number, x, y);
+                    }
+                }
+                TouchChange::Up => {
+                    slot.active = false;
+                    gateway.emit_touch_up(*number);
+                }
+            }
+            slot.change = TouchChange::None;
+            changed = true;
+        }
+
+        if changed {
+            gateway.emit_touch_frame();
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -5,16 +5,53 @@
 
 // -------------------------------------------------------------------------------------------------
 
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use uinput_sys;
 
-use qualia::{perceptron, Perceptron, InputConfig};
-use qualia::{Axis, Button, Key, OptionalPosition, Slide, Vector};
+use qualia::{perceptron, Perceptron, Direction, InputConfig, KeyboardConfig};
+use qualia::input_manager::GestureBinding;
+use qualia::{Axis, AxisSource, Button, Key, Milliseconds, OptionalPosition, Slide, Touch, Vector};
 use qualia::{modifier, InputManager, KeyCatchResult, KeyCode, KeyValue, KeyState};
 use dharma::Signaler;
 
 // For built-in VT swithing
 use virtual_terminal::VirtualTerminal;
 
+use acceleration::Accelerator;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Time after last key press during which touchpads are disabled, in milliseconds.
+const DISABLE_WHILE_TYPING_TIMEOUT: u64 = 500;
+
+// -------------------------------------------------------------------------------------------------
+
+/// State shared by gateways of all devices.
+pub struct InputState {
+    /// Time of the last key press.
+    pub typing_time: Option<Instant>,
+
+    /// Configuration of the most recently used keyboard.
+    pub keyboard: KeyboardConfig,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl InputState {
+    /// Constructs new `InputState` wrapped for sharing between gateways.
+    pub fn new_ref(keyboard: KeyboardConfig) -> InputStateRef {
+        Arc::new(Mutex::new(InputState {
+            typing_time: None,
+            keyboard: keyboard,
+        }))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub type InputStateRef = Arc<Mutex<InputState>>;
+
 // -------------------------------------------------------------------------------------------------
 
 pub struct InputGateway {
@@ -24,17 +61,26 @@ pub struct InputGateway {
     signaler: Signaler<Perceptron>,
     vt: Option<VirtualTerminal>,
     modifier_keys: Vec<(KeyCode, modifier::ModifierType)>,
+    output: Option<String>,
+    keyboard: KeyboardConfig,
+    state: InputStateRef,
+    accelerator: Accelerator,
 }
 
 // -------------------------------------------------------------------------------------------------
 
 impl InputGateway {
-    /// `InputGateway` constructor.
+    /// `InputGateway` constructor. `config` and `keyboard` are effective configurations for the
+    /// device.
     pub fn new(config: InputConfig,
+               keyboard: KeyboardConfig,
                input_manager: InputManager,
                signaler: Signaler<Perceptron>,
-               vt: Option<VirtualTerminal>)
+               vt: Option<VirtualTerminal>,
+               output: Option<String>,
+               state: InputStateRef)
                -> Self {
+        let accelerator = Accelerator::new(&config.acceleration);
         InputGateway {
             modifiers: modifier::NONE,
             config: config,
@@ -49,6 +95,10 @@ impl InputGateway {
                                 (uinput_sys::KEY_RIGHTALT as KeyCode, modifier::RALT),
                                 (uinput_sys::KEY_LEFTMETA as KeyCode, modifier::LMTA),
                                 (uinput_sys::KEY_RIGHTMETA as KeyCode, modifier::RMTA)],
+            output: output,
+            keyboard: keyboard,
+            state: state,
+            accelerator: accelerator,
         }
     }
 }
@@ -68,6 +118,11 @@ impl InputGateway {
             return;
         }
 
+        // Remember when user was typing and which keyboard was used
+        if value == KeyState::Pressed as KeyValue {
+            self.update_state(code);
+        }
+
         // Catch built-in key bindings
         if self.catch_key(code, value) != KeyCatchResult::Passed {
             return;
@@ -81,10 +136,16 @@ impl InputGateway {
         }
     }
 
-    /// Scales displacements and emits pointer motion event.
-    pub fn emit_motion(&mut self, x: isize, y: isize) {
+    /// Scales and accelerates displacements and emits pointer motion event. `time` is time stamp
+    /// of the event in milliseconds.
+    pub fn emit_motion(&mut self, x: isize, y: isize, time: u64) {
         // Scale event values
-        let vector = Vector::new(x, y).scaled(self.config.mouse_scale as f32);
+        let scale = self.config.mouse_scale;
+        let (x, y) = self.accelerator.accelerate(scale * x as f32, scale * y as f32, time);
+        if (x == 0) && (y == 0) {
+            return;
+        }
+        let vector = Vector::new(x, y);
 
         // Signal event
         self.signaler.emit(perceptron::INPUT_POINTER_MOTION,
@@ -101,23 +162,82 @@ impl InputGateway {
                            Perceptron::InputPointerPosition(pos));
     }
 
-    /// Emits button event.
+    /// Emits button event. Left and right buttons are swapped for left-handed devices.
     pub fn emit_button(&mut self, code: u16, value: i32) {
+        let code = if self.config.left_handed {
+            match code as i32 {
+                uinput_sys::BTN_LEFT => uinput_sys::BTN_RIGHT as u16,
+                uinput_sys::BTN_RIGHT => uinput_sys::BTN_LEFT as u16,
+                _ => code,
+            }
+        } else {
+            code
+        };
+
         let btn = Button::new_now(code, value);
 
         // Signal event
         self.signaler.emit(perceptron::INPUT_POINTER_BUTTON, Perceptron::InputPointerButton(btn));
     }
 
-    /// Emits exist event.
-    pub fn emit_axis(&mut self, horizontal: isize, vertical: isize) {
-        let axis = Axis::new_now(Vector::new(horizontal, vertical),
-                                 Slide::new(10.0 * horizontal as f32, 10.0 * vertical as f32));
+    /// Emits click (press and release) of button generated by tapping touchpad. Taps are not
+    /// affected by left-handed setting.
+    pub fn emit_tap(&mut self, code: u16) {
+        let press = Button::new_now(code, KeyState::Pressed as KeyValue);
+        self.signaler.emit(perceptron::INPUT_POINTER_BUTTON, Perceptron::InputPointerButton(press));
+
+        let release = Button::new_now(code, KeyState::Released as KeyValue);
+        self.signaler.emit(perceptron::INPUT_POINTER_BUTTON,
+                           Perceptron::InputPointerButton(release));
+    }
+
+    /// Emits wheel axis event. `discrete` is number of wheel notches, `continuous` is the distance
+    /// of scroll.
+    pub fn emit_axis(&mut self, discrete: Vector, continuous: Slide) {
+        let axis = if self.config.natural_scrolling {
+            Axis::new_now(Vector::new(-discrete.x, -discrete.y),
+                          Slide::new(-continuous.x, -continuous.y),
+                          AxisSource::Wheel)
+        } else {
+            Axis::new_now(discrete, continuous, AxisSource::Wheel)
+        };
 
         // Signal event
         self.signaler.emit(perceptron::INPUT_POINTER_AXIS, Perceptron::InputPointerAxis(axis));
     }
 
+    /// Emits continuous axis event for finger scrolling. Values are given as finger displacement.
+    pub fn emit_scroll(&mut self, x: f32, y: f32) {
+        // Scale event values. Vertical value is negated to follow mouse wheel convention
+        let scale = if self.config.natural_scrolling {
+            -self.config.touchpad_scale
+        } else {
+            self.config.touchpad_scale
+        };
+        let axis = Axis::new_now(Vector::default(),
+                                 Slide::new(scale * x, -scale * y),
+                                 AxisSource::Finger);
+
+        // Signal event
+        self.signaler.emit(perceptron::INPUT_POINTER_AXIS, Perceptron::InputPointerAxis(axis));
+    }
+
+    /// Emits axis event informing that fingers were lifted and scrolling stopped.
+    pub fn emit_scroll_stop(&mut self) {
+        let axis = Axis::new_now(Vector::default(), Slide::new(0.0, 0.0), AxisSource::Finger);
+
+        // Signal event
+        self.signaler.emit(perceptron::INPUT_POINTER_AXIS, Perceptron::InputPointerAxis(axis));
+    }
+
+    /// Tries to execute binding assigned to swipe gesture.
+    pub fn emit_swipe(&mut self, fingers: u32, direction: Direction) {
+        let binding = GestureBinding::new(fingers, direction);
+        if self.input_manager.catch_gesture(binding) == KeyCatchResult::Passed {
+            log_info3!("Unbound swipe gesture (fingers: {}, direction: {:?})", fingers, direction);
+        }
+    }
+
     /// Emits position reset event.
     pub fn emit_position_reset(&mut self) {
         // Signal event
@@ -125,15 +245,67 @@ impl InputGateway {
                            Perceptron::InputPointerPositionReset);
     }
 
+    /// Emits touch down event. Position is given as fraction of the device size.
+    pub fn emit_touch_down(&mut self, slot: i32, x: f32, y: f32) {
+        let touch = Touch::new_now(slot, Slide::new(x, y), self.output.clone());
+        self.signaler.emit(perceptron::INPUT_TOUCH_DOWN, Perceptron::InputTouchDown(touch));
+    }
+
+    /// Emits touch motion event. Position is given as fraction of the device size.
+    pub fn emit_touch_motion(&mut self, slot: i32, x: f32, y: f32) {
+        let touch = Touch::new_now(slot, Slide::new(x, y), self.output.clone());
+        self.signaler.emit(perceptron::INPUT_TOUCH_MOTION, Perceptron::InputTouchMotion(touch));
+    }
+
+    /// Emits touch up event.
+    pub fn emit_touch_up(&mut self, slot: i32) {
+        self.signaler.emit(perceptron::INPUT_TOUCH_UP,
+                           Perceptron::InputTouchUp(slot, Milliseconds::now()));
+    }
+
+    /// Emits touch frame event closing set of touch events sent together.
+    pub fn emit_touch_frame(&mut self) {
+        self.signaler.emit(perceptron::INPUT_TOUCH_FRAME, Perceptron::InputTouchFrame);
+    }
+
     /// Emits system activity event.
     pub fn emit_system_activity_event(&mut self) {
         self.signaler.emit(perceptron::NOTIFY, Perceptron::Notify);
     }
+
+    /// Checks if any key was pressed recently.
+    pub fn is_typing(&self) -> bool {
+        if let Some(time) = self.state.lock().unwrap().typing_time {
+            time.elapsed() < Duration::from_millis(DISABLE_WHILE_TYPING_TIMEOUT)
+        } else {
+            false
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
 
 impl InputGateway {
+    /// Updates state shared between devices after key press. If this keyboard has different
+    /// layout than the one used previously, informs about the need of changing key map.
+    fn update_state(&mut self, code: KeyCode) {
+        let mut state = self.state.lock().unwrap();
+        if !self.is_modifier(code) {
+            state.typing_time = Some(Instant::now());
+        }
+
+        if state.keyboard != self.keyboard {
+            state.keyboard = self.keyboard.clone();
+            self.signaler.emit(perceptron::KEYMAP_CHANGED,
+                               Perceptron::KeymapChanged(self.keyboard.clone()));
+        }
+    }
+
+    /// Checks if given key is a modifier.
+    fn is_modifier(&self, code: KeyCode) -> bool {
+        self.modifier_keys.iter().any(|&(modifier_code, _)| modifier_code == code)
+    }
+
     /// Helper method for updating modifiers.
     fn update_modifiers(&mut self, code: KeyCode, value: KeyValue) -> KeyCatchResult {
         let mut result = KeyCatchResult::Passed;
@@ -22,7 +22,9 @@ extern crate coordination;
 
 mod device_access;
 mod input_gateway;
+mod acceleration;
 mod evdev;
+mod touchpad;
 mod drivers;
 mod pageflip;
 mod output_collector;
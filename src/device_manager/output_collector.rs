@@ -2,12 +2,11 @@
 // the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
 
 //! Output Collector.
-//!
-//! TODO: Implement adding and removing outputs.
 
 // -------------------------------------------------------------------------------------------------
 
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::os::unix::io;
 use nix::fcntl;
 use nix::sys::stat;
@@ -20,11 +19,23 @@ use pageflip::PageFlipEventHandler;
 
 // -------------------------------------------------------------------------------------------------
 
+/// State of scanned DRM device.
+struct DrmDevice {
+    /// File descriptor of the device.
+    fd: io::RawFd,
+
+    /// Outputs currently connected to the device indexed by connector ID.
+    outputs: HashMap<u32, DrmBundle>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Output Collector manages output devices. When output if found or lost Collector notifies the
 /// rest of application about this event.
 pub struct OutputCollector {
     dispatcher: DispatcherController,
     signaler: Signaler<Perceptron>,
+    devices: HashMap<PathBuf, DrmDevice>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -35,6 +46,7 @@ impl OutputCollector {
         OutputCollector {
             dispatcher: dispatcher,
             signaler: signaler,
+            devices: HashMap::new(),
         }
     }
 
@@ -50,42 +62,105 @@ impl OutputCollector {
             }
         };
 
-        // Scan for connected outputs
-        if let Some(resources) = drm_mode::get_resources(fd) {
-            for id in resources.get_connectors() {
-                if let Some(connector) = drm_mode::get_connector(fd, id) {
-                    self.process_connector(fd, &connector);
-                } else {
-                    log_warn1!("Failed to get connector info!");
-                }
-            }
-        } else {
-            log_warn1!("No resources for device {:?}", path);
-        }
-
         // Register for pageflip events
         let pageflip_event_handler = Box::new(PageFlipEventHandler::new(fd, self.signaler.clone()));
         self.dispatcher.add_source(pageflip_event_handler, event_kind::READ);
 
+        // Scan for connected outputs
+        let mut device = DrmDevice {
+            fd: fd,
+            outputs: HashMap::new(),
+        };
+        self.process_connectors(&mut device);
+        self.devices.insert(path.to_owned(), device);
+
         Ok(())
     }
 
-    /// Helper method for `scan_devices`.
-    fn process_connector(&mut self, fd: io::RawFd, connector: &drm_mode::Connector) {
-        log_info1!("{:?}", connector);
-
-        if connector.get_connection() == drm_mode::Connection::Connected {
-            if let Some(encoder) = drm_mode::get_encoder(fd, connector.get_encoder_id()) {
-                let bundle = DrmBundle {
-                    fd: fd,
-                    connector_id: connector.get_connector_id(),
-                    crtc_id: encoder.get_crtc_id(),
-                };
-                self.signaler.emit(perceptron::OUTPUT_FOUND, Perceptron::OutputFound(bundle));
+    /// Rescans already known DRM device after connectors changed. Emits `OutputFound` signal for
+    /// every newly connected output and `OutputLost` for every disconnected one. Unknown devices
+    /// are scanned from scratch.
+    pub fn rescan_device(&mut self, path: &Path) -> Result<(), Illusion> {
+        if let Some(mut device) = self.devices.remove(path) {
+            log_info1!("OutputCollector: rescan device '{:?}'", path);
+            self.process_connectors(&mut device);
+            self.devices.insert(path.to_owned(), device);
+            Ok(())
+        } else {
+            self.scan_device(path)
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Helper methods.
+impl OutputCollector {
+    /// Compares state of connectors with known outputs and notifies about changes.
+    fn process_connectors(&mut self, device: &mut DrmDevice) {
+        let resources = if let Some(resources) = drm_mode::get_resources(device.fd) {
+            resources
+        } else {
+            log_warn1!("No resources for device with descriptor {}", device.fd);
+            return;
+        };
+
+        let mut connected = Vec::new();
+        for id in resources.get_connectors() {
+            if let Some(connector) = drm_mode::get_connector(device.fd, id) {
+                log_info1!("{:?}", connector);
+                if connector.get_connection() == drm_mode::Connection::Connected {
+                    connected.push(id);
+                    if !device.outputs.contains_key(&id) {
+                        if let Some(bundle) = Self::create_bundle(device, &resources, &connector) {
+                            device.outputs.insert(id, bundle);
+                            self.signaler.emit(perceptron::OUTPUT_FOUND,
+                                               Perceptron::OutputFound(bundle));
+                        }
+                    }
+                }
             } else {
-                log_warn1!("No encoder for connector '{:?}'", connector.get_connector_id());
+                log_warn1!("Failed to get connector info!");
             }
         }
+
+        let lost: Vec<u32> =
+            device.outputs.keys().filter(|&&id| !connected.contains(&id)).cloned().collect();
+        for id in lost {
+            if let Some(bundle) = device.outputs.remove(&id) {
+                log_info1!("OutputCollector: lost output on connector {}", id);
+                self.signaler.emit(perceptron::OUTPUT_LOST, Perceptron::OutputLost(bundle));
+            }
+        }
+    }
+
+    /// Chooses CRTC for newly connected output. Connectors active at startup already have encoder
+    /// with CRTC assigned. Outputs connected later get the first CRTC not used by other outputs.
+    fn create_bundle(device: &DrmDevice,
+                     resources: &drm_mode::Resources,
+                     connector: &drm_mode::Connector)
+                     -> Option<DrmBundle> {
+        let is_free = |crtc_id: u32| {
+            crtc_id != 0 && device.outputs.values().all(|bundle| bundle.crtc_id != crtc_id)
+        };
+
+        let encoder_crtc_id = drm_mode::get_encoder(device.fd, connector.get_encoder_id())
+            .map(|encoder| encoder.get_crtc_id())
+            .and_then(|crtc_id| if is_free(crtc_id) { Some(crtc_id) } else { None });
+        let crtc_id = encoder_crtc_id.or_else(|| {
+            resources.get_crtcs().into_iter().find(|crtc_id| is_free(*crtc_id))
+        });
+
+        if let Some(crtc_id) = crtc_id {
+            Some(DrmBundle {
+                fd: device.fd,
+                connector_id: connector.get_connector_id(),
+                crtc_id: crtc_id,
+            })
+        } else {
+            log_warn1!("No free CRTC for connector '{:?}'", connector.get_connector_id());
+            None
+        }
     }
 }
 
@@ -9,7 +9,7 @@ use std::os::unix::io;
 use libdrm::drm;
 
 use dharma::{EventHandler, EventKind, Signaler, event_kind};
-use qualia::{perceptron, Perceptron};
+use qualia::{perceptron, Perceptron, Presentation};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -37,7 +37,9 @@ impl drm::EventContext for PageFlipContext {
 
     #[allow(unused_variables)]
     fn page_flip_handler(&mut self, fd: io::RawFd, sequence: u32, sec: u32, usec: u32, data: i32) {
-        self.signaler.emit(perceptron::PAGE_FLIP, Perceptron::PageFlip(data));
+        // `data` is ID of the output. Time stamps are given in `CLOCK_MONOTONIC` domain.
+        let presentation = Presentation::new(data, sec as u64, usec * 1000, sequence as u64);
+        self.signaler.emit(perceptron::PAGE_FLIP, Perceptron::PageFlip(presentation));
     }
 }
 
@@ -0,0 +1,453 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! State machine turning touchpad finger events into pointer motion, clicks, scrolling and swipe
+//! gestures.
+//!
+//! Fingers are tracked using multitouch slots. Events are accumulated until `SYN_REPORT` and then
+//! interpreted depending on number of fingers touching the touchpad:
+//!  - one finger moves the pointer,
+//!  - two fingers scroll (or one finger placed at the right edge if edge scrolling is chosen),
+//!  - three or more fingers perform swipe which is reported when one of the fingers is lifted.
+//! Short touch without movement is a tap, which clicks left, right or middle button depending on
+//! the number of fingers.
+
+// -------------------------------------------------------------------------------------------------
+
+use std::cmp::max;
+use std::f32;
+use std::collections::BTreeMap;
+use std::os::unix::io;
+use uinput_sys::{self, input_event};
+
+use qualia::{Direction, InputConfig};
+
+use evdev::ioctl;
+use input_gateway::InputGateway;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Maximal duration of tap in milliseconds.
+const TAP_TIMEOUT: u64 = 180;
+
+/// Maximal distance finger may move during tap given as fraction of touchpad width.
+const TAP_MOVE_THRESHOLD: f32 = 0.03;
+
+/// Minimal distance of swipe given as fraction of touchpad width.
+const SWIPE_THRESHOLD: f32 = 0.15;
+
+/// Width of the area at the right edge used for edge scrolling given as fraction of touchpad
+/// width.
+const EDGE_SCROLL_WIDTH: f32 = 0.08;
+
+/// Value of `ABS_MT_TOOL_TYPE` reported for palms.
+const MT_TOOL_PALM: i32 = 2;
+
+// -------------------------------------------------------------------------------------------------
+
+/// State of finger touching the touchpad.
+#[derive(Clone, Copy, Debug)]
+struct Finger {
+    x: i32,
+    y: i32,
+    start_x: i32,
+    start_y: i32,
+
+    /// `true` if the finger was just placed and its start position is not known yet.
+    fresh: bool,
+
+    /// `true` if the touch was recognized as palm. Palms are ignored until lifted.
+    palm: bool,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Finger {
+    /// Constructs new `Finger`.
+    fn new() -> Self {
+        Finger {
+            x: 0,
+            y: 0,
+            start_x: 0,
+            start_y: 0,
+            fresh: true,
+            palm: false,
+        }
+    }
+
+    /// Returns distance from position where the finger was placed.
+    fn get_distance(&self) -> f32 {
+        let dx = (self.x - self.start_x) as f32;
+        let dy = (self.y - self.start_y) as f32;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Method of scrolling.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScrollMethod {
+    None,
+    TwoFinger,
+    Edge,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Interpretation of motion of fingers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Gesture {
+    None,
+    Pointer,
+    Scroll { scrolled: bool },
+    EdgeScroll { scrolled: bool },
+    Swipe { fingers: u32, dx: f32, dy: f32 },
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// State of possible tap.
+#[derive(Clone, Copy, Debug)]
+struct Tap {
+    /// Time of the first touch in milliseconds.
+    start: u64,
+
+    /// Maximal number of fingers touching the touchpad during the tap.
+    fingers: usize,
+
+    /// `true` if any finger moved too far for the touch to be a tap.
+    moved: bool,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// State of touchpad.
+pub struct Touchpad {
+    config: InputConfig,
+    scroll_method: ScrollMethod,
+
+    /// `true` if device reports multitouch slots. Otherwise single finger is emulated from
+    /// `ABS_X`, `ABS_Y` and `BTN_TOUCH`.
+    multitouch: bool,
+
+    current_slot: i32,
+    fingers: BTreeMap<i32, Finger>,
+    pressure: i32,
+    button_pressed: bool,
+
+    /// Width of touchpad in device units.
+    width: f32,
+
+    /// Horizontal position from which edge scrolling area starts.
+    scroll_edge: f32,
+
+    /// Number of fingers in previous frame.
+    last_count: usize,
+
+    /// Center of fingers in previous frame.
+    last_center: (f32, f32),
+
+    gesture: Gesture,
+    tap: Option<Tap>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Touchpad {
+    /// Constructs new `Touchpad` reading range of horizontal axis from the device.
+    pub fn new(fd: io::RawFd, config: InputConfig) -> Self {
+        let mut x_range = ioctl::AbsInfo::default();
+        if let Err(err) = unsafe { ioctl::get_abs_mt_position_x(fd, &mut x_range) } {
+            log_warn1!("Failed to read touchpad X range: {:?}", err);
+        }
+
+        // If range is not known edge scrolling area is placed outside of the touchpad
+        let (width, scroll_edge) = if x_range.maximum > x_range.minimum {
+            let width = (x_range.maximum - x_range.minimum) as f32;
+            (width, x_range.maximum as f32 - EDGE_SCROLL_WIDTH * width)
+        } else {
+            (1000.0, f32::INFINITY)
+        };
+
+        let scroll_method = match config.scroll_method.as_ref() {
+            "none" => ScrollMethod::None,
+            "two_finger" => ScrollMethod::TwoFinger,
+            "edge" => ScrollMethod::Edge,
+            _ => {
+                log_warn1!("Unknown scroll method '{}', using 'two_finger'", config.scroll_method);
+                ScrollMethod::TwoFinger
+            }
+        };
+
+        Touchpad {
+            config: config,
+            scroll_method: scroll_method,
+            multitouch: false,
+            current_slot: 0,
+            fingers: BTreeMap::new(),
+            pressure: 0,
+            button_pressed: false,
+            width: width,
+            scroll_edge: scroll_edge,
+            last_count: 0,
+            last_center: (0.0, 0.0),
+            gesture: Gesture::None,
+            tap: None,
+        }
+    }
+
+    /// Processes event from the device.
+    pub fn process_event(&mut self, ev: &input_event, gateway: &mut InputGateway) {
+        if ev.kind == uinput_sys::EV_SYN as _ {
+            if ev.code == uinput_sys::SYN_REPORT as _ {
+                let time = ev.time.tv_sec as u64 * 1000 + ev.time.tv_usec as u64 / 1000;
+                self.process_frame(time, gateway);
+            }
+        } else if ev.kind == uinput_sys::EV_KEY as _ {
+            self.process_key(ev, gateway);
+        } else if ev.kind == uinput_sys::EV_ABS as _ {
+            self.process_abs(ev);
+        } else {
+            log_nyimp!("Unhandled touchpad event (type: {}, code: {}, value: {})",
+                       ev.kind,
+                       ev.code,
+                       ev.value);
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Event handlers.
+impl Touchpad {
+    /// Handles key events. Physical buttons are passed further. Touch state is emulated from
+    /// `BTN_TOUCH` only for devices without multitouch.
+    fn process_key(&mut self, ev: &input_event, gateway: &mut InputGateway) {
+        if (ev.code == uinput_sys::BTN_LEFT as _) || (ev.code == uinput_sys::BTN_MIDDLE as _) ||
+           (ev.code == uinput_sys::BTN_RIGHT as _) {
+            self.button_pressed = ev.value != 0;
+            gateway.emit_button(ev.code, ev.value);
+        } else if ev.code == uinput_sys::BTN_TOUCH as _ {
+            if !self.multitouch {
+                if ev.value != 0 {
+                    self.fingers.insert(0, Finger::new());
+                } else {
+                    self.fingers.remove(&0);
+                }
+            }
+        } else if (ev.code >= uinput_sys::BTN_TOOL_FINGER as _) &&
+                  (ev.code <= uinput_sys::BTN_TOOL_QUADTAP as _) {
+            // Number of fingers is calculated from slots.
+        } else {
+            log_nyimp!("Unhandled touchpad key event (code: {}, value: {})", ev.code, ev.value);
+        }
+    }
+
+    /// Handles absolute axis events.
+    fn process_abs(&mut self, ev: &input_event) {
+        if ev.code == uinput_sys::ABS_PRESSURE as _ {
+            log_info4!("Touchpad pressure: {:?}", ev.value);
+            self.pressure = ev.value;
+        } else if ev.code == uinput_sys::ABS_MT_SLOT as _ {
+            self.multitouch = true;
+            self.current_slot = ev.value;
+        } else if ev.code == uinput_sys::ABS_MT_TRACKING_ID as _ {
+            self.multitouch = true;
+            if ev.value >= 0 {
+                self.fingers.insert(self.current_slot, Finger::new());
+            } else {
+                self.fingers.remove(&self.current_slot);
+            }
+        } else if ev.code == uinput_sys::ABS_MT_POSITION_X as _ {
+            if let Some(finger) = self.fingers.get_mut(&self.current_slot) {
+                finger.x = ev.value;
+            }
+        } else if ev.code == uinput_sys::ABS_MT_POSITION_Y as _ {
+            if let Some(finger) = self.fingers.get_mut(&self.current_slot) {
+                finger.y = ev.value;
+            }
+        } else if ev.code == uinput_sys::ABS_MT_PRESSURE as _ {
+            let threshold = self.config.touchpad_palm_pressure_threshold;
+            if let Some(finger) = self.fingers.get_mut(&self.current_slot) {
+                finger.palm |= ev.value > threshold;
+            }
+        } else if ev.code == uinput_sys::ABS_MT_TOOL_TYPE as _ {
+            if let Some(finger) = self.fingers.get_mut(&self.current_slot) {
+                finger.palm |= ev.value == MT_TOOL_PALM;
+            }
+        } else if !self.multitouch {
+            if let Some(finger) = self.fingers.get_mut(&0) {
+                if ev.code == uinput_sys::ABS_X as _ {
+                    finger.x = ev.value;
+                } else if ev.code == uinput_sys::ABS_Y as _ {
+                    finger.y = ev.value;
+                }
+            }
+        }
+    }
+
+    /// Interprets state of fingers after all changes in the frame were received.
+    fn process_frame(&mut self, time: u64, gateway: &mut InputGateway) {
+        for finger in self.fingers.values_mut() {
+            if finger.fresh {
+                finger.start_x = finger.x;
+                finger.start_y = finger.y;
+                finger.fresh = false;
+            }
+        }
+
+        let count = self.fingers.values().filter(|finger| !finger.palm).count();
+        let center = self.calculate_center();
+        let typing = self.config.touchpad_disable_while_typing && gateway.is_typing();
+
+        self.update_tap(count, time, typing, gateway);
+
+        if count != self.last_count {
+            // Number of fingers changed - finish current gesture and start new one
+            self.finish_gesture(gateway);
+            self.gesture = self.choose_gesture(count, center);
+            gateway.emit_position_reset();
+        } else {
+            let dx = center.0 - self.last_center.0;
+            let dy = center.1 - self.last_center.1;
+            let gesture = self.gesture;
+            match gesture {
+                Gesture::None => {}
+                Gesture::Pointer => {
+                    if !typing && (self.pressure > self.config.touchpad_pressure_threshold) {
+                        gateway.emit_position(Some(center.0 as isize), Some(center.1 as isize));
+                    }
+                }
+                Gesture::Scroll { .. } => {
+                    if !typing && ((dx != 0.0) || (dy != 0.0)) {
+                        gateway.emit_scroll(dx, dy);
+                        self.gesture = Gesture::Scroll { scrolled: true };
+                    }
+                }
+                Gesture::EdgeScroll { .. } => {
+                    if !typing && (dy != 0.0) {
+                        gateway.emit_scroll(0.0, dy);
+                        self.gesture = Gesture::EdgeScroll { scrolled: true };
+                    }
+                }
+                Gesture::Swipe { fingers, dx: swipe_dx, dy: swipe_dy } => {
+                    self.gesture = Gesture::Swipe {
+                        fingers: fingers,
+                        dx: swipe_dx + dx,
+                        dy: swipe_dy + dy,
+                    };
+                }
+            }
+        }
+
+        self.last_count = count;
+        self.last_center = center;
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Helper methods.
+impl Touchpad {
+    /// Chooses gesture basing on number of fingers and their position.
+    fn choose_gesture(&self, count: usize, center: (f32, f32)) -> Gesture {
+        match (count, self.scroll_method) {
+            (0, _) => Gesture::None,
+            (1, ScrollMethod::Edge) if center.0 >= self.scroll_edge => {
+                Gesture::EdgeScroll { scrolled: false }
+            }
+            (1, _) => Gesture::Pointer,
+            (2, ScrollMethod::TwoFinger) => Gesture::Scroll { scrolled: false },
+            (2, _) => Gesture::None,
+            _ => {
+                Gesture::Swipe {
+                    fingers: count as u32,
+                    dx: 0.0,
+                    dy: 0.0,
+                }
+            }
+        }
+    }
+
+    /// Calculates center of fingers which are not palms.
+    fn calculate_center(&self) -> (f32, f32) {
+        let mut count = 0;
+        let mut center = (0.0, 0.0);
+        for finger in self.fingers.values().filter(|finger| !finger.palm) {
+            center.0 += finger.x as f32;
+            center.1 += finger.y as f32;
+            count += 1;
+        }
+
+        if count > 0 {
+            (center.0 / count as f32, center.1 / count as f32)
+        } else {
+            center
+        }
+    }
+
+    /// Checks if any finger moved too far for the touch to be a tap.
+    fn has_moved(&self) -> bool {
+        let threshold = TAP_MOVE_THRESHOLD * self.width;
+        self.fingers.values().any(|finger| !finger.palm && finger.get_distance() > threshold)
+    }
+
+    /// Tracks touches and clicks when all fingers were lifted quickly without moving.
+    fn update_tap(&mut self, count: usize, time: u64, typing: bool, gateway: &mut InputGateway) {
+        if count > 0 {
+            let moved = self.has_moved();
+            if self.tap.is_none() {
+                if self.last_count == 0 {
+                    self.tap = Some(Tap {
+                        start: time,
+                        fingers: count,
+                        moved: moved,
+                    });
+                }
+            } else if let Some(ref mut tap) = self.tap {
+                tap.fingers = max(tap.fingers, count);
+                tap.moved |= moved;
+            }
+        } else if let Some(tap) = self.tap.take() {
+            if self.config.touchpad_tap_to_click && !typing && !tap.moved && !self.button_pressed &&
+               (time - tap.start <= TAP_TIMEOUT) {
+                let code = match tap.fingers {
+                    1 => Some(uinput_sys::BTN_LEFT),
+                    2 => Some(uinput_sys::BTN_RIGHT),
+                    3 => Some(uinput_sys::BTN_MIDDLE),
+                    _ => None,
+                };
+
+                if let Some(code) = code {
+                    gateway.emit_tap(code as u16);
+                }
+            }
+        }
+    }
+
+    /// Finishes current gesture: stops scrolling or reports swipe if it was long enough.
+    fn finish_gesture(&mut self, gateway: &mut InputGateway) {
+        match self.gesture {
+            Gesture::Scroll { scrolled: true } |
+            Gesture::EdgeScroll { scrolled: true } => gateway.emit_scroll_stop(),
+            Gesture::Swipe { fingers, dx, dy } => {
+                let threshold = SWIPE_THRESHOLD * self.width;
+                let direction = if (dx.abs() > dy.abs()) && (dx.abs() > threshold) {
+                    if dx > 0.0 { Direction::East } else { Direction::West }
+                } else if (dy.abs() >= dx.abs()) && (dy.abs() > threshold) {
+                    if dy > 0.0 { Direction::South } else { Direction::North }
+                } else {
+                    Direction::None
+                };
+
+                if direction != Direction::None {
+                    gateway.emit_swipe(fingers, direction);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -7,36 +7,39 @@
 
 use libudev;
 use nix;
-use std::os::unix::io::AsRawFd;
 use std::path::Path;
 
 use qualia;
 
-use device_monitor::DeviceMonitor;
-
 // -------------------------------------------------------------------------------------------------
 
 const INPUT_MOUSE: &'static str = "ID_INPUT_MOUSE";
 const INPUT_TOUCHPAD: &'static str = "ID_INPUT_TOUCHPAD";
 const INPUT_KEYBOARD: &'static str = "ID_INPUT_KEYBOARD";
+const INPUT_TOUCHSCREEN: &'static str = "ID_INPUT_TOUCHSCREEN";
+
+/// Property of parent input device containing its name.
+const NAME: &'static str = "NAME";
+
+/// Property of parent input device containing its bus type, vendor, product and version.
+const PRODUCT: &'static str = "PRODUCT";
+
+/// Property assigning input device to output with given name.
+const OUTPUT_NAME: &'static str = "WL_OUTPUT";
 
 // -------------------------------------------------------------------------------------------------
 
 /// Wrapper for `libudev`'s context.
-pub struct Udev<'a> {
+pub struct Udev {
     context: libudev::Context,
-    monitor_socket: Option<libudev::MonitorSocket<'a>>,
 }
 
 // -------------------------------------------------------------------------------------------------
 
-impl<'a> Udev<'a> {
+impl Udev {
     /// `Udev` constructor.
     pub fn new() -> Self {
-        Udev {
-            context: libudev::Context::new().expect("Failed to create udev context"),
-            monitor_socket: None,
-        }
+        Udev { context: libudev::Context::new().expect("Failed to create udev context") }
     }
 
     /// Iterate over connected input event devices and pass results to given handler.
@@ -48,16 +51,9 @@ impl<'a> Udev<'a> {
             libudev::Enumerator::new(&self.context).expect("Failed to create device enumerator");
         enumerator.match_subsystem("input").expect("Failed to apply filter for device enumerator");
         for device in enumerator.scan_devices().expect("Failed to scan devices") {
-            if let Some(devnode) = device.devnode() {
-                if let Ok(sysname) = device.sysname().to_os_string().into_string() {
-                    if is_event_device(devnode, &sysname) {
-                        let device_kind = determine_device_kind(&device);
-                        if device_kind != qualia::DeviceKind::Unknown {
-                            log_info1!("Found {:?}: {:?}", device_kind, devnode);
-                            f(devnode, device_kind, &device);
-                        }
-                    }
-                }
+            if let Some((devnode, device_kind)) = check_event_device(&device) {
+                log_info1!("Found {:?}: {:?}", device_kind, devnode);
+                f(devnode, device_kind, &device);
             }
         }
     }
@@ -80,24 +76,6 @@ impl<'a> Udev<'a> {
         }
     }
 
-    /// Start device monitoring and return instance of `Dispatcher` `EventHandler` for processing
-    /// device events.
-    ///
-    /// Returned `DeviceMonitor` contains file descriptor from `udev` monitor. `DeviceMonitor` will
-    /// handle situations when the file descriptor becomes invalid.
-    pub fn start_device_monitor(&mut self) -> Result<DeviceMonitor, qualia::Illusion> {
-        if self.monitor_socket.is_none() {
-            let mut monitor = libudev::Monitor::new(&self.context)?;
-            ensure!(monitor.match_subsystem("input"));
-            ensure!(monitor.match_subsystem("drm"));
-            // self.monitor_socket = Some(try!(monitor.listen()));
-        }
-
-        match self.monitor_socket {
-            Some(ref monitor_socket) => Ok(DeviceMonitor::new(monitor_socket.as_raw_fd())),
-            None => Err(qualia::Illusion::General("Failed to create device monitor".to_owned())),
-        }
-    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -112,6 +90,23 @@ fn is_event_device(devnode: &Path, sysname: &String) -> bool {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Checks if given device is usable input event device. Returns its device node and kind.
+fn check_event_device<'a>(device: &'a libudev::Device) -> Option<(&'a Path, qualia::DeviceKind)> {
+    if let Some(devnode) = device.devnode() {
+        if let Ok(sysname) = device.sysname().to_os_string().into_string() {
+            if is_event_device(devnode, &sysname) {
+                let device_kind = determine_device_kind(device);
+                if device_kind != qualia::DeviceKind::Unknown {
+                    return Some((devnode, device_kind));
+                }
+            }
+        }
+    }
+    None
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Checks if given device exists is output device.
 fn is_output_device(devnode: &Path, sysname: &String) -> bool {
     match nix::sys::stat::stat(devnode) {
@@ -123,7 +118,13 @@ fn is_output_device(devnode: &Path, sysname: &String) -> bool {
 // -------------------------------------------------------------------------------------------------
 
 /// Reads devices properties and determines device kind basing on them.
+///
+/// Touchscreens may also be marked as mice so they are checked first.
 pub fn determine_device_kind(device: &libudev::Device) -> qualia::enums::DeviceKind {
+    if device.property_value(INPUT_TOUCHSCREEN).is_some() {
+        return qualia::DeviceKind::Touchscreen;
+    }
+
     for property in device.properties() {
         if property.name() == INPUT_MOUSE {
             return qualia::DeviceKind::Mouse;
@@ -137,3 +138,58 @@ pub fn determine_device_kind(device: &libudev::Device) -> qualia::enums::DeviceK
 }
 
 // -------------------------------------------------------------------------------------------------
+
+/// Reads name of output the device was assigned to in `udev` rules.
+pub fn get_output_name(device: &libudev::Device) -> Option<String> {
+    device.property_value(OUTPUT_NAME).and_then(|value| value.to_os_string().into_string().ok())
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Collects data needed to initialize input device. Returns `None` if given device is not usable
+/// input event device.
+pub fn get_input_device_bundle(device: &libudev::Device) -> Option<qualia::InputDeviceBundle> {
+    check_event_device(device).map(|(devnode, device_kind)| {
+        qualia::InputDeviceBundle {
+            syspath: device.syspath().to_owned(),
+            devnode: devnode.to_owned(),
+            info: get_device_info(device, device_kind),
+            output_name: get_output_name(device),
+        }
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Reads name and vendor and product IDs of input device. Event devices do not have this
+/// information - it is read from their parent input device.
+pub fn get_device_info(device: &libudev::Device,
+                       kind: qualia::DeviceKind)
+                       -> qualia::InputDeviceInfo {
+    let mut info = qualia::InputDeviceInfo {
+        name: String::new(),
+        vendor: 0,
+        product: 0,
+        kind: kind,
+    };
+
+    if let Some(parent) = device.parent() {
+        if let Some(name) = parent.property_value(NAME).and_then(|value| value.to_str()) {
+            info.name = name.trim_matches('"').to_owned();
+        }
+
+        // Product is in format "bus/vendor/product/version" with hexadecimal numbers
+        if let Some(product) = parent.property_value(PRODUCT).and_then(|value| value.to_str()) {
+            let ids: Vec<u32> = product.split('/')
+                .map(|id| u32::from_str_radix(id, 16).unwrap_or(0))
+                .collect();
+            if ids.len() >= 3 {
+                info.vendor = ids[1];
+                info.product = ids[2];
+            }
+        }
+    }
+    info
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -7,9 +7,10 @@
 // -------------------------------------------------------------------------------------------------
 
 use std;
+use std::collections::HashMap;
 
 use timber;
-use qualia::{Action, Area, Command, Direction, Vector};
+use qualia::{Action, Area, Command, Direction, Position, Vector};
 use qualia::{SurfaceId, CompositorConfig, ExhibitorCoordinationTrait};
 
 use surface_history::SurfaceHistory;
@@ -65,6 +66,16 @@ impl std::fmt::Display for CommandResult {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Placement of transient surface and its parent remembered before changing layout.
+struct TransientPlacement {
+    sid: SurfaceId,
+    parent_sid: SurfaceId,
+    position: Position,
+    parent_center: Position,
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Compositor main structure.
 pub struct Compositor<C> where C: ExhibitorCoordinationTrait {
     history: SurfaceHistory,
@@ -73,6 +84,12 @@ pub struct Compositor<C> where C: ExhibitorCoordinationTrait {
     selection: Frame,
     strategist: Strategist,
     config: CompositorConfig,
+
+    /// Display frame keeping workspaces after the last display was destroyed.
+    detached_display: Option<Frame>,
+
+    /// Identities of outputs of displays indexed by display titles (output connector names).
+    identities: HashMap<String, Vec<String>>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -89,30 +106,73 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
             selection: root,
             strategist: strategist,
             config: config,
+            detached_display: None,
+            identities: HashMap::new(),
         }
     }
 
-    /// Creates new display with default workspace.
-    pub fn create_display(&mut self, area: Area, name: String) -> Frame {
-        let mut display = Frame::new_display(area, name);
-        let mut workspace = self.create_next_workspace()
-            .expect("Could not create workspace. This probably indicates compositor logic error");
+    /// Creates new display for output with given connector name and identities. If workspaces were
+    /// left after destroying the last display they are moved to the new one. Otherwise workspaces
+    /// pinned to the output are moved from other displays or default workspace is created.
+    pub fn create_display(&mut self,
+                          area: Area,
+                          name: String,
+                          identities: Vec<String>)
+                          -> Frame {
+        let placements = self.remember_transient_placements();
+        let mut display = Frame::new_display(area, name.clone());
+        self.identities.insert(name, identities);
         self.root.append(&mut display);
-        workspace.settle(&mut display, None, &mut self.coordinator);
-        self.select(workspace);
+        if let Some(mut detached) = self.detached_display.take() {
+            self.move_workspaces(&mut detached, &mut display);
+            detached.destroy();
+        } else {
+            self.bring_pinned_workspaces(&mut display);
+            if display.count_children() == 0 {
+                let mut workspace = self.create_next_workspace()
+                    .expect("Could not create workspace. This indicates compositor logic error");
+                workspace.settle(&mut display, None, &mut self.coordinator);
+                self.select(workspace);
+            }
+        }
+        self.follow_parents(placements);
         display
     }
 
+    /// Destroys display frame. Its workspaces are moved to `target` display. If there is no other
+    /// display the workspaces are kept detached until next display is created.
+    pub fn destroy_display(&mut self, mut display: Frame, target: Option<Frame>) {
+        self.identities.remove(&display.get_title());
+        display.remove();
+        if let Some(mut target) = target {
+            let placements = self.remember_transient_placements();
+            self.move_workspaces(&mut display, &mut target);
+            self.follow_parents(placements);
+            display.destroy();
+        } else {
+            self.detached_display = Some(display);
+        }
+    }
+
     /// Executes given command.
     pub fn execute_command(&mut self, command: Command) {
         // Execute command
+        let placements = self.remember_transient_placements();
         let mut frame = self.selection.clone();
         let result = match command.action {
             Action::Configure => self.configure(&mut frame, command.direction),
             Action::Focus => {
                 match command.direction {
                     Direction::Workspace => {
-                        self.focus_workspace(&command.string);
+                        if command.string.is_empty() {
+                            self.focus_adjacent_workspace(command.magnitude);
+                        } else {
+                            self.focus_workspace(&command.string);
+                        }
+                        CommandResult::Ok
+                    }
+                    Direction::Display => {
+                        self.focus_adjacent_display(command.magnitude);
                         CommandResult::Ok
                     }
                     _ => self.focus(&mut frame, command.direction, command.magnitude),
@@ -154,12 +214,16 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
             Action::Anchor => {
                 self.anchorize(frame)
             }
+            Action::Transfer => {
+                self.transfer_workspace(command.direction, command.magnitude)
+            }
             _ => CommandResult::NotHandled,
         };
 
         // Check result and print appropriate log
         match result {
             CommandResult::Ok => {
+                self.follow_parents(placements);
                 self.coordinator.notify();
                 self.log_frames();
             }
@@ -174,8 +238,23 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
             // Get surface
             let surface = try_get_surface!(self, sid);
 
-            // Consult about placement strategy
-            let mut decision = self.strategist.choose_target(&self.get_selection(), &surface);
+            // Consult about placement strategy. Transient surfaces are placed over their parents
+            // if possible.
+            let parent = if surface.transient_for_sid.is_valid() {
+                self.root.find_with_sid(surface.transient_for_sid)
+            } else {
+                None
+            };
+            let decision = if let Some(ref parent) = parent {
+                self.strategist.choose_transient(parent, &surface)
+            } else {
+                None
+            };
+            let mut decision = if let Some(decision) = decision {
+                decision
+            } else {
+                self.strategist.choose_target(&self.get_selection(), &surface)
+            };
             let area = if let Some(floating) = decision.floating {
                 Some(floating.area)
             } else {
@@ -183,6 +262,7 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
             };
 
             // Settle and optionally select new frame
+            let placements = self.remember_transient_placements();
             let mut frame = Frame::new_leaf(sid, decision.geometry);
             frame.settle(&mut decision.target, area, &mut self.coordinator);
             if decision.selection {
@@ -190,6 +270,7 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
             }
 
             // Finalize
+            self.follow_parents(placements);
             self.history.add(sid);
             self.coordinator.notify();
             self.log_frames();
@@ -211,7 +292,9 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
                 self.select(new_selection);
             }
 
+            let placements = self.remember_transient_placements();
             frame.destroy_self(&mut self.coordinator);
+            self.follow_parents(placements);
             self.coordinator.notify();
             self.log_frames();
         }
@@ -271,7 +354,11 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
             Direction::East | Direction::West => Geometry::Horizontal,
             Direction::Begin | Direction::End => Geometry::Stacked,
             Direction::Up => parent.get_geometry(),
-            Direction::None | Direction::Backward | Direction::Forward | Direction::Workspace => {
+            Direction::None |
+            Direction::Backward |
+            Direction::Forward |
+            Direction::Workspace |
+            Direction::Display => {
                 return CommandResult::NotHandled;
             }
         };
@@ -443,7 +530,8 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
     fn anchorize(&mut self, mut frame: Frame) -> CommandResult {
         if frame.is_anchored() {
             let workspace = self.find_current_workspace();
-            let decision = self.strategist.choose_floating(workspace.get_size(), None);
+            let size_hints = self.coordinator.get_size_hints(frame.get_sid());
+            let decision = self.strategist.choose_floating(workspace.get_size(), None, size_hints);
             frame.deanchorize(decision.area, &mut self.coordinator);
         } else {
             frame.anchorize(&mut self.coordinator);
@@ -501,6 +589,72 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
         }
         reference
     }
+
+    /// Finds frames of surfaces transient for surface with given ID (e.g. its dialogs).
+    fn find_transients(&self, sid: SurfaceId) -> Vec<Frame> {
+        let mut result = Vec::new();
+        if sid.is_valid() {
+            for transient_sid in self.history.iter() {
+                if let Some(surface) = self.coordinator.get_surface(transient_sid) {
+                    if surface.transient_for_sid == sid {
+                        if let Some(frame) = self.root.find_with_sid(transient_sid) {
+                            result.push(frame);
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Remembers positions of all transient frames and centers of frames of their parents.
+    fn remember_transient_placements(&self) -> Vec<TransientPlacement> {
+        let mut placements = Vec::new();
+        for sid in self.history.iter() {
+            if let Some(surface) = self.coordinator.get_surface(sid) {
+                let parent_sid = surface.transient_for_sid;
+                if parent_sid.is_valid() {
+                    let frame = self.root.find_with_sid(sid);
+                    let parent = self.root.find_with_sid(parent_sid);
+                    if let (Some(frame), Some(parent)) = (frame, parent) {
+                        placements.push(TransientPlacement {
+                            sid: sid,
+                            parent_sid: parent_sid,
+                            position: frame.get_position(),
+                            parent_center: parent.get_area().calculate_center(),
+                        });
+                    }
+                }
+            }
+        }
+        placements
+    }
+
+    /// Moves floating transient frames by the same vector as centers of their parents moved since
+    /// `placements` were remembered, so dialogs stay over their parents when they are moved,
+    /// retiled or (de)anchorized. Transients of transients are moved in consecutive passes.
+    fn follow_parents(&mut self, placements: Vec<TransientPlacement>) {
+        for _ in 0..placements.len() {
+            let mut moved = false;
+            for placement in placements.iter() {
+                let frame = self.root.find_with_sid(placement.sid);
+                let parent = self.root.find_with_sid(placement.parent_sid);
+                if let (Some(mut frame), Some(parent)) = (frame, parent) {
+                    if !frame.is_anchored() {
+                        let shift = parent.get_area().calculate_center() - placement.parent_center;
+                        let vector = placement.position + shift - frame.get_position();
+                        if !vector.is_zero() {
+                            frame.move_with_contents(vector);
+                            moved = true;
+                        }
+                    }
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -509,7 +663,7 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
 impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
     /// Search for existing workspace with given title.
     fn find_workspace(&self, title: &String) -> Option<Frame> {
-        for display_frame in self.root.time_iter() {
+        for display_frame in self.root.time_iter().chain(self.detached_display.iter().cloned()) {
             for workspace_frame in display_frame.time_iter() {
                 if workspace_frame.get_title() == *title {
                     return Some(workspace_frame.clone());
@@ -564,21 +718,157 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
         None
     }
 
-    /// Search for existing workspace or create new with given title.
+    /// Search for existing workspace or create new with given title. New workspace is created on
+    /// display of output it is pinned to or on current display if that output is not connected.
     fn bring_workspace(&mut self, title: &String, focus: bool) -> Frame {
         if let Some(workspace) = self.find_workspace(&title) {
             workspace.clone()
         } else {
-            // TODO: For many output setup this should be configurable on which output the
-            // workspace will be created.
-            let mut display_frame = self.find_current_workspace()
-                .get_parent()
-                .expect("workspace must be contained in display frame");
+            let mut display_frame = if let Some(display_frame) = self.find_pinned_display(title) {
+                display_frame
+            } else {
+                self.find_current_workspace()
+                    .get_parent()
+                    .expect("workspace must be contained in display frame")
+            };
 
             self.create_new_workspace(&mut display_frame, title, focus)
         }
     }
 
+    /// Checks if workspace with given title is pinned to output of given display.
+    fn is_pinned(&self, title: &String, display: &Frame) -> bool {
+        if let Some(output) = self.config.workspaces.get(title) {
+            let name = display.get_title();
+            *output == name ||
+            self.identities.get(&name).map_or(false, |identities| identities.contains(output))
+        } else {
+            false
+        }
+    }
+
+    /// Searches for display of output to which workspace with given title is pinned.
+    fn find_pinned_display(&self, title: &String) -> Option<Frame> {
+        self.root.space_iter().find(|display| self.is_pinned(title, display))
+    }
+
+    /// Moves workspaces pinned to output of given display from other displays.
+    fn bring_pinned_workspaces(&mut self, display: &mut Frame) {
+        let mut workspaces = Vec::new();
+        for other in self.root.space_iter() {
+            if !other.equals_exact(display) {
+                for workspace in other.space_iter() {
+                    if self.is_pinned(&workspace.get_title(), display) {
+                        workspaces.push(workspace);
+                    }
+                }
+            }
+        }
+
+        for mut workspace in workspaces {
+            self.move_workspace(&mut workspace, display);
+        }
+    }
+
+    /// Moves workspace to another display. Display left without workspaces gets a new one.
+    /// Workspace visible on target display stays visible unless selected frame was moved.
+    fn move_workspace(&mut self, workspace: &mut Frame, target: &mut Frame) {
+        log_info2!("Compositor: move workspace '{}'", workspace.get_title());
+        let mut source = workspace.get_parent()
+            .expect("workspace must be contained in display frame");
+        let visible = target.get_first_time();
+        workspace.resettle(target, &mut self.coordinator);
+
+        if source.count_children() == 0 {
+            if let Some(mut new_workspace) = self.create_next_workspace() {
+                new_workspace.settle(&mut source, None, &mut self.coordinator);
+            }
+        }
+
+        if let Some(mut visible) = visible {
+            target.pop_recursively(&mut visible);
+        }
+        self.root.pop_recursively(&mut self.selection);
+    }
+
+    /// Moves current workspace to display placed `distance` displays away in given direction.
+    /// Workspace stays focused.
+    fn transfer_workspace(&mut self, mut direction: Direction, distance: i32) -> CommandResult {
+        log_info2!("Compositor: transfer workspace");
+        let distance = if distance < 0 {
+            direction = direction.reversed();
+            -distance
+        } else {
+            distance
+        };
+
+        let mut workspace = self.find_current_workspace();
+        let mut reference = workspace.get_parent()
+            .expect("workspace must be contained in display frame");
+        let mut target = None;
+        for _ in 0..distance {
+            let display = self.find_display_in_direction(&reference, direction);
+            if let Some(display) = display {
+                reference = display.clone();
+                target = Some(display);
+            } else {
+                break;
+            }
+        }
+
+        if let Some(mut target) = target {
+            self.move_workspace(&mut workspace, &mut target);
+        }
+        CommandResult::Ok
+    }
+
+    /// Finds the nearest display in given direction from given display. Distance is measured
+    /// between centers of displays.
+    fn find_display_in_direction(&self, display: &Frame, direction: Direction) -> Option<Frame> {
+        let center = display.get_area().calculate_center();
+        let mut result = None;
+        let mut best_score = 0;
+        for other in self.root.space_iter() {
+            if other.equals_exact(display) {
+                continue;
+            }
+
+            let other_center = other.get_area().calculate_center();
+            let dx = other_center.x - center.x;
+            let dy = other_center.y - center.y;
+            let (distance, offset) = match direction {
+                Direction::North => (-dy, dx),
+                Direction::East => (dx, dy),
+                Direction::South => (dy, dx),
+                Direction::West => (-dx, dy),
+                _ => return None,
+            };
+
+            let score = distance + offset.abs();
+            if distance > 0 && (result.is_none() || score < best_score) {
+                result = Some(other.clone());
+                best_score = score;
+            }
+        }
+        result
+    }
+
+    /// Moves all workspaces from one display to another preserving their order. Workspace visible
+    /// on target display stays visible unless selected frame was moved.
+    fn move_workspaces(&mut self, source: &mut Frame, target: &mut Frame) {
+        let visible = target.get_first_time();
+        let workspaces: Vec<Frame> = source.space_rev_iter().collect();
+        for mut workspace in workspaces {
+            log_info2!("Compositor: move workspace '{}'", workspace.get_title());
+            workspace.resettle(target, &mut self.coordinator);
+        }
+
+        if let Some(mut visible) = visible {
+            target.pop_recursively(&mut visible);
+        }
+        self.root.pop_recursively(&mut self.selection);
+    }
+
     /// Focus workspace with given title.
     fn focus_workspace(&mut self, title: &String) {
         log_info1!("Compositor: Change workspace to '{}'", title);
@@ -587,6 +877,58 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
         self.select(most_recent.clone());
         self.root.pop_recursively(&mut most_recent);
     }
+
+    /// Focus workspace placed `offset` positions from the current one on the same display.
+    /// Workspaces are ordered by their titles. Focus wraps around at the first and last workspace.
+    fn focus_adjacent_workspace(&mut self, offset: i32) {
+        let current = self.find_current_workspace();
+        let display = current.get_parent().expect("workspace must be contained in display frame");
+
+        let mut titles: Vec<String> = display.space_iter().map(|w| w.get_title()).collect();
+        titles.sort_by(|a, b| match (a.parse::<i32>(), b.parse::<i32>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        });
+
+        let current_title = current.get_title();
+        if let Some(index) = titles.iter().position(|title| *title == current_title) {
+            let len = titles.len() as i32;
+            let index = (((index as i32 + offset) % len) + len) % len;
+            let title = titles[index as usize].clone();
+            if title != current_title {
+                self.focus_workspace(&title);
+            }
+        }
+    }
+
+    /// Focus visible workspace of display placed `offset` positions from the current one.
+    /// Displays are ordered by their positions from left to right. Focus wraps around at the first
+    /// and last display.
+    fn focus_adjacent_display(&mut self, offset: i32) {
+        let current = self.find_current_workspace()
+            .get_parent()
+            .expect("workspace must be contained in display frame");
+
+        let mut displays: Vec<Frame> = self.root.space_iter().collect();
+        displays.sort_by_key(|display| {
+            let pos = display.get_area().pos;
+            (pos.x, pos.y)
+        });
+
+        if let Some(index) = displays.iter().position(|display| display.equals_exact(&current)) {
+            let len = displays.len() as i32;
+            let index = (((index as i32 + offset) % len) + len) % len;
+            let display = displays[index as usize].clone();
+            if !display.equals_exact(&current) {
+                if let Some(workspace) = display.get_first_time() {
+                    log_info1!("Compositor: Change display to '{}'", display.get_title());
+                    let mut most_recent = self.find_most_recent(workspace);
+                    self.select(most_recent.clone());
+                    self.root.pop_recursively(&mut most_recent);
+                }
+            }
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -594,8 +936,13 @@ impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
 /// Miscellaneous private methods.
 impl<C> Compositor<C> where C: ExhibitorCoordinationTrait {
     /// Set given frame as selected.
+    ///
+    /// Frames of surfaces transient for selected one are popped above it.
     fn select(&mut self, mut frame: Frame) {
         self.root.pop_recursively(&mut frame);
+        for mut transient in self.find_transients(frame.get_sid()) {
+            self.root.pop_recursively(&mut transient);
+        }
         self.selection = frame;
         if self.selection.get_sid().is_valid() {
             self.coordinator.set_keyboard_focus(self.selection.get_sid());
@@ -8,13 +8,17 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 
-use qualia::{Buffer, Illusion, Milliseconds, OutputInfo, perceptron, Perceptron, Position};
-use qualia::{ExhibitorCoordinationTrait, SurfaceContext, SurfaceId};
+use qualia::{Illusion, OutputInfo, perceptron, Perceptron, Position, Presentation};
+use qualia::{Screenshot, ScreenshotRequest};
+use qualia::{Area, Layer, LayerSurfaceInfo, Region};
+use qualia::{ExhibitorCoordinationTrait, SurfaceContext, SurfaceId, surface_state};
 
 use frames::{Frame, Displaying};
+use frames::packing::Packing;
 use output::Output;
 
-use pointer::Pointer;
+use layers::Layers;
+use pointer::{self, Pointer};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -27,6 +31,18 @@ pub struct Display<C> where C: ExhibitorCoordinationTrait {
     redraw_needed: bool,
     page_flip_scheduled: bool,
     background_sid: SurfaceId,
+    surfaces: Vec<SurfaceContext>,
+    layers: Layers,
+
+    /// IDs and areas of surfaces drawn in last frame used to find changes in the scene.
+    scene: Vec<(SurfaceId, Area)>,
+
+    /// IDs of surfaces visible in frame waiting for page flip. They will be notified about
+    /// presentation after the flip completes.
+    presented_sids: Vec<SurfaceId>,
+
+    /// Screenshot requests waiting for the next frame to be drawn.
+    screenshot_requests: Vec<ScreenshotRequest>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -45,7 +61,12 @@ impl<C> Display<C> where C: ExhibitorCoordinationTrait {
             frame: frame,
             redraw_needed: true,
             page_flip_scheduled: false,
-            background_sid: SurfaceId::invalid()
+            background_sid: SurfaceId::invalid(),
+            surfaces: Vec::new(),
+            layers: Layers::new(),
+            scene: Vec::new(),
+            presented_sids: Vec::new(),
+            screenshot_requests: Vec::new(),
         };
         d.redraw_all(); // TODO: Remove when notifications are supported in Wayland module.
         d
@@ -56,6 +77,11 @@ impl<C> Display<C> where C: ExhibitorCoordinationTrait {
         self.output.get_info()
     }
 
+    /// Returns display frame containing workspaces of this display.
+    pub fn get_frame(&self) -> Frame {
+        self.frame.clone()
+    }
+
     /// Schedule page flip on assigned output.
     pub fn schedule_pageflip(&mut self) -> Result<(), Illusion> {
         if !self.page_flip_scheduled {
@@ -65,9 +91,16 @@ impl<C> Display<C> where C: ExhibitorCoordinationTrait {
         Ok(())
     }
 
-    /// Handle page flip: redraw everything.
-    pub fn on_pageflip(&mut self) {
+    /// Handle page flip: notify surfaces presented in flipped frame and redraw everything if
+    /// needed.
+    pub fn on_pageflip(&mut self, mut presentation: Presentation) {
         self.page_flip_scheduled = false;
+        let refresh_rate = self.output.get_info().refresh_rate;
+        if refresh_rate > 0 {
+            presentation.refresh = (1_000_000_000_000 / refresh_rate as u64) as u32;
+        }
+        self.notify_presented(presentation);
+
         if self.redraw_needed {
             self.redraw_all();
         }
@@ -101,69 +134,306 @@ impl<C> Display<C> where C: ExhibitorCoordinationTrait {
         }
     }
 
-    /// Prepare rendering context for layover.
+    /// Prepare rendering context for layover. Top and overlay layers are drawn above workspaces.
+    /// Drag icon (if any) is drawn under the cursor.
     pub fn prepare_layover_context(&self) -> Vec<SurfaceContext> {
-        vec![SurfaceContext::new(self.pointer.borrow().get_cursor_sid(),
-                                 self.pointer.borrow().get_global_position())]
+        let pointer = self.pointer.borrow();
+        let mut layover = self.layers.prepare_context(Layer::Top, &self.coordinator);
+        layover.append(&mut self.layers.prepare_context(Layer::Overlay, &self.coordinator));
+        if pointer.get_drag_icon_sid().is_valid() {
+            layover.push(SurfaceContext::new(pointer.get_drag_icon_sid(),
+                                             pointer.get_global_position()));
+        }
+        layover.push(SurfaceContext::new(pointer.get_cursor_sid(), pointer.get_global_position()));
+        self.to_display_coordinates(layover)
     }
 
-    /// Prepare rendering context for layunder.
+    /// Prepare rendering context for layunder. Background and bottom layers are drawn above
+    /// background image.
     pub fn prepare_layunder_context(&self) -> Vec<SurfaceContext> {
-        if self.background_sid.is_valid() {
+        let mut layunder = if self.background_sid.is_valid() {
             vec![SurfaceContext::new(self.background_sid, Position::default())]
         } else {
             Vec::new()
+        };
+        let mut layers = self.layers.prepare_context(Layer::Background, &self.coordinator);
+        layers.append(&mut self.layers.prepare_context(Layer::Bottom, &self.coordinator));
+        layunder.append(&mut self.to_display_coordinates(layers));
+        layunder
+    }
+
+    /// Finds surface accepting input in given point in global coordinates. Returns ID of the
+    /// surface and position relative to it.
+    pub fn get_surface_at(&self, position: Position) -> Option<(SurfaceId, Position)> {
+        let area = self.output.get_info().area;
+        let display_relative = Position::new(position.x - area.pos.x, position.y - area.pos.y);
+        pointer::find_surface_at(&self.coordinator, display_relative, &self.prepare_input_context())
+    }
+
+    /// Prepare list of surfaces which can receive pointer input ordered from the bottom-most.
+    fn prepare_input_context(&self) -> Vec<SurfaceContext> {
+        let mut surfaces = self.layers.prepare_context(Layer::Background, &self.coordinator);
+        surfaces.append(&mut self.layers.prepare_context(Layer::Bottom, &self.coordinator));
+        let mut surfaces = self.to_display_coordinates(surfaces);
+        surfaces.extend(self.surfaces.iter().cloned());
+        let mut layers = self.layers.prepare_context(Layer::Top, &self.coordinator);
+        layers.append(&mut self.layers.prepare_context(Layer::Overlay, &self.coordinator));
+        surfaces.append(&mut self.to_display_coordinates(layers));
+        surfaces
+    }
+
+    /// Translates rendering context from global coordinates to coordinates relative to the
+    /// display.
+    fn to_display_coordinates(&self, contexts: Vec<SurfaceContext>) -> Vec<SurfaceContext> {
+        let offset = Position::default() - self.output.get_info().area.pos;
+        if offset.is_zero() {
+            contexts
+        } else {
+            contexts.iter().map(|context| context.moved(offset)).collect()
         }
     }
 
     /// Draw the scene and then schedule page flip.
     ///
+    /// While layout transaction is pending surfaces are drawn in their previous layout. Coordinator
+    /// notifies when the transaction finishes or times out and then the new layout is drawn.
+    ///
     /// TODO: Benchmark drawing.
     fn redraw_all(&mut self) {
-        let surfaces = self.frame
-            .get_first_time()
-            .expect("display must have at least one workspace")
-            .to_array(&self.coordinator);
+        if !self.coordinator.is_transaction_pending() {
+            let surfaces = self.frame
+                .get_first_time()
+                .expect("display must have at least one workspace")
+                .to_array(&self.coordinator);
+            self.surfaces = self.to_display_coordinates(surfaces);
+        }
 
         let layover = self.prepare_layover_context();
         let layunder = self.prepare_layunder_context();
-        self.pointer.borrow_mut().update_hover_state(self.output.get_info().area, &surfaces);
+        let input_surfaces = self.prepare_input_context();
+        self.pointer.borrow_mut().update_hover_state(self.output.get_info().area, &input_surfaces);
 
-        if let Err(err) = self.output.draw(&layunder, &surfaces, &layover, &self.coordinator) {
-            log_error!("Display: {}", err);
+        let damage = self.calculate_damage(&layunder, &layover);
+        let requests = self.take_ready_screenshot_requests(&damage);
+        if requests.iter().any(|request| !request.overlay_cursor) {
+            // Cursor is the top-most surface of layover. Frame without it has to be drawn
+            // separately. Whole display is repainted to keep damage history of back buffers valid.
+            let full_damage = Region::from_area(Area::new(Position::default(),
+                                                          self.output.get_info().area.size));
+            let mut cursorless_layover = layover.clone();
+            cursorless_layover.pop();
+            self.draw(&layunder, &cursorless_layover, &full_damage);
+            for request in requests.iter().filter(|request| !request.overlay_cursor) {
+                self.capture_screenshot(request, &damage);
+            }
+            self.draw(&layunder, &layover, &full_damage);
+        } else {
+            self.draw(&layunder, &layover, &damage);
+        }
+
+        for request in requests.iter().filter(|request| request.overlay_cursor) {
+            self.capture_screenshot(request, &damage);
         }
 
         if let Err(err) = self.output.swap_buffers() {
             log_error!("Display: {}", err);
         }
 
-        // Send frame notifications
-        for context in surfaces {
-            let frame = Perceptron::SurfaceFrame(context.id, Milliseconds::now());
-            self.coordinator.emit(perceptron::SURFACE_FRAME, frame);
+        // Frame notifications will be sent after page flip
+        for sid in self.find_visible_surfaces() {
+            if !self.presented_sids.contains(&sid) {
+                self.presented_sids.push(sid);
+            }
         }
 
         self.redraw_needed = false;
         if let Err(err) = self.schedule_pageflip() {
             log_error!("Display: {}", err);
+
+            // Page flip will not come so do not let clients wait for it.
+            let presentation = Presentation::now(self.output.get_info().id);
+            self.notify_presented(presentation);
         }
     }
 
-    /// Requests output to take screenshot. Return `Buffer` containing image data.
-    pub fn take_screenshot(&self) -> Option<Buffer> {
-        match self.output.take_screenshot() {
-            Ok(buffer) => Some(buffer),
-            Err(err) => {
-                log_error!("Display: {}", err);
-                None
+    /// Draws the scene on output.
+    fn draw(&mut self,
+            layunder: &Vec<SurfaceContext>,
+            layover: &Vec<SurfaceContext>,
+            damage: &Region) {
+        if let Err(err) = self.output.draw(layunder,
+                                           &self.surfaces,
+                                           layover,
+                                           damage,
+                                           &self.coordinator) {
+            log_error!("Display: {}", err);
+        }
+    }
+
+    /// Sends frame notifications to surfaces presented in the last frame.
+    fn notify_presented(&mut self, presentation: Presentation) {
+        for sid in self.presented_sids.drain(..) {
+            let frame = Perceptron::SurfaceFrame(sid, presentation);
+            self.coordinator.emit(perceptron::SURFACE_FRAME, frame);
+        }
+    }
+
+    /// Finds surfaces from the last drawn scene which are visible on the output. Surfaces outside
+    /// of the output or fully covered by opaque regions of surfaces above them are not visible.
+    fn find_visible_surfaces(&self) -> Vec<SurfaceId> {
+        let output_area = Area::new(Position::default(), self.output.get_info().area.size);
+        let mut covered = Region::new();
+        let mut visible = Vec::with_capacity(self.scene.len());
+        for &(sid, area) in self.scene.iter().rev() {
+            if output_area.intersection(&area).is_none() || covered.covers(&area) {
+                continue;
+            }
+            if let Some(info) = self.coordinator.get_surface(sid) {
+                covered.union(&info.opaque_region.translated(area.pos));
             }
+            visible.push(sid);
         }
+        visible
+    }
+
+    /// Calculates area of display which changed since last redraw. It consists of damage reported
+    /// by clients and areas of surfaces which appeared, disappeared, moved or were resized. Change
+    /// of stacking order damages whole display.
+    fn calculate_damage(&mut self,
+                        layunder: &Vec<SurfaceContext>,
+                        layover: &Vec<SurfaceContext>)
+                        -> Region {
+        let display_id = self.output.get_info().id;
+        let mut damage = Region::new();
+        let mut scene = Vec::with_capacity(self.scene.len());
+        for context in layunder.iter().chain(self.surfaces.iter()).chain(layover.iter()) {
+            if let Some(info) = self.coordinator.get_surface(context.id) {
+                if let Some(size) = info.get_logical_size() {
+                    let pos = context.pos - info.offset;
+                    let surface_damage = self.coordinator.take_surface_damage(context.id,
+                                                                              display_id);
+                    damage.union(&surface_damage.translated(pos));
+                    scene.push((context.id, Area::new(pos, size)));
+                }
+            }
+        }
+
+        if scene.iter().map(|s| s.0).ne(self.scene.iter().map(|s| s.0)) {
+            let size = self.output.get_info().area.size;
+            damage.add(&Area::new(Position::default(), size));
+        } else {
+            for (current, previous) in scene.iter().zip(self.scene.iter()) {
+                if current.1 != previous.1 {
+                    damage.add(&current.1);
+                    damage.add(&previous.1);
+                }
+            }
+        }
+
+        self.scene = scene;
+        damage
+    }
+
+    /// Handles screenshot request. Screenshot will be taken while drawing the next frame. If the
+    /// request waits for damage, the first frame changing captured area will be used.
+    pub fn take_screenshot(&mut self, request: ScreenshotRequest) {
+        self.screenshot_requests.push(request);
+        if !request.wait_for_damage {
+            self.on_notify();
+        }
+    }
+
+    /// Returns screenshot requests which can be fulfilled with frame containing given damage and
+    /// keeps the rest for later frames.
+    fn take_ready_screenshot_requests(&mut self, damage: &Region) -> Vec<ScreenshotRequest> {
+        let output_area = Area::new(Position::default(), self.output.get_info().area.size);
+        let (ready, waiting): (Vec<_>, Vec<_>) = self.screenshot_requests
+            .drain(..)
+            .partition(|request| {
+                let area = request.area.unwrap_or(output_area);
+                !request.wait_for_damage ||
+                damage.get_rects().iter().any(|rect| rect.intersection(&area).is_some())
+            });
+        self.screenshot_requests = waiting;
+        ready
+    }
+
+    /// Takes screenshot of the frame drawn in back buffer and passes it to the requester. Damage
+    /// of the frame is translated to coordinates of the screenshot.
+    fn capture_screenshot(&mut self, request: &ScreenshotRequest, damage: &Region) {
+        let info = self.output.get_info();
+        let output_area = Area::new(Position::default(), info.area.size);
+        let area = if let Some(area) = request.area {
+            area.intersection(&output_area)
+        } else {
+            Some(output_area)
+        };
+
+        let screenshot = if let Some(area) = area {
+            match self.output.take_screenshot(area) {
+                Ok(buffer) => {
+                    let scale = info.scale as f32;
+                    let mut screenshot_damage = Region::new();
+                    for rect in damage.get_rects().iter().filter_map(|r| r.intersection(&area)) {
+                        let pos = Position::new(rect.pos.x - area.pos.x, rect.pos.y - area.pos.y);
+                        screenshot_damage.add(&Area::new(pos.scaled(scale),
+                                                         rect.size.scaled(scale)));
+                    }
+                    Some(Screenshot {
+                        buffer: buffer,
+                        damage: screenshot_damage,
+                        time: Presentation::now(info.id),
+                    })
+                }
+                Err(err) => {
+                    log_error!("Display: {}", err);
+                    None
+                }
+            }
+        } else {
+            log_warn2!("Display: requested screenshot area is outside of output");
+            None
+        };
+
+        self.coordinator.set_screenshot(request.id, screenshot);
     }
 
     /// Handles request to change background surface ID.
     pub fn on_background_change(&mut self, sid: SurfaceId) {
         self.background_sid = sid;
     }
+
+    /// Returns state of given layer surface if it is placed on this display.
+    pub fn get_layer_surface(&self, sid: SurfaceId) -> Option<LayerSurfaceInfo> {
+        self.layers.get_info(sid)
+    }
+
+    /// Handles adding layer surface to this display or changing its state.
+    pub fn on_layer_surface_change(&mut self, sid: SurfaceId, info: LayerSurfaceInfo) {
+        self.layers.update(sid, info);
+        self.arrange_layers();
+    }
+
+    /// Handles removal of layer surface. Does nothing if the surface is not placed on this
+    /// display.
+    pub fn remove_layer_surface(&mut self, sid: SurfaceId) {
+        if self.layers.remove(sid) {
+            self.arrange_layers();
+        }
+    }
+
+    /// Places layer surfaces on display, configures them and gives area left by their exclusive
+    /// zones to workspaces.
+    fn arrange_layers(&mut self) {
+        let usable_area = self.layers.arrange(self.output.get_info().area);
+        for (sid, area) in self.layers.get_areas() {
+            self.coordinator.reconfigure(sid, area.size, surface_state::REGULAR);
+        }
+
+        if usable_area != self.frame.get_area() {
+            self.frame.set_area(usable_area, &mut self.coordinator);
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -17,7 +17,9 @@ extern crate output;
 mod surface_history;
 mod compositor;
 mod pointer;
+mod touch;
 mod display;
+mod layers;
 
 mod strategies;
 mod strategist;
@@ -30,13 +32,17 @@ use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
 
-use qualia::{SurfaceId, Button, Command, OptionalPosition, Vector};
-use qualia::{perceptron, Perceptron};
-use qualia::{CompositorConfig, ExhibitorCoordinationTrait};
+use qualia::{SurfaceId, Button, Command, Milliseconds, OptionalPosition, Position, Presentation};
+use qualia::{buffer_transform, Vector};
+use qualia::Touch as TouchEvent;
+use qualia::{KeyboardInteractivity, Layer, LayerSurfaceInfo};
+use qualia::{perceptron, Perceptron, ScreenshotRequest};
+use qualia::{ExhibitorConfig, ExhibitorCoordinationTrait};
 use output::Output;
 
 use compositor::Compositor;
 use pointer::Pointer;
+use touch::Touch;
 use display::Display;
 
 // -------------------------------------------------------------------------------------------------
@@ -45,8 +51,10 @@ use display::Display;
 pub struct Exhibitor<C> where C: ExhibitorCoordinationTrait {
     compositor: Compositor<C>,
     pointer: Rc<RefCell<Pointer<C>>>,
+    touch: Touch<C>,
     displays: HashMap<i32, Display<C>>,
     coordinator: C,
+    config: ExhibitorConfig,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -54,15 +62,14 @@ pub struct Exhibitor<C> where C: ExhibitorCoordinationTrait {
 /// General methods.
 impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait + Clone {
     /// `Exhibitor` constructor.
-    pub fn new(coordinator: C,
-               strategist: Strategist,
-               compositor_config: CompositorConfig)
-               -> Self {
+    pub fn new(coordinator: C, strategist: Strategist, config: ExhibitorConfig) -> Self {
         Exhibitor {
-            compositor: Compositor::new(coordinator.clone(), strategist, compositor_config),
+            compositor: Compositor::new(coordinator.clone(), strategist, config.compositor.clone()),
             pointer: Rc::new(RefCell::new(Pointer::new(coordinator.clone()))),
+            touch: Touch::new(coordinator.clone()),
             displays: HashMap::new(),
             coordinator: coordinator,
+            config: config,
         }
     }
 }
@@ -92,16 +99,27 @@ impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait + Clone {
         }
     }
 
-    /// This method is called when new output was found.
-    pub fn on_output_found(&mut self, output: Box<Output>) {
+    /// This method is called when new output was found. Scale, transformation and position of the
+    /// output are set as configured. Outputs without configured position are placed to the right
+    /// of already existing displays.
+    pub fn on_output_found(&mut self, mut output: Box<Output>) {
         log_info1!("Exhibitor: found output");
+        let config = self.config.get_output_config(&output.get_info()).clone();
+        output.set_scale(config.scale);
+        match buffer_transform::from_name(&config.transform) {
+            Some(transform) => output.set_transform(transform),
+            None => log_warn1!("Exhibitor: unknown output transform '{}'", config.transform),
+        }
+        output.set_position(config.position.unwrap_or_else(|| self.find_free_position()));
+
         let info = output.get_info();
         if self.displays.len() == 0 {
             self.pointer.borrow_mut().change_display(info.area);
         }
 
         log_info1!("Exhibitor: creating display");
-        let display_frame = self.compositor.create_display(info.area, info.make.clone());
+        let display_frame =
+            self.compositor.create_display(info.area, info.name.clone(), info.get_identities());
         let display = Display::new(self.coordinator.clone(),
                                    self.pointer.clone(),
                                    output,
@@ -111,12 +129,25 @@ impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait + Clone {
         self.coordinator.emit(perceptron::DISPLAY_CREATED, Perceptron::DisplayCreated(info));
     }
 
+    /// This method is called when output was lost. Workspaces of its display are moved to the
+    /// display with the lowest ID.
+    pub fn on_output_lost(&mut self, id: i32) {
+        if let Some(display) = self.displays.remove(&id) {
+            log_info1!("Exhibitor: destroying display {}", id);
+            let target = self.displays.keys().min().map(|id| self.displays[id].get_frame());
+            self.compositor.destroy_display(display.get_frame(), target);
+            self.pointer.borrow_mut().on_display_lost(display.get_info().area, &self.displays);
+            self.coordinator.emit(perceptron::DISPLAY_DESTROYED, Perceptron::DisplayDestroyed(id));
+            self.coordinator.notify();
+        }
+    }
+
     /// This method is called when pageflip occurred.
-    /// `id` is ID of output that scheduled the pageflip.
-    pub fn on_pageflip(&mut self, id: i32) {
+    /// `presentation` contains ID of output that scheduled the pageflip and time of the flip.
+    pub fn on_pageflip(&mut self, presentation: Presentation) {
         // Pass notification to associated display
-        if let Some(ref mut display) = self.displays.get_mut(&id) {
-            display.on_pageflip();
+        if let Some(ref mut display) = self.displays.get_mut(&presentation.output_id) {
+            display.on_pageflip(presentation);
         }
     }
 
@@ -131,6 +162,11 @@ impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait + Clone {
         self.pointer.borrow_mut().on_surface_change(sid);
     }
 
+    /// This method is called when changing drag icon surface was requested.
+    pub fn on_drag_icon_surface_change(&mut self, sid: SurfaceId) {
+        self.pointer.borrow_mut().on_drag_icon_change(sid);
+    }
+
     /// This method is called when changing background surface was requested.
     ///
     /// TODO: Make change background request be display specific.
@@ -140,6 +176,37 @@ impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait + Clone {
         }
     }
 
+    /// This method is called when placing surface in one of the layers or changing its layer
+    /// state was requested. Surface is placed on requested display or on the first one if the
+    /// display was not specified.
+    pub fn on_layer_surface_change(&mut self, sid: SurfaceId, info: Option<LayerSurfaceInfo>) {
+        let target_id = if let Some(ref info) = info {
+            match info.output_id {
+                Some(id) if self.displays.contains_key(&id) => Some(id),
+                _ => self.displays.keys().min().cloned(),
+            }
+        } else {
+            None
+        };
+
+        for (id, display) in self.displays.iter_mut() {
+            match (target_id, info) {
+                (Some(target_id), Some(info)) if target_id == *id => {
+                    display.on_layer_surface_change(sid, info);
+                }
+                _ => display.remove_layer_surface(sid),
+            }
+        }
+
+        match info {
+            Some(ref info) if Self::takes_keyboard_focus(info) => {
+                self.coordinator.set_keyboard_focus(sid);
+            }
+            Some(ref info) if info.keyboard_interactivity == KeyboardInteractivity::OnDemand => {}
+            _ => self.restore_keyboard_focus(sid),
+        }
+    }
+
     /// This method is called when new surface is ready to be managed.
     pub fn on_surface_ready(&mut self, sid: SurfaceId) {
         self.compositor.manage_surface(sid);
@@ -147,8 +214,15 @@ impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait + Clone {
 
     /// This method is called when surface was destroyed.
     pub fn on_surface_destroyed(&mut self, sid: SurfaceId) {
+        if self.find_layer_surface(sid).is_some() {
+            for display in self.displays.values_mut() {
+                display.remove_layer_surface(sid);
+            }
+            self.restore_keyboard_focus(sid);
+        }
         self.compositor.unmanage_surface(sid);
         self.pointer.borrow_mut().on_surface_destroyed(sid);
+        self.touch.on_surface_destroyed(sid);
     }
 
     /// This method is called when keyboard focus changed.
@@ -157,12 +231,14 @@ impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait + Clone {
     }
 
     /// This method is called when screenshot was requested.
-    pub fn take_screenshot(&mut self, id: i32) {
-        if let Some(ref mut display) = self.displays.get_mut(&id) {
-            if let Some(buffer) = display.take_screenshot() {
-                self.coordinator.set_screenshot_buffer(buffer);
-            }
+    pub fn take_screenshot(&mut self, request: ScreenshotRequest) {
+        if let Some(ref mut display) = self.displays.get_mut(&request.output_id) {
+            display.take_screenshot(request);
+            return;
         }
+
+        log_warn2!("Requested screenshot of unknown output {}", request.output_id);
+        self.coordinator.set_screenshot(request.id, None);
     }
 }
 
@@ -188,7 +264,14 @@ impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait {
         if button.value != 0 {
             let pfsid = self.pointer.borrow_mut().get_pointer_focussed_sid();
             if self.pointer.borrow_mut().get_keyboard_focussed_sid() != pfsid {
-                self.compositor.pop_surface(pfsid);
+                match self.find_layer_surface(pfsid) {
+                    Some(info) => {
+                        if info.keyboard_interactivity != KeyboardInteractivity::None {
+                            self.coordinator.set_keyboard_focus(pfsid);
+                        }
+                    }
+                    None => self.compositor.pop_surface(pfsid),
+                }
             }
         }
     }
@@ -197,6 +280,70 @@ impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait {
     pub fn on_position_reset(&self) {
         self.pointer.borrow_mut().reset_position()
     }
+
+    /// Handle touch down event.
+    pub fn on_touch_down(&mut self, touch: TouchEvent) {
+        self.touch.on_down(touch, &self.displays);
+    }
+
+    /// Handle touch motion event.
+    pub fn on_touch_motion(&mut self, touch: TouchEvent) {
+        self.touch.on_motion(touch, &self.displays);
+    }
+
+    /// Handle touch up event.
+    pub fn on_touch_up(&mut self, slot: i32, time: Milliseconds) {
+        self.touch.on_up(slot, time);
+    }
+
+    /// Handle touch frame event.
+    pub fn on_touch_frame(&mut self) {
+        self.touch.on_frame();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Display helpers.
+impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait {
+    /// Returns position to the right of all existing displays.
+    fn find_free_position(&self) -> Position {
+        let right = self.displays
+            .values()
+            .map(|display| {
+                let area = display.get_info().area;
+                area.pos.x + area.size.width as isize
+            })
+            .max()
+            .unwrap_or(0);
+        Position::new(right, 0)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Layer surface helpers.
+impl<C> Exhibitor<C> where C: ExhibitorCoordinationTrait {
+    /// Returns state of given layer surface placed on any display.
+    fn find_layer_surface(&self, sid: SurfaceId) -> Option<LayerSurfaceInfo> {
+        self.displays.values().filter_map(|display| display.get_layer_surface(sid)).next()
+    }
+
+    /// Checks if layer surface should take keyboard focus as soon as it is placed. Only surfaces
+    /// above workspaces may take focus exclusively.
+    fn takes_keyboard_focus(info: &LayerSurfaceInfo) -> bool {
+        info.keyboard_interactivity == KeyboardInteractivity::Exclusive && info.layer >= Layer::Top
+    }
+
+    /// Gives keyboard focus back to selected frame if given surface has it.
+    fn restore_keyboard_focus(&mut self, sid: SurfaceId) {
+        if self.pointer.borrow().get_keyboard_focussed_sid() == sid {
+            let selected_sid = self.compositor.get_selection().get_sid();
+            if selected_sid.is_valid() && selected_sid != sid {
+                self.coordinator.set_keyboard_focus(selected_sid);
+            }
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -0,0 +1,113 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! This module contains functionality related to layer surfaces (panels, docks, wallpapers,
+//! notifications) placed on display outside of workspaces.
+
+// -------------------------------------------------------------------------------------------------
+
+use qualia::{Area, Layer, LayerSurfaceInfo, SurfaceContext, SurfaceId, SurfaceListing};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Order in which layers reserve their exclusive zones. The top-most layers are the closest to the
+/// edges of display.
+const EXCLUSIVE_ORDER: [Layer; 4] = [Layer::Overlay, Layer::Top, Layer::Bottom, Layer::Background];
+
+// -------------------------------------------------------------------------------------------------
+
+/// Layer surface placed on display.
+struct LayerSurface {
+    sid: SurfaceId,
+    info: LayerSurfaceInfo,
+    area: Area,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Set of layer surfaces placed on one display. Surfaces in the same layer are stacked in order of
+/// adding.
+pub struct Layers {
+    surfaces: Vec<LayerSurface>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Layers {
+    /// Constructs new `Layers`.
+    pub fn new() -> Self {
+        Layers { surfaces: Vec::new() }
+    }
+
+    /// Adds new surface or updates state of already added one.
+    pub fn update(&mut self, sid: SurfaceId, info: LayerSurfaceInfo) {
+        if let Some(surface) = self.surfaces.iter_mut().find(|s| s.sid == sid) {
+            surface.info = info;
+            return;
+        }
+
+        self.surfaces.push(LayerSurface {
+            sid: sid,
+            info: info,
+            area: Area::default(),
+        });
+    }
+
+    /// Removes given surface. Returns `true` if the surface was found, `false` otherwise.
+    pub fn remove(&mut self, sid: SurfaceId) -> bool {
+        let len = self.surfaces.len();
+        self.surfaces.retain(|s| s.sid != sid);
+        len != self.surfaces.len()
+    }
+
+    /// Returns state of given surface if it is placed in one of the layers.
+    pub fn get_info(&self, sid: SurfaceId) -> Option<LayerSurfaceInfo> {
+        self.surfaces.iter().find(|s| s.sid == sid).map(|s| s.info)
+    }
+
+    /// Places all surfaces inside given display area and returns part of the area left for
+    /// workspaces.
+    ///
+    /// Surfaces with exclusive zone are placed first, each reserving part of display along the
+    /// edge it is anchored to. Other surfaces are placed in the area left unless they do not want
+    /// to be moved to avoid exclusive zones.
+    pub fn arrange(&mut self, display_area: Area) -> Area {
+        let mut usable_area = display_area;
+        for layer in EXCLUSIVE_ORDER.iter() {
+            for surface in self.surfaces.iter_mut() {
+                if surface.info.layer == *layer && surface.info.exclusive_zone > 0 {
+                    surface.area = surface.info.place(usable_area);
+                    usable_area = surface.info.reserve(usable_area);
+                }
+            }
+        }
+
+        for surface in self.surfaces.iter_mut() {
+            if surface.info.exclusive_zone == 0 {
+                surface.area = surface.info.place(usable_area);
+            } else if surface.info.exclusive_zone < 0 {
+                surface.area = surface.info.place(display_area);
+            }
+        }
+
+        usable_area
+    }
+
+    /// Returns IDs and areas of all surfaces as calculated during last arrangement.
+    pub fn get_areas(&self) -> Vec<(SurfaceId, Area)> {
+        self.surfaces.iter().map(|s| (s.sid, s.area)).collect()
+    }
+
+    /// Prepares rendering context for surfaces in given layer (including their subsurfaces).
+    pub fn prepare_context(&self, layer: Layer, listing: &SurfaceListing) -> Vec<SurfaceContext> {
+        let mut result = Vec::new();
+        for surface in self.surfaces.iter().filter(|s| s.info.layer == layer) {
+            if let Some(array) = listing.get_renderer_context(surface.sid) {
+                result.extend(array.iter().map(|c| c.moved(surface.area.pos)));
+            }
+        }
+        result
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
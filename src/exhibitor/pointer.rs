@@ -14,6 +14,36 @@ use display::Display;
 
 // -------------------------------------------------------------------------------------------------
 
+/// Finds the top-most surface placed in given display-relative position which accepts input in
+/// this point. Returns ID of the surface and position relative to it.
+pub fn find_surface_at<C>(coordinator: &C,
+                          position: Position,
+                          surfaces: &Vec<SurfaceContext>)
+                          -> Option<(SurfaceId, Position)>
+    where C: ExhibitorCoordinationTrait
+{
+    for context in surfaces.iter().rev() {
+        if let Some(info) = coordinator.get_surface(context.id) {
+            let surface_area = Area::new(context.pos, info.requested_size);
+            if surface_area.contains(&position) {
+                let relative = position - context.pos.clone() + info.offset;
+                let accepts_input = if let Some(ref region) = info.input_region {
+                    region.contains(&relative)
+                } else {
+                    true
+                };
+
+                if accepts_input {
+                    return Some((context.id, relative));
+                }
+            }
+        }
+    }
+    None
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// State of the pointer.
 pub struct Pointer<C> where C: ExhibitorCoordinationTrait {
     /// Position in global coordinates.
@@ -31,6 +61,9 @@ pub struct Pointer<C> where C: ExhibitorCoordinationTrait {
     /// Surface ID of cursor surface.
     csid: SurfaceId,
 
+    /// Surface ID of drag-and-drop icon surface.
+    disid: SurfaceId,
+
     /// Surface ID of pointer-focused surface.
     pfsid: SurfaceId,
 
@@ -52,6 +85,7 @@ impl<C> Pointer<C> where C: ExhibitorCoordinationTrait {
             last_surface_relative: Position::default(),
             display_area: Area::default(),
             csid: SurfaceId::invalid(),
+            disid: SurfaceId::invalid(),
             pfsid: SurfaceId::invalid(),
             kfsid: SurfaceId::invalid(),
             coordinator: coordinator,
@@ -62,6 +96,16 @@ impl<C> Pointer<C> where C: ExhibitorCoordinationTrait {
         self.position = area.calculate_center();
         self.display_area = area;
     }
+
+    /// Handles removal of display with given area. If pointer was placed on it, it is moved to
+    /// the display with the lowest ID.
+    pub fn on_display_lost(&mut self, area: Area, displays: &HashMap<i32, Display<C>>) {
+        if self.display_area == area {
+            if let Some(id) = displays.keys().min() {
+                self.change_display(displays[id].get_info().area);
+            }
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -79,6 +123,11 @@ impl<C> Pointer<C> where C: ExhibitorCoordinationTrait {
         self.csid
     }
 
+    /// Returns ID of the drag-and-drop icon surface.
+    pub fn get_drag_icon_sid(&self) -> SurfaceId {
+        self.disid
+    }
+
     /// Return ID of the surface with keyboard focus.
     pub fn get_keyboard_focussed_sid(&self) -> SurfaceId {
         self.kfsid
@@ -138,22 +187,13 @@ impl<C> Pointer<C> where C: ExhibitorCoordinationTrait {
             return;
         }
 
-        let mut sid = SurfaceId::invalid();
-        let mut surface_relative = Position::default();
         let display_relative = Position::new(self.position.x - display_area.pos.x,
                                              self.position.y - display_area.pos.y);
 
         // Find surface pointer hovers
-        for context in surfaces.iter().rev() {
-            if let Some(info) = self.coordinator.get_surface(context.id) {
-                let surface_area = Area::new(context.pos, info.requested_size);
-                if surface_area.contains(&display_relative) {
-                    sid = context.id;
-                    surface_relative = display_relative - context.pos.clone() + info.offset;
-                    break;
-                }
-            }
-        }
+        let (sid, surface_relative) =
+            find_surface_at(&self.coordinator, display_relative, surfaces)
+                .unwrap_or((SurfaceId::invalid(), Position::default()));
 
         // Handle focus change if hovered surface is different than current one or handle motion
         // otherwise
@@ -174,11 +214,14 @@ impl<C> Pointer<C> where C: ExhibitorCoordinationTrait {
 
 /// Other requests.
 impl<C> Pointer<C> where C: ExhibitorCoordinationTrait {
-    /// Handles destruction of cursor surface.
+    /// Handles destruction of cursor or drag icon surface.
     pub fn on_surface_destroyed(&mut self, sid: SurfaceId) {
         if self.csid == sid {
             self.csid = SurfaceId::invalid();
         }
+        if self.disid == sid {
+            self.disid = SurfaceId::invalid();
+        }
     }
 
     /// Sets surface ID of currently keyboard focused surface.
@@ -190,6 +233,11 @@ impl<C> Pointer<C> where C: ExhibitorCoordinationTrait {
     pub fn on_surface_change(&mut self, sid: SurfaceId) {
         self.csid = sid;
     }
+
+    /// Handles drag icon surface change request.
+    pub fn on_drag_icon_change(&mut self, sid: SurfaceId) {
+        self.disid = sid;
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -12,7 +12,7 @@
 use rand;
 use rand::distributions::{IndependentSample, Range};
 
-use qualia::{Area, Position, Size, SurfaceInfo};
+use qualia::{Area, Position, Size, SizeHints, SurfaceInfo};
 
 use frames::{self, Frame};
 use frames::searching::Searching;
@@ -35,7 +35,9 @@ pub fn choose_target_always_floating(strategist: &Strategist,
     };
 
     let workspace = selection.find_top().expect("searching workspace for floating");
-    let floating = strategist.choose_floating(workspace.get_size(), preferred_size);
+    let floating = strategist.choose_floating(workspace.get_size(),
+                                              preferred_size,
+                                              surface.size_hints);
     TargetDecision {
         target: workspace,
         geometry: frames::Geometry::Vertical,
@@ -69,21 +71,60 @@ pub fn choose_target_anchored_but_popups(strategist: &Strategist,
 
 // -------------------------------------------------------------------------------------------------
 
+/// Decides how to handle new transient surface.
+///
+/// The surface will be floating and centered over frame of its parent. If requested size was not
+/// provided the frame will have 1/2 of width and height of the parent frame. The size is limited
+/// by size hints. No decision is made if the parent frame is not placed in any workspace.
+pub fn choose_transient_centered_over_parent(_strategist: &Strategist,
+                                             parent: &Frame,
+                                             surface: &SurfaceInfo)
+                                             -> Option<TargetDecision> {
+    let workspace = if let Some(workspace) = parent.find_top() {
+        workspace
+    } else {
+        return None;
+    };
+
+    let parent_area = parent.get_area();
+    let size = if !surface.requested_size.is_zero() {
+        surface.requested_size
+    } else {
+        parent_area.size.scaled(0.5)
+    };
+    let size = surface.size_hints.clamp(size);
+
+    let center = parent_area.calculate_center();
+    let pos = Position::new(center.x - (size.width / 2) as isize,
+                            center.y - (size.height / 2) as isize);
+
+    Some(TargetDecision {
+        target: workspace,
+        geometry: frames::Geometry::Vertical,
+        selection: true,
+        floating: Some(FloatingDecision { area: Area::new(pos, size) }),
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Decides where to place floating surface.
 ///
 /// The frame will always be centered. If preferred size was not provided the frame will have 1/2
-/// of width and height of workspace.
+/// of width and height of workspace. The size is limited by size hints.
 pub fn choose_floating_always_centered(_strategist: &Strategist,
                                        workspace_size: Size,
-                                       preferred_size: Option<Size>)
+                                       preferred_size: Option<Size>,
+                                       size_hints: SizeHints)
                                        -> FloatingDecision {
     let size = if let Some(preferred_size) = preferred_size {
         preferred_size
     } else {
         workspace_size.scaled(0.5)
     };
-    let pos = Position::new((workspace_size.width / 4) as isize,
-                            (workspace_size.height / 4) as isize);
+    let size = size_hints.clamp(size);
+    let pos = Position::new((workspace_size.width as isize - size.width as isize) / 2,
+                            (workspace_size.height as isize - size.height as isize) / 2);
     FloatingDecision {
         area: Area::new(pos, size),
     }
@@ -94,10 +135,11 @@ pub fn choose_floating_always_centered(_strategist: &Strategist,
 /// Decides where to place floating surface.
 ///
 /// The frame will be placed in random position in workspace. If preferred size was not provided
-/// the frame will have 1/2 of width and height of workspace.
+/// the frame will have 1/2 of width and height of workspace. The size is limited by size hints.
 pub fn choose_floating_random(_strategist: &Strategist,
                               workspace_size: Size,
-                              preferred_size: Option<Size>)
+                              preferred_size: Option<Size>,
+                              size_hints: SizeHints)
                               -> FloatingDecision {
     let mut rng = rand::thread_rng();
 
@@ -106,9 +148,10 @@ pub fn choose_floating_random(_strategist: &Strategist,
     } else {
         workspace_size.scaled(0.5)
     };
+    let size = size_hints.clamp(size);
 
-    let x_range = Range::new(0, workspace_size.width - size.width);
-    let y_range = Range::new(0, workspace_size.height - size.height);
+    let x_range = Range::new(0, workspace_size.width.saturating_sub(size.width) + 1);
+    let y_range = Range::new(0, workspace_size.height.saturating_sub(size.height) + 1);
     let pos = Position::new(x_range.ind_sample(&mut rng) as isize,
                             y_range.ind_sample(&mut rng) as isize);
     FloatingDecision {
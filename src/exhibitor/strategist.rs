@@ -5,7 +5,7 @@
 
 // -------------------------------------------------------------------------------------------------
 
-use qualia::{Area, Size, StrategistConfig, SurfaceInfo};
+use qualia::{Area, Size, SizeHints, StrategistConfig, SurfaceInfo};
 use frames::{self, Frame};
 
 use strategies;
@@ -38,7 +38,8 @@ pub struct FloatingDecision {
 // -------------------------------------------------------------------------------------------------
 
 type TargetDecider = fn(&Strategist, &Frame, &SurfaceInfo) -> TargetDecision;
-type FloatingDecider = fn(&Strategist, Size, Option<Size>) -> FloatingDecision;
+type FloatingDecider = fn(&Strategist, Size, Option<Size>, SizeHints) -> FloatingDecision;
+type TransientDecider = fn(&Strategist, &Frame, &SurfaceInfo) -> Option<TargetDecision>;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -49,6 +50,7 @@ type FloatingDecider = fn(&Strategist, Size, Option<Size>) -> FloatingDecision;
 pub struct Strategist {
     choose_target: TargetDecider,
     choose_floating: FloatingDecider,
+    choose_transient: TransientDecider,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -59,6 +61,7 @@ impl Strategist {
         Strategist {
             choose_target: choose_target,
             choose_floating: choose_floating,
+            choose_transient: strategies::choose_transient_centered_over_parent,
         }
     }
 
@@ -114,12 +117,23 @@ impl Strategist {
         (self.choose_target)(self, frame, surface)
     }
 
-    /// Decides where to place floating surface.
+    /// Decides where to place floating surface. Size of the surface should be limited by given
+    /// size hints.
     pub fn choose_floating(&self,
                            workspace_size: Size,
-                           preferred_size: Option<Size>)
+                           preferred_size: Option<Size>,
+                           size_hints: SizeHints)
                            -> FloatingDecision {
-        (self.choose_floating)(self, workspace_size, preferred_size)
+        (self.choose_floating)(self, workspace_size, preferred_size, size_hints)
+    }
+
+    /// Decides how to handle new surface transient for surface in frame `parent` (e.g. dialog).
+    /// Returns `None` if the surface can not be placed relatively to its parent.
+    pub fn choose_transient(&self,
+                            parent: &Frame,
+                            surface: &SurfaceInfo)
+                            -> Option<TargetDecision> {
+        (self.choose_transient)(self, parent, surface)
     }
 }
 
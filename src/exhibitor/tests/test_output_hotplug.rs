@@ -0,0 +1,93 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Unit tests of connecting and disconnecting outputs.
+
+extern crate qualia;
+extern crate frames;
+extern crate exhibitor;
+extern crate testing;
+
+use qualia::OutputInfo;
+use qualia::{Area, Position, Size};
+use qualia::ExhibitorConfig;
+use frames::Frame;
+use exhibitor::{Exhibitor, Strategist};
+use testing::output_mock::OutputMock;
+use testing::coordinator_mock::CoordinatorMock;
+
+// -------------------------------------------------------------------------------------------------
+
+struct Environment {
+    exhibitor: Exhibitor<CoordinatorMock>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Environment {
+    pub fn create() -> Self {
+        let coordinator = CoordinatorMock::new();
+        Environment {
+            exhibitor: Exhibitor::new(coordinator,
+                                      Strategist::default(),
+                                      ExhibitorConfig::default()),
+        }
+    }
+
+    /// Connects output with given ID, position and size.
+    pub fn connect(&mut self, id: i32, x: isize, width: usize) {
+        let info = OutputInfo::new(id,
+                                   Area::new(Position::new(x, 0), Size::new(width, 100)),
+                                   Size::new(width, 100),
+                                   60_000,
+                                   "test_make".to_owned(),
+                                   "test_model".to_owned());
+        self.exhibitor.on_output_found(Box::new(OutputMock::new(info)));
+    }
+
+    /// Returns titles of workspaces of each display. Displays are ordered from left to right.
+    pub fn get_workspace_titles(&self) -> Vec<Vec<String>> {
+        let mut displays: Vec<Frame> = self.exhibitor.get_root().space_iter().collect();
+        displays.sort_by_key(|display| display.get_area().pos.x);
+        displays.iter()
+            .map(|display| display.space_iter().map(|workspace| workspace.get_title()).collect())
+            .collect()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if workspaces of disconnected output are moved to the remaining one and selected
+/// workspace stays selected and is resized to the new display.
+#[test]
+fn test_moving_workspaces_of_disconnected_output() {
+    let mut e = Environment::create();
+    e.connect(1, 0, 100);
+    e.connect(2, 100, 200);
+    assert_eq!(e.get_workspace_titles(), vec![vec!["1".to_owned()], vec!["2".to_owned()]]);
+
+    e.exhibitor.on_output_lost(2);
+    assert_eq!(e.get_workspace_titles(), vec![vec!["2".to_owned(), "1".to_owned()]]);
+
+    let selection = e.exhibitor.get_selection();
+    assert_eq!(selection.get_title(), "2");
+    assert_eq!(selection.get_size(), Size::new(100, 100));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if workspaces are kept after disconnecting the last output and placed on the next
+/// connected one instead of creating a new workspace.
+#[test]
+fn test_restoring_workspaces_after_disconnecting_all_outputs() {
+    let mut e = Environment::create();
+    e.connect(1, 0, 100);
+    e.exhibitor.on_output_lost(1);
+    assert_eq!(e.get_workspace_titles(), Vec::<Vec<String>>::new());
+
+    e.connect(2, 0, 200);
+    assert_eq!(e.get_workspace_titles(), vec![vec!["1".to_owned()]]);
+    assert_eq!(e.exhibitor.get_selection().get_size(), Size::new(200, 100));
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -0,0 +1,94 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Unit tests of placing outputs according to configuration.
+
+extern crate qualia;
+extern crate frames;
+extern crate exhibitor;
+extern crate testing;
+
+use qualia::OutputInfo;
+use qualia::{Area, Position, Size};
+use qualia::{ExhibitorConfig, OutputConfig};
+use exhibitor::{Exhibitor, Strategist};
+use testing::output_mock::OutputMock;
+use testing::coordinator_mock::CoordinatorMock;
+
+// -------------------------------------------------------------------------------------------------
+
+struct Environment {
+    exhibitor: Exhibitor<CoordinatorMock>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Environment {
+    pub fn create(config: ExhibitorConfig) -> Self {
+        let coordinator = CoordinatorMock::new();
+        Environment { exhibitor: Exhibitor::new(coordinator, Strategist::default(), config) }
+    }
+
+    /// Connects output with given ID, connector name and width.
+    pub fn connect(&mut self, id: i32, name: &str, width: usize) {
+        let mut info = OutputInfo::new(id,
+                                       Area::new(Position::default(), Size::new(width, 100)),
+                                       Size::new(width, 100),
+                                       60_000,
+                                       "test_make".to_owned(),
+                                       "test_model".to_owned());
+        info.name = name.to_owned();
+        self.exhibitor.on_output_found(Box::new(OutputMock::new(info)));
+    }
+
+    /// Returns areas of all displays ordered from left to right.
+    pub fn get_display_areas(&self) -> Vec<Area> {
+        let mut areas: Vec<Area> =
+            self.exhibitor.get_root().space_iter().map(|display| display.get_area()).collect();
+        areas.sort_by_key(|area| area.pos.x);
+        areas
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if outputs without configured position are placed next to each other from left to right.
+#[test]
+fn test_placing_outputs_side_by_side() {
+    let mut e = Environment::create(ExhibitorConfig::default());
+    e.connect(1, "HDMI-A-1", 100);
+    e.connect(2, "HDMI-A-2", 200);
+    e.connect(3, "HDMI-A-3", 300);
+
+    assert_eq!(e.get_display_areas(),
+               vec![Area::create(0, 0, 100, 100),
+                    Area::create(100, 0, 200, 100),
+                    Area::create(300, 0, 300, 100)]);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if outputs are placed in configured positions and outputs without configured position
+/// are placed to the right of the right-most one.
+#[test]
+fn test_placing_outputs_in_configured_positions() {
+    let mut config = ExhibitorConfig::default();
+    let mut output = OutputConfig::default();
+    output.position = Some(Position::new(-200, 50));
+    config.outputs.insert("HDMI-A-1".to_owned(), output);
+    let mut output = OutputConfig::default();
+    output.position = Some(Position::new(300, 0));
+    config.outputs.insert("HDMI-A-2".to_owned(), output);
+
+    let mut e = Environment::create(config);
+    e.connect(1, "HDMI-A-1", 200);
+    e.connect(2, "HDMI-A-2", 100);
+    e.connect(3, "HDMI-A-3", 100);
+
+    assert_eq!(e.get_display_areas(),
+               vec![Area::create(-200, 50, 200, 100),
+                    Area::create(300, 0, 100, 100),
+                    Area::create(400, 0, 100, 100)]);
+}
+
+// -------------------------------------------------------------------------------------------------
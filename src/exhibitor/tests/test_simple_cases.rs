@@ -14,7 +14,7 @@ use qualia::{Action, Command, Direction};
 use qualia::ExhibitorConfig;
 use frames::Geometry::{Stacked, Vertical};
 use frames::Parameters;
-use exhibitor::Exhibitor;
+use exhibitor::{Exhibitor, Strategist};
 use testing::frame_representation::FrameRepresentation;
 use testing::output_mock::OutputMock;
 use testing::coordinator_mock::CoordinatorMock;
@@ -33,13 +33,15 @@ impl Environment {
         let output_info = OutputInfo::new(1,
                                           Area::new(Position::new(0, 0), Size::new(100, 100)),
                                           Size::new(100, 100),
-                                          60,
+                                          60_000,
                                           "test_make".to_owned(),
                                           "test_model".to_owned());
 
         let output = Box::new(OutputMock::new(output_info.clone()));
         let coordinator = CoordinatorMock::new();
-        let mut exhibitor = Exhibitor::new(coordinator.clone(), ExhibitorConfig::default());
+        let mut exhibitor = Exhibitor::new(coordinator.clone(),
+                                           Strategist::default(),
+                                           ExhibitorConfig::default());
 
         exhibitor.on_output_found(output);
 
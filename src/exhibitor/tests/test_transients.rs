@@ -0,0 +1,185 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Unit tests of placing transient surfaces (e.g. dialogs) over their parents.
+
+extern crate qualia;
+extern crate frames;
+extern crate exhibitor;
+extern crate testing;
+
+use qualia::{OutputInfo, SurfaceId};
+use qualia::{Action, Command, Direction};
+use qualia::{Area, Position, Size};
+use qualia::{ExhibitorConfig, StrategistConfig, SurfaceControl, SurfaceViewer};
+use frames::{Frame, Geometry};
+use frames::searching::Searching;
+use exhibitor::{Exhibitor, Strategist};
+use testing::output_mock::OutputMock;
+use testing::coordinator_mock::CoordinatorMock;
+
+// -------------------------------------------------------------------------------------------------
+
+struct Environment {
+    exhibitor: Exhibitor<CoordinatorMock>,
+    coordinator: CoordinatorMock,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Environment {
+    /// Creates environment with one output using given strategist.
+    pub fn create(strategist: Strategist) -> Self {
+        let info = OutputInfo::new(1,
+                                   Area::new(Position::default(), Size::new(100, 100)),
+                                   Size::new(100, 100),
+                                   60_000,
+                                   "test_make".to_owned(),
+                                   "test_model".to_owned());
+
+        let coordinator = CoordinatorMock::new();
+        let mut exhibitor =
+            Exhibitor::new(coordinator.clone(), strategist, ExhibitorConfig::default());
+        exhibitor.on_output_found(Box::new(OutputMock::new(info)));
+
+        Environment {
+            exhibitor: exhibitor,
+            coordinator: coordinator,
+        }
+    }
+
+    /// Creates environment in which new surfaces are floating.
+    pub fn create_floating() -> Self {
+        Self::create(Strategist::default())
+    }
+
+    /// Creates environment in which new surfaces are anchored.
+    pub fn create_anchored() -> Self {
+        Self::create(Strategist::new_from_config(StrategistConfig {
+            choose_target: "anchored_but_popups".to_owned(),
+            choose_floating: "always_centered".to_owned(),
+        }))
+    }
+
+    /// Makes new surface with ID `sid` ready. If `parent` is given the surface is transient for
+    /// it.
+    pub fn add(&mut self, sid: u64, parent: Option<u64>) {
+        if let Some(parent) = parent {
+            self.coordinator.set_surface_transient_for(SurfaceId::new(sid), SurfaceId::new(parent));
+        }
+        self.exhibitor.on_surface_ready(SurfaceId::new(sid));
+    }
+
+    /// Executes command with given action, direction and magnitude.
+    pub fn execute(&mut self, action: Action, direction: Direction, magnitude: i32) {
+        self.exhibitor.on_command(Command {
+            action: action,
+            direction: direction,
+            magnitude: magnitude,
+            string: String::default(),
+        });
+    }
+
+    /// Returns frame of surface with given ID.
+    pub fn get_frame(&self, sid: u64) -> Frame {
+        self.exhibitor.get_root().find_with_sid(SurfaceId::new(sid)).expect("find frame")
+    }
+
+    /// Returns center of frame of surface with given ID.
+    pub fn get_center(&self, sid: u64) -> Position {
+        self.get_frame(sid).get_area().calculate_center()
+    }
+
+    /// Checks if frame of surface with ID `sid` is floating and centered over frame of surface
+    /// with ID `parent`.
+    pub fn assert_centered(&self, sid: u64, parent: u64) {
+        assert!(!self.get_frame(sid).is_anchored());
+        assert_eq!(self.get_center(sid), self.get_center(parent));
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if transient surface is floating, centered over its parent and has half of its size.
+#[test]
+fn test_placing_transient_over_parent() {
+    let mut e = Environment::create_floating();
+    e.add(1, None);
+    e.add(2, Some(1));
+
+    e.assert_centered(2, 1);
+    assert_eq!(e.get_frame(2).get_size(), e.get_frame(1).get_size().scaled(0.5));
+    assert_eq!(e.exhibitor.get_selection().get_sid(), SurfaceId::new(2));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if transient surface is moved together with its parent.
+#[test]
+fn test_moving_transient_with_parent() {
+    let mut e = Environment::create_floating();
+    e.add(1, None);
+    e.add(2, Some(1));
+    e.execute(Action::Focus, Direction::Backward, 1);
+    assert_eq!(e.exhibitor.get_selection().get_sid(), SurfaceId::new(1));
+
+    let position = e.get_frame(2).get_position();
+    e.execute(Action::Move, Direction::East, 1);
+    assert_eq!(e.get_frame(2).get_position(), position + Position::new(10, 0));
+    e.assert_centered(2, 1);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if transient surface follows its parent when the parent is anchored and deanchored.
+#[test]
+fn test_following_reanchored_parent() {
+    let mut e = Environment::create_floating();
+    e.add(1, None);
+    e.add(2, Some(1));
+    e.execute(Action::Focus, Direction::Backward, 1);
+
+    e.execute(Action::Anchor, Direction::None, 0);
+    assert!(e.get_frame(1).is_anchored());
+    assert_eq!(e.get_center(1), Position::new(50, 50));
+    e.assert_centered(2, 1);
+
+    e.execute(Action::Anchor, Direction::None, 0);
+    assert!(!e.get_frame(1).is_anchored());
+    e.assert_centered(2, 1);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if transient surface follows its parent when the parent is retiled after its sibling was
+/// destroyed.
+#[test]
+fn test_following_retiled_parent() {
+    let mut e = Environment::create_anchored();
+    e.add(1, None);
+    e.add(2, None);
+    e.execute(Action::Jump, Direction::End, 0);
+    e.execute(Action::Configure, Direction::North, 0);
+    e.add(3, None);
+    assert_eq!(e.get_frame(2).get_area(), Area::new(Position::new(0, 0), Size::new(100, 50)));
+
+    e.add(4, Some(2));
+    e.assert_centered(4, 2);
+
+    e.exhibitor.on_surface_destroyed(SurfaceId::new(3));
+    assert_eq!(e.get_frame(2).get_area(), Area::new(Position::new(0, 0), Size::new(100, 100)));
+    e.assert_centered(4, 2);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if no transient placement is decided for parent which is not placed in any workspace.
+#[test]
+fn test_not_deciding_placement_over_detached_parent() {
+    let e = Environment::create_floating();
+    let parent = Frame::new_leaf(SurfaceId::new(1), Geometry::Stacked);
+    let surface = e.coordinator.get_surface(SurfaceId::new(2)).unwrap();
+    assert!(Strategist::default().choose_transient(&parent, &surface).is_none());
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -0,0 +1,160 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Unit tests of assigning workspaces to outputs and moving workspaces between displays.
+
+extern crate qualia;
+extern crate frames;
+extern crate exhibitor;
+extern crate testing;
+
+use qualia::OutputInfo;
+use qualia::{Action, Command, Direction};
+use qualia::{Area, Position, Size};
+use qualia::ExhibitorConfig;
+use frames::Frame;
+use exhibitor::{Exhibitor, Strategist};
+use testing::output_mock::OutputMock;
+use testing::coordinator_mock::CoordinatorMock;
+
+// -------------------------------------------------------------------------------------------------
+
+struct Environment {
+    exhibitor: Exhibitor<CoordinatorMock>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Environment {
+    /// Creates environment with workspaces pinned to outputs as given in `workspaces`.
+    pub fn create(workspaces: &[(&str, &str)]) -> Self {
+        let mut config = ExhibitorConfig::default();
+        for &(title, output) in workspaces.iter() {
+            config.compositor.workspaces.insert(title.to_owned(), output.to_owned());
+        }
+
+        let coordinator = CoordinatorMock::new();
+        Environment { exhibitor: Exhibitor::new(coordinator, Strategist::default(), config) }
+    }
+
+    /// Connects output with given ID, connector name and model. Every output has unique serial
+    /// number.
+    pub fn connect(&mut self, id: i32, name: &str, model: &str) {
+        let mut info = OutputInfo::new(id,
+                                       Area::new(Position::default(), Size::new(100, 100)),
+                                       Size::new(100, 100),
+                                       60_000,
+                                       "test_make".to_owned(),
+                                       model.to_owned());
+        info.name = name.to_owned();
+        info.serial = format!("serial_{}", id);
+        self.exhibitor.on_output_found(Box::new(OutputMock::new(info)));
+    }
+
+    /// Executes command with given action, direction, magnitude and string.
+    pub fn execute(&mut self, action: Action, direction: Direction, magnitude: i32, string: &str) {
+        self.exhibitor.on_command(Command {
+            action: action,
+            direction: direction,
+            magnitude: magnitude,
+            string: string.to_owned(),
+        });
+    }
+
+    /// Returns titles of workspaces of each display. Displays are ordered from left to right.
+    pub fn get_workspace_titles(&self) -> Vec<Vec<String>> {
+        let mut displays: Vec<Frame> = self.exhibitor.get_root().space_iter().collect();
+        displays.sort_by_key(|display| display.get_area().pos.x);
+        displays.iter()
+            .map(|display| display.space_iter().map(|workspace| workspace.get_title()).collect())
+            .collect()
+    }
+
+    /// Returns title of the workspace containing selection.
+    pub fn get_selected_title(&self) -> String {
+        self.exhibitor.get_selection().get_title()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if new workspace is created on the output it is pinned to instead of the current one.
+#[test]
+fn test_creating_pinned_workspace_on_its_output() {
+    let mut e = Environment::create(&[("mail", "connector-1")]);
+    e.connect(1, "connector-1", "model_a");
+    e.connect(2, "connector-2", "model_a");
+    assert_eq!(e.get_selected_title(), "2");
+
+    e.execute(Action::Focus, Direction::Workspace, 1, "mail");
+    assert_eq!(e.get_workspace_titles(),
+               vec![vec!["mail".to_owned(), "1".to_owned()], vec!["2".to_owned()]]);
+    assert_eq!(e.get_selected_title(), "mail");
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if workspace pinned to disconnected output falls back to a remaining output and comes
+/// back when its output is connected again. Output is identified by its make and model only.
+#[test]
+fn test_moving_pinned_workspace_back_to_reconnected_output() {
+    let mut e = Environment::create(&[("mail", "test_make model_b")]);
+    e.connect(1, "connector-1", "model_a");
+    e.execute(Action::Focus, Direction::Workspace, 1, "mail");
+    assert_eq!(e.get_workspace_titles(), vec![vec!["mail".to_owned(), "1".to_owned()]]);
+
+    e.connect(2, "connector-2", "model_b");
+    assert_eq!(e.get_workspace_titles(), vec![vec!["1".to_owned()], vec!["mail".to_owned()]]);
+    assert_eq!(e.get_selected_title(), "mail");
+
+    e.exhibitor.on_output_lost(2);
+    assert_eq!(e.get_workspace_titles(), vec![vec!["mail".to_owned(), "1".to_owned()]]);
+    assert_eq!(e.get_selected_title(), "mail");
+
+    e.connect(3, "connector-3", "model_b");
+    assert_eq!(e.get_workspace_titles(), vec![vec!["1".to_owned()], vec!["mail".to_owned()]]);
+    assert_eq!(e.get_selected_title(), "mail");
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if current workspace is moved to display in given direction and new workspace is created
+/// on the display left without workspaces.
+#[test]
+fn test_transferring_workspace_to_display_in_direction() {
+    let mut e = Environment::create(&[]);
+    e.connect(1, "connector-1", "model_a");
+    e.connect(2, "connector-2", "model_a");
+    assert_eq!(e.get_selected_title(), "2");
+
+    e.execute(Action::Transfer, Direction::North, 1, "");
+    assert_eq!(e.get_workspace_titles(), vec![vec!["1".to_owned()], vec!["2".to_owned()]]);
+
+    e.execute(Action::Transfer, Direction::West, 1, "");
+    assert_eq!(e.get_workspace_titles(),
+               vec![vec!["2".to_owned(), "1".to_owned()], vec!["3".to_owned()]]);
+    assert_eq!(e.get_selected_title(), "2");
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if focus is changed to the next or previous display and wraps around.
+#[test]
+fn test_focusing_adjacent_displays() {
+    let mut e = Environment::create(&[]);
+    e.connect(1, "connector-1", "model_a");
+    e.connect(2, "connector-2", "model_a");
+    e.connect(3, "connector-3", "model_a");
+    assert_eq!(e.get_selected_title(), "3");
+
+    e.execute(Action::Focus, Direction::Display, 1, "");
+    assert_eq!(e.get_selected_title(), "1");
+
+    e.execute(Action::Focus, Direction::Display, 1, "");
+    assert_eq!(e.get_selected_title(), "2");
+
+    e.execute(Action::Focus, Direction::Display, -2, "");
+    assert_eq!(e.get_selected_title(), "3");
+}
+
+// -------------------------------------------------------------------------------------------------
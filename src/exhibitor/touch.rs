@@ -0,0 +1,157 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! This module contains functionality related to touch input like mapping touch points to outputs
+//! and surfaces.
+
+// -------------------------------------------------------------------------------------------------
+
+use std::collections::HashMap;
+
+use qualia::{Milliseconds, Position, Slide, SurfaceId, Touch as TouchEvent};
+use qualia::{perceptron, Perceptron, ExhibitorCoordinationTrait};
+
+use display::Display;
+
+// -------------------------------------------------------------------------------------------------
+
+/// State of single touch point.
+struct TouchPoint {
+    /// ID of the display touch point was placed on.
+    display_id: i32,
+
+    /// ID of the surface which received touch down.
+    sid: SurfaceId,
+
+    /// Global position of the origin of the surface. Touch point stays assigned to the surface
+    /// even if it moves outside of it.
+    origin: Position,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// State of the touch device.
+pub struct Touch<C> where C: ExhibitorCoordinationTrait {
+    /// Touch points currently in contact with device identified by slot.
+    points: HashMap<i32, TouchPoint>,
+
+    /// Surfaces which received events since last frame.
+    frame_sids: Vec<SurfaceId>,
+
+    /// Coordinator.
+    coordinator: C,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl<C> Touch<C> where C: ExhibitorCoordinationTrait {
+    /// `Touch` constructor.
+    pub fn new(coordinator: C) -> Self {
+        Touch {
+            points: HashMap::new(),
+            frame_sids: Vec::new(),
+            coordinator: coordinator,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Input handlers.
+impl<C> Touch<C> where C: ExhibitorCoordinationTrait {
+    /// Handles new touch point. Finds display the touch device is mapped to and the surface under
+    /// the touch point.
+    pub fn on_down(&mut self, touch: TouchEvent, displays: &HashMap<i32, Display<C>>) {
+        let display_id = match Self::find_display(&touch.output, displays) {
+            Some(display_id) => display_id,
+            None => return,
+        };
+
+        let position = Self::to_global(&displays[&display_id], touch.position);
+        if let Some((sid, relative)) = displays[&display_id].get_surface_at(position) {
+            let point = TouchPoint {
+                display_id: display_id,
+                sid: sid,
+                origin: position - relative,
+            };
+            self.points.insert(touch.slot, point);
+            self.add_frame_sid(sid);
+            self.coordinator.emit(perceptron::TOUCH_DOWN,
+                                  Perceptron::TouchDown(sid, touch.slot, relative, touch.time));
+        }
+    }
+
+    /// Handles motion of touch point.
+    pub fn on_motion(&mut self, touch: TouchEvent, displays: &HashMap<i32, Display<C>>) {
+        let (sid, relative) = if let Some(point) = self.points.get(&touch.slot) {
+            if let Some(display) = displays.get(&point.display_id) {
+                (point.sid, Self::to_global(display, touch.position) - point.origin)
+            } else {
+                return;
+            }
+        } else {
+            return;
+        };
+
+        self.add_frame_sid(sid);
+        self.coordinator.emit(perceptron::TOUCH_MOTION,
+                              Perceptron::TouchMotion(sid, touch.slot, relative, touch.time));
+    }
+
+    /// Handles end of touch point.
+    pub fn on_up(&mut self, slot: i32, time: Milliseconds) {
+        if let Some(point) = self.points.remove(&slot) {
+            self.add_frame_sid(point.sid);
+            self.coordinator.emit(perceptron::TOUCH_UP, Perceptron::TouchUp(point.sid, slot, time));
+        }
+    }
+
+    /// Handles end of set of touch events. Informs every surface which received any of them.
+    pub fn on_frame(&mut self) {
+        for sid in self.frame_sids.drain(..) {
+            self.coordinator.emit(perceptron::TOUCH_FRAME, Perceptron::TouchFrame(sid));
+        }
+    }
+
+    /// Forgets touch points assigned to destroyed surface.
+    pub fn on_surface_destroyed(&mut self, sid: SurfaceId) {
+        self.points.retain(|_, point| point.sid != sid);
+        self.frame_sids.retain(|frame_sid| *frame_sid != sid);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Helper methods.
+impl<C> Touch<C> where C: ExhibitorCoordinationTrait {
+    /// Finds display with given connector name or identity. If name is not given or there is no
+    /// such display, the one with the lowest ID is chosen.
+    fn find_display(name: &Option<String>, displays: &HashMap<i32, Display<C>>) -> Option<i32> {
+        if let Some(ref name) = *name {
+            for (id, display) in displays.iter() {
+                let info = display.get_info();
+                if info.matches(name) {
+                    return Some(*id);
+                }
+            }
+            log_warn3!("Touch device mapped to unknown output '{}'", name);
+        }
+        displays.keys().min().cloned()
+    }
+
+    /// Converts position given as fraction of the device size to global coordinates.
+    fn to_global(display: &Display<C>, position: Slide) -> Position {
+        let area = display.get_info().area;
+        Position::new(area.pos.x + (position.x * area.size.width as f32) as isize,
+                      area.pos.y + (position.y * area.size.height as f32) as isize)
+    }
+
+    /// Remembers surface to inform it about end of the frame.
+    fn add_frame_sid(&mut self, sid: SurfaceId) {
+        if !self.frame_sids.contains(&sid) {
+            self.frame_sids.push(sid);
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -5,7 +5,7 @@
 
 // -------------------------------------------------------------------------------------------------
 
-use qualia::{Size, Vector};
+use qualia::{Area, Position, Size, SizeHints, Vector};
 use qualia::{SurfaceAccess, surface_state};
 
 use frame::{Frame, Geometry};
@@ -20,11 +20,19 @@ pub trait Packing {
 
     /// Make all subsurfaces have the same size and proper layout.
     /// Homogenizing works only on directed frames.
+    ///
+    /// Size hints of surfaces are respected: space which could not be used by surface is given to
+    /// its siblings and surface smaller than its slot is centered inside it.
     fn homogenize(&mut self, sa: &mut SurfaceAccess);
 
-    /// Set size of the frame and resize its subframe accordingly.
+    /// Set size of the frame (limited by size hints of its surface) and resize its subframe
+    /// accordingly.
     fn set_size(&mut self, size: Size, sa: &mut SurfaceAccess);
 
+    /// Set position and size of the frame and move and resize its subframes accordingly. Used for
+    /// example to shrink display when part of it was reserved for panels.
+    fn set_area(&mut self, area: Area, sa: &mut SurfaceAccess);
+
     /// Remove given frame and relax old parent.
     fn remove_self(&mut self, sa: &mut SurfaceAccess);
 }
@@ -42,38 +50,52 @@ impl Packing for Frame {
             return;
         }
 
+        // Gather size hints of twigs
+        let hints: Vec<SizeHints> = self.space_iter()
+            .map(|frame| get_size_hints(&frame, sa))
+            .collect();
+
         // Decide how to resize and move twigs
-        let mut size = Size::new(0, 0);
-        let mut increment = Vector::new(0, 0);
-        match self.get_geometry() {
-            Geometry::Stacked => {
-                size = self.get_size();
-            }
+        let size = self.get_size();
+        let slots: Vec<Size> = match self.get_geometry() {
+            Geometry::Stacked => vec![size; len],
             Geometry::Vertical => {
-                size.width = self.get_size().width;
-                size.height = self.get_size().height / len;
-                increment.y = size.height as isize;
+                let limits: Vec<(usize, usize)> = hints.iter()
+                    .map(|h| (h.min_size.height, h.max_size.height))
+                    .collect();
+                distribute(size.height, &limits)
+                    .iter()
+                    .map(|height| Size::new(size.width, *height))
+                    .collect()
             }
             Geometry::Horizontal => {
-                size.height = self.get_size().height;
-                size.width = self.get_size().width / len;
-                increment.x = size.width as isize;
+                let limits: Vec<(usize, usize)> = hints.iter()
+                    .map(|h| (h.min_size.width, h.max_size.width))
+                    .collect();
+                distribute(size.width, &limits)
+                    .iter()
+                    .map(|width| Size::new(*width, size.height))
+                    .collect()
             }
-        }
+        };
 
         // Resize and reposition all subframes recursively
         let mut pos = self.get_position();
-        for mut frame in self.space_iter() {
+        for (mut frame, slot) in self.space_iter().zip(slots.into_iter()) {
             if frame.is_anchored() {
-                frame.set_size(size.clone(), sa);
-                frame.set_position(pos.clone());
+                fit(&mut frame, pos, slot, sa);
+            }
+            match self.get_geometry() {
+                Geometry::Vertical => pos.y += slot.height as isize,
+                Geometry::Horizontal => pos.x += slot.width as isize,
+                Geometry::Stacked => {}
             }
-            pos = pos + increment.clone();
         }
     }
 
     fn set_size(&mut self, size: Size, sa: &mut SurfaceAccess) {
         // Set size for given frame.
+        let size = get_size_hints(self, sa).clamp(size);
         let old_size = self.get_size();
         self.set_plumbing_size(size.clone());
         sa.reconfigure(self.get_sid(), size.clone(), surface_state::MAXIMIZED);
@@ -103,13 +125,23 @@ impl Packing for Frame {
                 }
             }
             _ => {
+                let pos = self.get_position();
                 for mut frame in self.space_iter() {
-                    frame.set_size(size.clone(), sa);
+                    if frame.is_anchored() {
+                        fit(&mut frame, pos, size.clone(), sa);
+                    } else {
+                        frame.set_size(size.clone(), sa);
+                    }
                 }
             }
         }
     }
 
+    fn set_area(&mut self, area: Area, sa: &mut SurfaceAccess) {
+        self.set_position(area.pos);
+        self.set_size(area.size, sa);
+    }
+
     fn remove_self(&mut self, sa: &mut SurfaceAccess) {
         if let Some(ref mut parent) = self.get_parent() {
             self.remove();
@@ -124,3 +156,80 @@ impl Packing for Frame {
 }
 
 // -------------------------------------------------------------------------------------------------
+
+/// Returns size hints of surface contained in given frame. Only leafs have size hints.
+fn get_size_hints(frame: &Frame, sa: &SurfaceAccess) -> SizeHints {
+    let sid = frame.get_sid();
+    if frame.get_mode().is_leaf() && sid.is_valid() {
+        sa.get_size_hints(sid)
+    } else {
+        SizeHints::default()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Distributes given length between frames with given minimal and maximal lengths (zero maximum
+/// means no limit). Every frame gets the same share unless the share does not fit its limits. In
+/// such case the frame gets the limit and the rest of the length is shared between its siblings.
+fn distribute(length: usize, limits: &[(usize, usize)]) -> Vec<usize> {
+    let len = limits.len();
+    let mut result = vec![0; len];
+    let mut fixed = vec![false; len];
+
+    loop {
+        // Calculate length left for frames which fit their limits
+        let mut free_length = length;
+        let mut free_count = 0;
+        for (&is_fixed, &value) in fixed.iter().zip(result.iter()) {
+            if is_fixed {
+                free_length = free_length.saturating_sub(value);
+            } else {
+                free_count += 1;
+            }
+        }
+
+        if free_count == 0 {
+            break;
+        }
+
+        // Share the length and fix frames which do not fit their limits
+        let share = free_length / free_count;
+        let mut changed = false;
+        let states = result.iter_mut().zip(fixed.iter_mut());
+        for ((value, is_fixed), &(min, max)) in states.zip(limits.iter()) {
+            if !*is_fixed {
+                *value = if (max != 0) && (share > max) {
+                    *is_fixed = true;
+                    max
+                } else if share < min {
+                    *is_fixed = true;
+                    min
+                } else {
+                    share
+                };
+                changed = changed || *is_fixed;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    result
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Sets size of the frame to fill given slot as much as size hints allow and centers the frame
+/// inside the slot.
+fn fit(frame: &mut Frame, pos: Position, slot: Size, sa: &mut SurfaceAccess) {
+    frame.set_size(slot.clone(), sa);
+    let size = frame.get_size();
+    let offset = Vector::new(slot.width.saturating_sub(size.width) as isize / 2,
+                             slot.height.saturating_sub(size.height) as isize / 2);
+    frame.set_position(pos + offset);
+}
+
+// -------------------------------------------------------------------------------------------------
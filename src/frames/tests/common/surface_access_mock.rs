@@ -5,7 +5,9 @@
 
 // -------------------------------------------------------------------------------------------------
 
-use qualia::{SurfaceAccess, SurfaceId, Size, surface_state};
+use std::collections::HashMap;
+
+use qualia::{SizeHints, SurfaceAccess, SurfaceId, Size, surface_state};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -13,13 +15,20 @@ use qualia::{SurfaceAccess, SurfaceId, Size, surface_state};
 ///
 /// FIXME: Currently it is only stub. Test should be extended to also check `SurfaceAccess`
 /// functionality.
-pub struct SurfaceAccessMock {}
+pub struct SurfaceAccessMock {
+    size_hints: HashMap<SurfaceId, SizeHints>,
+}
 
 // -------------------------------------------------------------------------------------------------
 
 impl SurfaceAccessMock {
     pub fn new() -> Self {
-        SurfaceAccessMock {}
+        SurfaceAccessMock { size_hints: HashMap::new() }
+    }
+
+    /// Sets size hints to be returned for given surface.
+    pub fn set_size_hints(&mut self, sid: SurfaceId, size_hints: SizeHints) {
+        self.size_hints.insert(sid, size_hints);
     }
 }
 
@@ -31,6 +40,17 @@ impl SurfaceAccess for SurfaceAccessMock {
                    _size: Size,
                    _state_flags: surface_state::SurfaceState) {
     }
+
+    fn get_size_hints(&self, sid: SurfaceId) -> SizeHints {
+        match self.size_hints.get(&sid) {
+            Some(size_hints) => *size_hints,
+            None => SizeHints::default(),
+        }
+    }
+
+    fn is_transaction_pending(&self) -> bool {
+        false
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -12,11 +12,13 @@ extern crate testing;
 
 mod common;
 
+use frames::Frame;
+use frames::Geometry::{Horizontal, Stacked, Vertical};
 use frames::packing::Packing;
 
 use common::{assertions, layouts, surface_access_mock};
 
-use qualia::{Position, Size};
+use qualia::{Area, Position, Size, SizeHints, SurfaceId};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -87,3 +89,82 @@ fn test_removing_self_without_siblings() {
 }
 
 // -------------------------------------------------------------------------------------------------
+
+/// Test if homogenizing gives space not used by surface with maximal size to its siblings and
+/// centers the surface inside its slot.
+#[test]
+fn test_homogenizing_with_max_size() {
+    let mut sa = surface_access_mock::SurfaceAccessMock::new();
+    sa.set_size_hints(SurfaceId::new(2), SizeHints::new(Size::new(0, 0), Size::new(60, 100)));
+
+    let mut r = Frame::new_container(Horizontal);
+    let mut a = Frame::new_leaf(SurfaceId::new(1), Stacked);
+    let mut b = Frame::new_leaf(SurfaceId::new(2), Stacked);
+    let mut c = Frame::new_leaf(SurfaceId::new(3), Stacked);
+    r.append(&mut a);
+    r.append(&mut b);
+    r.append(&mut c);
+    r.set_plumbing_position_and_size(Position::new(0, 0), Size::new(300, 200));
+
+    r.homogenize(&mut sa);
+
+    assertions::assert_area(&a, Position::new(  0,  0), Size::new(120, 200));
+    assertions::assert_area(&b, Position::new(120, 50), Size::new( 60, 100));
+    assertions::assert_area(&c, Position::new(180,  0), Size::new(120, 200));
+
+    r.destroy();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Test if homogenizing takes space needed by surface with minimal size from its siblings.
+#[test]
+fn test_homogenizing_with_min_size() {
+    let mut sa = surface_access_mock::SurfaceAccessMock::new();
+    sa.set_size_hints(SurfaceId::new(1), SizeHints::new(Size::new(0, 200), Size::new(0, 0)));
+
+    let mut r = Frame::new_container(Vertical);
+    let mut a = Frame::new_leaf(SurfaceId::new(1), Stacked);
+    let mut b = Frame::new_leaf(SurfaceId::new(2), Stacked);
+    let mut c = Frame::new_leaf(SurfaceId::new(3), Stacked);
+    r.append(&mut a);
+    r.append(&mut b);
+    r.append(&mut c);
+    r.set_plumbing_position_and_size(Position::new(0, 0), Size::new(100, 300));
+
+    r.homogenize(&mut sa);
+
+    assertions::assert_area(&a, Position::new(0,   0), Size::new(100, 200));
+    assertions::assert_area(&b, Position::new(0, 200), Size::new(100,  50));
+    assertions::assert_area(&c, Position::new(0, 250), Size::new(100,  50));
+
+    r.destroy();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Test if setting area of display moves and resizes its workspace and the workspace's children.
+#[test]
+fn test_setting_area() {
+    let mut sa = surface_access_mock::SurfaceAccessMock::new();
+
+    let mut r = Frame::new_display(Area::create(0, 0, 300, 200), "display".to_owned());
+    let mut w = Frame::new_workspace("1".to_owned(), Horizontal);
+    let mut a = Frame::new_leaf(SurfaceId::new(1), Stacked);
+    let mut b = Frame::new_leaf(SurfaceId::new(2), Stacked);
+    r.append(&mut w);
+    w.append(&mut a);
+    w.append(&mut b);
+    r.homogenize(&mut sa);
+
+    r.set_area(Area::create(0, 30, 300, 170), &mut sa);
+
+    assertions::assert_area(&r, Position::new(  0, 30), Size::new(300, 170));
+    assertions::assert_area(&w, Position::new(  0, 30), Size::new(300, 170));
+    assertions::assert_area(&a, Position::new(  0, 30), Size::new(150, 170));
+    assertions::assert_area(&b, Position::new(150, 30), Size::new(150, 170));
+
+    r.destroy();
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -11,17 +11,61 @@ use libdrm::drm_mode;
 use std::collections::HashMap;
 use std::collections::VecDeque;
 
-use qualia::{Buffer, DrmBundle, Illusion, SurfaceContext, SurfaceViewer};
-use qualia::{Area, OutputInfo, Position, Size};
+use qualia::{Buffer, DrmBundle, Illusion, Region, SurfaceContext, SurfaceViewer};
+use qualia::{buffer_transform, Area, OutputConfig, OutputInfo, OutputMode, OutputModeInfo};
+use qualia::{Position, Size};
 use renderer_gl::{egl_tools, RendererGl};
 
 use gbm_tools::GbmBucket;
+use edid::Edid;
 use output::Output;
 
 // -------------------------------------------------------------------------------------------------
 
 const INVALID_FRAMEBUFFER: u32 = 0;
 
+/// Names of connector types used by kernel indexed by values of `DRM_MODE_CONNECTOR_*`.
+const CONNECTOR_TYPE_NAMES: [&'static str; 21] = ["Unknown",
+                                                  "VGA",
+                                                  "DVI-I",
+                                                  "DVI-D",
+                                                  "DVI-A",
+                                                  "Composite",
+                                                  "SVIDEO",
+                                                  "LVDS",
+                                                  "Component",
+                                                  "DIN",
+                                                  "DP",
+                                                  "HDMI-A",
+                                                  "HDMI-B",
+                                                  "TV",
+                                                  "eDP",
+                                                  "Virtual",
+                                                  "DSI",
+                                                  "DPI",
+                                                  "Writeback",
+                                                  "SPI",
+                                                  "USB"];
+
+// -------------------------------------------------------------------------------------------------
+
+/// Identification of display device connected to the output.
+#[derive(Clone, Debug)]
+struct Identity {
+    /// Name of the connector.
+    name: String,
+
+    make: String,
+    model: String,
+    serial: String,
+
+    /// Size of the display in millimeters.
+    physical_size: Size,
+
+    /// Modes described in EDID. The first one is preferred.
+    modes: Vec<OutputModeInfo>,
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// `DrmOutput` is representation of physical output device.
@@ -29,14 +73,20 @@ pub struct DrmOutput {
     /// Size of the output in pixels.
     size: Size,
 
-    /// Size of the output in millimeters.
-    physical_size: Size,
+    /// Identification of connected display device.
+    identity: Identity,
+
+    /// Position of the output in global logical coordinates.
+    position: Position,
 
     /// Id of the output. Guarantied to be unique in application.
     id: i32,
 
-    /// Name of the output.
-    name: String,
+    /// Integer scale of the output.
+    scale: i32,
+
+    /// Transformation of the output as defined by `wl_output.transform`.
+    transform: i32,
 
     /// Map from Buffer Object handle to Framebuffer id.
     buffers: HashMap<u32, u32>,
@@ -66,22 +116,160 @@ pub struct DrmOutput {
 // -------------------------------------------------------------------------------------------------
 
 impl DrmOutput {
-    /// Constructs new `DrmOutput`.
-    pub fn new(drm: DrmBundle, id: i32) -> Result<Box<Output>, Illusion> {
-        // Get size
-        let mode;
-        let size;
-        let modes;
-        let physical_size;
+    /// Constructs new `DrmOutput` using mode requested in configuration. If the mode is not
+    /// supported preferred mode is used.
+    pub fn new(drm: DrmBundle, id: i32, config: &OutputConfig) -> Result<Box<Output>, Illusion> {
+        let connector = Self::get_connector(drm)?;
+        let identity = Self::identify(drm, &connector);
+        let mode = Self::choose_mode(&connector, &identity, config.get_mode())?;
+        let output = Self::initialize(drm, id, mode, identity)?;
+        Ok(Box::new(output))
+    }
+
+    /// Returns info about output connected to given connector without initializing it. Area of the
+    /// output corresponds to its preferred mode.
+    pub fn probe(drm: DrmBundle, id: i32) -> Result<OutputInfo, Illusion> {
+        let connector = Self::get_connector(drm)?;
+        let identity = Self::identify(drm, &connector);
+        let mode = Self::choose_mode(&connector, &identity, None)?;
+        let size = Size::new(mode.get_hdisplay() as usize, mode.get_vdisplay() as usize);
+        Ok(Self::create_info(id, &identity, Area::new(Position::default(), size), &mode))
+    }
+
+    /// Returns name of the connector made of its type and index among connectors of the same type
+    /// (e.g. `eDP-1` or `HDMI-A-1`). This is the same name as used by kernel in `sysfs`.
+    pub fn get_connector_name(connector: &drm_mode::Connector) -> String {
+        let connector_type = connector.get_connector_type() as usize;
+        let type_name = CONNECTOR_TYPE_NAMES.get(connector_type).cloned().unwrap_or("Unknown");
+        format!("{}-{}", type_name, connector.get_connector_type_id())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Construction helpers
+impl DrmOutput {
+    /// Reads identification of display connected to the connector from its EDID. Connector name and
+    /// physical size reported by the connector are used if EDID is not available.
+    fn identify(drm: DrmBundle, connector: &drm_mode::Connector) -> Identity {
+        let name = Self::get_connector_name(connector);
+        let connector_size = Size::new(connector.get_mm_width() as usize,
+                                       connector.get_mm_height() as usize);
+        match Edid::read(drm.fd, &name) {
+            Ok(edid) => {
+                let physical_size = if edid.physical_size.is_zero() {
+                    connector_size
+                } else {
+                    edid.physical_size
+                };
+                Identity {
+                    name: name,
+                    make: edid.manufacturer,
+                    model: edid.model,
+                    serial: edid.serial,
+                    physical_size: physical_size,
+                    modes: edid.modes
+                        .iter()
+                        .map(|mode| OutputModeInfo::new(mode.size, mode.refresh as usize))
+                        .collect(),
+                }
+            }
+            Err(err) => {
+                log_warn2!("Failed to read EDID of connector {}: {}", name, err);
+                Identity {
+                    make: name.clone(),
+                    model: name.clone(),
+                    name: name,
+                    serial: String::new(),
+                    physical_size: connector_size,
+                    modes: Vec::new(),
+                }
+            }
+        }
+    }
+
+    /// Calculates exact refresh rate of the mode in mHz.
+    fn get_refresh_rate(mode: &drm_mode::ModeInfo) -> usize {
+        let total = mode.get_htotal() as u64 * mode.get_vtotal() as u64;
+        if total > 0 {
+            ((1_000_000 * mode.get_clock() as u64 + total / 2) / total) as usize
+        } else {
+            1000 * mode.get_vrefresh() as usize
+        }
+    }
+
+    /// Creates info about output.
+    fn create_info(id: i32,
+                   identity: &Identity,
+                   area: Area,
+                   mode: &drm_mode::ModeInfo)
+                   -> OutputInfo {
+        let mut info = OutputInfo::new(id,
+                                       area,
+                                       identity.physical_size,
+                                       Self::get_refresh_rate(mode),
+                                       identity.make.clone(),
+                                       identity.model.clone());
+        info.name = identity.name.clone();
+        info.serial = identity.serial.clone();
+        info.modes = identity.modes.clone();
+        info
+    }
+
+    /// Fetches information about connector.
+    fn get_connector(drm: DrmBundle) -> Result<drm_mode::Connector, Illusion> {
         if let Some(connector) = drm_mode::get_connector(drm.fd, drm.connector_id) {
-            modes = connector.get_modes();
-            mode = modes.get(0).unwrap().clone();
-            size = Size::new(mode.get_hdisplay() as usize, mode.get_vdisplay() as usize);
-            physical_size = Size::new(connector.get_mm_width() as usize,
-                                      connector.get_mm_height() as usize);
+            Ok(connector)
         } else {
-            return Err(Illusion::General(format!("Failed to get mode for connector")));
+            Err(Illusion::General(format!("Failed to get connector {}", drm.connector_id)))
         }
+    }
+
+    /// Chooses mode of the output. Requested mode is chosen if it is supported. Otherwise mode
+    /// preferred in EDID or, if not available, the first mode reported by connector is used.
+    fn choose_mode(connector: &drm_mode::Connector,
+                   identity: &Identity,
+                   requested: Option<OutputMode>)
+                   -> Result<drm_mode::ModeInfo, Illusion> {
+        let modes = connector.get_modes();
+        if let Some(requested) = requested {
+            let found = modes.iter().find(|mode| {
+                mode.get_hdisplay() as usize == requested.width &&
+                mode.get_vdisplay() as usize == requested.height &&
+                requested.refresh.map_or(true, |refresh| mode.get_vrefresh() as u32 == refresh)
+            });
+            if let Some(mode) = found {
+                return Ok(mode.clone());
+            }
+            log_warn1!("Mode {:?} not supported by connector {}",
+                       requested,
+                       connector.get_connector_id());
+        }
+
+        if let Some(preferred) = identity.modes.first() {
+            let found = modes.iter().find(|mode| {
+                let size = Size::new(mode.get_hdisplay() as usize, mode.get_vdisplay() as usize);
+                preferred.matches(size, Self::get_refresh_rate(mode))
+            });
+            if let Some(mode) = found {
+                return Ok(mode.clone());
+            }
+        }
+
+        if let Some(mode) = modes.get(0) {
+            Ok(mode.clone())
+        } else {
+            Err(Illusion::General(format!("Failed to get mode for connector")))
+        }
+    }
+
+    /// Initializes output with given mode.
+    fn initialize(drm: DrmBundle,
+                  id: i32,
+                  mode: drm_mode::ModeInfo,
+                  identity: Identity)
+                  -> Result<DrmOutput, Illusion> {
+        let size = Size::new(mode.get_hdisplay() as usize, mode.get_vdisplay() as usize);
 
         // GBM
         let gbm = GbmBucket::new(drm.fd, size.clone())?;
@@ -97,8 +285,10 @@ impl DrmOutput {
         let mut mine = DrmOutput {
             id: id,
             size: size,
-            physical_size: physical_size,
-            name: "".to_owned(),
+            identity: identity,
+            position: Position::default(),
+            scale: 1,
+            transform: buffer_transform::NORMAL,
             renderer: renderer,
             mode: mode,
             drm: drm,
@@ -112,7 +302,7 @@ impl DrmOutput {
         mine.renderer.initialize()?;
         mine.swap_buffers()?;
 
-        Ok(Box::new(mine))
+        Ok(mine)
     }
 }
 
@@ -125,27 +315,47 @@ impl Output for DrmOutput {
             layunder: &Vec<SurfaceContext>,
             surfaces: &Vec<SurfaceContext>,
             layover: &Vec<SurfaceContext>,
+            damage: &Region,
             viewer: &SurfaceViewer)
             -> Result<(), Illusion> {
-        self.renderer.draw(layunder, surfaces, layover, viewer)
+        self.renderer.draw(layunder, surfaces, layover, damage, viewer)
     }
 
-    /// Takes screenshot. Returns `Buffer` containing image data.
-    fn take_screenshot(&self) -> Result<Buffer, Illusion> {
-        self.renderer.take_screenshot()
+    /// Takes screenshot of given area. Returns `Buffer` containing image data.
+    fn take_screenshot(&self, area: Area) -> Result<Buffer, Illusion> {
+        self.renderer.take_screenshot(area)
     }
 
-    /// Returns info about output.
+    /// Returns info about output. Area of the output is given in logical coordinates.
     fn get_info(&self) -> OutputInfo {
-        // TODO: Make Output aware of its position.
-        let area = Area::new(Position::new(0, 0), self.size.clone());
+        let size = buffer_transform::logical_size(self.size, self.scale, self.transform);
+        let area = Area::new(self.position, size);
+
+        let mut info = Self::create_info(self.id, &self.identity, area, &self.mode);
+        info.scale = self.scale;
+        info.transform = self.transform;
+        info
+    }
+
+    /// Sets integer scale of the output.
+    fn set_scale(&mut self, scale: i32) {
+        self.scale = if scale > 0 { scale } else { 1 };
+        self.renderer.set_scale(self.scale);
+    }
+
+    /// Sets transformation of the output.
+    fn set_transform(&mut self, transform: i32) {
+        self.transform = if buffer_transform::is_valid(transform) {
+            transform
+        } else {
+            buffer_transform::NORMAL
+        };
+        self.renderer.set_transform(self.transform);
+    }
 
-        OutputInfo::new(self.id,
-                        area,
-                        self.physical_size,
-                        60, // TODO: make output aware of its refresh rate.
-                        self.name.clone(),
-                        self.name.clone())
+    /// Sets position of the output.
+    fn set_position(&mut self, position: Position) {
+        self.position = position;
     }
 
     /// Swaps renderers and devices buffers.
@@ -172,9 +382,15 @@ impl Output for DrmOutput {
         }
     }
 
-    /// Reinitializes the output.
+    /// Reinitializes the output. Mode, position, scale and transformation of the output are
+    /// preserved.
     fn recreate(&self) -> Result<Box<Output>, Illusion> {
-        DrmOutput::new(self.drm, self.id)
+        let mut output =
+            DrmOutput::initialize(self.drm, self.id, self.mode.clone(), self.identity.clone())?;
+        output.set_position(self.position);
+        output.set_scale(self.scale);
+        output.set_transform(self.transform);
+        Ok(Box::new(output))
     }
 
 }
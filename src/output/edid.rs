@@ -0,0 +1,250 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! This module contains parser of EDID (Extended Display Identification Data) describing display
+//! devices connected to outputs.
+
+// -------------------------------------------------------------------------------------------------
+
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io;
+use std::path::Path;
+
+use qualia::{Illusion, Size};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Size of base EDID block and extension blocks.
+const BLOCK_SIZE: usize = 128;
+
+/// Fixed header of base EDID block.
+const HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+
+/// Offset of the first descriptor in base block.
+const DESCRIPTORS_OFFSET: usize = 54;
+
+/// Number of descriptors in base block.
+const NUM_DESCRIPTORS: usize = 4;
+
+/// Size of detailed timing descriptor or display descriptor.
+const DESCRIPTOR_SIZE: usize = 18;
+
+/// Offset of number of extension blocks in base block.
+const NUM_EXTENSIONS_OFFSET: usize = 126;
+
+/// Tag of CEA-861 extension block.
+const CEA_EXTENSION_TAG: u8 = 0x02;
+
+/// Tag of display descriptor containing serial number string.
+const SERIAL_DESCRIPTOR_TAG: u8 = 0xFF;
+
+/// Tag of display descriptor containing name of the display.
+const NAME_DESCRIPTOR_TAG: u8 = 0xFC;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Display mode described by detailed timing descriptor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdidMode {
+    /// Size of the mode in pixels.
+    pub size: Size,
+
+    /// Refresh rate in mHz calculated from pixel clock and total size of the mode.
+    pub refresh: u32,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Information about display device parsed from EDID.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edid {
+    /// Three letter code of manufacturer.
+    pub manufacturer: String,
+
+    /// Name of the display or product code if name was not given.
+    pub model: String,
+
+    /// Serial number of the display. Empty if not given.
+    pub serial: String,
+
+    /// Size of the display in millimeters. Zero if not given.
+    pub physical_size: Size,
+
+    /// Modes described by detailed timing descriptors in base and CEA-861 extension blocks. The
+    /// first one is the preferred mode.
+    pub modes: Vec<EdidMode>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Edid {
+    /// Parses EDID blob. Extension blocks other than CEA-861 are ignored.
+    pub fn parse(data: &[u8]) -> Result<Self, Illusion> {
+        if data.len() < BLOCK_SIZE {
+            return Err(Illusion::InvalidArgument(format!("EDID too short: {}", data.len())));
+        }
+
+        let base = &data[0..BLOCK_SIZE];
+        if base[0..HEADER.len()] != HEADER {
+            return Err(Illusion::InvalidArgument("Invalid EDID header".to_owned()));
+        }
+        Self::verify_checksum(base)?;
+
+        let mut edid = Edid {
+            manufacturer: Self::parse_manufacturer(base[8], base[9]),
+            model: format!("0x{:04x}", (base[11] as u16) << 8 | base[10] as u16),
+            serial: String::new(),
+            physical_size: Size::new(10 * base[21] as usize, 10 * base[22] as usize),
+            modes: Vec::new(),
+        };
+
+        let serial_number = (base[15] as u32) << 24 | (base[14] as u32) << 16 |
+                            (base[13] as u32) << 8 | base[12] as u32;
+        if serial_number != 0 {
+            edid.serial = format!("{}", serial_number);
+        }
+
+        for i in 0..NUM_DESCRIPTORS {
+            let offset = DESCRIPTORS_OFFSET + i * DESCRIPTOR_SIZE;
+            edid.parse_descriptor(&base[offset..offset + DESCRIPTOR_SIZE]);
+        }
+
+        let num_extensions = base[NUM_EXTENSIONS_OFFSET] as usize;
+        for i in 1..(num_extensions + 1) {
+            let offset = i * BLOCK_SIZE;
+            if data.len() < offset + BLOCK_SIZE {
+                break;
+            }
+
+            let block = &data[offset..offset + BLOCK_SIZE];
+            if block[0] == CEA_EXTENSION_TAG && Self::verify_checksum(block).is_ok() {
+                edid.parse_cea_extension(block);
+            }
+        }
+
+        Ok(edid)
+    }
+
+    /// Reads and parses EDID of display connected to connector with given name (e.g. `HDMI-A-1`)
+    /// of DRM device. EDID is read from `/sys/dev/char/<major>:<minor>/<card>-<name>/edid`.
+    pub fn read(fd: io::RawFd, connector_name: &str) -> Result<Self, Illusion> {
+        let rdev = fs::metadata(format!("/proc/self/fd/{}", fd))?.rdev();
+        let major = ((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff);
+        let minor = (rdev & 0xff) | ((rdev >> 12) & !0xff);
+        let device_dir = fs::canonicalize(format!("/sys/dev/char/{}:{}", major, minor))?;
+
+        let card = if let Some(card) = device_dir.file_name().and_then(|name| name.to_str()) {
+            card.to_owned()
+        } else {
+            return Err(Illusion::General(format!("Invalid DRM device {:?}", device_dir)));
+        };
+
+        let path = device_dir.join(format!("{}-{}", card, connector_name)).join("edid");
+        Self::parse(&Self::read_file(&path)?)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Parsing helpers.
+impl Edid {
+    /// Checks if sum of all bytes of the block is zero.
+    fn verify_checksum(block: &[u8]) -> Result<(), Illusion> {
+        let sum = block.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        if sum == 0 {
+            Ok(())
+        } else {
+            Err(Illusion::InvalidArgument("Invalid EDID checksum".to_owned()))
+        }
+    }
+
+    /// Decodes manufacturer ID made of three five-bit letters.
+    fn parse_manufacturer(high: u8, low: u8) -> String {
+        let id = (high as u16) << 8 | low as u16;
+        [(id >> 10) & 0x1F, (id >> 5) & 0x1F, id & 0x1F]
+            .iter()
+            .map(|code| (b'A' - 1 + *code as u8) as char)
+            .collect()
+    }
+
+    /// Parses detailed timing descriptor or display descriptor.
+    fn parse_descriptor(&mut self, descriptor: &[u8]) {
+        if descriptor[0] == 0 && descriptor[1] == 0 {
+            let text = Self::parse_descriptor_text(&descriptor[5..DESCRIPTOR_SIZE]);
+            match descriptor[3] {
+                NAME_DESCRIPTOR_TAG if !text.is_empty() => self.model = text,
+                SERIAL_DESCRIPTOR_TAG if !text.is_empty() => self.serial = text,
+                _ => {}
+            }
+        } else if let Some(mode) = Self::parse_detailed_timing(descriptor) {
+            // Image size given with preferred mode is more precise than the one in basic block
+            if self.modes.is_empty() {
+                let width = descriptor[12] as usize | ((descriptor[14] as usize & 0xF0) << 4);
+                let height = descriptor[13] as usize | ((descriptor[14] as usize & 0x0F) << 8);
+                if width != 0 && height != 0 {
+                    self.physical_size = Size::new(width, height);
+                }
+            }
+            self.modes.push(mode);
+        }
+    }
+
+    /// Parses text of display descriptor. Text is terminated by line feed and padded with spaces.
+    fn parse_descriptor_text(data: &[u8]) -> String {
+        let text: String = data.iter()
+            .take_while(|byte| **byte != b'\n')
+            .map(|byte| *byte as char)
+            .collect();
+        text.trim().to_owned()
+    }
+
+    /// Parses detailed timing descriptor. Interlaced modes are skipped.
+    fn parse_detailed_timing(descriptor: &[u8]) -> Option<EdidMode> {
+        let clock = 10_000 * ((descriptor[1] as u64) << 8 | descriptor[0] as u64);
+        let width = descriptor[2] as u64 | ((descriptor[4] as u64 & 0xF0) << 4);
+        let hblank = descriptor[3] as u64 | ((descriptor[4] as u64 & 0x0F) << 8);
+        let height = descriptor[5] as u64 | ((descriptor[7] as u64 & 0xF0) << 4);
+        let vblank = descriptor[6] as u64 | ((descriptor[7] as u64 & 0x0F) << 8);
+        let interlaced = (descriptor[17] & 0x80) != 0;
+
+        let total = (width + hblank) * (height + vblank);
+        if interlaced || total == 0 {
+            return None;
+        }
+
+        Some(EdidMode {
+            size: Size::new(width as usize, height as usize),
+            refresh: ((1000 * clock + total / 2) / total) as u32,
+        })
+    }
+
+    /// Parses detailed timing descriptors from CEA-861 extension block.
+    fn parse_cea_extension(&mut self, block: &[u8]) {
+        let mut offset = block[2] as usize;
+        if offset < 4 {
+            return;
+        }
+
+        while offset + DESCRIPTOR_SIZE < BLOCK_SIZE {
+            let descriptor = &block[offset..offset + DESCRIPTOR_SIZE];
+            if descriptor[0] == 0 && descriptor[1] == 0 {
+                break;
+            }
+            if let Some(mode) = Self::parse_detailed_timing(descriptor) {
+                self.modes.push(mode);
+            }
+            offset += DESCRIPTOR_SIZE;
+        }
+    }
+
+    /// Reads whole file.
+    fn read_file(path: &Path) -> Result<Vec<u8>, Illusion> {
+        let mut data = Vec::new();
+        fs::File::open(path)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -11,6 +11,9 @@ extern crate egl;
 extern crate drm as libdrm;
 extern crate gbm_rs as libgbm;
 
+#[macro_use]
+extern crate timber;
+#[macro_use]
 extern crate qualia;
 extern crate dharma;
 extern crate renderer_gl;
@@ -20,6 +23,9 @@ pub mod gbm_tools;
 mod output;
 pub use output::Output;
 
+mod edid;
+pub use edid::{Edid, EdidMode};
+
 mod drm_output;
 pub use drm_output::DrmOutput;
 
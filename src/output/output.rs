@@ -3,26 +3,41 @@
 
 //! This module contains interface for all output devices or mocks.
 
-use qualia::{Buffer, Illusion, OutputInfo, SurfaceContext, SurfaceViewer};
+use qualia::{Area, Buffer, Illusion, OutputInfo, Position, Region, SurfaceContext, SurfaceViewer};
 
 // -------------------------------------------------------------------------------------------------
 
 /// `Output` is representation of physical output device.
 pub trait Output {
-    /// Draws passed scene using renderer.
+    /// Draws passed scene using renderer. Only `damage` (given in logical output coordinates) and
+    /// areas not yet updated in current back buffer are repainted.
     fn draw(&mut self,
             layunder: &Vec<SurfaceContext>,
             surfaces: &Vec<SurfaceContext>,
             layover: &Vec<SurfaceContext>,
+            damage: &Region,
             viewer: &SurfaceViewer)
             -> Result<(), Illusion>;
 
-    /// Takes screenshot. Returns `Buffer` containing image data.
-    fn take_screenshot(&self) -> Result<Buffer, Illusion>;
+    /// Takes screenshot of given area (in logical output coordinates) of the most recently drawn
+    /// frame. Must be called after drawing and before swapping buffers. Returns `Buffer`
+    /// containing image data.
+    fn take_screenshot(&self, area: Area) -> Result<Buffer, Illusion>;
 
     /// Returns info about output.
     fn get_info(&self) -> OutputInfo;
 
+    /// Sets integer scale of output. Surfaces are placed in logical coordinates which are scaled
+    /// up when drawing.
+    fn set_scale(&mut self, scale: i32);
+
+    /// Sets transformation of output as defined by `wl_output.transform`. Rotated outputs have
+    /// width and height of their logical area swapped.
+    fn set_transform(&mut self, transform: i32);
+
+    /// Sets position of output in global logical coordinates.
+    fn set_position(&mut self, position: Position);
+
     /// Swaps buffers.
     fn swap_buffers(&mut self) -> Result<u32, Illusion>;
 
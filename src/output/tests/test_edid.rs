@@ -0,0 +1,116 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Unit tests of EDID parser.
+
+extern crate qualia;
+extern crate output;
+
+use qualia::Size;
+use output::{Edid, EdidMode};
+
+// -------------------------------------------------------------------------------------------------
+
+/// EDID with display name and serial number string descriptors and 1920x1080 mode at 60 Hz.
+const EDID_WITH_NAME: [u8; 128] = [
+    0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x40, 0x74, 0x34, 0x12, 0x04, 0x03, 0x02, 0x01,
+    0x01, 0x1c, 0x01, 0x04, 0xa5, 0x35, 0x1e, 0x78, 0x3a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x02, 0x3a, 0x80, 0x18, 0x71, 0x38, 0x2d, 0x40, 0x58, 0x2c,
+    0x45, 0x00, 0x0f, 0x28, 0x21, 0x00, 0x00, 0x1e, 0x00, 0x00, 0x00, 0xfc, 0x00, 0x54, 0x65, 0x73,
+    0x74, 0x20, 0x4d, 0x6f, 0x6e, 0x69, 0x74, 0x6f, 0x72, 0x0a, 0x00, 0x00, 0x00, 0xff, 0x00, 0x41,
+    0x42, 0x43, 0x31, 0x32, 0x33, 0x0a, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0xfd,
+    0x00, 0x38, 0x4c, 0x1e, 0x53, 0x11, 0x00, 0x0a, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x5d,
+];
+
+/// EDID without display name with 1920x1080 mode at 59.94 Hz and CEA-861 extension block
+/// containing progressive 1280x720 mode and interlaced 1920x1080 mode.
+const EDID_WITH_EXTENSION: [u8; 256] = [
+    0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x63, 0x3a, 0xcd, 0xab, 0x39, 0x30, 0x00, 0x00,
+    0x01, 0x1c, 0x01, 0x04, 0xa5, 0x3c, 0x22, 0x78, 0x3a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0xf3, 0x39, 0x80, 0x18, 0x71, 0x38, 0x2d, 0x40, 0x58, 0x2c,
+    0x45, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1e, 0x00, 0x00, 0x00, 0xfd, 0x00, 0x38, 0x4c, 0x1e,
+    0x53, 0x11, 0x00, 0x0a, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xf2,
+    0x02, 0x03, 0x04, 0x00, 0x01, 0x1d, 0x00, 0x72, 0x51, 0xd0, 0x1e, 0x20, 0x58, 0x2c, 0x45, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x1e, 0x01, 0x1d, 0x80, 0x18, 0x71, 0x1c, 0x16, 0x20, 0x58, 0x2c,
+    0x45, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x9e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x41,
+];
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if make, model, serial, physical size and modes are parsed from display descriptors and
+/// detailed timing descriptors.
+#[test]
+fn test_parsing_edid_with_display_name() {
+    let edid = Edid::parse(&EDID_WITH_NAME).unwrap();
+    assert_eq!(edid.manufacturer, "PCT");
+    assert_eq!(edid.model, "Test Monitor");
+    assert_eq!(edid.serial, "ABC123");
+    assert_eq!(edid.physical_size, Size::new(527, 296));
+    assert_eq!(edid.modes,
+               vec![EdidMode {
+                        size: Size::new(1920, 1080),
+                        refresh: 60000,
+                    }]);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if product code, serial number and physical size from basic block are used when there are
+/// no display descriptors and if progressive modes from extension block are added.
+#[test]
+fn test_parsing_edid_with_extension() {
+    let edid = Edid::parse(&EDID_WITH_EXTENSION).unwrap();
+    assert_eq!(edid.manufacturer, "XYZ");
+    assert_eq!(edid.model, "0xabcd");
+    assert_eq!(edid.serial, "12345");
+    assert_eq!(edid.physical_size, Size::new(600, 340));
+    assert_eq!(edid.modes,
+               vec![EdidMode {
+                        size: Size::new(1920, 1080),
+                        refresh: 59939,
+                    },
+                    EdidMode {
+                        size: Size::new(1280, 720),
+                        refresh: 60000,
+                    }]);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if extension block is ignored when it is missing.
+#[test]
+fn test_parsing_edid_with_truncated_extension() {
+    let edid = Edid::parse(&EDID_WITH_EXTENSION[0..128]).unwrap();
+    assert_eq!(edid.modes,
+               vec![EdidMode {
+                        size: Size::new(1920, 1080),
+                        refresh: 59939,
+                    }]);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if too short blobs and blobs with invalid header or checksum are rejected.
+#[test]
+fn test_rejecting_invalid_edid() {
+    assert!(Edid::parse(&EDID_WITH_NAME[0..100]).is_err());
+
+    let mut data = EDID_WITH_NAME;
+    data[0] = 0xff;
+    assert!(Edid::parse(&data).is_err());
+
+    let mut data = EDID_WITH_NAME;
+    data[60] = data[60].wrapping_add(1);
+    assert!(Edid::parse(&data).is_err());
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -12,13 +12,13 @@ use device_manager::DeviceManager;
 
 // -------------------------------------------------------------------------------------------------
 
-pub struct DeviceManagerModule<'a> {
-    manager: DeviceManager<'a>,
+pub struct DeviceManagerModule {
+    manager: DeviceManager,
 }
 
 // -------------------------------------------------------------------------------------------------
 
-impl<'a> DeviceManagerModule<'a> {
+impl DeviceManagerModule {
     /// `DeviceManagerModule` constructor.
     pub fn new(context: &mut Context) -> Self {
         DeviceManagerModule { manager: DeviceManager::new(context.clone()) }
@@ -27,12 +27,16 @@ impl<'a> DeviceManagerModule<'a> {
 
 // -------------------------------------------------------------------------------------------------
 
-impl<'a> Module for DeviceManagerModule<'a> {
+impl Module for DeviceManagerModule {
     type T = Perceptron;
     type C = Context;
 
     fn initialize(&mut self) -> InitResult {
-        vec![perceptron::SUSPEND, perceptron::WAKEUP]
+        vec![perceptron::SUSPEND,
+             perceptron::WAKEUP,
+             perceptron::INPUT_DEVICE_ADDED,
+             perceptron::INPUT_DEVICE_REMOVED,
+             perceptron::OUTPUT_DEVICE_CHANGED]
     }
 
     // FIXME: Finnish handling signals in `DeviceManagerModule`.
@@ -40,6 +44,15 @@ impl<'a> Module for DeviceManagerModule<'a> {
         match *package {
             Perceptron::Suspend => self.manager.on_suspend(),
             Perceptron::WakeUp => self.manager.on_wakeup(),
+            Perceptron::InputDeviceAdded(ref bundle) => {
+                self.manager.on_input_device_added(bundle)
+            }
+            Perceptron::InputDeviceRemoved(ref syspath) => {
+                self.manager.on_input_device_removed(syspath)
+            }
+            Perceptron::OutputDeviceChanged(ref devnode) => {
+                self.manager.on_output_device_changed(devnode)
+            }
             _ => {}
         }
     }
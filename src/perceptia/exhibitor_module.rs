@@ -5,8 +5,10 @@
 
 // -------------------------------------------------------------------------------------------------
 
+use std::collections::HashMap;
+
 use dharma::{InitResult, Module, ModuleConstructor};
-use qualia::{DrmBundle, perceptron, Perceptron};
+use qualia::{DrmBundle, ExhibitorConfig, perceptron, Perceptron};
 use output::DrmOutput;
 use coordination::{Context, Coordinator};
 use exhibitor::{Exhibitor, Strategist};
@@ -17,6 +19,10 @@ use exhibitor::{Exhibitor, Strategist};
 pub struct ExhibitorModule {
     last_output_id: i32,
     exhibitor: Exhibitor<Coordinator>,
+    config: ExhibitorConfig,
+
+    /// IDs of outputs indexed by device file descriptor and connector ID.
+    output_ids: HashMap<(i32, u32), i32>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -30,7 +36,9 @@ impl ExhibitorModule {
             last_output_id: 0,
             exhibitor: Exhibitor::new(coordinator,
                                       Strategist::new_from_config(config.strategist.clone()),
-                                      config.compositor.clone()),
+                                      config.clone()),
+            config: config.clone(),
+            output_ids: HashMap::new(),
         }
     }
 }
@@ -48,13 +56,20 @@ impl Module for ExhibitorModule {
              perceptron::WAKEUP,
              perceptron::PAGE_FLIP,
              perceptron::OUTPUT_FOUND,
+             perceptron::OUTPUT_LOST,
              perceptron::COMMAND,
              perceptron::INPUT_POINTER_MOTION,
              perceptron::INPUT_POINTER_POSITION,
              perceptron::INPUT_POINTER_BUTTON,
              perceptron::INPUT_POINTER_POSITION_RESET,
+             perceptron::INPUT_TOUCH_DOWN,
+             perceptron::INPUT_TOUCH_MOTION,
+             perceptron::INPUT_TOUCH_UP,
+             perceptron::INPUT_TOUCH_FRAME,
              perceptron::CURSOR_SURFACE_CHANGE,
              perceptron::BACKGROUND_SURFACE_CHANGE,
+             perceptron::DRAG_ICON_SURFACE_CHANGE,
+             perceptron::LAYER_SURFACE_CHANGE,
              perceptron::SURFACE_READY,
              perceptron::SURFACE_DESTROYED,
              perceptron::KEYBOARD_FOCUS_CHANGED,
@@ -65,15 +80,25 @@ impl Module for ExhibitorModule {
         match *package {
             Perceptron::Notify => self.exhibitor.on_notify(),
             Perceptron::OutputFound(bundle) => self.on_output_found(bundle),
-            Perceptron::PageFlip(id) => self.exhibitor.on_pageflip(id),
+            Perceptron::OutputLost(bundle) => self.on_output_lost(bundle),
+            Perceptron::PageFlip(presentation) => self.exhibitor.on_pageflip(presentation),
             Perceptron::Command(ref command) => self.exhibitor.on_command(command.clone()),
 
             Perceptron::InputPointerMotion(ref vector) => self.exhibitor.on_motion(vector.clone()),
             Perceptron::InputPointerPosition(ref pos) => self.exhibitor.on_position(pos.clone()),
             Perceptron::InputPointerButton(ref btn) => self.exhibitor.on_button(btn.clone()),
             Perceptron::InputPointerPositionReset => self.exhibitor.on_position_reset(),
+            Perceptron::InputTouchDown(ref touch) => self.exhibitor.on_touch_down(touch.clone()),
+            Perceptron::InputTouchMotion(ref touch) => {
+                self.exhibitor.on_touch_motion(touch.clone())
+            }
+            Perceptron::InputTouchUp(slot, time) => self.exhibitor.on_touch_up(slot, time),
+            Perceptron::InputTouchFrame => self.exhibitor.on_touch_frame(),
 
             Perceptron::CursorSurfaceChange(sid) => self.exhibitor.on_cursor_surface_change(sid),
+            Perceptron::DragIconSurfaceChange(sid) => {
+                self.exhibitor.on_drag_icon_surface_change(sid)
+            }
 
             Perceptron::SurfaceReady(sid) => self.exhibitor.on_surface_ready(sid),
             Perceptron::SurfaceDestroyed(sid) => self.exhibitor.on_surface_destroyed(sid),
@@ -83,10 +108,13 @@ impl Module for ExhibitorModule {
             }
             Perceptron::Suspend => self.exhibitor.on_suspend(),
             Perceptron::WakeUp => self.exhibitor.on_wakeup(),
-            Perceptron::TakeScreenshot(id) => self.exhibitor.take_screenshot(id),
+            Perceptron::TakeScreenshot(request) => self.exhibitor.take_screenshot(request),
             Perceptron::BackgroundSurfaceChange(sid) => {
                 self.exhibitor.on_background_surface_change(sid);
             }
+            Perceptron::LayerSurfaceChange(sid, info) => {
+                self.exhibitor.on_layer_surface_change(sid, info);
+            }
             _ => {}
         }
     }
@@ -100,14 +128,29 @@ impl Module for ExhibitorModule {
 
 // Event handling helpers
 impl ExhibitorModule {
-    /// Helper method for handling new output.
+    /// Helper method for handling new output. Outputs disabled in configuration are ignored.
     ///
     /// For unit testing construction of the output must be done outside `Exhibitor`.
     fn on_output_found(&mut self, bundle: DrmBundle) {
+        let info = match DrmOutput::probe(bundle, self.last_output_id + 1) {
+            Ok(info) => info,
+            Err(err) => {
+                log_error!("Could not probe output: {}", err);
+                return;
+            }
+        };
+
+        let config = self.config.get_output_config(&info);
+        if !config.enabled {
+            log_info1!("Output '{}' is disabled", info.name);
+            return;
+        }
+
         self.last_output_id += 1;
-        match DrmOutput::new(bundle, self.last_output_id) {
+        match DrmOutput::new(bundle, self.last_output_id, config) {
             Ok(output) => {
                 log_info2!("Created output: {}", output.get_info().make);
+                self.output_ids.insert((bundle.fd, bundle.connector_id), self.last_output_id);
                 self.exhibitor.on_output_found(output);
             }
             Err(err) => {
@@ -116,6 +159,13 @@ impl ExhibitorModule {
             }
         }
     }
+
+    /// Helper method for handling lost output.
+    fn on_output_lost(&mut self, bundle: DrmBundle) {
+        if let Some(id) = self.output_ids.remove(&(bundle.fd, bundle.connector_id)) {
+            self.exhibitor.on_output_lost(id);
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -37,9 +37,23 @@ fn main() {
 
     // Prepare tools
     let env = qualia::Env::create(qualia::LogDestination::LogFile);
-    let config = env.read_config();
-    let keymap = qualia::Keymap::new(&env, config.get_keyboard_config()).unwrap();
-    let settings = qualia::Settings::new(keymap.get_settings());
+    let mut config = env.read_config();
+    config.replace_invalid_keyboard_configs(|keyboard_config| {
+        qualia::keymap::XkbKeymap::new(keyboard_config).is_some()
+    });
+    let keymaps: Vec<(qualia::KeyboardConfig, qualia::Keymap)> = config.get_keyboard_configs()
+        .into_iter()
+        .filter_map(|keyboard_config| match qualia::Keymap::new(&env, &keyboard_config) {
+            Ok(keymap) => Some((keyboard_config, keymap)),
+            Err(err) => {
+                log_error!("Failed to create key map for {:?}: {}", keyboard_config, err);
+                None
+            }
+        })
+        .collect();
+    let settings = qualia::Settings::new(keymaps.iter()
+        .map(|&(ref keyboard_config, ref keymap)| (keyboard_config.clone(), keymap.get_settings()))
+        .collect());
 
     // Prepare state
     let signaler = Signaler::new();
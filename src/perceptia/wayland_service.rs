@@ -71,6 +71,7 @@ impl WaylandService {
     fn initialize(&mut self) {
         self.context.get_signaler().register(&self.receiver);
         for s in vec![perceptron::DISPLAY_CREATED,
+                      perceptron::DISPLAY_DESTROYED,
                       perceptron::INPUT_KEYBOARD,
                       perceptron::INPUT_POINTER_BUTTON,
                       perceptron::INPUT_POINTER_AXIS,
@@ -78,7 +79,13 @@ impl WaylandService {
                       perceptron::POINTER_FOCUS_CHANGED,
                       perceptron::POINTER_RELATIVE_MOTION,
                       perceptron::KEYBOARD_FOCUS_CHANGED,
+                      perceptron::TOUCH_DOWN,
+                      perceptron::TOUCH_MOTION,
+                      perceptron::TOUCH_UP,
+                      perceptron::TOUCH_FRAME,
+                      perceptron::KEYMAP_CHANGED,
                       perceptron::SURFACE_RECONFIGURED,
+                      perceptron::SURFACE_STATE_APPLIED,
                       perceptron::SCREENSHOT_DONE] {
             self.context.get_signaler().subscribe(s, &self.receiver);
         }
@@ -140,6 +147,9 @@ impl WaylandService {
             Perceptron::DisplayCreated(info) => {
                 self.engine.on_display_created(info);
             }
+            Perceptron::DisplayDestroyed(id) => {
+                self.engine.on_display_destroyed(id);
+            }
             Perceptron::InputKeyboard(key) => {
                 self.engine.on_keyboard_input(key, None);
             }
@@ -149,8 +159,8 @@ impl WaylandService {
             Perceptron::InputPointerAxis(axis) => {
                 self.engine.on_pointer_axis(axis);
             }
-            Perceptron::SurfaceFrame(sid, milliseconds) => {
-                self.engine.on_surface_frame(sid, milliseconds);
+            Perceptron::SurfaceFrame(sid, presentation) => {
+                self.engine.on_surface_frame(sid, presentation);
             }
             Perceptron::PointerFocusChanged(old_sid, new_sid, pos) => {
                 self.engine.on_pointer_focus_changed(old_sid, new_sid, pos);
@@ -161,13 +171,31 @@ impl WaylandService {
             Perceptron::KeyboardFocusChanged(old_sid, new_sid) => {
                 self.engine.on_keyboard_focus_changed(old_sid, new_sid);
             }
+            Perceptron::TouchDown(sid, id, pos, time) => {
+                self.engine.on_touch_down(sid, id, pos, time);
+            }
+            Perceptron::TouchMotion(sid, id, pos, time) => {
+                self.engine.on_touch_motion(sid, id, pos, time);
+            }
+            Perceptron::TouchUp(sid, id, time) => {
+                self.engine.on_touch_up(sid, id, time);
+            }
+            Perceptron::TouchFrame(sid) => {
+                self.engine.on_touch_frame(sid);
+            }
+            Perceptron::KeymapChanged(config) => {
+                self.engine.on_keymap_changed(config);
+            }
             Perceptron::SurfaceReconfigured(sid) => {
                 if let Some(info) = self.context.get_coordinator().get_surface(sid) {
                     self.engine.on_surface_reconfigured(sid, info.desired_size, info.state_flags);
                 }
             }
-            Perceptron::ScreenshotDone => {
-                self.engine.on_screenshot_done();
+            Perceptron::SurfaceStateApplied(sid, serial) => {
+                self.engine.on_surface_state_applied(sid, serial);
+            }
+            Perceptron::ScreenshotDone(id) => {
+                self.engine.on_screenshot_done(id);
             }
             _ => {}
         }
@@ -84,7 +84,14 @@ fn print_connectors(fd: io::RawFd, resources: &drm_mode::Resources) {
     println!("\t\tcount connectors: {}", resources.get_count_connectors());
     for id in resources.get_connectors() {
         match drm_mode::get_connector(fd, id) {
-            Some(connector) => println!("\t\t - {:?}", connector),
+            Some(connector) => {
+                println!("\t\t - {:?}", connector);
+                let name = output::DrmOutput::get_connector_name(&connector);
+                println!("\t\t   name: {}", name);
+                if connector.get_connection() == drm_mode::Connection::Connected {
+                    print_edid(fd, &name);
+                }
+            }
             None => println!("\t\t - failed to get info"),
         }
     }
@@ -92,6 +99,29 @@ fn print_connectors(fd: io::RawFd, resources: &drm_mode::Resources) {
 
 // -------------------------------------------------------------------------------------------------
 
+fn print_edid(fd: io::RawFd, connector_name: &str) {
+    match output::Edid::read(fd, connector_name) {
+        Ok(edid) => {
+            println!("\t\t   manufacturer: {}", edid.manufacturer);
+            println!("\t\t   model: {}", edid.model);
+            println!("\t\t   serial: {}", edid.serial);
+            println!("\t\t   physical size: {}x{} mm",
+                     edid.physical_size.width,
+                     edid.physical_size.height);
+            for mode in edid.modes.iter() {
+                println!("\t\t   mode: {}x{} @ {}.{:03} Hz",
+                         mode.size.width,
+                         mode.size.height,
+                         mode.refresh / 1000,
+                         mode.refresh % 1000);
+            }
+        }
+        Err(err) => println!("\t\t   failed to read EDID: {}", err),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 fn print_drm_info(fd: io::RawFd) {
     println!("\tDRM info:");
 
@@ -469,6 +469,88 @@ pub fn focus_workspace(context: &mut InputContext) {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Focuses the workspace following the current one on the same display.
+pub fn focus_next_workspace(context: &mut InputContext) {
+    context.set_action(Action::Focus);
+    context.set_direction(Direction::Workspace);
+    context.set_magnitude(1);
+    context.set_string(String::new());
+    context.execute_command();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Focuses the workspace preceding the current one on the same display.
+pub fn focus_previous_workspace(context: &mut InputContext) {
+    context.set_action(Action::Focus);
+    context.set_direction(Direction::Workspace);
+    context.set_magnitude(-1);
+    context.set_string(String::new());
+    context.execute_command();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Focuses the display following the current one.
+pub fn focus_next_display(context: &mut InputContext) {
+    context.set_action(Action::Focus);
+    context.set_direction(Direction::Display);
+    context.set_magnitude(1);
+    context.execute_command();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Focuses the display preceding the current one.
+pub fn focus_previous_display(context: &mut InputContext) {
+    context.set_action(Action::Focus);
+    context.set_direction(Direction::Display);
+    context.set_magnitude(-1);
+    context.execute_command();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Executes command moving current workspace to display on the right.
+pub fn transfer_workspace_right(context: &mut InputContext) {
+    context.set_action(Action::Transfer);
+    context.set_direction(Direction::East);
+    context.set_magnitude(1);
+    context.execute_command();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Executes command moving current workspace to display below.
+pub fn transfer_workspace_down(context: &mut InputContext) {
+    context.set_action(Action::Transfer);
+    context.set_direction(Direction::South);
+    context.set_magnitude(1);
+    context.execute_command();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Executes command moving current workspace to display on the left.
+pub fn transfer_workspace_left(context: &mut InputContext) {
+    context.set_action(Action::Transfer);
+    context.set_direction(Direction::West);
+    context.set_magnitude(1);
+    context.execute_command();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Executes command moving current workspace to display above.
+pub fn transfer_workspace_up(context: &mut InputContext) {
+    context.set_action(Action::Transfer);
+    context.set_direction(Direction::North);
+    context.set_magnitude(1);
+    context.execute_command();
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Switches normal mode off and insert mode on.
 pub fn swap_mode_normal_to_insert(context: &mut InputContext) {
     log_info2!("Swap mode from normal to insert");
@@ -6,14 +6,17 @@
 // -------------------------------------------------------------------------------------------------
 
 use std;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use yaml_rust;
 use serde_yaml;
 use serde::ser::{Serialize, Serializer, SerializeMap};
 
-use defs::modifier;
-use input_manager::Binding;
+use defs::{modifier, OutputInfo, Position};
+use input_manager::{Binding, GestureBinding};
+use enums::{DeviceKind, Direction};
 use binding_functions;
+use surface::buffer_transform;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -33,6 +36,11 @@ macro_rules! load_config {
             $config.$key = value as u32;
         }
     };
+    ( _entry_ $config:expr; $section:expr; $key:ident: bool ) => {
+        if let Some(value) = $section[stringify!($key)].as_bool() {
+            $config.$key = value;
+        }
+    };
     ( _entry_ $config:expr; $section:expr; $key:ident: f32 ) => {
         if let Some(value) = $section[stringify!($key)].as_f64() {
             $config.$key = value as f32;
@@ -102,6 +110,53 @@ impl BindingEntry {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Helper structure used to keep configuration entry for one touchpad gesture binding.
+pub struct GestureBindingEntry {
+    pub mode_name: &'static str,
+    pub binding: GestureBinding,
+    pub executor: binding_functions::Executor,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Manually implement `Clone` for the same reason as for `BindingEntry`.
+impl Clone for GestureBindingEntry {
+    fn clone(&self) -> Self {
+        GestureBindingEntry {
+            mode_name: self.mode_name,
+            binding: self.binding.clone(),
+            executor: self.executor,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl std::fmt::Debug for GestureBindingEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self.binding)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl GestureBindingEntry {
+    /// `GestureBindingEntry` constructor.
+    pub fn new(mode_name: &'static str,
+               fingers: u32,
+               direction: Direction,
+               executor: binding_functions::Executor)
+               -> Self {
+        GestureBindingEntry {
+            mode_name: mode_name,
+            binding: GestureBinding::new(fingers, direction),
+            executor: executor,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Configuration of aesthetics.
 #[derive(Clone, Debug, Serialize)]
 pub struct AestheticsConfig {
@@ -116,6 +171,10 @@ pub struct AestheticsConfig {
 pub struct CompositorConfig {
     /// Distance in pixels by which frames are moved by `move` command.
     pub move_step: u32,
+
+    /// Outputs on which workspaces with given titles are placed. Outputs are identified by their
+    /// connector names or identities.
+    pub workspaces: HashMap<String, String>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -128,6 +187,116 @@ pub struct ExhibitorConfig {
 
     /// Configuration of strategist.
     pub strategist: StrategistConfig,
+
+    /// Configuration used for outputs not listed in `outputs`.
+    pub output: OutputConfig,
+
+    /// Configuration of outputs identified by their connector names or identities.
+    pub outputs: HashMap<String, OutputConfig>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl ExhibitorConfig {
+    /// Returns configuration for given output. Configuration is searched by connector name first
+    /// and then by identities of the output from the most specific one.
+    pub fn get_output_config(&self, info: &OutputInfo) -> &OutputConfig {
+        if let Some(config) = self.outputs.get(&info.name) {
+            return config;
+        }
+        for identity in info.get_identities() {
+            if let Some(config) = self.outputs.get(&identity) {
+                return config;
+            }
+        }
+        &self.output
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Configuration of pointer acceleration.
+#[derive(Clone, Debug, Serialize)]
+pub struct AccelerationConfig {
+    /// Acceleration profile. `flat` multiplies motion by constant factor, `adaptive` makes the
+    /// factor depend on velocity of the device.
+    pub profile: String,
+
+    /// Factor by which motion is multiplied. For `adaptive` profile this is the factor used when
+    /// device moves with moderate velocity.
+    pub speed: f32,
+
+    /// Exponent of velocity in `adaptive` profile. The bigger it is the more fast moves are
+    /// accelerated and slow moves decelerated. Zero makes `adaptive` profile equal to `flat`.
+    pub curve: f32,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Information identifying input device.
+#[derive(Clone, Debug)]
+pub struct InputDeviceInfo {
+    /// Name of the device as reported by kernel.
+    pub name: String,
+
+    /// Vendor ID.
+    pub vendor: u32,
+
+    /// Product ID.
+    pub product: u32,
+
+    /// Kind of the device.
+    pub kind: DeviceKind,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Configuration of input device overriding global settings for devices matching given name,
+/// vendor and product ID or kind. Criteria not given are not checked. Settings not given are not
+/// overridden.
+#[derive(Clone, Debug, Serialize)]
+pub struct InputDeviceConfig {
+    /// Name of the device.
+    pub name: Option<String>,
+
+    /// Vendor ID of the device.
+    pub vendor: Option<u32>,
+
+    /// Product ID of the device.
+    pub product: Option<u32>,
+
+    /// Kind of the device (`keyboard`, `mouse`, `touchpad` or `touchscreen`).
+    pub kind: Option<String>,
+
+    /// Scale for motion values. Overrides `mouse_scale` or `touchpad_scale`.
+    pub scale: Option<f32>,
+
+    /// Configuration of pointer acceleration.
+    pub acceleration: Option<AccelerationConfig>,
+
+    /// If `true` scrolling direction is reversed.
+    pub natural_scrolling: Option<bool>,
+
+    /// If `true` left and right buttons are swapped.
+    pub left_handed: Option<bool>,
+
+    /// Method of scrolling using touchpad.
+    pub scroll_method: Option<String>,
+
+    /// Configuration of keyboard layout.
+    pub keyboard: Option<KeyboardConfig>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl InputDeviceConfig {
+    /// Checks if this configuration applies to given device.
+    pub fn matches(&self, info: &InputDeviceInfo) -> bool {
+        self.name.as_ref().map_or(true, |name| *name == info.name) &&
+        self.vendor.map_or(true, |vendor| vendor == info.vendor) &&
+        self.product.map_or(true, |product| product == info.product) &&
+        self.kind.as_ref().map_or(true, |kind| *kind == format!("{:?}", info.kind))
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -142,15 +311,138 @@ pub struct InputConfig {
     /// Threshold value for touchpad pressure below which move events will be ignored.
     pub touchpad_pressure_threshold: i32,
 
-    /// Scale for mouse event motion values.
-    /// In future will be replaced by non-linear scale per dimension.
+    /// Threshold value for pressure of single finger above which the touch is considered to be a
+    /// palm and ignored.
+    pub touchpad_palm_pressure_threshold: i32,
+
+    /// If `true` short touches of touchpad generate button clicks.
+    pub touchpad_tap_to_click: bool,
+
+    /// If `true` touchpad is disabled for a short time after a key was pressed.
+    pub touchpad_disable_while_typing: bool,
+
+    /// Scale for mouse event motion values. Acceleration is applied on top of it.
     pub mouse_scale: f32,
+
+    /// Configuration of pointer acceleration.
+    pub acceleration: AccelerationConfig,
+
+    /// If `true` scrolling direction is reversed, so content follows fingers.
+    pub natural_scrolling: bool,
+
+    /// If `true` left and right buttons are swapped.
+    pub left_handed: bool,
+
+    /// Method of scrolling using touchpad: `two_finger`, `edge` or `none`.
+    pub scroll_method: String,
+
+    /// Configuration of specific devices. All matching entries are applied in order.
+    pub devices: Vec<InputDeviceConfig>,
 }
 
 // -------------------------------------------------------------------------------------------------
 
-/// Configuration of keyboard.
+impl InputConfig {
+    /// Returns effective configuration for given device.
+    pub fn get_device_config(&self, info: &InputDeviceInfo) -> InputConfig {
+        let mut config = self.clone();
+        for device in self.devices.iter().filter(|device| device.matches(info)) {
+            if let Some(scale) = device.scale {
+                config.mouse_scale = scale;
+                config.touchpad_scale = scale;
+            }
+            if let Some(ref acceleration) = device.acceleration {
+                config.acceleration = acceleration.clone();
+            }
+            if let Some(natural_scrolling) = device.natural_scrolling {
+                config.natural_scrolling = natural_scrolling;
+            }
+            if let Some(left_handed) = device.left_handed {
+                config.left_handed = left_handed;
+            }
+            if let Some(ref scroll_method) = device.scroll_method {
+                config.scroll_method = scroll_method.clone();
+            }
+        }
+        config
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Configuration of output.
 #[derive(Clone, Debug, Serialize)]
+pub struct OutputConfig {
+    /// If `false` the output is not used.
+    pub enabled: bool,
+
+    /// Position of top left corner of output in global logical coordinates. If not given output
+    /// is placed to the right of already existing outputs.
+    pub position: Option<Position>,
+
+    /// Mode of output in format `<width>x<height>` or `<width>x<height>@<refresh rate>`. If not
+    /// given or not supported by output preferred mode is used.
+    pub mode: String,
+
+    /// Integer scale of output. Clients are asked to render their surfaces in this scale.
+    pub scale: i32,
+
+    /// Transformation of output (`normal`, `90`, `180`, `270`, `flipped`, `flipped-90`,
+    /// `flipped-180` or `flipped-270`).
+    pub transform: String,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Mode of output requested in configuration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutputMode {
+    /// Width in pixels.
+    pub width: usize,
+
+    /// Height in pixels.
+    pub height: usize,
+
+    /// Refresh rate in Hz. If not given any refresh rate is accepted.
+    pub refresh: Option<u32>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl OutputConfig {
+    /// Parses requested mode. Returns `None` if mode was not given or is invalid.
+    pub fn get_mode(&self) -> Option<OutputMode> {
+        let mut parts = self.mode.split('@');
+        let resolution = parts.next().unwrap_or("");
+        let refresh = match parts.next() {
+            Some(refresh) => {
+                match refresh.trim().parse::<f32>() {
+                    Ok(refresh) if refresh > 0.0 => Some(refresh.round() as u32),
+                    _ => return None,
+                }
+            }
+            None => None,
+        };
+
+        let mut dimensions =
+            resolution.split('x').map(|dimension| dimension.trim().parse::<usize>());
+        match (dimensions.next(), dimensions.next(), dimensions.next(), parts.next()) {
+            (Some(Ok(width)), Some(Ok(height)), None, None) if width > 0 && height > 0 => {
+                Some(OutputMode {
+                    width: width,
+                    height: height,
+                    refresh: refresh,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Configuration of keyboard.
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct KeyboardConfig {
     pub layout: String,
     pub variant: String,
@@ -187,6 +479,9 @@ pub struct Config {
 
     /// Set of key bindings.
     bindings: Vec<BindingEntry>,
+
+    /// Set of touchpad gesture bindings.
+    gesture_bindings: Vec<GestureBindingEntry>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -197,7 +492,8 @@ impl Config {
                exhibitor: ExhibitorConfig,
                input: InputConfig,
                keyboard: KeyboardConfig,
-               bindings: Vec<BindingEntry>)
+               bindings: Vec<BindingEntry>,
+               gesture_bindings: Vec<GestureBindingEntry>)
                -> Self {
         Config {
             aesthetics: aesthetics,
@@ -205,6 +501,7 @@ impl Config {
             input: input,
             keyboard: keyboard,
             bindings: bindings,
+            gesture_bindings: gesture_bindings,
         }
     }
 
@@ -221,21 +518,149 @@ impl Config {
                 move_step: u32
             }
 
+            if let Some(workspaces) = yaml["exhibitor"]["workspaces"].as_hash() {
+                for (title, output) in workspaces.iter() {
+                    let title = match *title {
+                        yaml_rust::Yaml::String(ref title) => title.clone(),
+                        yaml_rust::Yaml::Integer(title) => title.to_string(),
+                        _ => continue,
+                    };
+                    if let Some(output) = output.as_str() {
+                        self.exhibitor.compositor.workspaces.insert(title, output.to_owned());
+                    }
+                }
+            }
+
             load_config!{self.exhibitor.strategist; yaml["exhibitor"]["strategist"];
                 choose_target: String,
                 choose_floating: String
             }
 
+            Self::load_output_config(&mut self.exhibitor.output, &yaml["exhibitor"]["output"]);
+
+            if let Some(outputs) = yaml["exhibitor"]["outputs"].as_hash() {
+                for (name, section) in outputs.iter() {
+                    if let Some(name) = name.as_str() {
+                        let mut output = self.exhibitor
+                            .outputs
+                            .get(name)
+                            .unwrap_or(&self.exhibitor.output)
+                            .clone();
+                        Self::load_output_config(&mut output, section);
+                        self.exhibitor.outputs.insert(name.to_owned(), output);
+                    }
+                }
+            }
+
             load_config!{self.input; yaml["input"];
                 touchpad_scale: f32,
                 touchpad_pressure_threshold: i32,
-                mouse_scale: f32
+                touchpad_palm_pressure_threshold: i32,
+                touchpad_tap_to_click: bool,
+                touchpad_disable_while_typing: bool,
+                mouse_scale: f32,
+                natural_scrolling: bool,
+                left_handed: bool,
+                scroll_method: String
+            }
+
+            load_config!{self.input.acceleration; yaml["input"]["acceleration"];
+                profile: String,
+                speed: f32,
+                curve: f32
             }
 
             load_config!{self.keyboard; yaml["keyboard"];
                 layout: String,
                 variant: String
             }
+
+            if let Some(devices) = yaml["input"]["devices"].as_vec() {
+                for section in devices.iter() {
+                    let device = self.load_device_config(section);
+                    self.input.devices.push(device);
+                }
+            }
+        }
+    }
+
+    /// Loads configuration of output. Position is given as mapping with `x` and `y` keys.
+    fn load_output_config(output: &mut OutputConfig, section: &yaml_rust::Yaml) {
+        load_config!{output; section;
+            enabled: bool,
+            mode: String,
+            scale: i32
+        }
+
+        // Rotations are likely to be written as numbers.
+        let transform = &section["transform"];
+        let name = match (transform.as_str(), transform.as_i64()) {
+            (Some(name), _) => Some(name.to_owned()),
+            (None, Some(angle)) => Some(angle.to_string()),
+            _ => None,
+        };
+        if let Some(name) = name {
+            if buffer_transform::from_name(&name).is_some() {
+                output.transform = name;
+            } else {
+                log_error!("Config error: unknown output transform '{}'", name);
+            }
+        }
+
+        let position = &section["position"];
+        if let (Some(x), Some(y)) = (position["x"].as_i64(), position["y"].as_i64()) {
+            output.position = Some(Position::new(x as isize, y as isize));
+        }
+    }
+
+    /// Loads configuration of input device. Acceleration and keyboard sections inherit settings
+    /// not given in them from global sections.
+    fn load_device_config(&self, section: &yaml_rust::Yaml) -> InputDeviceConfig {
+        let acceleration = if section["acceleration"].as_hash().is_some() {
+            let mut acceleration = self.input.acceleration.clone();
+            load_config!{acceleration; section["acceleration"];
+                profile: String,
+                speed: f32,
+                curve: f32
+            }
+            Some(acceleration)
+        } else {
+            None
+        };
+
+        let keyboard = if section["keyboard"].as_hash().is_some() {
+            let mut keyboard = self.keyboard.clone();
+            load_config!{keyboard; section["keyboard"];
+                layout: String,
+                variant: String
+            }
+            Some(keyboard)
+        } else {
+            None
+        };
+
+        InputDeviceConfig {
+            name: section["name"].as_str().map(|name| name.to_owned()),
+            vendor: Self::load_id(&section["vendor"]),
+            product: Self::load_id(&section["product"]),
+            kind: section["kind"].as_str().map(|kind| kind.to_owned()),
+            scale: section["scale"].as_f64().map(|scale| scale as f32),
+            acceleration: acceleration,
+            natural_scrolling: section["natural_scrolling"].as_bool(),
+            left_handed: section["left_handed"].as_bool(),
+            scroll_method: section["scroll_method"].as_str().map(|method| method.to_owned()),
+            keyboard: keyboard,
+        }
+    }
+
+    /// Loads device ID given as integer or hexadecimal string.
+    fn load_id(yaml: &yaml_rust::Yaml) -> Option<u32> {
+        if let Some(id) = yaml.as_i64() {
+            Some(id as u32)
+        } else if let Some(id) = yaml.as_str() {
+            u32::from_str_radix(id.trim_left_matches("0x"), 16).ok()
+        } else {
+            None
         }
     }
 
@@ -271,10 +696,59 @@ impl Config {
         &self.keyboard
     }
 
+    /// Returns effective keyboard configuration for given device.
+    pub fn get_device_keyboard_config(&self, info: &InputDeviceInfo) -> KeyboardConfig {
+        self.input
+            .devices
+            .iter()
+            .filter(|device| device.matches(info))
+            .filter_map(|device| device.keyboard.clone())
+            .last()
+            .unwrap_or(self.keyboard.clone())
+    }
+
+    /// Returns all distinct keyboard configurations. The global one is the first.
+    pub fn get_keyboard_configs(&self) -> Vec<KeyboardConfig> {
+        let mut configs = vec![self.keyboard.clone()];
+        for device in self.input.devices.iter() {
+            if let Some(ref keyboard) = device.keyboard {
+                if !configs.contains(keyboard) {
+                    configs.push(keyboard.clone());
+                }
+            }
+        }
+        configs
+    }
+
+    /// Replaces keyboard configurations rejected by `is_valid` with the default one, so that every
+    /// user of the configuration falls back to the same key map.
+    pub fn replace_invalid_keyboard_configs<F>(&mut self, is_valid: F)
+        where F: Fn(&KeyboardConfig) -> bool
+    {
+        if !is_valid(&self.keyboard) {
+            log_error!("Config error: invalid keyboard config {:?}, using default", self.keyboard);
+            self.keyboard = KeyboardConfig::default();
+        }
+
+        for device in self.input.devices.iter_mut() {
+            let invalid = device.keyboard.as_ref().map_or(false, |keyboard| !is_valid(keyboard));
+            if invalid {
+                log_error!("Config error: invalid keyboard config {:?}, using default",
+                           device.keyboard);
+                device.keyboard = Some(KeyboardConfig::default());
+            }
+        }
+    }
+
     /// Returns configuration for key binding.
     pub fn get_key_binding_config(&self) -> &Vec<BindingEntry> {
         &self.bindings
     }
+
+    /// Returns configuration for touchpad gesture bindings.
+    pub fn get_gesture_binding_config(&self) -> &Vec<GestureBindingEntry> {
+        &self.gesture_bindings
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -5,14 +5,16 @@
 
 // -------------------------------------------------------------------------------------------------
 
+use std::collections::HashMap;
 use std::default::Default;
 use uinput_sys;
 
 use defs::{modifier, mode_name};
+use enums::Direction;
 use binding_functions;
-use config::{BindingEntry, Config};
-use config::{AestheticsConfig, KeyboardConfig, InputConfig};
-use config::{CompositorConfig, ExhibitorConfig, StrategistConfig};
+use config::{BindingEntry, GestureBindingEntry, Config};
+use config::{AccelerationConfig, AestheticsConfig, KeyboardConfig, InputConfig};
+use config::{CompositorConfig, ExhibitorConfig, OutputConfig, StrategistConfig};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -30,6 +32,7 @@ impl Default for CompositorConfig {
     fn default() -> Self {
         CompositorConfig {
             move_step: 10,
+            workspaces: HashMap::new(),
         }
     }
 }
@@ -41,6 +44,22 @@ impl Default for ExhibitorConfig {
         ExhibitorConfig {
             compositor: CompositorConfig::default(),
             strategist: StrategistConfig::default(),
+            output: OutputConfig::default(),
+            outputs: HashMap::new(),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig {
+            enabled: true,
+            position: None,
+            mode: String::new(),
+            scale: 1,
+            transform: "normal".to_owned(),
         }
     }
 }
@@ -52,7 +71,27 @@ impl Default for InputConfig {
         InputConfig {
             touchpad_scale: 1.0,
             touchpad_pressure_threshold: 50,
+            touchpad_palm_pressure_threshold: 150,
+            touchpad_tap_to_click: true,
+            touchpad_disable_while_typing: true,
             mouse_scale: 1.0,
+            acceleration: AccelerationConfig::default(),
+            natural_scrolling: false,
+            left_handed: false,
+            scroll_method: "two_finger".to_owned(),
+            devices: Vec::new(),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Default for AccelerationConfig {
+    fn default() -> Self {
+        AccelerationConfig {
+            profile: "flat".to_owned(),
+            speed: 1.0,
+            curve: 0.5,
         }
     }
 }
@@ -336,6 +375,32 @@ impl Default for Config {
                               uinput_sys::KEY_9,
                               modifier::LMTA | modifier::LCTL,
                               binding_functions::jump_to_workspace),
+            // moving workspaces between displays
+            BindingEntry::new(mode_name::INSERT,
+                              uinput_sys::KEY_RIGHT,
+                              modifier::LMTA | modifier::LCTL,
+                              binding_functions::transfer_workspace_right),
+            BindingEntry::new(mode_name::INSERT,
+                              uinput_sys::KEY_DOWN,
+                              modifier::LMTA | modifier::LCTL,
+                              binding_functions::transfer_workspace_down),
+            BindingEntry::new(mode_name::INSERT,
+                              uinput_sys::KEY_LEFT,
+                              modifier::LMTA | modifier::LCTL,
+                              binding_functions::transfer_workspace_left),
+            BindingEntry::new(mode_name::INSERT,
+                              uinput_sys::KEY_UP,
+                              modifier::LMTA | modifier::LCTL,
+                              binding_functions::transfer_workspace_up),
+            // focus display
+            BindingEntry::new(mode_name::INSERT,
+                              uinput_sys::KEY_TAB,
+                              modifier::LMTA | modifier::LCTL,
+                              binding_functions::focus_next_display),
+            BindingEntry::new(mode_name::INSERT,
+                              uinput_sys::KEY_TAB,
+                              modifier::LMTA | modifier::LCTL | modifier::LSHF,
+                              binding_functions::focus_previous_display),
             // diving
             BindingEntry::new(mode_name::INSERT,
                               uinput_sys::KEY_RIGHT,
@@ -405,11 +470,41 @@ impl Default for Config {
                               binding_functions::toggle_anchorization),
         ];
 
+        let gesture_bindings = vec![
+            // focusing
+            GestureBindingEntry::new(mode_name::COMMON,
+                                     3,
+                                     Direction::East,
+                                     binding_functions::focus_right),
+            GestureBindingEntry::new(mode_name::COMMON,
+                                     3,
+                                     Direction::South,
+                                     binding_functions::focus_down),
+            GestureBindingEntry::new(mode_name::COMMON,
+                                     3,
+                                     Direction::West,
+                                     binding_functions::focus_left),
+            GestureBindingEntry::new(mode_name::COMMON,
+                                     3,
+                                     Direction::North,
+                                     binding_functions::focus_up),
+            // switching workspaces
+            GestureBindingEntry::new(mode_name::COMMON,
+                                     4,
+                                     Direction::West,
+                                     binding_functions::focus_next_workspace),
+            GestureBindingEntry::new(mode_name::COMMON,
+                                     4,
+                                     Direction::East,
+                                     binding_functions::focus_previous_workspace),
+        ];
+
         Config::new(AestheticsConfig::default(),
                     ExhibitorConfig::default(),
                     InputConfig::default(),
                     KeyboardConfig::default(),
-                    bindings)
+                    bindings,
+                    gesture_bindings)
     }
 }
 
@@ -6,9 +6,12 @@
 // -------------------------------------------------------------------------------------------------
 
 use std;
+use std::path::PathBuf;
 
 use enums;
 use timing;
+use surface::buffer_transform;
+use config::InputDeviceInfo;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -122,7 +125,7 @@ pub mod mode_name {
 
 /// Type defining position, point coordinates or 2D vector.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
 pub struct Position {
     pub x: isize,
     pub y: isize,
@@ -376,8 +379,8 @@ impl Area {
 
     /// Calculate position in center of the area.
     pub fn calculate_center(&self) -> Position {
-        Position::new((self.pos.x + self.size.width as isize) / 2,
-                      (self.pos.y + self.size.height as isize) / 2)
+        Position::new(self.pos.x + self.size.width as isize / 2,
+                      self.pos.y + self.size.height as isize / 2)
     }
 
     /// Inflates this `Area` so that it contains passed `area`.
@@ -406,6 +409,60 @@ impl Area {
             self.size.height += diff as usize;
         }
     }
+
+    /// Returns common part of this and passed `area` or `None` if they do not overlap.
+    pub fn intersection(&self, area: &Area) -> Option<Area> {
+        let left = std::cmp::max(self.pos.x, area.pos.x);
+        let top = std::cmp::max(self.pos.y, area.pos.y);
+        let right = std::cmp::min(self.pos.x + self.size.width as isize,
+                                  area.pos.x + area.size.width as isize);
+        let bottom = std::cmp::min(self.pos.y + self.size.height as isize,
+                                   area.pos.y + area.size.height as isize);
+
+        if (left < right) && (top < bottom) {
+            Some(Area::create(left, top, (right - left) as usize, (bottom - top) as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Returns list of non-overlapping areas covering part of this `Area` not covered by passed
+    /// `area`.
+    pub fn subtracted(&self, area: &Area) -> Vec<Area> {
+        let common = if let Some(common) = self.intersection(area) {
+            common
+        } else {
+            return if self.is_zero() { Vec::new() } else { vec![*self] };
+        };
+
+        let mut result = Vec::with_capacity(4);
+        let right = self.pos.x + self.size.width as isize;
+        let bottom = self.pos.y + self.size.height as isize;
+        let common_right = common.pos.x + common.size.width as isize;
+        let common_bottom = common.pos.y + common.size.height as isize;
+
+        // Bands above and below the common part span whole width
+        if common.pos.y > self.pos.y {
+            let height = (common.pos.y - self.pos.y) as usize;
+            result.push(Area::create(self.pos.x, self.pos.y, self.size.width, height));
+        }
+        if common_bottom < bottom {
+            let height = (bottom - common_bottom) as usize;
+            result.push(Area::create(self.pos.x, common_bottom, self.size.width, height));
+        }
+
+        // Bands on the left and right span only height of the common part
+        if common.pos.x > self.pos.x {
+            let width = (common.pos.x - self.pos.x) as usize;
+            result.push(Area::create(self.pos.x, common.pos.y, width, common.size.height));
+        }
+        if common_right < right {
+            let width = (right - common_right) as usize;
+            result.push(Area::create(common_right, common.pos.y, width, common.size.height));
+        }
+
+        result
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -421,6 +478,98 @@ impl std::default::Default for Area {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Type defining 2D region as a list of non-overlapping rectangles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    rects: Vec<Area>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Region {
+    /// Constructs empty `Region`.
+    pub fn new() -> Self {
+        Region { rects: Vec::new() }
+    }
+
+    /// Constructs `Region` covering given `Area`.
+    pub fn from_area(area: Area) -> Self {
+        let mut region = Region::new();
+        region.add(&area);
+        region
+    }
+
+    /// Returns list of rectangles making up the region.
+    pub fn get_rects(&self) -> &Vec<Area> {
+        &self.rects
+    }
+
+    /// Checks if region does not cover anything.
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// Returns the smallest `Area` containing whole region.
+    pub fn get_extents(&self) -> Area {
+        let mut iter = self.rects.iter();
+        if let Some(first) = iter.next() {
+            let mut extents = *first;
+            for rect in iter {
+                extents.inflate(rect);
+            }
+            extents
+        } else {
+            Area::default()
+        }
+    }
+
+    /// Check if region contains given position.
+    pub fn contains(&self, pos: &Position) -> bool {
+        self.rects.iter().any(|rect| rect.contains(pos))
+    }
+
+    /// Check if region fully covers given area.
+    pub fn covers(&self, area: &Area) -> bool {
+        let mut remains = if area.is_zero() { Vec::new() } else { vec![*area] };
+        for rect in self.rects.iter() {
+            remains = remains.iter().flat_map(|r| r.subtracted(rect)).collect();
+            if remains.is_empty() {
+                break;
+            }
+        }
+        remains.is_empty()
+    }
+
+    /// Adds given area to the region.
+    pub fn add(&mut self, area: &Area) {
+        if !area.is_zero() {
+            self.subtract(area);
+            self.rects.push(*area);
+        }
+    }
+
+    /// Removes given area from the region.
+    pub fn subtract(&mut self, area: &Area) {
+        self.rects = self.rects.iter().flat_map(|rect| rect.subtracted(area)).collect();
+    }
+
+    /// Adds all areas from given region to this region.
+    pub fn union(&mut self, region: &Region) {
+        for rect in region.rects.iter() {
+            self.add(rect);
+        }
+    }
+
+    /// Returns copy of this region moved by given vector.
+    pub fn translated(&self, vector: Vector) -> Region {
+        Region {
+            rects: self.rects.iter().map(|rect| Area::new(rect.pos + vector, rect.size)).collect(),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Data for button event.
 #[derive(Clone, Copy, Debug)]
 pub struct Button {
@@ -453,29 +602,86 @@ impl Button {
 
 // -------------------------------------------------------------------------------------------------
 
-/// Data for axis event.
+/// Source of axis event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AxisSource {
+    /// Mouse wheel.
+    Wheel,
+
+    /// Finger on touch surface.
+    Finger,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Data for axis event. Event with all values equal to zero informs about end of scrolling.
 #[derive(Clone, Copy, Debug)]
 pub struct Axis {
     pub discrete: Vector,
     pub continuous: Slide,
+    pub source: AxisSource,
     pub time: timing::Milliseconds,
 }
 
 // -------------------------------------------------------------------------------------------------
 
 impl Axis {
-    pub fn new(discrete: Vector, continuous: Slide, time: timing::Milliseconds) -> Self {
+    pub fn new(discrete: Vector,
+               continuous: Slide,
+               source: AxisSource,
+               time: timing::Milliseconds)
+               -> Self {
         Axis {
             discrete: discrete,
             continuous: continuous,
+            source: source,
             time: time,
         }
     }
 
-    pub fn new_now(discrete: Vector, continuous: Slide) -> Self {
+    pub fn new_now(discrete: Vector, continuous: Slide, source: AxisSource) -> Self {
         Axis {
             discrete: discrete,
             continuous: continuous,
+            source: source,
+            time: timing::Milliseconds::now(),
+        }
+    }
+
+    /// Checks if this event informs about end of scrolling.
+    pub fn is_stop(&self) -> bool {
+        self.discrete.x == 0 && self.discrete.y == 0 &&
+        self.continuous.x == 0.0 && self.continuous.y == 0.0
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Data for touch event.
+#[derive(Clone, Debug)]
+pub struct Touch {
+    /// Number of multitouch slot. Identifies touch point for the whole time it is in contact with
+    /// the device.
+    pub slot: i32,
+
+    /// Position as fraction of width and height of the device (values from 0.0 to 1.0).
+    pub position: Slide,
+
+    /// Name of the output touch device is mapped to or `None` if not specified.
+    pub output: Option<String>,
+
+    pub time: timing::Milliseconds,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Touch {
+    /// Constructs `Touch` with current time.
+    pub fn new_now(slot: i32, position: Slide, output: Option<String>) -> Self {
+        Touch {
+            slot: slot,
+            position: position,
+            output: output,
             time: timing::Milliseconds::now(),
         }
     }
@@ -483,15 +689,54 @@ impl Axis {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Mode supported by output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutputModeInfo {
+    /// Size of the mode in pixels.
+    pub size: Size,
+
+    /// Refresh rate in mHz.
+    pub refresh_rate: usize,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl OutputModeInfo {
+    /// Constructs new `OutputModeInfo`.
+    pub fn new(size: Size, refresh_rate: usize) -> Self {
+        OutputModeInfo {
+            size: size,
+            refresh_rate: refresh_rate,
+        }
+    }
+
+    /// Checks if this mode has given size and refresh rate. Refresh rates are compared with
+    /// precision of 1 Hz as they may be calculated from differently rounded pixel clocks.
+    pub fn matches(&self, size: Size, refresh_rate: usize) -> bool {
+        self.size == size && (self.refresh_rate + 500) / 1000 == (refresh_rate + 500) / 1000
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Set of informations about output.
 #[derive(Clone, Debug)]
 pub struct OutputInfo {
     pub id: i32,
     pub area: Area,
     pub physical_size: Size,
+
+    /// Refresh rate in mHz.
     pub refresh_rate: usize,
     pub make: String,
     pub model: String,
+    pub serial: String,
+    pub name: String,
+    pub scale: i32,
+    pub transform: i32,
+
+    /// Modes supported by display device as described in its EDID. The first one is preferred.
+    pub modes: Vec<OutputModeInfo>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -512,8 +757,50 @@ impl OutputInfo {
             refresh_rate: refresh_rate,
             make: make,
             model: model,
+            serial: String::new(),
+            name: String::new(),
+            scale: 1,
+            transform: buffer_transform::NORMAL,
+            modes: Vec::new(),
         }
     }
+
+    /// Returns size of output mode in pixels. `area` is given in logical coordinates.
+    pub fn get_mode_size(&self) -> Size {
+        let size = self.area.size.scaled(self.scale as f32);
+        if buffer_transform::is_rotated(self.transform) {
+            Size::new(size.height, size.width)
+        } else {
+            size
+        }
+    }
+
+    /// Returns identity of the output display device made of its make, model and serial number
+    /// (if known). Unlike connector name it does not change when the device is plugged into other
+    /// port.
+    pub fn get_identity(&self) -> String {
+        if self.serial.is_empty() {
+            format!("{} {}", self.make, self.model)
+        } else {
+            format!("{} {} {}", self.make, self.model, self.serial)
+        }
+    }
+
+    /// Returns identities by which the output can be referred to, the most specific first: make,
+    /// model and serial number (if known) and make and model only.
+    pub fn get_identities(&self) -> Vec<String> {
+        let make_and_model = format!("{} {}", self.make, self.model);
+        if self.serial.is_empty() {
+            vec![make_and_model]
+        } else {
+            vec![self.get_identity(), make_and_model]
+        }
+    }
+
+    /// Checks if given key is connector name or one of identities of the output.
+    pub fn matches(&self, key: &str) -> bool {
+        self.name == key || self.get_identities().iter().any(|identity| identity == key)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -528,6 +815,17 @@ pub struct DrmBundle {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Structure containing all data needed to initialize input device.
+#[derive(Clone, Debug)]
+pub struct InputDeviceBundle {
+    pub syspath: PathBuf,
+    pub devnode: PathBuf,
+    pub info: InputDeviceInfo,
+    pub output_name: Option<String>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Command context for compositor.
 #[derive(Clone, Debug)]
 pub struct Command {
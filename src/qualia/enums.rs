@@ -10,11 +10,12 @@ use std::fmt;
 // -------------------------------------------------------------------------------------------------
 
 /// Enum describing kind of input device.
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum DeviceKind {
     Keyboard,
     Mouse,
     Touchpad,
+    Touchscreen,
     Unknown,
 }
 
@@ -26,6 +27,7 @@ impl fmt::Debug for DeviceKind {
             DeviceKind::Keyboard => write!(f, "keyboard"),
             DeviceKind::Mouse => write!(f, "mouse"),
             DeviceKind::Touchpad => write!(f, "touchpad"),
+            DeviceKind::Touchscreen => write!(f, "touchscreen"),
             DeviceKind::Unknown => write!(f, "unknown device"),
         }
     }
@@ -71,12 +73,15 @@ pub enum Action {
 
     /// Change size.
     Resize,
+
+    /// Move workspace to another display.
+    Transfer,
 }
 
 // -------------------------------------------------------------------------------------------------
 
 /// Enum representing directions on screen, in time and between frames.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Direction {
     /// Dummy; point nowhere.
     None,
@@ -110,6 +115,9 @@ pub enum Direction {
 
     /// Workspace.
     Workspace,
+
+    /// Display; output.
+    Display,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -129,6 +137,7 @@ impl Direction {
             Direction::End => Direction::Begin,
             Direction::Up => Direction::Up,
             Direction::Workspace => Direction::Workspace,
+            Direction::Display => Direction::Display,
         }
     }
 }
@@ -66,11 +66,35 @@ impl Binding {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Structure for identifying touchpad swipe gesture binding.
+///
+/// Used as key in hash maps.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct GestureBinding {
+    fingers: u32,
+    direction: Direction,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl GestureBinding {
+    /// `GestureBinding` constructor.
+    pub fn new(fingers: u32, direction: Direction) -> Self {
+        GestureBinding {
+            fingers: fingers,
+            direction: direction,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Structure representing mode.
 pub struct Mode {
     active: bool,
     name: String,
     bindings: HashMap<Binding, Executor>,
+    gesture_bindings: HashMap<GestureBinding, Executor>,
     default_executor: Option<Executor>,
 }
 
@@ -83,6 +107,7 @@ impl Mode {
             active: active,
             name: name,
             bindings: HashMap::new(),
+            gesture_bindings: HashMap::new(),
             default_executor: default_executor,
         }
     }
@@ -115,6 +140,16 @@ impl Mode {
         }
         executor
     }
+
+    /// Add new gesture binding.
+    pub fn add_gesture_binding(&mut self, binding: GestureBinding, executor: Executor) {
+        self.gesture_bindings.insert(binding, executor);
+    }
+
+    /// Returns executor for given gesture binding. Default executor does not apply to gestures.
+    pub fn get_gesture_executor(&self, binding: &GestureBinding) -> Option<&Executor> {
+        self.gesture_bindings.get(binding)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -155,6 +190,9 @@ impl InnerInputManager {
         for b in bindings.iter() {
             inner.add_binding(b.mode_name.to_owned(), b.binding.clone(), b.executor);
         }
+        for b in config.get_gesture_binding_config().iter() {
+            inner.add_gesture_binding(b.mode_name.to_owned(), b.binding.clone(), b.executor);
+        }
 
         // Activate default modes
         inner.make_mode_active(mode_name::COMMON.to_string(), true);
@@ -174,6 +212,18 @@ impl InnerInputManager {
         None
     }
 
+    /// Helper method for finding executor for given gesture binding in active modes.
+    fn find_gesture_executor(&self, binding: &GestureBinding) -> Option<Executor> {
+        for ref mode in self.modes.iter() {
+            if mode.is_active() {
+                if let Some(executor) = mode.get_gesture_executor(binding) {
+                    return Some(*executor);
+                }
+            }
+        }
+        None
+    }
+
     /// Tries for find executor matching to given key and state of modifiers and execute it if
     /// found.
     pub fn catch_key(&mut self,
@@ -192,6 +242,16 @@ impl InnerInputManager {
         }
     }
 
+    /// Tries to find executor matching given gesture and execute it if found.
+    pub fn catch_gesture(&mut self, binding: GestureBinding) -> KeyCatchResult {
+        if let Some(executor) = self.find_gesture_executor(&binding) {
+            executor(self);
+            KeyCatchResult::Caught
+        } else {
+            KeyCatchResult::Passed
+        }
+    }
+
     /// Activates or deactivates mode identified by name.
     pub fn make_mode_active(&mut self, mode_name: String, active: bool) {
         for ref mut mode in self.modes.iter_mut() {
@@ -221,6 +281,25 @@ impl InnerInputManager {
             self.modes.push(mode);
         }
     }
+
+    /// Adds given gesture binding to mode identified by name.
+    pub fn add_gesture_binding(&mut self,
+                               mode_name: String,
+                               binding: GestureBinding,
+                               executor: Executor) {
+        // Try to find mode and add binding to it
+        for ref mut mode in self.modes.iter_mut() {
+            if mode.get_name() == mode_name {
+                mode.add_gesture_binding(binding, executor);
+                return;
+            }
+        }
+
+        // If mode not found - create new
+        let mut mode = Mode::new(false, mode_name, None);
+        mode.add_gesture_binding(binding, executor);
+        self.modes.push(mode);
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -262,6 +341,21 @@ impl InputManager {
         let mut mine = self.inner.lock().unwrap();
         mine.add_binding(mode_name, binding, executor)
     }
+
+    /// Lock and call corresponding method from `InnerInputManager`.
+    pub fn catch_gesture(&mut self, binding: GestureBinding) -> KeyCatchResult {
+        let mut mine = self.inner.lock().unwrap();
+        mine.catch_gesture(binding)
+    }
+
+    /// Lock and call corresponding method from `InnerInputManager`.
+    pub fn add_gesture_binding(&mut self,
+                               mode_name: String,
+                               binding: GestureBinding,
+                               executor: Executor) {
+        let mut mine = self.inner.lock().unwrap();
+        mine.add_gesture_binding(mode_name, binding, executor)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -83,8 +83,8 @@ impl Keymap {
             return Err(Illusion::General(format!("Failed to create key map")));
         };
 
-        // Save keymap to file
-        let file_name = "keymap".to_owned();
+        // Save keymap to file. Every layout needs its own file.
+        let file_name = format!("keymap-{}-{}", config.layout, config.variant);
         let keymap_str = xkb_keymap.keymap.get_as_string(DEFAULT_FORMAT);
         let mut file = env.open_file(file_name, env::Directory::Runtime)?;
         file.write_all(keymap_str.as_bytes())?;
@@ -38,28 +38,38 @@ pub use errors::Illusion;
 #[macro_use]
 pub mod macros;
 
+#[macro_use]
+pub mod log;
+pub use log::level;
+
 pub mod timing;
-pub use timing::Milliseconds;
+pub use timing::{Milliseconds, Presentation};
 
 pub mod defs;
-pub use defs::{Area, Point, Position, OptionalPosition, Size, Slide, Vector};
+pub use defs::{Area, Point, Position, OptionalPosition, Region, Size, Slide, Vector};
 pub use defs::{Axis, Button, Command, DrmBundle, modifier, Key, KeyCode, KeyValue, OutputInfo};
-pub use defs::{MemoryPoolId, MemoryViewId, SignalId};
+pub use defs::{InputDeviceBundle, OutputModeInfo};
+pub use defs::{AxisSource, MemoryPoolId, MemoryViewId, SignalId, Touch};
 
 pub mod config;
 pub use config::{Config, AestheticsConfig, InputConfig, KeyboardConfig};
-pub use config::{CompositorConfig, ExhibitorConfig, StrategistConfig};
+pub use config::{AccelerationConfig, InputDeviceConfig, InputDeviceInfo};
+pub use config::{CompositorConfig, ExhibitorConfig, OutputConfig, OutputMode, StrategistConfig};
 
 pub mod config_defaults;
 
 pub mod memory;
 pub use memory::{Buffer, Pixmap, MappedMemory, MemoryPool, MemoryView};
 
+pub mod screenshot;
+pub use screenshot::{Screenshot, ScreenshotId, ScreenshotRequest};
+
 pub mod surface;
-pub use surface::{SurfaceContext, SurfaceId, SurfaceIdType, SurfaceInfo};
+pub use surface::{SizeHints, SurfaceContext, SurfaceId, SurfaceIdType, SurfaceInfo};
 pub use surface::{SurfaceManagement, SurfaceControl, SurfaceViewer};
 pub use surface::{SurfaceAccess, SurfaceListing, SurfaceFocusing};
-pub use surface::{show_reason, surface_state};
+pub use surface::{buffer_transform, layer_anchor, show_reason, surface_state};
+pub use surface::{KeyboardInteractivity, Layer, LayerSurfaceInfo, Margins};
 
 pub mod perceptron;
 pub use perceptron::Perceptron;
@@ -68,10 +78,6 @@ pub mod traits;
 pub use traits::{AppearanceManagement, Emiter, Screenshooting, MemoryManagement};
 pub use traits::{AestheticsCoordinationTrait, ExhibitorCoordinationTrait};
 
-#[macro_use]
-pub mod log;
-pub use log::level;
-
 pub mod functions;
 
 pub mod env;
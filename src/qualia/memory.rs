@@ -101,6 +101,16 @@ impl Buffer {
     pub fn is_empty(&self) -> bool {
         (self.width == 0) || (self.height == 0) || (self.stride == 0) || (self.data.len() == 0)
     }
+
+    /// Converts data in RGBA format to BGRA format (or vice versa) by swapping red and blue
+    /// channels of every pixel.
+    pub fn swap_red_and_blue(&mut self) {
+        for row in self.data.chunks_mut(self.stride) {
+            for pixel in row[..4 * self.width].chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -208,6 +218,45 @@ unsafe impl Sync for MemoryView {}
 
 // -------------------------------------------------------------------------------------------------
 
+impl MemoryView {
+    /// Copies contents of given buffer to viewed memory.
+    ///
+    /// Width and height of the buffer must be the same as of the view. Only memory mapped from
+    /// client can be written.
+    pub unsafe fn absorb(&self, buffer: &Buffer) -> Result<(), errors::Illusion> {
+        if (buffer.width != self.width) || (buffer.height != self.height) {
+            return Err(errors::Illusion::General(format!("Sizes differ: view is {}x{}, but \
+                                                          buffer {}x{}",
+                                                         self.width,
+                                                         self.height,
+                                                         buffer.width,
+                                                         buffer.height)));
+        }
+
+        let map = match *self.memory {
+            MemoryKind::Mapped(ref map) => map,
+            MemoryKind::Buffered(_) => {
+                return Err(errors::Illusion::General(format!("Buffered memory is read-only")));
+            }
+        };
+
+        let offset = self.data as usize - map.data as usize;
+        if (offset + self.stride * self.height) > map.size {
+            return Err(errors::Illusion::General(format!("View exceeds mapped memory")));
+        }
+
+        let row_size = std::cmp::min(buffer.stride, self.stride);
+        for row in 0..self.height {
+            let src = buffer.data.as_ptr().offset((row * buffer.stride) as isize);
+            let dst = (self.data as *mut u8).offset((row * self.stride) as isize);
+            std::ptr::copy_nonoverlapping(src, dst, row_size);
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 impl Pixmap for MemoryView {
     #[inline]
     fn get_size(&self) -> Size {
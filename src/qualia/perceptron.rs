@@ -6,12 +6,16 @@
 // -------------------------------------------------------------------------------------------------
 
 use std;
+use std::path::PathBuf;
 
 use dharma::SignalId;
 
-use timing::Milliseconds;
-use defs::{Command, DrmBundle, OutputInfo, SurfaceId};
-use defs::{Axis, Position, OptionalPosition, Vector, Button, Key};
+use timing::{Milliseconds, Presentation};
+use config::KeyboardConfig;
+use defs::{Command, DrmBundle, InputDeviceBundle, OutputInfo, SurfaceId};
+use surface::LayerSurfaceInfo;
+use screenshot::{ScreenshotId, ScreenshotRequest};
+use defs::{Axis, Position, OptionalPosition, Vector, Button, Key, Touch};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -21,23 +25,40 @@ pub const WAKEUP: SignalId = 2;
 pub const VERTICAL_BLANK: SignalId = 3;
 pub const PAGE_FLIP: SignalId = 4;
 pub const OUTPUT_FOUND: SignalId = 5;
+pub const OUTPUT_LOST: SignalId = 6;
 pub const COMMAND: SignalId = 7;
 pub const DISPLAY_CREATED: SignalId = 8;
+pub const DISPLAY_DESTROYED: SignalId = 9;
 pub const INPUT_POINTER_MOTION: SignalId = 10;
 pub const INPUT_POINTER_POSITION: SignalId = 11;
 pub const INPUT_POINTER_BUTTON: SignalId = 12;
 pub const INPUT_POINTER_AXIS: SignalId = 13;
 pub const INPUT_POINTER_POSITION_RESET: SignalId = 14;
 pub const INPUT_KEYBOARD: SignalId = 15;
+pub const INPUT_TOUCH_DOWN: SignalId = 16;
+pub const INPUT_TOUCH_MOTION: SignalId = 17;
+pub const INPUT_TOUCH_UP: SignalId = 18;
+pub const INPUT_TOUCH_FRAME: SignalId = 19;
 pub const SURFACE_READY: SignalId = 20;
 pub const SURFACE_DESTROYED: SignalId = 21;
 pub const SURFACE_RECONFIGURED: SignalId = 22;
+pub const INPUT_DEVICE_ADDED: SignalId = 23;
+pub const INPUT_DEVICE_REMOVED: SignalId = 24;
 pub const CURSOR_SURFACE_CHANGE: SignalId = 25;
 pub const BACKGROUND_SURFACE_CHANGE: SignalId = 26;
+pub const DRAG_ICON_SURFACE_CHANGE: SignalId = 27;
+pub const LAYER_SURFACE_CHANGE: SignalId = 28;
+pub const OUTPUT_DEVICE_CHANGED: SignalId = 29;
 pub const SURFACE_FRAME: SignalId = 30;
 pub const POINTER_FOCUS_CHANGED: SignalId = 31;
 pub const POINTER_RELATIVE_MOTION: SignalId = 32;
 pub const KEYBOARD_FOCUS_CHANGED: SignalId = 33;
+pub const TOUCH_DOWN: SignalId = 34;
+pub const TOUCH_MOTION: SignalId = 35;
+pub const TOUCH_UP: SignalId = 36;
+pub const TOUCH_FRAME: SignalId = 37;
+pub const KEYMAP_CHANGED: SignalId = 38;
+pub const SURFACE_STATE_APPLIED: SignalId = 39;
 pub const TAKE_SCREENSHOT: SignalId = 101;
 pub const SCREENSHOT_DONE: SignalId = 102;
 
@@ -54,27 +75,44 @@ pub enum Perceptron {
     CustomEmpty,
     CustomId(u64),
     VerticalBlank(i32),
-    PageFlip(i32),
+    PageFlip(Presentation),
     OutputFound(DrmBundle),
+    OutputLost(DrmBundle),
     Command(Command),
     DisplayCreated(OutputInfo),
+    DisplayDestroyed(i32),
     InputPointerMotion(Vector),
     InputPointerPosition(OptionalPosition),
     InputPointerButton(Button),
     InputPointerAxis(Axis),
     InputPointerPositionReset,
     InputKeyboard(Key),
+    InputTouchDown(Touch),
+    InputTouchMotion(Touch),
+    InputTouchUp(i32, Milliseconds),
+    InputTouchFrame,
+    InputDeviceAdded(InputDeviceBundle),
+    InputDeviceRemoved(PathBuf),
+    OutputDeviceChanged(PathBuf),
     SurfaceReady(SurfaceId),
     SurfaceDestroyed(SurfaceId),
     SurfaceReconfigured(SurfaceId),
+    SurfaceStateApplied(SurfaceId, u32),
     CursorSurfaceChange(SurfaceId),
     BackgroundSurfaceChange(SurfaceId),
-    SurfaceFrame(SurfaceId, Milliseconds),
+    DragIconSurfaceChange(SurfaceId),
+    LayerSurfaceChange(SurfaceId, Option<LayerSurfaceInfo>),
+    SurfaceFrame(SurfaceId, Presentation),
     PointerFocusChanged(SurfaceId, SurfaceId, Position),
     PointerRelativeMotion(SurfaceId, Position, Milliseconds),
     KeyboardFocusChanged(SurfaceId, SurfaceId),
-    TakeScreenshot(i32),
-    ScreenshotDone,
+    TouchDown(SurfaceId, i32, Position, Milliseconds),
+    TouchMotion(SurfaceId, i32, Position, Milliseconds),
+    TouchUp(SurfaceId, i32, Milliseconds),
+    TouchFrame(SurfaceId),
+    KeymapChanged(KeyboardConfig),
+    TakeScreenshot(ScreenshotRequest),
+    ScreenshotDone(ScreenshotId),
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -88,10 +126,12 @@ impl std::fmt::Display for Perceptron {
             Perceptron::CustomEmpty => write!(f, "CustomEmpty"),
             Perceptron::CustomId(ref id) => write!(f, "CustomId({:?})", id),
             Perceptron::VerticalBlank(ref data) => write!(f, "VerticalBlank({:?})", data),
-            Perceptron::PageFlip(ref data) => write!(f, "PageFlip({:?})", data),
+            Perceptron::PageFlip(ref presentation) => write!(f, "PageFlip({:?})", presentation),
             Perceptron::OutputFound(ref bundle) => write!(f, "OutputFound({:?})", bundle),
+            Perceptron::OutputLost(ref bundle) => write!(f, "OutputLost({:?})", bundle),
             Perceptron::Command(ref command) => write!(f, "Command({:?})", command),
             Perceptron::DisplayCreated(ref info) => write!(f, "DisplayCreated({:?})", info),
+            Perceptron::DisplayDestroyed(ref id) => write!(f, "DisplayDestroyed({:?})", id),
             Perceptron::InputPointerMotion(ref vector) => {
                 write!(f, "InputPointerMotion({:?})", vector)
             }
@@ -102,16 +142,40 @@ impl std::fmt::Display for Perceptron {
             Perceptron::InputPointerAxis(ref axis) => write!(f, "InputPointerAxis({:?})", axis),
             Perceptron::InputPointerPositionReset => write!(f, "InputPointerPositionReset"),
             Perceptron::InputKeyboard(ref key) => write!(f, "InputKeyboard({:?})", key),
+            Perceptron::InputTouchDown(ref touch) => write!(f, "InputTouchDown({:?})", touch),
+            Perceptron::InputTouchMotion(ref touch) => write!(f, "InputTouchMotion({:?})", touch),
+            Perceptron::InputTouchUp(ref slot, ref time) => {
+                write!(f, "InputTouchUp({:?}, {:?})", slot, time.get_value())
+            }
+            Perceptron::InputTouchFrame => write!(f, "InputTouchFrame"),
+            Perceptron::InputDeviceAdded(ref bundle) => {
+                write!(f, "InputDeviceAdded({:?})", bundle)
+            }
+            Perceptron::InputDeviceRemoved(ref path) => {
+                write!(f, "InputDeviceRemoved({:?})", path)
+            }
+            Perceptron::OutputDeviceChanged(ref path) => {
+                write!(f, "OutputDeviceChanged({:?})", path)
+            }
 
             Perceptron::SurfaceReady(ref sid) => write!(f, "SurfaceReady({})", sid),
             Perceptron::SurfaceDestroyed(ref sid) => write!(f, "SurfaceDestroyed({})", sid),
             Perceptron::SurfaceReconfigured(ref sid) => write!(f, "SurfaceReconfigured({})", sid),
+            Perceptron::SurfaceStateApplied(ref sid, ref serial) => {
+                write!(f, "SurfaceStateApplied({}, serial: {})", sid, serial)
+            }
             Perceptron::CursorSurfaceChange(ref sid) => write!(f, "CursorSurfaceChange({})", sid),
             Perceptron::BackgroundSurfaceChange(ref sid) => {
                 write!(f, "BackgroundSurfaceChange({})", sid)
             }
-            Perceptron::SurfaceFrame(sid, time) => {
-                write!(f, "SurfaceFrame(sid: {}, milliseconds: {})", sid, time.get_value())
+            Perceptron::DragIconSurfaceChange(ref sid) => {
+                write!(f, "DragIconSurfaceChange({})", sid)
+            }
+            Perceptron::LayerSurfaceChange(ref sid, ref info) => {
+                write!(f, "LayerSurfaceChange({}, {:?})", sid, info)
+            }
+            Perceptron::SurfaceFrame(sid, ref presentation) => {
+                write!(f, "SurfaceFrame(sid: {}, {:?})", sid, presentation)
             }
             Perceptron::PointerFocusChanged(ref old_sid, ref new_sid, ref pos) => {
                 write!(f, "PointerFocusChanged(old: {:?}, new: {:?}, {:?})", old_sid, new_sid, pos)
@@ -122,8 +186,19 @@ impl std::fmt::Display for Perceptron {
             Perceptron::KeyboardFocusChanged(ref old_sid, ref new_sid) => {
                 write!(f, "KeyboardFocusChanged({:?}, {:?})", old_sid, new_sid)
             }
-            Perceptron::TakeScreenshot(ref id) => write!(f, "TakeScreenshot({:?})", id),
-            Perceptron::ScreenshotDone => write!(f, "ScreenshotDone"),
+            Perceptron::TouchDown(ref sid, ref id, ref pos, ref time) => {
+                write!(f, "TouchDown({:?}, {:?}, {:?}, {:?})", sid, id, pos, time.get_value())
+            }
+            Perceptron::TouchMotion(ref sid, ref id, ref pos, ref time) => {
+                write!(f, "TouchMotion({:?}, {:?}, {:?}, {:?})", sid, id, pos, time.get_value())
+            }
+            Perceptron::TouchUp(ref sid, ref id, ref time) => {
+                write!(f, "TouchUp({:?}, {:?}, {:?})", sid, id, time.get_value())
+            }
+            Perceptron::TouchFrame(ref sid) => write!(f, "TouchFrame({:?})", sid),
+            Perceptron::KeymapChanged(ref config) => write!(f, "KeymapChanged({:?})", config),
+            Perceptron::TakeScreenshot(ref request) => write!(f, "TakeScreenshot({:?})", request),
+            Perceptron::ScreenshotDone(ref id) => write!(f, "ScreenshotDone({:?})", id),
         }
     }
 }
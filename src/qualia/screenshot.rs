@@ -0,0 +1,68 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! This module contains definitions related to capturing contents of outputs.
+
+// -------------------------------------------------------------------------------------------------
+
+use defs::{Area, Region};
+use memory::Buffer;
+use timing::Presentation;
+
+// -------------------------------------------------------------------------------------------------
+
+define_id!(pub ScreenshotId: u64);
+
+// -------------------------------------------------------------------------------------------------
+
+/// Request for capturing contents of output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenshotRequest {
+    /// ID used to match result with request.
+    pub id: ScreenshotId,
+
+    /// ID of output to capture.
+    pub output_id: i32,
+
+    /// Captured area in logical coordinates of output. Whole output is captured if `None`.
+    pub area: Option<Area>,
+
+    /// `true` if cursor should be visible on the screenshot.
+    pub overlay_cursor: bool,
+
+    /// If `true` screenshot will be taken only after contents of captured area change.
+    pub wait_for_damage: bool,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl ScreenshotRequest {
+    /// Constructs request for capturing whole output together with cursor.
+    pub fn new(id: ScreenshotId, output_id: i32) -> Self {
+        ScreenshotRequest {
+            id: id,
+            output_id: output_id,
+            area: None,
+            overlay_cursor: true,
+            wait_for_damage: false,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Result of capturing contents of output.
+#[derive(Clone, Debug)]
+pub struct Screenshot {
+    /// Image data in RGBA format.
+    pub buffer: Buffer,
+
+    /// Part of the image which changed in the frame the screenshot was taken from, in buffer
+    /// coordinates.
+    pub damage: Region,
+
+    /// Time when the frame was drawn.
+    pub time: Presentation,
+}
+
+// -------------------------------------------------------------------------------------------------
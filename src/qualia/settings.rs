@@ -7,6 +7,7 @@
 
 use std::sync::{Arc, Mutex};
 
+use config::KeyboardConfig;
 use keymap;
 
 // -------------------------------------------------------------------------------------------------
@@ -14,7 +15,11 @@ use keymap;
 /// Helper structure for global settings.
 #[derive(Clone)]
 struct InnerSettings {
-    pub keymap: keymap::Settings,
+    /// Key maps for every configured keyboard layout.
+    pub keymaps: Vec<(KeyboardConfig, keymap::Settings)>,
+
+    /// Index of currently used key map.
+    pub current_keymap: usize,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -28,15 +33,32 @@ pub struct Settings {
 // -------------------------------------------------------------------------------------------------
 
 impl Settings {
-    /// `Settings` constructor.
-    pub fn new(keymap: keymap::Settings) -> Self {
-        Settings { inner: Arc::new(Mutex::new(InnerSettings { keymap: keymap })) }
+    /// `Settings` constructor. First of given key maps will be used by default.
+    pub fn new(keymaps: Vec<(KeyboardConfig, keymap::Settings)>) -> Self {
+        Settings {
+            inner: Arc::new(Mutex::new(InnerSettings {
+                keymaps: keymaps,
+                current_keymap: 0,
+            })),
+        }
     }
 
-    /// Get key map related settings.
+    /// Get currently used key map related settings.
     pub fn get_keymap(&self) -> keymap::Settings {
         let mine = self.inner.lock().unwrap();
-        mine.keymap.clone()
+        mine.keymaps[mine.current_keymap].1.clone()
+    }
+
+    /// Switches to key map for given keyboard configuration. Returns `false` if there is no such
+    /// key map.
+    pub fn select_keymap(&self, config: &KeyboardConfig) -> bool {
+        let mut mine = self.inner.lock().unwrap();
+        if let Some(index) = mine.keymaps.iter().position(|&(ref c, _)| c == config) {
+            mine.current_keymap = index;
+            true
+        } else {
+            false
+        }
     }
 }
 
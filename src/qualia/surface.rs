@@ -6,14 +6,14 @@
 // -------------------------------------------------------------------------------------------------
 
 use defs::MemoryViewId;
-use memory::MemoryView;
-use defs::{Position, Size, Vector};
+use memory::{MemoryView, Pixmap};
+use defs::{Area, Position, Region, Size, Vector};
 pub use defs::{SurfaceId, SurfaceIdType};
 
 // -------------------------------------------------------------------------------------------------
 
 /// This structure defines how the surface should be drawn.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct SurfaceContext {
     pub id: SurfaceId,
     pub pos: Position,
@@ -63,15 +63,380 @@ pub mod surface_state {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Edges of output to which layer surface is anchored. Values match `zwlr_layer_surface_v1.anchor`.
+pub mod layer_anchor {
+    bitflags!(
+        pub flags LayerAnchor: u32 {
+            const NONE = 0b0000,
+            const TOP = 0b0001,
+            const BOTTOM = 0b0010,
+            const LEFT = 0b0100,
+            const RIGHT = 0b1000,
+        }
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Transformations of buffer content as defined by `wl_output.transform`.
+pub mod buffer_transform {
+    use defs::{Area, Size};
+
+    pub const NORMAL: i32 = 0;
+    pub const ROTATED_90: i32 = 1;
+    pub const ROTATED_180: i32 = 2;
+    pub const ROTATED_270: i32 = 3;
+    pub const FLIPPED: i32 = 4;
+    pub const FLIPPED_90: i32 = 5;
+    pub const FLIPPED_180: i32 = 6;
+    pub const FLIPPED_270: i32 = 7;
+
+    /// Checks if given value is valid transformation.
+    pub fn is_valid(transform: i32) -> bool {
+        (NORMAL <= transform) && (transform <= FLIPPED_270)
+    }
+
+    /// Returns transformation with given name as used in configuration (`normal`, `90`, `180`,
+    /// `270`, `flipped`, `flipped-90`, `flipped-180` or `flipped-270`).
+    pub fn from_name(name: &str) -> Option<i32> {
+        match name {
+            "normal" => Some(NORMAL),
+            "90" => Some(ROTATED_90),
+            "180" => Some(ROTATED_180),
+            "270" => Some(ROTATED_270),
+            "flipped" => Some(FLIPPED),
+            "flipped-90" => Some(FLIPPED_90),
+            "flipped-180" => Some(FLIPPED_180),
+            "flipped-270" => Some(FLIPPED_270),
+            _ => None,
+        }
+    }
+
+    /// Checks if given transformation swaps width and height.
+    pub fn is_rotated(transform: i32) -> bool {
+        (transform % 2) == 1
+    }
+
+    /// Calculates logical size of surface from size of its buffer, scale and transformation.
+    pub fn logical_size(buffer_size: Size, scale: i32, transform: i32) -> Size {
+        let scale = if scale > 0 { scale as usize } else { 1 };
+        let size = Size::new(buffer_size.width / scale, buffer_size.height / scale);
+        if is_rotated(transform) {
+            Size::new(size.height, size.width)
+        } else {
+            size
+        }
+    }
+
+    /// Maps normalized surface coordinates to normalized buffer coordinates.
+    pub fn map_coords(transform: i32, s: f32, t: f32) -> (f32, f32) {
+        match transform {
+            ROTATED_90 => (1.0 - t, s),
+            ROTATED_180 => (1.0 - s, 1.0 - t),
+            ROTATED_270 => (t, 1.0 - s),
+            FLIPPED => (1.0 - s, t),
+            FLIPPED_90 => (1.0 - t, 1.0 - s),
+            FLIPPED_180 => (s, 1.0 - t),
+            FLIPPED_270 => (t, s),
+            _ => (s, t),
+        }
+    }
+
+    /// Returns transformation reverting given one.
+    pub fn inverse(transform: i32) -> i32 {
+        match transform {
+            ROTATED_90 => ROTATED_270,
+            ROTATED_270 => ROTATED_90,
+            _ => transform,
+        }
+    }
+
+    /// Converts area in buffer coordinates to the smallest area in surface coordinates containing
+    /// it.
+    pub fn buffer_to_surface(area: Area, buffer_size: Size, scale: i32, transform: i32) -> Area {
+        if (buffer_size.width == 0) || (buffer_size.height == 0) {
+            return Area::default();
+        }
+
+        let surface_size = logical_size(buffer_size, scale, transform);
+        let inverse = inverse(transform);
+        let (s1, t1) = map_coords(inverse,
+                                  area.pos.x as f32 / buffer_size.width as f32,
+                                  area.pos.y as f32 / buffer_size.height as f32);
+        let (s2, t2) = map_coords(inverse,
+                                  (area.pos.x + area.size.width as isize) as f32 /
+                                  buffer_size.width as f32,
+                                  (area.pos.y + area.size.height as isize) as f32 /
+                                  buffer_size.height as f32);
+
+        let width = surface_size.width as f32;
+        let height = surface_size.height as f32;
+        let left = (s1.min(s2) * width).floor() as isize;
+        let top = (t1.min(t2) * height).floor() as isize;
+        let right = (s1.max(s2) * width).ceil() as isize;
+        let bottom = (t1.max(t2) * height).ceil() as isize;
+        Area::create(left, top, (right - left) as usize, (bottom - top) as usize)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Minimal and maximal size of surface requested by client. Zero dimension means there is no
+/// limit in that dimension.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SizeHints {
+    pub min_size: Size,
+    pub max_size: Size,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl SizeHints {
+    /// `SizeHints` constructor.
+    pub fn new(min_size: Size, max_size: Size) -> Self {
+        SizeHints {
+            min_size: min_size,
+            max_size: max_size,
+        }
+    }
+
+    /// Returns given size limited to be not smaller than minimal and not bigger than maximal size.
+    /// Minimal size takes precedence over maximal size.
+    pub fn clamp(&self, size: Size) -> Size {
+        Size::new(Self::clamp_dimension(size.width, self.min_size.width, self.max_size.width),
+                  Self::clamp_dimension(size.height, self.min_size.height, self.max_size.height))
+    }
+
+    /// Helper method for clamping single dimension.
+    fn clamp_dimension(value: usize, min: usize, max: usize) -> usize {
+        let value = if (max != 0) && (value > max) { max } else { value };
+        if value < min { min } else { value }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Default for SizeHints {
+    fn default() -> Self {
+        SizeHints::new(Size::default(), Size::default())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Layers in which layer surfaces are stacked. Background and bottom layers are drawn under
+/// workspaces, top and overlay layers above them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Background,
+    Bottom,
+    Top,
+    Overlay,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Describes if and when layer surface wants to receive keyboard focus.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyboardInteractivity {
+    /// Surface never receives keyboard focus.
+    None,
+
+    /// Surface takes keyboard focus as long as it is mapped.
+    Exclusive,
+
+    /// Surface receives keyboard focus when user clicks it.
+    OnDemand,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Distances between edges of layer surface and edges of area it is anchored to.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Margins {
+    pub top: isize,
+    pub right: isize,
+    pub bottom: isize,
+    pub left: isize,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// State of layer surface (panel, dock, wallpaper, notification, etc.) requested by client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerSurfaceInfo {
+    /// ID of output the surface should be placed on. `None` lets compositor choose.
+    pub output_id: Option<i32>,
+
+    /// Layer the surface is stacked in.
+    pub layer: Layer,
+
+    /// Edges the surface is anchored to.
+    pub anchor: layer_anchor::LayerAnchor,
+
+    /// Requested size. Zero dimension means the surface should be stretched between the edges it
+    /// is anchored to.
+    pub size: Size,
+
+    /// Size of the area along anchored edge which should not be occluded by other surfaces.
+    /// Negative value means the surface does not want to be moved to avoid exclusive zones of
+    /// other surfaces.
+    pub exclusive_zone: i32,
+
+    /// Margins from anchored edges.
+    pub margins: Margins,
+
+    /// Keyboard focus policy.
+    pub keyboard_interactivity: KeyboardInteractivity,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl LayerSurfaceInfo {
+    /// Constructs new `LayerSurfaceInfo` not anchored to any edge.
+    pub fn new(output_id: Option<i32>, layer: Layer) -> Self {
+        LayerSurfaceInfo {
+            output_id: output_id,
+            layer: layer,
+            anchor: layer_anchor::NONE,
+            size: Size::default(),
+            exclusive_zone: 0,
+            margins: Margins::default(),
+            keyboard_interactivity: KeyboardInteractivity::None,
+        }
+    }
+
+    /// Checks if the surface is anchored to both opposite edges in dimensions in which it wants to
+    /// be stretched.
+    pub fn is_size_valid(&self) -> bool {
+        let horizontal = layer_anchor::LEFT | layer_anchor::RIGHT;
+        let vertical = layer_anchor::TOP | layer_anchor::BOTTOM;
+        (self.size.width != 0 || self.anchor.contains(horizontal)) &&
+        (self.size.height != 0 || self.anchor.contains(vertical))
+    }
+
+    /// Calculates area of the surface placed inside given bounds. Surface is attached to edges it
+    /// is anchored to (keeping margins) and centered along dimensions in which it is anchored to
+    /// none or both edges.
+    pub fn place(&self, bounds: Area) -> Area {
+        let (x, width) = Self::place_dimension(bounds.pos.x,
+                                               bounds.size.width,
+                                               self.size.width,
+                                               self.anchor.contains(layer_anchor::LEFT),
+                                               self.anchor.contains(layer_anchor::RIGHT),
+                                               self.margins.left,
+                                               self.margins.right);
+        let (y, height) = Self::place_dimension(bounds.pos.y,
+                                                bounds.size.height,
+                                                self.size.height,
+                                                self.anchor.contains(layer_anchor::TOP),
+                                                self.anchor.contains(layer_anchor::BOTTOM),
+                                                self.margins.top,
+                                                self.margins.bottom);
+        Area::new(Position::new(x, y), Size::new(width, height))
+    }
+
+    /// Returns given area shrunk by exclusive zone of the surface. Zone is reserved along the edge
+    /// the surface is anchored to. Surface anchored to no edge or to two opposite edges does not
+    /// reserve any space.
+    pub fn reserve(&self, area: Area) -> Area {
+        if self.exclusive_zone <= 0 {
+            return area;
+        }
+
+        let horizontal = self.anchor & (layer_anchor::LEFT | layer_anchor::RIGHT);
+        let vertical = self.anchor & (layer_anchor::TOP | layer_anchor::BOTTOM);
+        let spans_horizontally = horizontal != layer_anchor::LEFT &&
+                                 horizontal != layer_anchor::RIGHT;
+        let spans_vertically = vertical != layer_anchor::TOP && vertical != layer_anchor::BOTTOM;
+
+        let zone = self.exclusive_zone as isize;
+        let mut result = area;
+        if spans_horizontally && vertical == layer_anchor::TOP {
+            let zone = Self::clamp_length(zone + self.margins.top, area.size.height);
+            result.pos.y += zone as isize;
+            result.size.height -= zone;
+        } else if spans_horizontally && vertical == layer_anchor::BOTTOM {
+            let zone = Self::clamp_length(zone + self.margins.bottom, area.size.height);
+            result.size.height -= zone;
+        } else if spans_vertically && horizontal == layer_anchor::LEFT {
+            let zone = Self::clamp_length(zone + self.margins.left, area.size.width);
+            result.pos.x += zone as isize;
+            result.size.width -= zone;
+        } else if spans_vertically && horizontal == layer_anchor::RIGHT {
+            let zone = Self::clamp_length(zone + self.margins.right, area.size.width);
+            result.size.width -= zone;
+        }
+        result
+    }
+
+    /// Helper method for placing the surface along single dimension. Returns position and length.
+    fn place_dimension(start: isize,
+                       length: usize,
+                       requested: usize,
+                       anchored_start: bool,
+                       anchored_end: bool,
+                       margin_start: isize,
+                       margin_end: isize)
+                       -> (isize, usize) {
+        let available = Self::clamp_length(length as isize - margin_start - margin_end, length);
+        let size = if requested == 0 { available } else { requested };
+        let pos = if anchored_start && !anchored_end {
+            start + margin_start
+        } else if anchored_end && !anchored_start {
+            start + length as isize - margin_end - size as isize
+        } else {
+            start + margin_start + (available as isize - size as isize) / 2
+        };
+        (pos, size)
+    }
+
+    /// Helper method limiting given length to range from zero to `max`.
+    fn clamp_length(value: isize, max: usize) -> usize {
+        if value < 0 {
+            0
+        } else if value as usize > max {
+            max
+        } else {
+            value as usize
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Structure containing public information about surface.
 pub struct SurfaceInfo {
     pub id: SurfaceId,
     pub offset: Vector,
     pub parent_sid: SurfaceId,
+    pub transient_for_sid: SurfaceId,
     pub desired_size: Size,
     pub requested_size: Size,
+    pub size_hints: SizeHints,
     pub state_flags: surface_state::SurfaceState,
     pub buffer: Option<MemoryView>,
+    pub damage: Vec<Area>,
+    pub buffer_damage: Vec<Area>,
+    pub input_region: Option<Region>,
+    pub opaque_region: Region,
+    pub buffer_scale: i32,
+    pub buffer_transform: i32,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl SurfaceInfo {
+    /// Returns size of surface content in logical coordinates or `None` if surface has no buffer.
+    pub fn get_logical_size(&self) -> Option<Size> {
+        if let Some(ref buffer) = self.buffer {
+            Some(buffer_transform::logical_size(buffer.get_size(),
+                                                self.buffer_scale,
+                                                self.buffer_transform))
+        } else {
+            None
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -84,10 +449,25 @@ pub trait SurfaceManagement {
     /// Sets given buffer as pending for given surface.
     fn attach_surface(&self, mvid: MemoryViewId, sid: SurfaceId);
 
-    /// Informs other parts of application the surface is now not visible.
+    /// Removes pending buffer of given surface. The surface will be unmapped on commit.
     fn detach_surface(&self, sid: SurfaceId);
 
-    /// Commits the surface.
+    /// Marks given area (in surface coordinates) of surface as damaged.
+    fn damage_surface(&self, sid: SurfaceId, area: Area);
+
+    /// Marks given area (in buffer coordinates) of surface as damaged.
+    fn damage_surface_buffer(&self, sid: SurfaceId, area: Area);
+
+    /// Sets pending scale of surfaces buffer.
+    fn set_surface_buffer_scale(&self, sid: SurfaceId, scale: i32);
+
+    /// Sets pending transformation of surfaces buffer.
+    fn set_surface_buffer_transform(&self, sid: SurfaceId, transform: i32);
+
+    /// Commits the surface. Pending buffer, offset, damage, input region, scale and
+    /// transformation become current at once. Signal `surface state applied` is emitted when it
+    /// happens, which may be later than the commit for synchronized subsurfaces or during layout
+    /// transactions.
     fn commit_surface(&self, sid: SurfaceId);
 
     /// Detaches and forgets given surface.
@@ -104,12 +484,24 @@ pub trait SurfaceControl {
     /// Subtracts given show reason flag from set of surfaces show reason.
     fn hide_surface(&self, sid: SurfaceId, reason: show_reason::ShowReason);
 
-    /// Sets position offset given surface.
+    /// Sets pending position offset given surface.
     fn set_surface_offset(&self, sid: SurfaceId, offset: Vector);
 
+    /// Sets pending input region of given surface. `None` means the whole surface accepts input.
+    fn set_surface_input_region(&self, sid: SurfaceId, region: Option<Region>);
+
+    /// Sets pending opaque region of given surface.
+    fn set_surface_opaque_region(&self, sid: SurfaceId, region: Region);
+
     /// Sets requested size for given surface.
     fn set_surface_requested_size(&self, sid: SurfaceId, size: Size);
 
+    /// Sets minimal size requested by client for given surface.
+    fn set_surface_min_size(&self, sid: SurfaceId, size: Size);
+
+    /// Sets maximal size requested by client for given surface.
+    fn set_surface_max_size(&self, sid: SurfaceId, size: Size);
+
     /// Sets satellite surface position relative to its parent.
     fn set_surface_relative_position(&self, sid: SurfaceId, offset: Vector);
 
@@ -118,6 +510,24 @@ pub trait SurfaceControl {
 
     /// Unrelates two surfaces.
     fn unrelate_surface(&self, sid: SurfaceId);
+
+    /// Informs that client acknowledged the most recent configuration of given surface. The next
+    /// commit will conclude surfaces part in ongoing layout transaction.
+    fn acknowledge_surface_configuration(&self, sid: SurfaceId);
+
+    /// Marks surface as transient for other surface (e.g. dialog for its parent window). Passing
+    /// invalid ID as `parent_sid` cancels the relation.
+    fn set_surface_transient_for(&self, sid: SurfaceId, parent_sid: SurfaceId);
+
+    /// Sets if given subsurface is synchronized with its parent. State committed by synchronized
+    /// subsurface is applied when its parent is committed.
+    fn set_surface_synchronized(&self, sid: SurfaceId, synchronized: bool);
+
+    /// Places subsurface directly above its sibling or parent. Takes effect on parents commit.
+    fn place_surface_above(&self, sid: SurfaceId, sibling_sid: SurfaceId);
+
+    /// Places subsurface directly below its sibling or parent. Takes effect on parents commit.
+    fn place_surface_below(&self, sid: SurfaceId, sibling_sid: SurfaceId);
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -126,17 +536,30 @@ pub trait SurfaceControl {
 pub trait SurfaceViewer {
     /// Returns information about surface.
     fn get_surface(&self, sid: SurfaceId) -> Option<SurfaceInfo>;
+
+    /// Returns area (in surface coordinates) of surface content changed since last call for given
+    /// display and clears it. Damage is tracked separately for every display so surface shown on
+    /// many displays is redrawn on all of them.
+    fn take_surface_damage(&self, sid: SurfaceId, display_id: i32) -> Region;
 }
 
 // -------------------------------------------------------------------------------------------------
 
 /// Trait used for configuring and manipulating surfaces.
 pub trait SurfaceAccess {
-    /// Reconfigure surface and send notification about this event.
+    /// Reconfigure surface and send notification about this event. Surface becomes part of layout
+    /// transaction.
     fn reconfigure(&mut self,
                    sid: SurfaceId,
                    size: Size,
                    state_flags: surface_state::SurfaceState);
+
+    /// Returns minimal and maximal size requested by client for given surface.
+    fn get_size_hints(&self, sid: SurfaceId) -> SizeHints;
+
+    /// Checks if layout transaction is in progress, i.e. not all reconfigured surfaces
+    /// acknowledged and committed new configuration. Transaction lasting too long is finished.
+    fn is_transaction_pending(&self) -> bool;
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -0,0 +1,64 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Tests for buffer transformations.
+
+// -------------------------------------------------------------------------------------------------
+
+extern crate qualia;
+
+use self::qualia::defs::{Area, Size};
+use self::qualia::buffer_transform;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if logical size takes scale and rotation into account.
+#[test]
+fn should_calculate_logical_size() {
+    let buffer_size = Size::new(200, 100);
+    let tests: [(i32, i32, Size); 4] = [
+            (1, buffer_transform::NORMAL, Size::new(200, 100)),
+            (2, buffer_transform::NORMAL, Size::new(100, 50)),
+            (1, buffer_transform::ROTATED_90, Size::new(100, 200)),
+            (2, buffer_transform::FLIPPED_270, Size::new(50, 100)),
+        ];
+
+    for test in &tests {
+        let size = buffer_transform::logical_size(buffer_size, test.0, test.1);
+        assert!(size == test.2,
+                "Logical size for scale {} and transform {} should be {:?}, is {:?}",
+                test.0,
+                test.1,
+                test.2,
+                size);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if damage in buffer coordinates is correctly converted to surface coordinates.
+#[test]
+fn should_convert_buffer_area_to_surface_area() {
+    let buffer_size = Size::new(200, 100);
+    let area = Area::create(0, 0, 20, 10);
+    let tests: [(i32, i32, Area); 6] = [
+            (1, buffer_transform::NORMAL, Area::create(0, 0, 20, 10)),
+            (2, buffer_transform::NORMAL, Area::create(0, 0, 10, 5)),
+            (1, buffer_transform::ROTATED_90, Area::create(0, 180, 10, 20)),
+            (1, buffer_transform::ROTATED_180, Area::create(180, 90, 20, 10)),
+            (1, buffer_transform::ROTATED_270, Area::create(90, 0, 10, 20)),
+            (1, buffer_transform::FLIPPED, Area::create(180, 0, 20, 10)),
+        ];
+
+    for test in &tests {
+        let result = buffer_transform::buffer_to_surface(area, buffer_size, test.0, test.1);
+        assert!(result == test.2,
+                "Area for scale {} and transform {} should be {:?}, is {:?}",
+                test.0,
+                test.1,
+                test.2,
+                result);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -0,0 +1,314 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Tests for loading configuration.
+
+// -------------------------------------------------------------------------------------------------
+
+extern crate yaml_rust;
+extern crate qualia;
+
+use self::yaml_rust::YamlLoader;
+use self::qualia::{Config, DeviceKind, InputDeviceInfo, KeyboardConfig};
+use self::qualia::{Area, OutputConfig, OutputInfo, OutputMode, Position, Size};
+
+// -------------------------------------------------------------------------------------------------
+
+const INPUT_CONFIG: &'static str = "
+input:
+  acceleration:
+    profile: adaptive
+    speed: 0.8
+  devices:
+    - name: Precise Mouse
+      acceleration:
+        profile: flat
+        speed: 0.3
+    - vendor: '046d'
+      product: '0xc52b'
+      acceleration:
+        curve: 1.5
+";
+
+const DEVICE_CONFIG: &'static str = "
+keyboard:
+  layout: us
+input:
+  natural_scrolling: false
+  devices:
+    - kind: touchpad
+      natural_scrolling: true
+      scale: 0.5
+    - name: Small Touchpad
+      scale: 2.0
+      scroll_method: edge
+    - kind: mouse
+      left_handed: true
+    - name: Polish Keyboard
+      keyboard:
+        layout: pl
+";
+
+const OUTPUT_CONFIG: &'static str = "
+exhibitor:
+  output:
+    scale: 2
+  outputs:
+    connector-1:
+      position:
+        x: 1920
+        y: -200
+      mode: 1280x1024@75
+      transform: 90
+    Acme Screen:
+      mode: 800x600
+      enabled: false
+    Acme Other 1234:
+      transform: 180
+";
+
+const TRANSFORM_CONFIG: &'static str = "
+exhibitor:
+  output:
+    transform: flipped-90
+  outputs:
+    connector-1:
+      transform: 45
+    connector-2:
+      transform: upside-down
+    connector-3:
+      transform: 270
+";
+
+const WORKSPACE_CONFIG: &'static str = "
+exhibitor:
+  workspaces:
+    1: connector-1
+    mail: Acme Screen
+";
+
+// -------------------------------------------------------------------------------------------------
+
+/// Returns info of output with given connector name, make, model and serial number.
+fn make_output_info(name: &str, make: &str, model: &str, serial: &str) -> OutputInfo {
+    let mut info = OutputInfo::new(1,
+                                   Area::new(Position::default(), Size::new(100, 100)),
+                                   Size::new(100, 100),
+                                   60_000,
+                                   make.to_owned(),
+                                   model.to_owned());
+    info.name = name.to_owned();
+    info.serial = serial.to_owned();
+    info
+}
+
+/// Returns info of mouse with given name and IDs.
+fn make_info(name: &str, vendor: u32, product: u32) -> InputDeviceInfo {
+    make_device_info(name, DeviceKind::Mouse, vendor, product)
+}
+
+/// Returns info of device with given name, kind and IDs.
+fn make_device_info(name: &str, kind: DeviceKind, vendor: u32, product: u32) -> InputDeviceInfo {
+    InputDeviceInfo {
+        name: name.to_owned(),
+        vendor: vendor,
+        product: product,
+        kind: kind,
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if device configuration is chosen by name or by vendor and product ID and if settings not
+/// given in device section are inherited from global section.
+#[test]
+fn should_choose_acceleration_config_for_device() {
+    let mut config = Config::default();
+    config.load(&YamlLoader::load_from_str(INPUT_CONFIG).unwrap());
+    let input = config.get_input_config();
+
+    let acceleration = input.get_device_config(&make_info("Precise Mouse", 1, 2)).acceleration;
+    assert_eq!(acceleration.profile, "flat");
+    assert_eq!(acceleration.speed, 0.3);
+
+    let acceleration = input.get_device_config(&make_info("Other", 0x046d, 0xc52b)).acceleration;
+    assert_eq!(acceleration.profile, "adaptive");
+    assert_eq!(acceleration.speed, 0.8);
+    assert_eq!(acceleration.curve, 1.5);
+
+    let acceleration = input.get_device_config(&make_info("Other", 0x046d, 0xc52c)).acceleration;
+    assert_eq!(acceleration.profile, "adaptive");
+    assert_eq!(acceleration.speed, 0.8);
+    assert_eq!(acceleration.curve, 0.5);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if settings from all matching device sections are applied in order and settings from
+/// sections not matching are not applied.
+#[test]
+fn should_merge_matching_device_configs() {
+    let mut config = Config::default();
+    config.load(&YamlLoader::load_from_str(DEVICE_CONFIG).unwrap());
+    let input = config.get_input_config();
+
+    let touchpad = make_device_info("Small Touchpad", DeviceKind::Touchpad, 1, 2);
+    let device = input.get_device_config(&touchpad);
+    assert_eq!(device.natural_scrolling, true);
+    assert_eq!(device.touchpad_scale, 2.0);
+    assert_eq!(device.scroll_method, "edge");
+    assert_eq!(device.left_handed, false);
+
+    let touchpad = make_device_info("Big Touchpad", DeviceKind::Touchpad, 1, 2);
+    let device = input.get_device_config(&touchpad);
+    assert_eq!(device.natural_scrolling, true);
+    assert_eq!(device.touchpad_scale, 0.5);
+    assert_eq!(device.scroll_method, "two_finger");
+
+    let device = input.get_device_config(&make_info("Mouse", 1, 2));
+    assert_eq!(device.natural_scrolling, false);
+    assert_eq!(device.left_handed, true);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if keyboard layout is chosen per device and all distinct layouts are listed.
+#[test]
+fn should_choose_keyboard_config_for_device() {
+    let mut config = Config::default();
+    config.load(&YamlLoader::load_from_str(DEVICE_CONFIG).unwrap());
+
+    let keyboard = make_device_info("Polish Keyboard", DeviceKind::Keyboard, 1, 2);
+    assert_eq!(config.get_device_keyboard_config(&keyboard).layout, "pl");
+
+    let keyboard = make_device_info("Other Keyboard", DeviceKind::Keyboard, 1, 2);
+    assert_eq!(config.get_device_keyboard_config(&keyboard).layout, "us");
+
+    let layouts: Vec<String> =
+        config.get_keyboard_configs().iter().map(|keyboard| keyboard.layout.clone()).collect();
+    assert_eq!(layouts, vec!["us".to_owned(), "pl".to_owned()]);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if invalid keyboard configurations are replaced with the default one both globally and
+/// for devices.
+#[test]
+fn should_replace_invalid_keyboard_configs_with_default() {
+    let mut config = Config::default();
+    config.load(&YamlLoader::load_from_str(DEVICE_CONFIG).unwrap());
+    config.replace_invalid_keyboard_configs(|keyboard| keyboard.layout != "pl");
+
+    let keyboard = make_device_info("Polish Keyboard", DeviceKind::Keyboard, 1, 2);
+    assert_eq!(config.get_device_keyboard_config(&keyboard), KeyboardConfig::default());
+    assert_eq!(config.get_keyboard_configs(), vec![KeyboardConfig::default()]);
+
+    config.replace_invalid_keyboard_configs(|_| false);
+    assert_eq!(config.get_keyboard_config(), &KeyboardConfig::default());
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if output configuration is chosen by connector name or by identity and if settings not
+/// given in output section are inherited from global section.
+#[test]
+fn should_choose_output_config_by_name_or_identity() {
+    let mut config = Config::default();
+    config.load(&YamlLoader::load_from_str(OUTPUT_CONFIG).unwrap());
+    let exhibitor = config.get_exhibitor_config();
+
+    let info = make_output_info("connector-1", "Acme", "Screen", "");
+    let output = exhibitor.get_output_config(&info);
+    assert_eq!(output.position, Some(Position::new(1920, -200)));
+    assert_eq!(output.transform, "90");
+    assert_eq!(output.scale, 2);
+    assert!(output.enabled);
+    assert_eq!(output.get_mode(),
+               Some(OutputMode {
+                   width: 1280,
+                   height: 1024,
+                   refresh: Some(75),
+               }));
+
+    let info = make_output_info("connector-2", "Acme", "Screen", "5678");
+    let output = exhibitor.get_output_config(&info);
+    assert_eq!(output.position, None);
+    assert_eq!(output.scale, 2);
+    assert!(!output.enabled);
+    assert_eq!(output.get_mode(),
+               Some(OutputMode {
+                   width: 800,
+                   height: 600,
+                   refresh: None,
+               }));
+
+    let info = make_output_info("connector-3", "Acme", "Other", "");
+    let output = exhibitor.get_output_config(&info);
+    assert_eq!(output.transform, "normal");
+    assert_eq!(output.scale, 2);
+    assert!(output.enabled);
+    assert_eq!(output.get_mode(), None);
+
+    let info = make_output_info("connector-4", "Acme", "Other", "1234");
+    let output = exhibitor.get_output_config(&info);
+    assert_eq!(output.transform, "180");
+    assert_eq!(output.scale, 2);
+    assert!(output.enabled);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if invalid output modes are rejected.
+#[test]
+fn should_reject_invalid_output_modes() {
+    let mut output = OutputConfig::default();
+    let modes =
+        ["", "1920", "1920x", "x1080", "0x1080", "1920x1080x2", "1920x1080@", "1920x1080@0"];
+    for mode in modes.iter() {
+        output.mode = mode.to_string();
+        assert_eq!(output.get_mode(), None, "mode: '{}'", mode);
+    }
+
+    output.mode = "1920x1080@59.94".to_owned();
+    assert_eq!(output.get_mode(),
+               Some(OutputMode {
+                   width: 1920,
+                   height: 1080,
+                   refresh: Some(60),
+               }));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if output transformations are accepted both as strings and numbers and if unknown ones are
+/// ignored.
+#[test]
+fn should_load_only_known_output_transforms() {
+    let mut config = Config::default();
+    config.load(&YamlLoader::load_from_str(TRANSFORM_CONFIG).unwrap());
+    let exhibitor = config.get_exhibitor_config();
+
+    let tests = [("connector-1", "flipped-90"),
+                 ("connector-2", "flipped-90"),
+                 ("connector-3", "270")];
+    for &(name, transform) in tests.iter() {
+        let info = make_output_info(name, "Acme", "Screen", "");
+        assert_eq!(exhibitor.get_output_config(&info).transform, transform, "output: {}", name);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if workspaces are pinned to outputs and if numeric workspace titles are accepted.
+#[test]
+fn should_load_workspace_outputs() {
+    let mut config = Config::default();
+    config.load(&YamlLoader::load_from_str(WORKSPACE_CONFIG).unwrap());
+    let workspaces = &config.get_exhibitor_config().compositor.workspaces;
+
+    assert_eq!(workspaces.len(), 2);
+    assert_eq!(workspaces.get("1"), Some(&"connector-1".to_owned()));
+    assert_eq!(workspaces.get("mail"), Some(&"Acme Screen".to_owned()));
+}
+
+// -------------------------------------------------------------------------------------------------
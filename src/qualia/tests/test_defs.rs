@@ -7,7 +7,7 @@
 
 extern crate qualia;
 
-use self::qualia::defs::{Area, Position, Size};
+use self::qualia::defs::{Area, Position, Region, Size};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -111,6 +111,28 @@ fn should_casted_to_zero_area_be_in_origin() {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Check if center of area is calculated relative to its position.
+#[test]
+fn should_calculate_center_of_area() {
+    let tests: [(Area, Position); 4] = [
+            (Area::create( 0,  0, 20, 40), Position::new(10, 20)),
+            (Area::create(10, 20, 30, 40), Position::new(25, 40)),
+            (Area::create(30, 10, 20, 10), Position::new(40, 15)),
+            (Area::create(50, 50,  0,  0), Position::new(50, 50)),
+        ];
+
+    for test in &tests {
+        let center = test.0.calculate_center();
+        assert!(center == test.1,
+                "Center of {:?} should be {:?}, is {:?}",
+                test.0,
+                test.1,
+                center);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Check if inflated area has correct size.
 #[test]
 fn should_inflate_area() {
@@ -139,3 +161,87 @@ fn should_inflate_area() {
 }
 
 // -------------------------------------------------------------------------------------------------
+
+/// Check if intersection of areas is calculated correctly.
+#[test]
+fn should_intersect_areas() {
+    let tests: [(Area, Option<Area>); 5] = [
+            (Area::create( 0,  0, 20, 20), Some(Area::create(10, 10, 10, 10))),
+            (Area::create(20, 20, 10, 10), Some(Area::create(20, 20, 10, 10))),
+            (Area::create(30, 30, 20, 20), Some(Area::create(30, 30, 10, 10))),
+            (Area::create( 0,  0, 10, 50), None),
+            (Area::create(40,  0, 10, 50), None),
+        ];
+
+    let area = Area::create(10, 10, 30, 30);
+    for test in &tests {
+        let intersection = area.intersection(&test.0);
+        assert!(intersection == test.1,
+                "Intersection with {:?} should be {:?}, is {:?}",
+                test.0,
+                test.1,
+                intersection);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if region built from overlapping areas contains correct points.
+#[test]
+fn should_add_areas_to_region() {
+    let mut region = Region::new();
+    region.add(&Area::create(0, 0, 20, 20));
+    region.add(&Area::create(10, 10, 20, 20));
+
+    assert!(region.contains(&Position::new(5, 5)));
+    assert!(region.contains(&Position::new(25, 25)));
+    assert!(region.contains(&Position::new(15, 15)));
+    assert!(!region.contains(&Position::new(25, 5)));
+    assert!(!region.contains(&Position::new(5, 25)));
+    assert_eq!(region.get_extents(), Area::create(0, 0, 30, 30));
+
+    // Rectangles must not overlap
+    let rects = region.get_rects();
+    for (i, a) in rects.iter().enumerate() {
+        for b in rects.iter().skip(i + 1) {
+            assert!(a.intersection(b).is_none(), "{:?} overlaps with {:?}", a, b);
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if subtracting area from region leaves a hole in it.
+#[test]
+fn should_subtract_area_from_region() {
+    let mut region = Region::from_area(Area::create(0, 0, 30, 30));
+    region.subtract(&Area::create(10, 10, 10, 10));
+
+    assert!(region.contains(&Position::new(5, 15)));
+    assert!(region.contains(&Position::new(25, 15)));
+    assert!(region.contains(&Position::new(15, 5)));
+    assert!(region.contains(&Position::new(15, 25)));
+    assert!(!region.contains(&Position::new(10, 10)));
+    assert!(!region.contains(&Position::new(19, 19)));
+    assert!(!region.covers(&Area::create(5, 5, 10, 10)));
+    assert!(region.covers(&Area::create(0, 0, 30, 10)));
+
+    region.subtract(&Area::create(0, 0, 30, 30));
+    assert!(region.is_empty());
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if region covers area only when there are no gaps.
+#[test]
+fn should_check_if_region_covers_area() {
+    let mut region = Region::from_area(Area::create(0, 0, 20, 40));
+    region.add(&Area::create(20, 0, 20, 40));
+
+    assert!(region.covers(&Area::create(10, 10, 20, 20)));
+    assert!(region.covers(&Area::create(0, 0, 40, 40)));
+    assert!(!region.covers(&Area::create(30, 30, 20, 20)));
+    assert!(!Region::new().covers(&Area::create(0, 0, 1, 1)));
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -0,0 +1,120 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Tests for placing layer surfaces.
+
+// -------------------------------------------------------------------------------------------------
+
+extern crate qualia;
+
+use self::qualia::defs::{Area, Size};
+use self::qualia::{layer_anchor, Layer, LayerSurfaceInfo, Margins};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Returns info of surface with given anchor and size.
+fn make_info(anchor: layer_anchor::LayerAnchor, size: Size) -> LayerSurfaceInfo {
+    let mut info = LayerSurfaceInfo::new(None, Layer::Top);
+    info.anchor = anchor;
+    info.size = size;
+    info
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if surface not anchored to any edge is centered and surface anchored to one edge is
+/// attached to it.
+#[test]
+fn should_place_surface_according_to_anchor() {
+    let bounds = Area::create(0, 0, 100, 80);
+    let size = Size::new(20, 10);
+    let tests = [(layer_anchor::NONE, Area::create(40, 35, 20, 10)),
+                 (layer_anchor::TOP, Area::create(40, 0, 20, 10)),
+                 (layer_anchor::BOTTOM, Area::create(40, 70, 20, 10)),
+                 (layer_anchor::LEFT, Area::create(0, 35, 20, 10)),
+                 (layer_anchor::RIGHT | layer_anchor::BOTTOM, Area::create(80, 70, 20, 10))];
+
+    for test in &tests {
+        let area = make_info(test.0, size).place(bounds);
+        assert!(area == test.1,
+                "Area for anchor {:?} should be {:?}, is {:?}",
+                test.0,
+                test.1,
+                area);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if surface with zero width anchored to left and right edge is stretched between them
+/// keeping margins.
+#[test]
+fn should_stretch_surface_between_anchored_edges() {
+    let bounds = Area::create(10, 0, 100, 80);
+    let anchor = layer_anchor::TOP | layer_anchor::LEFT | layer_anchor::RIGHT;
+    let mut info = make_info(anchor, Size::new(0, 20));
+    info.margins = Margins {
+        top: 5,
+        right: 10,
+        bottom: 0,
+        left: 20,
+    };
+
+    assert_eq!(info.place(bounds), Area::create(30, 5, 70, 20));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if surface may be stretched only between edges it is anchored to.
+#[test]
+fn should_validate_size() {
+    let horizontal = layer_anchor::LEFT | layer_anchor::RIGHT;
+    let vertical = layer_anchor::TOP | layer_anchor::BOTTOM;
+    assert!(make_info(layer_anchor::NONE, Size::new(10, 10)).is_size_valid());
+    assert!(make_info(horizontal, Size::new(0, 10)).is_size_valid());
+    assert!(make_info(horizontal | vertical, Size::new(0, 0)).is_size_valid());
+    assert!(!make_info(layer_anchor::LEFT, Size::new(0, 10)).is_size_valid());
+    assert!(!make_info(horizontal, Size::new(0, 0)).is_size_valid());
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if exclusive zone shrinks area along edge the surface is anchored to.
+#[test]
+fn should_reserve_exclusive_zone() {
+    let area = Area::create(0, 0, 100, 80);
+    let horizontal = layer_anchor::LEFT | layer_anchor::RIGHT;
+    let vertical = layer_anchor::TOP | layer_anchor::BOTTOM;
+    let tests = [(layer_anchor::TOP | horizontal, Area::create(0, 20, 100, 60)),
+                 (layer_anchor::BOTTOM, Area::create(0, 0, 100, 60)),
+                 (layer_anchor::LEFT | vertical, Area::create(20, 0, 80, 80)),
+                 (layer_anchor::RIGHT, Area::create(0, 0, 80, 80)),
+                 (layer_anchor::TOP | layer_anchor::LEFT, Area::create(0, 0, 100, 80)),
+                 (horizontal, Area::create(0, 0, 100, 80))];
+
+    for test in &tests {
+        let mut info = make_info(test.0, Size::new(0, 0));
+        info.exclusive_zone = 20;
+        let reserved = info.reserve(area);
+        assert!(reserved == test.1,
+                "Area for anchor {:?} should be {:?}, is {:?}",
+                test.0,
+                test.1,
+                reserved);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Check if surfaces without positive exclusive zone do not reserve space.
+#[test]
+fn should_not_reserve_without_exclusive_zone() {
+    let area = Area::create(0, 0, 100, 80);
+    let mut info = make_info(layer_anchor::TOP, Size::new(0, 10));
+    info.exclusive_zone = -1;
+    assert_eq!(info.reserve(area), area);
+    info.exclusive_zone = 0;
+    assert_eq!(info.reserve(area), area);
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -27,3 +27,68 @@ impl Milliseconds {
 }
 
 // -------------------------------------------------------------------------------------------------
+
+/// Information about presentation of frame on output.
+///
+/// Time is given in `CLOCK_MONOTONIC` domain, the same as used for time stamps of DRM page flip
+/// events.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Presentation {
+    /// ID of output on which frame was presented.
+    pub output_id: i32,
+
+    /// Seconds part of presentation time.
+    pub seconds: u64,
+
+    /// Nanoseconds part of presentation time.
+    pub nanoseconds: u32,
+
+    /// Duration of refresh cycle of the output in nanoseconds or zero if unknown.
+    pub refresh: u32,
+
+    /// Value of vertical retrace counter or zero if unknown.
+    pub sequence: u64,
+
+    /// `true` if time was taken from hardware page flip event synchronized with vertical retrace,
+    /// `false` if it is only an estimate.
+    pub vsync: bool,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Presentation {
+    /// Constructs new `Presentation` from time stamp of page flip. Refresh is unknown.
+    pub fn new(output_id: i32, seconds: u64, nanoseconds: u32, sequence: u64) -> Self {
+        Presentation {
+            output_id: output_id,
+            seconds: seconds,
+            nanoseconds: nanoseconds,
+            refresh: 0,
+            sequence: sequence,
+            vsync: true,
+        }
+    }
+
+    /// Constructs `Presentation` with current time. Used when real presentation time could not be
+    /// obtained.
+    pub fn now(output_id: i32) -> Self {
+        let nanoseconds = time::precise_time_ns();
+        Presentation {
+            output_id: output_id,
+            seconds: nanoseconds / 1_000_000_000,
+            nanoseconds: (nanoseconds % 1_000_000_000) as u32,
+            refresh: 0,
+            sequence: 0,
+            vsync: false,
+        }
+    }
+
+    /// Returns presentation time in milliseconds.
+    pub fn get_milliseconds(&self) -> Milliseconds {
+        Milliseconds {
+            milliseconds: self.seconds * 1000 + self.nanoseconds as u64 / 1_000_000,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
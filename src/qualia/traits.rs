@@ -5,9 +5,11 @@
 //! `Coordinator` from its implementation (mainly useful for mocking in unit test).
 
 use defs::{MemoryPoolId, MemoryViewId, SignalId, SurfaceId};
+use errors::Illusion;
 use memory::{Buffer, MappedMemory};
 use perceptron::Perceptron;
-use surface::{SurfaceManagement, SurfaceControl, SurfaceViewer};
+use screenshot::{Screenshot, ScreenshotId, ScreenshotRequest};
+use surface::{LayerSurfaceInfo, SurfaceManagement, SurfaceControl, SurfaceViewer};
 use surface::{SurfaceAccess, SurfaceListing, SurfaceFocusing};
 
 // -------------------------------------------------------------------------------------------------
@@ -19,6 +21,14 @@ pub trait AppearanceManagement {
 
     /// Sets given surface as background.
     fn set_surface_as_background(&self, sid: SurfaceId);
+
+    /// Sets given surface as icon drawn next to cursor during drag-and-drop. Invalid surface ID
+    /// removes the icon.
+    fn set_surface_as_drag_icon(&self, sid: SurfaceId);
+
+    /// Sets given surface as layer surface (panel, dock, wallpaper, etc.) or updates its state.
+    /// `None` makes the surface no longer be layer surface.
+    fn set_surface_as_layer(&self, sid: SurfaceId, info: Option<LayerSurfaceInfo>);
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -63,6 +73,10 @@ pub trait MemoryManagement {
 
     /// Destroys memory view.
     fn destroy_memory_view(&mut self, mpid: MemoryViewId);
+
+    /// Copies contents of given buffer to memory shared with client through given view. Sizes of
+    /// the buffer and the view must match.
+    fn fill_memory_view(&mut self, mvid: MemoryViewId, buffer: &Buffer) -> Result<(), Illusion>;
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -70,13 +84,13 @@ pub trait MemoryManagement {
 /// Screenshooting related functionality.
 pub trait Screenshooting {
     /// Makes screenshot request.
-    fn take_screenshot(&mut self, id: i32);
+    fn take_screenshot(&mut self, request: ScreenshotRequest);
 
-    /// Sets given buffer as results of screenshot.
-    fn set_screenshot_buffer(&mut self, buffer: Buffer);
+    /// Sets result of screenshot request. `None` means the screenshot could not be taken.
+    fn set_screenshot(&mut self, id: ScreenshotId, screenshot: Option<Screenshot>);
 
-    /// Returns and forgets screenshot buffer.
-    fn take_screenshot_buffer(&mut self) -> Option<Buffer>;
+    /// Returns and forgets result of screenshot request.
+    fn take_screenshot_result(&mut self, id: ScreenshotId) -> Option<Screenshot>;
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -36,6 +36,11 @@ static SURFACE_ATTRIB_LIST: [egl::EGLint; 0] = [];
 
 // -------------------------------------------------------------------------------------------------
 
+/// Attribute for querying age of back buffer defined by `EGL_EXT_buffer_age` extension.
+const EGL_BUFFER_AGE_EXT: egl::EGLint = 0x313D;
+
+// -------------------------------------------------------------------------------------------------
+
 /// Log EGL error.
 pub fn log_status() {
     log_info1!("Status - EGL: 0x{:x}", egl::get_error());
@@ -135,6 +140,17 @@ impl EglBucket {
         }
     }
 
+    /// Returns age of current back buffer (number of frames since its content was drawn) or `0` if
+    /// it is unknown (e.g. if `EGL_EXT_buffer_age` is not supported). Context must be current.
+    pub fn query_buffer_age(&self) -> i32 {
+        let mut age = 0;
+        if egl::query_surface(self.display, self.surface, EGL_BUFFER_AGE_EXT, &mut age) {
+            age
+        } else {
+            0
+        }
+    }
+
     /// Gets GBM display.
     ///
     /// First tries `eglGetDisplay`. If that fails, tries `eglGetPlatformDisplayEXT`.
@@ -6,10 +6,12 @@
 // -------------------------------------------------------------------------------------------------
 
 use std;
+use std::collections::VecDeque;
 use gl;
 use egl;
 
-use qualia::{SurfaceViewer, SurfaceContext, Illusion, Size, Buffer, Pixmap};
+use qualia::{SurfaceViewer, SurfaceContext, Illusion, Area, Position, Region, Size, Buffer, Pixmap};
+use qualia::buffer_transform;
 
 use gl_tools;
 use egl_tools;
@@ -18,6 +20,10 @@ use egl_tools;
 
 const MAX_TEXTURES: u32 = 32;
 
+/// Number of frames for which damage is remembered. Back buffers older than this are repainted
+/// fully.
+const MAX_DAMAGE_HISTORY: usize = 4;
+
 /// Vertex shader source code for OpenGL ES 2.0 (GLSL ES 100)
 const VERTEX_SHADER_100: &'static str = include_str!("vertex.100.glsl");
 
@@ -36,6 +42,8 @@ const FRAGMENT_SHADER_300: &'static str = include_str!("fragment.300.glsl");
 pub struct RendererGl {
     egl: egl_tools::EglBucket,
     size: Size,
+    scale: i32,
+    transform: i32,
 
     // GL rendering
     program: gl::types::GLuint,
@@ -46,6 +54,9 @@ pub struct RendererGl {
     vbo_vertices: gl::types::GLuint,
     vbo_texcoords: gl::types::GLuint,
     vbo_texture: [gl::types::GLuint; MAX_TEXTURES as usize],
+
+    // Damage of recently drawn frames, the most recent first
+    damage_history: VecDeque<Region>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -56,6 +67,8 @@ impl RendererGl {
         RendererGl {
             egl: egl,
             size: size,
+            scale: 1,
+            transform: buffer_transform::NORMAL,
             program: gl::types::GLuint::default(),
             loc_vertices: gl::types::GLint::default(),
             loc_texcoords: gl::types::GLint::default(),
@@ -64,9 +77,22 @@ impl RendererGl {
             vbo_vertices: gl::types::GLuint::default(),
             vbo_texcoords: gl::types::GLuint::default(),
             vbo_texture: [0; MAX_TEXTURES as usize],
+            damage_history: VecDeque::with_capacity(MAX_DAMAGE_HISTORY + 1),
         }
     }
 
+    /// Sets integer scale of the output. Surfaces are positioned in logical coordinates which are
+    /// multiplied by the scale when drawing.
+    pub fn set_scale(&mut self, scale: i32) {
+        self.scale = scale;
+    }
+
+    /// Sets transformation of the output as defined by `wl_output.transform`. Surfaces are
+    /// positioned in transformed coordinates which are mapped to framebuffer when drawing.
+    pub fn set_transform(&mut self, transform: i32) {
+        self.transform = transform;
+    }
+
     /// Initialize renderer.
     ///  - prepare shaders and program,
     ///  - bind locations,
@@ -119,19 +145,31 @@ impl RendererGl {
         Ok(())
     }
 
-    /// Draw passed frame scene.
+    /// Draw passed frame scene. Surfaces hidden behind opaque regions of surfaces placed above
+    /// them are not drawn. Drawing is limited to damaged areas (given in logical coordinates).
     pub fn draw(&mut self,
                 layunder: &Vec<SurfaceContext>,
                 surfaces: &Vec<SurfaceContext>,
                 layover: &Vec<SurfaceContext>,
+                damage: &Region,
                 viewer: &SurfaceViewer)
                 -> Result<(), Illusion> {
         let _context = self.egl.make_current()?;
-        self.prepare_view();
+        let repaint = self.calculate_repaint_region(damage);
+        let scissors = self.prepare_scissors(&repaint);
+        if scissors.is_empty() {
+            return Ok(());
+        }
+
+        let mut covered = Region::new();
+        let visible_surfaces = self.find_visible(surfaces, &mut covered, viewer);
+        let visible_layunder = self.find_visible(layunder, &mut covered, viewer);
+
+        self.prepare_view(&scissors);
         self.draw_bg_image();
-        self.draw_surfaces(layunder, viewer);
-        self.draw_surfaces(surfaces, viewer);
-        self.draw_surfaces(layover, viewer);
+        self.draw_surfaces(&visible_layunder, &scissors, viewer);
+        self.draw_surfaces(&visible_surfaces, &scissors, viewer);
+        self.draw_surfaces(layover, &scissors, viewer);
         self.release_view();
         Ok(())
     }
@@ -142,21 +180,28 @@ impl RendererGl {
         context.swap_buffers()
     }
 
-    /// Reads pixels for whole screen and returns image data as `Buffer`.
-    pub fn take_screenshot(&self) -> Result<Buffer, Illusion> {
+    /// Reads pixels of given area (in logical coordinates) from back buffer and returns image data
+    /// as `Buffer`. Image of transformed output is transformed back so it looks like the screen.
+    pub fn take_screenshot(&self, area: Area) -> Result<Buffer, Illusion> {
         let _context = self.egl.make_current()?;
 
-        let stride = 4 * self.size.width;
-        let size = stride * self.size.height;
+        let scale = self.scale as isize;
+        let fb_area = self.to_framebuffer(area);
+        let width = self.scale as usize * fb_area.size.width;
+        let height = self.scale as usize * fb_area.size.height;
+        let stride = 4 * width;
+        let size = stride * height;
         let mut dst: Vec<u8> = Vec::with_capacity(size);
         unsafe { dst.set_len(size) };
 
+        // Framebuffer coordinates start in bottom left corner
+        let bottom = fb_area.pos.y + fb_area.size.height as isize;
         unsafe {
             gl::ReadBuffer(gl::BACK);
-            gl::ReadPixels(0,
-                           0,
-                           self.size.width as i32,
-                           self.size.height as i32,
+            gl::ReadPixels((scale * fb_area.pos.x) as gl::types::GLint,
+                           (self.size.height as isize - scale * bottom) as gl::types::GLint,
+                           width as gl::types::GLsizei,
+                           height as gl::types::GLsizei,
                            gl::RGBA,
                            gl::UNSIGNED_BYTE,
                            dst.as_mut_ptr() as *mut std::os::raw::c_void);
@@ -168,7 +213,11 @@ impl RendererGl {
             data.extend(chunk);
         }
 
-        Ok(Buffer::new(self.size.width, self.size.height, stride, data))
+        if self.transform == buffer_transform::NORMAL {
+            Ok(Buffer::new(width, height, stride, data))
+        } else {
+            Ok(self.untransform_image(&data, Size::new(width, height)))
+        }
     }
 }
 
@@ -176,23 +225,93 @@ impl RendererGl {
 
 /// Drawing helpers.
 impl RendererGl {
-    /// Prepare view for drawing.
-    fn prepare_view(&self) {
+    /// Returns region (in logical coordinates) which has to be repainted in current back buffer.
+    /// Back buffer contains frame drawn `age` frames ago so damage of all frames since then has to
+    /// be repainted. Whole screen is repainted if age of the buffer is unknown.
+    fn calculate_repaint_region(&mut self, damage: &Region) -> Region {
+        let age = self.egl.query_buffer_age() as usize;
+        self.damage_history.push_front(damage.clone());
+        self.damage_history.truncate(MAX_DAMAGE_HISTORY);
+
+        if (age > 0) && (age <= self.damage_history.len()) {
+            let mut region = Region::new();
+            for frame_damage in self.damage_history.iter().take(age) {
+                region.union(frame_damage);
+            }
+            region
+        } else {
+            Region::from_area(Area::new(Position::default(), self.get_logical_size()))
+        }
+    }
+
+    /// Converts region in logical coordinates to list of scissor boxes (x, y, width, height) in
+    /// framebuffer coordinates. Parts of region outside of the screen are skipped.
+    fn prepare_scissors(&self, region: &Region) -> Vec<[gl::types::GLint; 4]> {
+        let scale = self.scale as isize;
+        let screen = Area::new(Position::default(), self.get_logical_size());
+
+        let mut scissors = Vec::with_capacity(region.get_rects().len());
+        for rect in region.get_rects().iter().filter_map(|rect| rect.intersection(&screen)) {
+            let rect = self.to_framebuffer(rect);
+
+            // Framebuffer coordinates start in bottom left corner
+            let bottom = rect.pos.y + rect.size.height as isize;
+            scissors.push([(scale * rect.pos.x) as gl::types::GLint,
+                           (self.size.height as isize - scale * bottom) as gl::types::GLint,
+                           (scale * rect.size.width as isize) as gl::types::GLint,
+                           (scale * rect.size.height as isize) as gl::types::GLint]);
+        }
+        scissors
+    }
+
+    /// Prepare view for drawing. Only areas inside of passed scissor boxes are cleared.
+    fn prepare_view(&self, scissors: &Vec<[gl::types::GLint; 4]>) {
         unsafe {
+            gl::Enable(gl::SCISSOR_TEST);
             gl::ClearColor(0.0, 0.3, 0.5, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            for scissor in scissors.iter() {
+                gl::Scissor(scissor[0], scissor[1], scissor[2], scissor[3]);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            }
 
             gl::Enable(gl::BLEND);
             gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
 
+            // Vertices are given in logical coordinates
             gl::UseProgram(self.program);
-            gl::Uniform2i(self.loc_screen_size, self.size.width as i32, self.size.height as i32);
+            gl::Uniform2i(self.loc_screen_size,
+                          self.size.width as i32 / self.scale,
+                          self.size.height as i32 / self.scale);
         }
     }
 
     /// Draw background image.
     fn draw_bg_image(&self) {}
 
+    /// Returns surfaces which are not fully covered by `covered` region or by opaque regions of
+    /// surfaces placed above them. Opaque regions of visible surfaces are added to `covered`.
+    fn find_visible(&self,
+                    surfaces: &Vec<SurfaceContext>,
+                    covered: &mut Region,
+                    viewer: &SurfaceViewer)
+                    -> Vec<SurfaceContext> {
+        let mut visible = Vec::with_capacity(surfaces.len());
+        for context in surfaces.iter().rev() {
+            if let Some(ref surface) = viewer.get_surface(context.id) {
+                if let Some(size) = surface.get_logical_size() {
+                    let pos = context.pos - surface.offset;
+                    if covered.covers(&Area::new(pos, size)) {
+                        continue;
+                    }
+                    covered.union(&surface.opaque_region.translated(pos));
+                }
+            }
+            visible.push(context.clone());
+        }
+        visible.reverse();
+        visible
+    }
+
     /// Load textures and prepare vertices.
     fn load_texture_and_prepare_vertices(&self,
                                          viewer: &SurfaceViewer,
@@ -216,10 +335,13 @@ impl RendererGl {
                                    (*buffer).as_ptr() as *const _);
                 }
 
+                let size = buffer_transform::logical_size((*buffer).get_size(),
+                                                          surface.buffer_scale,
+                                                          surface.buffer_transform);
                 let left = (context.pos.x - surface.offset.x) as gl::types::GLfloat;
                 let top = (context.pos.y - surface.offset.y) as gl::types::GLfloat;
-                let right = left + (*buffer).get_width() as gl::types::GLfloat;
-                let bottom = top + (*buffer).get_height() as gl::types::GLfloat;
+                let right = left + size.width as gl::types::GLfloat;
+                let bottom = top + size.height as gl::types::GLfloat;
 
                 vertices[0] = left;
                 vertices[1] = top;
@@ -234,18 +356,23 @@ impl RendererGl {
                 vertices[10] = left;
                 vertices[11] = bottom;
 
-                texcoords[0] = 0.0;
-                texcoords[1] = 0.0;
-                texcoords[2] = 1.0;
-                texcoords[3] = 0.0;
-                texcoords[4] = 0.0;
-                texcoords[5] = 1.0;
-                texcoords[6] = 1.0;
-                texcoords[7] = 0.0;
-                texcoords[8] = 1.0;
-                texcoords[9] = 1.0;
-                texcoords[10] = 0.0;
-                texcoords[11] = 1.0;
+                // Transformed outputs are drawn by transforming vertices to framebuffer
+                if self.transform != buffer_transform::NORMAL {
+                    for i in 0..6 {
+                        let (x, y) = self.transform_point(vertices[2 * i], vertices[2 * i + 1]);
+                        vertices[2 * i] = x;
+                        vertices[2 * i + 1] = y;
+                    }
+                }
+
+                // Rotated or flipped buffers are drawn by transforming texture coordinates
+                let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0),
+                               (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+                for (i, &(s, t)) in corners.iter().enumerate() {
+                    let (u, v) = buffer_transform::map_coords(surface.buffer_transform, s, t);
+                    texcoords[2 * i] = u;
+                    texcoords[2 * i + 1] = v;
+                }
             } else {
                 log_error!("Renderer: No buffer for surface {}", context.id);
             }
@@ -254,8 +381,11 @@ impl RendererGl {
         }
     }
 
-    /// Draw surfaces.
-    fn draw_surfaces(&self, surfaces: &Vec<SurfaceContext>, viewer: &SurfaceViewer) {
+    /// Draw surfaces. Drawing is limited to passed scissor boxes.
+    fn draw_surfaces(&self,
+                     surfaces: &Vec<SurfaceContext>,
+                     scissors: &Vec<[gl::types::GLint; 4]>,
+                     viewer: &SurfaceViewer) {
         if surfaces.len() == 0 {
             return;
         }
@@ -305,10 +435,13 @@ impl RendererGl {
                            texcoords.as_ptr() as *const _,
                            gl::DYNAMIC_DRAW);
 
-            // Redraw everything
-            for i in 0..surfaces.len() as i32 {
-                gl::Uniform1i(self.loc_texture, i);
-                gl::DrawArrays(gl::TRIANGLES, 6 * i, 6);
+            // Redraw damaged areas
+            for scissor in scissors.iter() {
+                gl::Scissor(scissor[0], scissor[1], scissor[2], scissor[3]);
+                for i in 0..surfaces.len() as i32 {
+                    gl::Uniform1i(self.loc_texture, i);
+                    gl::DrawArrays(gl::TRIANGLES, 6 * i, 6);
+                }
             }
 
             // Release resources
@@ -320,6 +453,7 @@ impl RendererGl {
     /// Unbind framebuffer and program.
     fn release_view(&self) {
         unsafe {
+            gl::Disable(gl::SCISSOR_TEST);
             gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
             gl::UseProgram(0);
         }
@@ -327,3 +461,62 @@ impl RendererGl {
 }
 
 // -------------------------------------------------------------------------------------------------
+
+/// Transformation helpers.
+impl RendererGl {
+    /// Returns size of the screen in logical coordinates.
+    fn get_logical_size(&self) -> Size {
+        buffer_transform::logical_size(self.size, self.scale, self.transform)
+    }
+
+    /// Maps point in logical coordinates to framebuffer coordinates (not multiplied by scale).
+    fn transform_point(&self,
+                       x: gl::types::GLfloat,
+                       y: gl::types::GLfloat)
+                       -> (gl::types::GLfloat, gl::types::GLfloat) {
+        let logical_size = self.get_logical_size();
+        let (s, t) = buffer_transform::map_coords(self.transform,
+                                                  x / logical_size.width as gl::types::GLfloat,
+                                                  y / logical_size.height as gl::types::GLfloat);
+        (s * (self.size.width / self.scale as usize) as gl::types::GLfloat,
+         t * (self.size.height / self.scale as usize) as gl::types::GLfloat)
+    }
+
+    /// Maps area in logical coordinates to framebuffer coordinates (not multiplied by scale).
+    fn to_framebuffer(&self, area: Area) -> Area {
+        if self.transform == buffer_transform::NORMAL {
+            return area;
+        }
+
+        let (x1, y1) = self.transform_point(area.pos.x as gl::types::GLfloat,
+                                            area.pos.y as gl::types::GLfloat);
+        let (x2, y2) = self.transform_point((area.pos.x + area.size.width as isize) as _,
+                                            (area.pos.y + area.size.height as isize) as _);
+        Area::create(x1.min(x2).round() as isize,
+                     y1.min(y2).round() as isize,
+                     (x1 - x2).abs().round() as usize,
+                     (y1 - y2).abs().round() as usize)
+    }
+
+    /// Transforms image read from framebuffer of given size back to logical orientation.
+    fn untransform_image(&self, data: &[u8], fb_size: Size) -> Buffer {
+        let size = buffer_transform::logical_size(fb_size, 1, self.transform);
+        let stride = 4 * size.width;
+        let mut result = vec![0; stride * size.height];
+        for y in 0..size.height {
+            for x in 0..size.width {
+                let (s, t) = buffer_transform::map_coords(self.transform,
+                                                          (x as f32 + 0.5) / size.width as f32,
+                                                          (y as f32 + 0.5) / size.height as f32);
+                let src_x = ((s * fb_size.width as f32) as usize).min(fb_size.width - 1);
+                let src_y = ((t * fb_size.height as f32) as usize).min(fb_size.height - 1);
+                let src = 4 * (src_y * fb_size.width + src_x);
+                let dst = 4 * (y * size.width + x);
+                result[dst..dst + 4].copy_from_slice(&data[src..src + 4]);
+            }
+        }
+        Buffer::new(size.width, size.height, stride, result)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
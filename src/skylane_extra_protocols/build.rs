@@ -0,0 +1,44 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Generates server side bindings from protocol description files.
+
+extern crate skylane_scanner;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Names of protocol description files in `protocols` directory.
+const PROTOCOLS: &'static [&'static str] = &["primary-selection-unstable-v1",
+                                             "xdg-shell",
+                                             "presentation-time",
+                                             "wlr-layer-shell-unstable-v1",
+                                             "wlr-screencopy-unstable-v1"];
+
+// -------------------------------------------------------------------------------------------------
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").expect("Read OUT_DIR variable");
+    let src_dir = env::current_dir().expect("Get current directory");
+
+    for protocol in PROTOCOLS {
+        let mut src_path = src_dir.join("protocols").join(protocol);
+        src_path.set_extension("xml");
+        println!("cargo:rerun-if-changed={}", src_path.display());
+
+        let mut scanner = skylane_scanner::Scanner::new(&src_path)
+            .expect(format!("Initialize scanner for file {:?}", &src_path).as_str());
+        let protocol_name = scanner.get_protocol_name().expect("Extract protocol name");
+
+        let dst_path = PathBuf::from(&out_dir).join(format!("{}_server.rs", protocol_name));
+        let mut file = File::create(&dst_path).expect("Create file");
+        file.write_all(scanner.generate_server_interface(0).as_bytes())
+            .expect(format!("Write to file: {:?}", &dst_path).as_str());
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
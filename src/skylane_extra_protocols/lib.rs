@@ -0,0 +1,108 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Server side bindings of `Wayland` protocols not provided by `skylane_protocols`.
+//!
+//! Bindings are generated at build time with `skylane_scanner` from protocol description files
+//! stored in `protocols` directory, the same way as `skylane_protocols` does it.
+//!
+//! `skylane_protocols` keeps its `Dispatcher` trait private so dispatchers generated outside of it
+//! can not be bound with its `Handler`. This crate provides its own `Handler` which can be
+//! registered in `skylane::server::Connection` next to the ones from `skylane_protocols`.
+
+#![warn(missing_docs)]
+
+extern crate skylane;
+extern crate byteorder;
+
+// -------------------------------------------------------------------------------------------------
+
+mod private {
+    use std::io::Cursor;
+    use skylane::server::{Bundle, Header, Object, SkylaneError, Task};
+
+    /// Trait implemented by generated `Dispatcher`s. `Dispatcher` parses data of request and
+    /// calls appropriate method of object implementing related interface.
+    pub trait Dispatcher<I> {
+        /// Constructs new `Dispatcher`.
+        fn new() -> Self;
+
+        /// Demarshals request and calls appropriate method of passed `object`.
+        fn dispatch(&mut self,
+                    object: &mut I,
+                    bundle: &mut Bundle,
+                    header: &Header,
+                    bytes_buf: &mut Cursor<&[u8]>,
+                    fds_buf: &mut Cursor<&[u8]>)
+                    -> Result<Task, SkylaneError>;
+    }
+
+    /// Binds `Dispatcher` with object implementing its interface.
+    pub struct Handler<I, D>
+        where D: Dispatcher<I>
+    {
+        object: I,
+        dispatcher: D,
+    }
+
+    impl<I, D> Handler<I, D>
+        where D: Dispatcher<I>
+    {
+        /// Constructs new `Handler`.
+        pub fn new(object: I) -> Self {
+            Handler {
+                object: object,
+                dispatcher: D::new(),
+            }
+        }
+    }
+
+    impl<I, D> Object for Handler<I, D>
+        where D: Dispatcher<I>
+    {
+        fn dispatch(&mut self,
+                    bundle: &mut Bundle,
+                    header: &Header,
+                    bytes_buf: &mut Cursor<&[u8]>,
+                    fds_buf: &mut Cursor<&[u8]>)
+                    -> Result<Task, SkylaneError> {
+            self.dispatcher.dispatch(&mut self.object, bundle, header, bytes_buf, fds_buf)
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Server-side protocols.
+pub mod server {
+    use skylane::server::{Bundle, Header, ObjectId, SkylaneError, Socket, Task};
+    use private::Dispatcher;
+    pub use private::Handler;
+
+    /// Protocol generated from `primary-selection-unstable-v1.xml`
+    pub mod primary_selection_unstable_v1 {
+        include!(concat!(env!("OUT_DIR"), "/wp_primary_selection_unstable_v1_server.rs"));
+    }
+
+    /// Protocol generated from `xdg-shell.xml`
+    pub mod xdg_shell {
+        include!(concat!(env!("OUT_DIR"), "/xdg_shell_server.rs"));
+    }
+
+    /// Protocol generated from `presentation-time.xml`
+    pub mod presentation_time {
+        include!(concat!(env!("OUT_DIR"), "/presentation_time_server.rs"));
+    }
+
+    /// Protocol generated from `wlr-layer-shell-unstable-v1.xml`
+    pub mod wlr_layer_shell_unstable_v1 {
+        include!(concat!(env!("OUT_DIR"), "/wlr_layer_shell_unstable_v1_server.rs"));
+    }
+
+    /// Protocol generated from `wlr-screencopy-unstable-v1.xml`
+    pub mod wlr_screencopy_unstable_v1 {
+        include!(concat!(env!("OUT_DIR"), "/wlr_screencopy_unstable_v1_server.rs"));
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -4,11 +4,13 @@
 //! This module contains wanna-be-mock of `Coordinator`. Currently it is more a stub.
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 
-use qualia::{Perceptron, Position, SignalId, Size, Vector};
-use qualia::{SurfaceContext, SurfaceId, SurfaceInfo, surface_state, show_reason};
-use qualia::{Buffer, MappedMemory, MemoryPoolId, MemoryViewId};
+use qualia::{Area, Perceptron, Position, Region, SignalId, Size, Vector};
+use qualia::{SizeHints, SurfaceContext, SurfaceId, SurfaceInfo, surface_state, show_reason};
+use qualia::{Buffer, Illusion, MappedMemory, MemoryPoolId, MemoryViewId};
+use qualia::{Screenshot, ScreenshotId, ScreenshotRequest};
 use qualia::{SurfaceManagement, SurfaceControl, SurfaceViewer};
 use qualia::{SurfaceAccess, SurfaceListing, SurfaceFocusing};
 use qualia::{Emiter, Screenshooting, MemoryManagement, ExhibitorCoordinationTrait};
@@ -16,13 +18,16 @@ use qualia::{Emiter, Screenshooting, MemoryManagement, ExhibitorCoordinationTrai
 // -------------------------------------------------------------------------------------------------
 
 /// Mock of `Coordinator`.
-pub struct InnerCoordinatorMock {}
+pub struct InnerCoordinatorMock {
+    /// IDs of surfaces for which surfaces are transient.
+    transients: HashMap<SurfaceId, SurfaceId>,
+}
 
 // -------------------------------------------------------------------------------------------------
 
 impl InnerCoordinatorMock {
     pub fn new() -> Self {
-        InnerCoordinatorMock {}
+        InnerCoordinatorMock { transients: HashMap::new() }
     }
 }
 
@@ -51,6 +56,10 @@ impl SurfaceManagement for CoordinatorMock {
     fn create_surface(&mut self) -> SurfaceId { SurfaceId::new(0) }
     fn attach_surface(&self, mvid: MemoryViewId, sid: SurfaceId) {}
     fn detach_surface(&self, sid: SurfaceId) {}
+    fn damage_surface(&self, sid: SurfaceId, area: Area) {}
+    fn damage_surface_buffer(&self, sid: SurfaceId, area: Area) {}
+    fn set_surface_buffer_scale(&self, sid: SurfaceId, scale: i32) {}
+    fn set_surface_buffer_transform(&self, sid: SurfaceId, transform: i32) {}
     fn commit_surface(&self, sid: SurfaceId) {}
     fn destroy_surface(&self, sid: SurfaceId) {}
 }
@@ -62,10 +71,21 @@ impl SurfaceControl for CoordinatorMock {
     fn show_surface(&self, sid: SurfaceId, reason: show_reason::ShowReason) {}
     fn hide_surface(&self, sid: SurfaceId, reason: show_reason::ShowReason) {}
     fn set_surface_offset(&self, sid: SurfaceId, offset: Vector) {}
+    fn set_surface_input_region(&self, sid: SurfaceId, region: Option<Region>) {}
+    fn set_surface_opaque_region(&self, sid: SurfaceId, region: Region) {}
     fn set_surface_requested_size(&self, sid: SurfaceId, size: Size) {}
+    fn set_surface_min_size(&self, sid: SurfaceId, size: Size) {}
+    fn set_surface_max_size(&self, sid: SurfaceId, size: Size) {}
     fn set_surface_relative_position(&self, sid: SurfaceId, offset: Vector) {}
     fn relate_surfaces(&self, sid: SurfaceId, parent_sid: SurfaceId) {}
     fn unrelate_surface(&self, sid: SurfaceId) {}
+    fn acknowledge_surface_configuration(&self, sid: SurfaceId) {}
+    fn set_surface_transient_for(&self, sid: SurfaceId, parent_sid: SurfaceId) {
+        self.mock.borrow_mut().transients.insert(sid, parent_sid);
+    }
+    fn set_surface_synchronized(&self, sid: SurfaceId, synchronized: bool) {}
+    fn place_surface_above(&self, sid: SurfaceId, sibling_sid: SurfaceId) {}
+    fn place_surface_below(&self, sid: SurfaceId, sibling_sid: SurfaceId) {}
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -73,16 +93,33 @@ impl SurfaceControl for CoordinatorMock {
 #[allow(unused_variables)]
 impl SurfaceViewer for CoordinatorMock {
     fn get_surface(&self, sid: SurfaceId) -> Option<SurfaceInfo> {
+        let transient_for_sid = match self.mock.borrow().transients.get(&sid) {
+            Some(parent_sid) => *parent_sid,
+            None => SurfaceId::invalid(),
+        };
+
         Some( SurfaceInfo {
             id: sid,
             offset: Vector::default(),
             parent_sid: SurfaceId::invalid(),
+            transient_for_sid: transient_for_sid,
             desired_size: Size::default(),
             requested_size: Size::default(),
+            size_hints: SizeHints::default(),
             state_flags: surface_state::REGULAR,
             buffer: None,
+            damage: Vec::new(),
+            buffer_damage: Vec::new(),
+            input_region: None,
+            opaque_region: Region::new(),
+            buffer_scale: 1,
+            buffer_transform: 0,
         })
     }
+
+    fn take_surface_damage(&self, sid: SurfaceId, display_id: i32) -> Region {
+        Region::new()
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -93,6 +130,12 @@ impl SurfaceAccess for CoordinatorMock {
                    sid: SurfaceId,
                    size: Size,
                    state_flags: surface_state::SurfaceState) {}
+    fn get_size_hints(&self, sid: SurfaceId) -> SizeHints {
+        SizeHints::default()
+    }
+    fn is_transaction_pending(&self) -> bool {
+        false
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -150,15 +193,18 @@ impl MemoryManagement for CoordinatorMock {
         None
     }
     fn destroy_memory_view(&mut self, mpid: MemoryViewId) {}
+    fn fill_memory_view(&mut self, mvid: MemoryViewId, buffer: &Buffer) -> Result<(), Illusion> {
+        Ok(())
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
 
 #[allow(unused_variables)]
 impl Screenshooting for CoordinatorMock {
-    fn take_screenshot(&mut self, id: i32) {}
-    fn set_screenshot_buffer(&mut self, buffer: Buffer) {}
-    fn take_screenshot_buffer(&mut self) -> Option<Buffer> {
+    fn take_screenshot(&mut self, request: ScreenshotRequest) {}
+    fn set_screenshot(&mut self, id: ScreenshotId, screenshot: Option<Screenshot>) {}
+    fn take_screenshot_result(&mut self, id: ScreenshotId) -> Option<Screenshot> {
         None
     }
 }
@@ -8,7 +8,7 @@ extern crate output;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use qualia::{OutputInfo, Illusion, Buffer, SurfaceContext, SurfaceViewer};
+use qualia::{Area, OutputInfo, Illusion, Buffer, Position, Region, SurfaceContext, SurfaceViewer};
 use self::output::Output;
 
 // -------------------------------------------------------------------------------------------------
@@ -54,12 +54,13 @@ impl Output for OutputMock {
             layunder: &Vec<SurfaceContext>,
             surfaces: &Vec<SurfaceContext>,
             layover: &Vec<SurfaceContext>,
+            damage: &Region,
             viewer: &SurfaceViewer)
             -> Result<(), Illusion> {
         Ok(())
     }
 
-    fn take_screenshot(&self) -> Result<Buffer, Illusion> {
+    fn take_screenshot(&self, area: Area) -> Result<Buffer, Illusion> {
         panic!("Taking screenshot not supported in unit test");
     }
 
@@ -68,6 +69,21 @@ impl Output for OutputMock {
         mine.info.clone()
     }
 
+    fn set_scale(&mut self, scale: i32) {
+        let mut mine = self.mock.borrow_mut();
+        mine.info.scale = scale;
+    }
+
+    fn set_transform(&mut self, transform: i32) {
+        let mut mine = self.mock.borrow_mut();
+        mine.info.transform = transform;
+    }
+
+    fn set_position(&mut self, position: Position) {
+        let mut mine = self.mock.borrow_mut();
+        mine.info.area.pos = position;
+    }
+
     fn swap_buffers(&mut self) -> Result<u32, Illusion> {
         Ok(u32::default())
     }
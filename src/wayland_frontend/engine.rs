@@ -9,16 +9,20 @@ use std::collections::HashMap;
 
 use dharma;
 use skylane::server as wl;
+use skylane_protocols::server::wayland::wl_data_source;
 
-use qualia::{Axis, Button, Key, Milliseconds, OutputInfo, Position, Size, KeyMods};
-use qualia::{KeyboardConfig, KeyboardState, Perceptron, Settings};
-use qualia::{surface_state, SurfaceId, SurfaceFocusing};
+use qualia::{Axis, Button, Key, Milliseconds, OutputInfo, Position, Presentation, Size, KeyMods};
+use qualia::{KeyboardConfig, KeyboardState, Perceptron, ScreenshotId, Screenshooting, Settings};
+use qualia::{surface_state, AppearanceManagement, SurfaceId, SurfaceFocusing};
 use coordination::Coordinator;
 
 use protocol;
+use protocol::data_device_manager::DataOffer;
+use protocol::primary_selection::PrimarySelectionOffer;
+use facade::SelectionKind;
 use gateway::Gateway;
 use proxy::{Proxy, ProxyRef};
-use mediator::{Mediator, MediatorRef};
+use mediator::{DragState, Mediator, MediatorRef};
 use event_handlers::{ClientEventHandler, DisplayEventHandler};
 
 // -------------------------------------------------------------------------------------------------
@@ -42,7 +46,9 @@ pub struct Engine {
     coordinator: Coordinator,
     settings: Settings,
     dispatcher: dharma::LocalDispatcher,
-    keyboard_state: KeyboardState,
+    keyboard_state: Option<KeyboardState>,
+    pointer_position: Position,
+    pressed_button: Option<u16>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -60,7 +66,9 @@ impl Engine {
             coordinator: coordinator,
             settings: settings,
             dispatcher: dharma::LocalDispatcher::new(),
-            keyboard_state: KeyboardState::new(&keyboard_config).expect("Creating keyboard state"),
+            keyboard_state: Self::create_keyboard_state(&keyboard_config),
+            pointer_position: Position::default(),
+            pressed_button: None,
         }
     }
 
@@ -104,12 +112,17 @@ impl Engine {
         proxy.register_global(protocol::compositor::get_global());
         proxy.register_global(protocol::shell::get_global());
         proxy.register_global(protocol::xdg_shell_v6::get_global());
+        proxy.register_global(protocol::xdg_shell::get_global());
         proxy.register_global(protocol::data_device_manager::get_global());
+        proxy.register_global(protocol::primary_selection::get_global());
         proxy.register_global(protocol::seat::get_global());
         proxy.register_global(protocol::subcompositor::get_global());
         proxy.register_global(protocol::weston_screenshooter::get_global());
+        proxy.register_global(protocol::screencopy::get_global());
+        proxy.register_global(protocol::presentation::get_global());
+        proxy.register_global(protocol::layer_shell::get_global());
         for info in self.output_infos.iter() {
-            proxy.register_global(protocol::output::get_global(info.clone()));
+            proxy.on_display_created(info.clone());
         }
         let proxy_ref = ProxyRef::new(proxy);
 
@@ -143,6 +156,9 @@ impl Engine {
         if result1 && result2 {
             log_wayl3!("Client {} terminated successfully", id);
         }
+
+        self.update_selection();
+        self.update_drag();
     }
 
     /// Handles request from client associated with given `id`.
@@ -157,6 +173,9 @@ impl Engine {
         } else {
             log_warn1!("Wayland Engine: No client: {}", id);
         }
+
+        self.update_selection();
+        self.update_drag();
     }
 }
 
@@ -167,6 +186,239 @@ impl Engine {
     fn logger(s: String) {
         log_wayl4!("Skylane: {}", s);
     }
+
+    /// Offers selections to client owning keyboard focus if they changed.
+    fn update_selection(&mut self) {
+        for &kind in [SelectionKind::Clipboard, SelectionKind::Primary].iter() {
+            if self.mediator.borrow_mut().take_selection_change(kind) {
+                let sid = self.coordinator.get_keyboard_focused_sid();
+                self.offer_selection(kind, sid);
+            }
+        }
+    }
+
+    /// Offers current selection of given kind to client owning given surface. Offers are created
+    /// by server so they have to be added to client connection here.
+    fn offer_selection(&mut self, kind: SelectionKind, sid: SurfaceId) {
+        let cid = self.mediator.borrow().get_client_for_sid(sid).cloned();
+        if let Some(cid) = cid {
+            if let Some(client) = self.clients.get_mut(&cid) {
+                let offer_oids = client.proxy.borrow_mut().offer_selection(kind);
+                for offer_oid in offer_oids {
+                    let proxy = client.proxy.clone();
+                    let offer = match kind {
+                        SelectionKind::Clipboard => DataOffer::new_object(offer_oid, proxy),
+                        SelectionKind::Primary => {
+                            PrimarySelectionOffer::new_object(offer_oid, proxy)
+                        }
+                    };
+                    client.connection.add_object(offer_oid, offer);
+                }
+            }
+        }
+    }
+
+    /// Informs clients about pointer focus change.
+    fn notify_pointer_focus_changed(&self,
+                                    old_sid: SurfaceId,
+                                    new_sid: SurfaceId,
+                                    position: Position) {
+        let mediator = self.mediator.borrow();
+        let old_client_id = mediator.get_client_for_sid(old_sid);
+        let new_client_id = mediator.get_client_for_sid(new_sid);
+
+        if new_client_id != old_client_id {
+            if let Some(client_id) = old_client_id {
+                if let Some(client) = self.clients.get(&client_id) {
+                    client.proxy.borrow_mut().on_pointer_focus_changed(old_sid,
+                                                                       SurfaceId::invalid(),
+                                                                       Position::default());
+                }
+            }
+            if let Some(client_id) = new_client_id {
+                if let Some(client) = self.clients.get(&client_id) {
+                    client.proxy.borrow_mut().on_pointer_focus_changed(SurfaceId::invalid(),
+                                                                       new_sid,
+                                                                       position);
+                }
+            }
+        } else {
+            if let Some(client_id) = old_client_id {
+                if let Some(client) = self.clients.get(&client_id) {
+                    client.proxy.borrow_mut().on_pointer_focus_changed(old_sid, new_sid, position);
+                }
+            }
+        }
+    }
+
+    /// Informs clients about keyboard focus change. Returns `true` if focus moved to other client.
+    fn notify_keyboard_focus_changed(&self, old_sid: SurfaceId, new_sid: SurfaceId) -> bool {
+        let mediator = self.mediator.borrow();
+        let old_client_id = mediator.get_client_for_sid(old_sid);
+        let new_client_id = mediator.get_client_for_sid(new_sid);
+        let is_client_changed = !mediator.is_same_client(old_sid, new_sid);
+
+        if is_client_changed {
+            if let Some(client_id) = old_client_id {
+                if let Some(client) = self.clients.get(&client_id) {
+                    client.proxy.borrow_mut().on_keyboard_focus_changed(old_sid,
+                                                                        SurfaceId::invalid());
+                }
+            }
+            if let Some(client_id) = new_client_id {
+                if let Some(client) = self.clients.get(&client_id) {
+                    client.proxy.borrow_mut().on_keyboard_focus_changed(SurfaceId::invalid(),
+                                                                        new_sid);
+                }
+            }
+        } else {
+            if let Some(client_id) = old_client_id {
+                if let Some(client) = self.clients.get(&client_id) {
+                    client.proxy.borrow_mut().on_keyboard_focus_changed(old_sid, new_sid);
+                }
+            }
+        }
+        is_client_changed
+    }
+
+    /// Returns proxy of client owning given surface.
+    fn get_proxy_for_sid(&self, sid: SurfaceId) -> Option<ProxyRef> {
+        let cid = self.mediator.borrow().get_client_for_sid(sid).cloned();
+        cid.and_then(|cid| self.clients.get(&cid)).map(|client| client.proxy.clone())
+    }
+
+    /// Creates keyboard state for given configuration. Without it modifiers are not reported to
+    /// clients, but keys still are.
+    fn create_keyboard_state(config: &KeyboardConfig) -> Option<KeyboardState> {
+        match KeyboardState::new(config) {
+            Ok(keyboard_state) => Some(keyboard_state),
+            Err(err) => {
+                log_error!("Failed to create keyboard state for {:?}: {:?}", config, err);
+                None
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Private helper methods related to drag-and-drop.
+impl Engine {
+    /// Returns ID of surface being current drag target (or invalid ID if there is no target) if
+    /// drag is ongoing and `None` otherwise.
+    fn get_drag_target_sid(&self) -> Option<SurfaceId> {
+        let mediator = self.mediator.borrow();
+        match mediator.get_drag() {
+            Some(drag) if drag.state == DragState::Ongoing => Some(drag.target_sid),
+            _ => None,
+        }
+    }
+
+    /// Checks if drag is ongoing.
+    fn is_dragging(&self) -> bool {
+        self.get_drag_target_sid().is_some()
+    }
+
+    /// Starts drag requested by client or finishes drag cancelled by its source.
+    fn update_drag(&mut self) {
+        let state = self.mediator.borrow().get_drag_state();
+        match state {
+            Some(DragState::Requested) => {
+                if let Some(drag) = self.mediator.borrow_mut().get_drag_mut() {
+                    drag.start(self.pressed_button);
+                }
+
+                // Pointer focus is taken away from clients for the time of dragging.
+                let sid = self.coordinator.get_pointer_focused_sid();
+                let position = self.pointer_position;
+                self.notify_pointer_focus_changed(sid, SurfaceId::invalid(), position);
+                self.enter_drag(sid, position);
+            }
+            Some(DragState::Cancelled) => self.finish_drag(),
+            _ => {}
+        }
+    }
+
+    /// Sends drag-and-drop `enter` to client owning given surface and makes the surface drag
+    /// target. Drags internal to client are not offered to other clients.
+    fn enter_drag(&mut self, sid: SurfaceId, position: Position) {
+        let cid = self.mediator.borrow().get_client_for_sid(sid).cloned();
+        if let Some(cid) = cid {
+            let is_allowed = if let Some(drag) = self.mediator.borrow_mut().get_drag_mut() {
+                drag.enter(sid, cid)
+            } else {
+                false
+            };
+
+            if is_allowed {
+                if let Some(client) = self.clients.get_mut(&cid) {
+                    let offer_oids = client.proxy.borrow_mut().on_drag_enter(sid, position);
+                    for offer_oid in offer_oids {
+                        let offer = DataOffer::new_object(offer_oid, client.proxy.clone());
+                        client.connection.add_object(offer_oid, offer);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends drag-and-drop `leave` to current drag target and forgets the target.
+    fn leave_drag(&mut self) {
+        let target_sid = if let Some(drag) = self.mediator.borrow_mut().get_drag_mut() {
+            let target_sid = drag.target_sid;
+            drag.reset_target();
+            target_sid
+        } else {
+            SurfaceId::invalid()
+        };
+
+        if let Some(proxy) = self.get_proxy_for_sid(target_sid) {
+            proxy.borrow_mut().on_drag_leave();
+        }
+    }
+
+    /// Drops dragged data on current target if it accepted the data or cancels the drag otherwise.
+    fn drop_drag(&mut self) {
+        let (is_droppable, target_sid, source) = {
+            let mut mediator = self.mediator.borrow_mut();
+            let (is_droppable, target_sid) = if let Some(drag) = mediator.get_drag_mut() {
+                (drag.drop_data(), drag.target_sid)
+            } else {
+                return;
+            };
+            (is_droppable, target_sid, mediator.get_drag_source())
+        };
+
+        if is_droppable {
+            if let Some(proxy) = self.get_proxy_for_sid(target_sid) {
+                proxy.borrow().on_drop();
+            }
+            if let Some((socket, source_oid)) = source {
+                send!(wl_data_source::dnd_drop_performed(&socket, source_oid));
+            }
+            self.restore_pointer_focus();
+        } else {
+            if let Some((socket, source_oid)) = source {
+                send!(wl_data_source::cancelled(&socket, source_oid));
+            }
+            self.finish_drag();
+        }
+    }
+
+    /// Ends drag without dropping data.
+    fn finish_drag(&mut self) {
+        self.leave_drag();
+        self.mediator.borrow_mut().end_drag();
+        self.restore_pointer_focus();
+    }
+
+    /// Removes drag icon and gives pointer focus back to surface under the pointer.
+    fn restore_pointer_focus(&mut self) {
+        self.coordinator.set_surface_as_drag_icon(SurfaceId::invalid());
+        let sid = self.coordinator.get_pointer_focused_sid();
+        let position = self.pointer_position;
+        self.notify_pointer_focus_changed(SurfaceId::invalid(), sid, position);
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -179,9 +431,20 @@ impl Gateway for Engine {
         }
     }
 
+    fn on_display_destroyed(&mut self, id: i32) {
+        self.output_infos.retain(|info| info.id != id);
+        for (_, client) in self.clients.iter() {
+            client.proxy.borrow_mut().on_display_destroyed(id);
+        }
+    }
+
     fn on_keyboard_input(&mut self, key: Key, _mods: Option<KeyMods>) {
-        let mods = if self.keyboard_state.update(key.code, key.value) {
-            Some(self.keyboard_state.get_mods())
+        let mods = if let Some(ref mut keyboard_state) = self.keyboard_state {
+            if keyboard_state.update(key.code, key.value) {
+                Some(keyboard_state.get_mods())
+            } else {
+                None
+            }
         } else {
             None
         };
@@ -194,58 +457,92 @@ impl Gateway for Engine {
         }
     }
 
-    fn on_surface_frame(&mut self, sid: SurfaceId, milliseconds: Milliseconds) {
+    fn on_keymap_changed(&mut self, config: KeyboardConfig) {
+        // Key map sent to clients and keyboard state have to be switched together.
+        let keyboard_state = if let Some(keyboard_state) = Self::create_keyboard_state(&config) {
+            keyboard_state
+        } else {
+            return;
+        };
+
+        if !self.settings.select_keymap(&config) {
+            log_warn2!("No key map for keyboard config {:?}", config);
+            return;
+        }
+        self.keyboard_state = Some(keyboard_state);
+
+        for (_, client) in self.clients.iter() {
+            client.proxy.borrow_mut().on_keymap_changed(config.clone());
+        }
+    }
+
+    fn on_surface_frame(&mut self, sid: SurfaceId, presentation: Presentation) {
         if let Some(id) = self.mediator.borrow().get_client_for_sid(sid) {
             if let Some(client) = self.clients.get(&id) {
-                client.proxy.borrow_mut().on_surface_frame(sid, milliseconds);
+                client.proxy.borrow_mut().on_surface_frame(sid, presentation);
             }
         }
     }
 
-    fn on_pointer_focus_changed(&self,
+    fn on_pointer_focus_changed(&mut self,
                                 old_sid: SurfaceId,
                                 new_sid: SurfaceId,
                                 position: Position) {
-        let mediator = self.mediator.borrow();
-        let old_client_id = mediator.get_client_for_sid(old_sid);
-        let new_client_id = mediator.get_client_for_sid(new_sid);
-
-        if new_client_id != old_client_id {
-            if let Some(client_id) = old_client_id {
-                if let Some(client) = self.clients.get(&client_id) {
-                    client.proxy.borrow_mut().on_pointer_focus_changed(old_sid,
-                                                                       SurfaceId::invalid(),
-                                                                       Position::default());
-                }
-            }
-            if let Some(client_id) = new_client_id {
-                if let Some(client) = self.clients.get(&client_id) {
-                    client.proxy.borrow_mut().on_pointer_focus_changed(SurfaceId::invalid(),
-                                                                       new_sid,
-                                                                       position);
-                }
-            }
+        self.pointer_position = position;
+        if self.is_dragging() {
+            self.leave_drag();
+            self.enter_drag(new_sid, position);
         } else {
-            if let Some(client_id) = old_client_id {
-                if let Some(client) = self.clients.get(&client_id) {
-                    client.proxy.borrow_mut().on_pointer_focus_changed(old_sid, new_sid, position);
-                }
-            }
+            self.notify_pointer_focus_changed(old_sid, new_sid, position);
         }
     }
 
-    fn on_pointer_relative_motion(&self,
+    fn on_pointer_relative_motion(&mut self,
                                   sid: SurfaceId,
                                   position: Position,
                                   milliseconds: Milliseconds) {
+        self.pointer_position = position;
+        let drag_target_sid = self.get_drag_target_sid();
         if let Some(id) = self.mediator.borrow().get_client_for_sid(sid) {
             if let Some(client) = self.clients.get(&id) {
-                client.proxy.borrow_mut().on_pointer_relative_motion(sid, position, milliseconds);
+                match drag_target_sid {
+                    Some(target_sid) => {
+                        if target_sid == sid {
+                            client.proxy.borrow().on_drag_motion(position, milliseconds);
+                        }
+                    }
+                    None => {
+                        client.proxy
+                            .borrow_mut()
+                            .on_pointer_relative_motion(sid, position, milliseconds);
+                    }
+                }
             }
         }
     }
 
-    fn on_pointer_button(&self, btn: Button) {
+    fn on_pointer_button(&mut self, btn: Button) {
+        if btn.value != 0 {
+            self.pressed_button = Some(btn.code);
+        } else if self.pressed_button == Some(btn.code) {
+            self.pressed_button = None;
+        }
+
+        if self.is_dragging() {
+            // Buttons are not passed to clients while dragging. Release of the button holding the
+            // drag ends it.
+            if btn.value == 0 {
+                let is_grab_button = match self.mediator.borrow().get_drag() {
+                    Some(drag) => drag.is_grab_button(btn.code),
+                    None => false,
+                };
+                if is_grab_button {
+                    self.drop_drag();
+                }
+            }
+            return;
+        }
+
         let sid = self.coordinator.get_pointer_focused_sid();
         if let Some(id) = self.mediator.borrow().get_client_for_sid(sid) {
             if let Some(client) = self.clients.get(&id) {
@@ -264,53 +561,82 @@ impl Gateway for Engine {
     }
 
     fn on_keyboard_focus_changed(&mut self, old_sid: SurfaceId, new_sid: SurfaceId) {
-        let mediator = self.mediator.borrow();
-        let old_client_id = mediator.get_client_for_sid(old_sid);
-        let new_client_id = mediator.get_client_for_sid(new_sid);
+        if self.notify_keyboard_focus_changed(old_sid, new_sid) {
+            // Client which received focus has to be informed about current selections.
+            self.offer_selection(SelectionKind::Clipboard, new_sid);
+            self.offer_selection(SelectionKind::Primary, new_sid);
+        }
+    }
 
-        if new_client_id != old_client_id {
-            if let Some(client_id) = old_client_id {
-                if let Some(client) = self.clients.get(&client_id) {
-                    client.proxy.borrow_mut().on_keyboard_focus_changed(old_sid,
-                                                                        SurfaceId::invalid());
-                }
+    fn on_touch_down(&mut self,
+                     sid: SurfaceId,
+                     id: i32,
+                     position: Position,
+                     milliseconds: Milliseconds) {
+        if let Some(cid) = self.mediator.borrow().get_client_for_sid(sid) {
+            if let Some(client) = self.clients.get(&cid) {
+                client.proxy.borrow_mut().on_touch_down(sid, id, position, milliseconds);
             }
-            if let Some(client_id) = new_client_id {
-                if let Some(client) = self.clients.get(&client_id) {
-                    client.proxy.borrow_mut().on_keyboard_focus_changed(SurfaceId::invalid(),
-                                                                        new_sid);
-                }
+        }
+    }
+
+    fn on_touch_motion(&mut self,
+                       sid: SurfaceId,
+                       id: i32,
+                       position: Position,
+                       milliseconds: Milliseconds) {
+        if let Some(cid) = self.mediator.borrow().get_client_for_sid(sid) {
+            if let Some(client) = self.clients.get(&cid) {
+                client.proxy.borrow_mut().on_touch_motion(sid, id, position, milliseconds);
             }
-        } else {
-            if let Some(client_id) = old_client_id {
-                if let Some(client) = self.clients.get(&client_id) {
-                    client.proxy.borrow_mut().on_keyboard_focus_changed(old_sid, new_sid);
-                }
+        }
+    }
+
+    fn on_touch_up(&mut self, sid: SurfaceId, id: i32, milliseconds: Milliseconds) {
+        if let Some(cid) = self.mediator.borrow().get_client_for_sid(sid) {
+            if let Some(client) = self.clients.get(&cid) {
+                client.proxy.borrow_mut().on_touch_up(sid, id, milliseconds);
+            }
+        }
+    }
+
+    fn on_touch_frame(&mut self, sid: SurfaceId) {
+        if let Some(cid) = self.mediator.borrow().get_client_for_sid(sid) {
+            if let Some(client) = self.clients.get(&cid) {
+                client.proxy.borrow_mut().on_touch_frame(sid);
             }
         }
     }
 
-    fn on_surface_reconfigured(&self,
+    fn on_surface_reconfigured(&mut self,
                                sid: SurfaceId,
                                size: Size,
                                state_flags: surface_state::SurfaceState) {
         if let Some(id) = self.mediator.borrow().get_client_for_sid(sid) {
             if let Some(client) = self.clients.get(&id) {
-                client.proxy.borrow().on_surface_reconfigured(sid, size, state_flags);
+                client.proxy.borrow_mut().on_surface_reconfigured(sid, size, state_flags);
             }
         }
     }
 
-    fn on_screenshot_done(&mut self) {
-        if let Some(id) = {
-            let mediator = self.mediator.borrow();
-            mediator.get_screenshooter()
-        } {
-            if let Some(client) = self.clients.get_mut(&id) {
-                client.proxy.borrow_mut().on_screenshot_done();
+    fn on_surface_state_applied(&mut self, sid: SurfaceId, serial: u32) {
+        if let Some(id) = self.mediator.borrow().get_client_for_sid(sid) {
+            if let Some(client) = self.clients.get(&id) {
+                client.proxy.borrow_mut().on_surface_state_applied(sid, serial);
             }
         }
     }
+
+    fn on_screenshot_done(&mut self, id: ScreenshotId) {
+        let cid = self.mediator.borrow_mut().take_screenshooter(id);
+        if let Some(client) = cid.and_then(|cid| self.clients.get(&cid)) {
+            client.proxy.borrow_mut().on_screenshot_done(id);
+            return;
+        }
+
+        // Requesting client is gone. Drop the result.
+        self.coordinator.take_screenshot_result(id);
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -7,7 +7,8 @@
 
 use skylane::server as wl;
 
-use qualia::{Area, MappedMemory, Size, SurfaceId, Vector, MemoryPoolId, MemoryViewId, show_reason};
+use qualia::{Area, MappedMemory, Region, Size, SurfaceId, Vector, MemoryPoolId, MemoryViewId};
+use qualia::{show_reason, LayerSurfaceInfo};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -16,6 +17,20 @@ use qualia::{Area, MappedMemory, Size, SurfaceId, Vector, MemoryPoolId, MemoryVi
 pub enum ShellSurfaceOid {
     Shell(wl::ObjectId),
     ZxdgToplevelV6(wl::ObjectId, wl::ObjectId),
+    XdgToplevel(wl::ObjectId, wl::ObjectId),
+    Layer(wl::ObjectId),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Enum describing kind of selection. Clipboard and primary selection are independent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SelectionKind {
+    /// Selection set using `wl_data_device`.
+    Clipboard,
+
+    /// Selection set using `zwp_primary_selection_device_v1`.
+    Primary,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -42,6 +57,11 @@ impl PositionerInfo {
     pub fn get_area(&self) -> Area {
         Area::new(self.offset + self.anchor.pos, self.size)
     }
+
+    /// Checks if size of positioned surface was set as required by stable `xdg_shell`.
+    pub fn is_complete(&self) -> bool {
+        (self.size.width > 0) && (self.size.height > 0)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -64,14 +84,15 @@ pub trait Facade {
                           offset: usize,
                           width: usize,
                           height: usize,
-                          stride: usize)
+                          stride: usize,
+                          format: u32)
                           -> Option<MemoryViewId>;
 
     /// Requests destruction of memory view.
     fn destroy_memory_view(&mut self, mvid: MemoryViewId);
 
-    /// Defines region. Regions may be used to define input area of surface.
-    fn define_region(&mut self, region_oid: wl::ObjectId, region: Area);
+    /// Defines region. Regions may be used to define input or opaque area of surface.
+    fn define_region(&mut self, region_oid: wl::ObjectId, region: Region);
 
     /// Undefines region.
     fn undefine_region(&mut self, region_oid: wl::ObjectId);
@@ -88,6 +109,12 @@ pub trait Facade {
     /// Removes keyboard OID.
     fn remove_keyboard_oid(&mut self, keyboard_oid: wl::ObjectId);
 
+    /// Adds touch OID.
+    fn add_touch_oid(&mut self, touch_oid: wl::ObjectId);
+
+    /// Removes touch OID.
+    fn remove_touch_oid(&mut self, touch_oid: wl::ObjectId);
+
     /// Sets positioner info.
     fn set_positioner(&mut self, wl::ObjectId, positioner: PositionerInfo);
 
@@ -97,24 +124,106 @@ pub trait Facade {
     /// Removes positioner info.
     fn remove_positioner(&mut self, oid: wl::ObjectId);
 
-    /// Sets given region as input region of surface.
+    /// Registers new `xdg_wm_base` object. These objects are used to ping the client.
+    fn add_xdg_wm_base_oid(&mut self, wm_base_oid: wl::ObjectId);
+
+    /// Unregisters `xdg_wm_base` object.
+    fn remove_xdg_wm_base_oid(&mut self, wm_base_oid: wl::ObjectId);
+
+    /// Informs that client responded to ping with given serial.
+    fn pong(&mut self, serial: u32);
+
+    /// Registers new data source.
+    fn create_data_source(&mut self, source_oid: wl::ObjectId);
+
+    /// Adds mime type offered by data source.
+    fn add_data_source_mime_type(&mut self, source_oid: wl::ObjectId, mime_type: String);
+
+    /// Sets drag-and-drop actions supported by data source.
+    fn set_data_source_actions(&mut self, source_oid: wl::ObjectId, dnd_actions: u32);
+
+    /// Unregisters data source. If the source was set as selection, the selection is cleared.
+    fn destroy_data_source(&mut self, source_oid: wl::ObjectId);
+
+    /// Adds data device OID.
+    fn add_data_device_oid(&mut self, data_device_oid: wl::ObjectId);
+
+    /// Removes data device OID.
+    fn remove_data_device_oid(&mut self, data_device_oid: wl::ObjectId);
+
+    /// Adds primary selection device OID.
+    fn add_primary_selection_device_oid(&mut self, device_oid: wl::ObjectId);
+
+    /// Removes primary selection device OID.
+    fn remove_primary_selection_device_oid(&mut self, device_oid: wl::ObjectId);
+
+    /// Sets given data source as selection of given kind. Null object ID clears the selection.
+    fn set_selection(&mut self, kind: SelectionKind, source_oid: wl::ObjectId);
+
+    /// Requests start of drag-and-drop operation. Null source means the drag is internal to the
+    /// client. Icon surface (if not null) will be drawn next to the cursor.
+    fn start_drag(&mut self, source_oid: wl::ObjectId, icon_surface_oid: wl::ObjectId);
+
+    /// Informs that destination client accepts given mime type of dragged data. Empty mime type
+    /// means data is not accepted.
+    fn accept_data_offer(&mut self, offer_oid: wl::ObjectId, mime_type: String);
+
+    /// Sets drag-and-drop actions supported and preferred by destination client.
+    fn set_data_offer_actions(&mut self,
+                              offer_oid: wl::ObjectId,
+                              dnd_actions: u32,
+                              preferred_action: u32);
+
+    /// Informs that destination client finished transfer of dropped data.
+    fn finish_data_offer(&mut self, offer_oid: wl::ObjectId);
+
+    /// Requests transfer of offered data in given mime type. Data will be written by the data
+    /// source owner to passed file descriptor.
+    fn receive_data_offer(&mut self, offer_oid: wl::ObjectId, mime_type: String, fd: i32);
+
+    /// Forgets data offer.
+    fn destroy_data_offer(&mut self, offer_oid: wl::ObjectId);
+
+    /// Sets given region as input region of surface. This will take effect after `commit`.
     fn set_input_region(&self, sid: SurfaceId, region_oid: wl::ObjectId);
 
+    /// Sets given region as opaque region of surface. This will take effect after `commit`.
+    fn set_opaque_region(&self, sid: SurfaceId, region_oid: wl::ObjectId);
+
     /// Requests creation of surface. Return ID of newly created surface.
     fn create_surface(&mut self, surface_oid: wl::ObjectId) -> SurfaceId;
 
-    /// Requests destruction of surface.
-    fn destroy_surface(&self, sid: SurfaceId);
+    /// Requests destruction of surface. Pending presentation feedbacks are discarded.
+    fn destroy_surface(&mut self, sid: SurfaceId);
 
     /// Attaches memory view to surface. This will take effect after `commit`.
     fn attach(&mut self, buffer_oid: wl::ObjectId, sid: SurfaceId, x: i32, y: i32);
 
-    /// Commits all requests to surface.
-    fn commit(&self, sid: SurfaceId);
+    /// Commits all requests to surface. Pending state becomes current at once.
+    fn commit(&mut self, sid: SurfaceId);
 
-    /// Requests (one-shot) notification about redrawing of given surface.
+    /// Requests (one-shot) notification about redrawing of given surface. This will take effect
+    /// after `commit`.
     fn set_frame(&mut self, sid: SurfaceId, frame_oid: wl::ObjectId);
 
+    /// Requests (one-shot) feedback about presentation of contents of given surface. This will take
+    /// effect after `commit`.
+    fn set_presentation_feedback(&mut self,
+                                 surface_oid: wl::ObjectId,
+                                 feedback_oid: wl::ObjectId);
+
+    /// Marks given area of surface as damaged. This will take effect after `commit`.
+    fn damage(&self, sid: SurfaceId, area: Area);
+
+    /// Marks given area of surface buffer as damaged. This will take effect after `commit`.
+    fn damage_buffer(&self, sid: SurfaceId, area: Area);
+
+    /// Sets scale of buffer attached to surface. This will take effect after `commit`.
+    fn set_buffer_scale(&self, sid: SurfaceId, scale: i32);
+
+    /// Sets transform of buffer attached to surface. This will take effect after `commit`.
+    fn set_buffer_transform(&self, sid: SurfaceId, transform: i32);
+
     /// Adds a reason to show given surface on screen.
     fn show(&mut self,
             surface_oid: wl::ObjectId,
@@ -124,13 +233,34 @@ pub trait Facade {
     /// Removes a reason to show given surface on screen.
     fn hide(&mut self, surface_oid: wl::ObjectId, reason: show_reason::ShowReason);
 
+    /// Gives given surface role of layer surface. Initial state will take effect after `commit`.
+    fn set_as_layer_surface(&mut self,
+                            surface_oid: wl::ObjectId,
+                            layer_surface_oid: wl::ObjectId,
+                            info: LayerSurfaceInfo);
+
+    /// Sets pending state of layer surface. This will take effect after `commit`.
+    fn set_layer_surface_state(&mut self, surface_oid: wl::ObjectId, info: LayerSurfaceInfo);
+
+    /// Removes given surface from layers.
+    fn unset_as_layer_surface(&mut self, surface_oid: wl::ObjectId);
+
     /// Defines offset between origin of buffer and real area of surface. Client for example may
     /// want to draw shadow, which should not be threated by compositor as internal part of
     /// surface.
-    fn set_offset(&self, sid: SurfaceId, offset: Vector);
+    fn set_offset(&self, surface_oid: wl::ObjectId, offset: Vector);
 
     /// Request setting size of surface.
-    fn set_requested_size(&self, sid: SurfaceId, size: Size);
+    fn set_requested_size(&self, surface_oid: wl::ObjectId, size: Size);
+
+    /// Requests setting minimal size of surface. Zero dimension means no limit.
+    fn set_min_size(&self, surface_oid: wl::ObjectId, size: Size);
+
+    /// Requests setting maximal size of surface. Zero dimension means no limit.
+    fn set_max_size(&self, surface_oid: wl::ObjectId, size: Size);
+
+    /// Informs that client acknowledged configuration with given serial.
+    fn ack_configure(&mut self, surface_oid: wl::ObjectId, serial: u32);
 
     /// Requests setting relation (child-parent) between two surfaces.
     fn relate(&self, surface_oid: wl::ObjectId, parent_surface_oid: wl::ObjectId);
@@ -138,6 +268,19 @@ pub trait Facade {
     /// Requests cancellation of relation between given surface and its parent.
     fn unrelate(&self, surface_oid: wl::ObjectId);
 
+    /// Requests setting if subsurface is synchronized with its parent.
+    fn set_synchronized(&self, surface_oid: wl::ObjectId, synchronized: bool);
+
+    /// Requests placing subsurface directly above its sibling or parent.
+    fn place_above(&self, surface_oid: wl::ObjectId, sibling_surface_oid: wl::ObjectId);
+
+    /// Requests placing subsurface directly below its sibling or parent.
+    fn place_below(&self, surface_oid: wl::ObjectId, sibling_surface_oid: wl::ObjectId);
+
+    /// Requests marking surface as transient for other surface (e.g. dialog for its parent). Null
+    /// parent object ID cancels the relation.
+    fn set_transient_for(&self, surface_oid: wl::ObjectId, parent_surface_oid: wl::ObjectId);
+
     /// Requests to set offset between related surfaces.
     fn set_relative_position(&self, surface_oid: wl::ObjectId, x: isize, y: isize);
 
@@ -152,6 +295,20 @@ pub trait Facade {
                        screenshoter_oid: wl::ObjectId,
                        output_oid: wl::ObjectId,
                        output_oid: wl::ObjectId);
+
+    /// Requests copying given area (in logical coordinates) of output to given buffer. Screencopy
+    /// frame will be informed when the copy is ready. If `with_damage` is `true` the copy will be
+    /// made only after contents of the area change.
+    fn copy_output(&mut self,
+                   frame_oid: wl::ObjectId,
+                   buffer_oid: wl::ObjectId,
+                   output_id: i32,
+                   area: Area,
+                   overlay_cursor: bool,
+                   with_damage: bool);
+
+    /// Cancels copying output requested by given screencopy frame.
+    fn cancel_output_copy(&mut self, frame_oid: wl::ObjectId);
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -6,6 +6,7 @@
 // -------------------------------------------------------------------------------------------------
 
 use qualia::{Axis, Button, Key, KeyMods, Milliseconds, OutputInfo, Position, Size, SurfaceId};
+use qualia::{KeyboardConfig, Presentation, ScreenshotId};
 use qualia::surface_state;
 
 // -------------------------------------------------------------------------------------------------
@@ -14,26 +15,32 @@ pub trait Gateway {
     /// Notifies output was found.
     fn on_display_created(&mut self, output_info: OutputInfo);
 
+    /// Notifies output was lost.
+    fn on_display_destroyed(&mut self, id: i32);
+
     /// Notifies keyboard key was pressed.
     fn on_keyboard_input(&mut self, key: Key, mods: Option<KeyMods>);
 
-    /// Notifies about redrawing surface.
-    fn on_surface_frame(&mut self, sid: SurfaceId, milliseconds: Milliseconds);
+    /// Notifies that keyboard with different layout started to be used.
+    fn on_keymap_changed(&mut self, config: KeyboardConfig);
+
+    /// Notifies that surface was presented on output.
+    fn on_surface_frame(&mut self, sid: SurfaceId, presentation: Presentation);
 
     /// Notifies that pointer was moved from above one surface above another.
-    fn on_pointer_focus_changed(&self,
+    fn on_pointer_focus_changed(&mut self,
                                 old_sid: SurfaceId,
                                 new_sid: SurfaceId,
                                 position: Position);
 
     /// Notifies that pointer moved.
-    fn on_pointer_relative_motion(&self,
+    fn on_pointer_relative_motion(&mut self,
                                   sid: SurfaceId,
                                   position: Position,
                                   milliseconds: Milliseconds);
 
     /// Notifies mouse or touchpad button was pressed.
-    fn on_pointer_button(&self, btn: Button);
+    fn on_pointer_button(&mut self, btn: Button);
 
     /// Notifies about pointer move.
     fn on_pointer_axis(&self, axis: Axis);
@@ -41,14 +48,38 @@ pub trait Gateway {
     /// Notifies about keyboard focus change.
     fn on_keyboard_focus_changed(&mut self, old_sid: SurfaceId, new_sid: SurfaceId);
 
+    /// Notifies about new touch point on surface.
+    fn on_touch_down(&mut self,
+                     sid: SurfaceId,
+                     id: i32,
+                     position: Position,
+                     milliseconds: Milliseconds);
+
+    /// Notifies about motion of touch point.
+    fn on_touch_motion(&mut self,
+                       sid: SurfaceId,
+                       id: i32,
+                       position: Position,
+                       milliseconds: Milliseconds);
+
+    /// Notifies that touch point was lifted.
+    fn on_touch_up(&mut self, sid: SurfaceId, id: i32, milliseconds: Milliseconds);
+
+    /// Notifies about end of set of touch events sent to the surface.
+    fn on_touch_frame(&mut self, sid: SurfaceId);
+
     /// Notifies about change of size or state of surface.
-    fn on_surface_reconfigured(&self,
+    fn on_surface_reconfigured(&mut self,
                                sid: SurfaceId,
                                size: Size,
                                state_flags: surface_state::SurfaceState);
 
+    /// Notifies that state committed by surface became current. `serial` is the number of commits
+    /// of the surface up to the one which latched the state.
+    fn on_surface_state_applied(&mut self, sid: SurfaceId, serial: u32);
+
     /// Notifies that screenshot data are ready.
-    fn on_screenshot_done(&mut self);
+    fn on_screenshot_done(&mut self, id: ScreenshotId);
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -36,6 +36,7 @@ extern crate nix;
 
 extern crate skylane;
 extern crate skylane_protocols;
+extern crate skylane_extra_protocols;
 
 extern crate dharma;
 #[macro_use]
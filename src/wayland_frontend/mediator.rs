@@ -6,19 +6,191 @@
 // -------------------------------------------------------------------------------------------------
 
 use std;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use dharma;
-use qualia::SurfaceId;
+use skylane::server as wl;
+use skylane_protocols::server::wayland::wl_data_device_manager::dnd_action;
+use qualia::{ScreenshotId, SurfaceId};
+
+use facade::SelectionKind;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Information about data source set as selection (clipboard or primary selection) by one of
+/// clients.
+#[derive(Clone)]
+pub struct Selection {
+    /// ID of client owning the selection.
+    pub cid: dharma::EventHandlerId,
+
+    /// Object ID of data source set as selection.
+    pub source_oid: wl::ObjectId,
+
+    /// Mime types offered by the data source.
+    pub mime_types: Vec<String>,
+}
 
 // -------------------------------------------------------------------------------------------------
 
-/// `Mediator` stores information about which surface was created by which client.
+/// State of drag-and-drop operation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DragState {
+    /// Client requested drag but `Engine` did not start it yet.
+    Requested,
+
+    /// Data is being dragged over surfaces.
+    Ongoing,
+
+    /// Data was dropped and destination client transfers it.
+    Dropped,
+
+    /// Drag was cancelled by its source (e.g. data source was destroyed).
+    Cancelled,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Information about drag-and-drop operation.
+pub struct Drag {
+    /// ID of client which started the drag.
+    pub cid: dharma::EventHandlerId,
+
+    /// Object ID of dragged `wl_data_source`. `None` for drags internal to client.
+    pub source_oid: Option<wl::ObjectId>,
+
+    /// Mime types offered by the data source.
+    pub mime_types: Vec<String>,
+
+    /// Actions supported by the data source.
+    pub source_actions: u32,
+
+    /// ID of surface under the pointer receiving drag-and-drop events.
+    pub target_sid: SurfaceId,
+
+    /// Informs if destination client accepted any of offered mime types.
+    pub accepted: bool,
+
+    /// Informs if destination client negotiated action.
+    pub negotiated: bool,
+
+    /// Action chosen for the operation.
+    pub action: u32,
+
+    /// State of the operation.
+    pub state: DragState,
+
+    /// Code of pointer button holding the drag. Only release of this button drops the data.
+    /// `None` if no button was pressed when the drag started.
+    pub button: Option<u16>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Drag {
+    /// Constructs new `Drag` in requested state.
+    pub fn new(cid: dharma::EventHandlerId,
+               source_oid: Option<wl::ObjectId>,
+               mime_types: Vec<String>,
+               source_actions: u32)
+               -> Self {
+        Drag {
+            cid: cid,
+            source_oid: source_oid,
+            mime_types: mime_types,
+            source_actions: source_actions,
+            target_sid: SurfaceId::invalid(),
+            accepted: false,
+            negotiated: false,
+            action: dnd_action::NONE,
+            state: DragState::Requested,
+            button: None,
+        }
+    }
+
+    /// Starts requested drag held by given pointer button.
+    pub fn start(&mut self, button: Option<u16>) {
+        self.state = DragState::Ongoing;
+        self.button = button;
+    }
+
+    /// Makes given surface of given client drag target. Drags internal to client are not offered
+    /// to other clients. Returns `true` if the surface became the target.
+    pub fn enter(&mut self, sid: SurfaceId, cid: dharma::EventHandlerId) -> bool {
+        let is_allowed = self.source_oid.is_some() || (self.cid == cid);
+        if is_allowed {
+            self.reset_target();
+            self.target_sid = sid;
+        }
+        is_allowed
+    }
+
+    /// Checks if release of button with given code should drop the data.
+    pub fn is_grab_button(&self, code: u16) -> bool {
+        self.button.map_or(true, |button| button == code)
+    }
+
+    /// Drops the data if drop on current target would succeed. Returns `true` on success.
+    pub fn drop_data(&mut self) -> bool {
+        let is_droppable = self.is_droppable();
+        if is_droppable {
+            self.state = DragState::Dropped;
+        }
+        is_droppable
+    }
+
+    /// Informs if drop on current target would succeed. Destination clients not supporting
+    /// actions only have to accept a mime type.
+    pub fn is_droppable(&self) -> bool {
+        self.target_sid.is_valid() && self.accepted &&
+        (!self.negotiated || (self.action != dnd_action::NONE))
+    }
+
+    /// Chooses action from the ones supported by both source and destination. Preferred action is
+    /// chosen if possible, otherwise copy, move and ask are tried in this order.
+    pub fn negotiate_action(&mut self, destination_actions: u32, preferred_action: u32) -> u32 {
+        let actions = self.source_actions & destination_actions;
+        self.negotiated = true;
+        self.action = if (actions & preferred_action) != 0 {
+            preferred_action
+        } else if (actions & dnd_action::COPY) != 0 {
+            dnd_action::COPY
+        } else if (actions & dnd_action::MOVE) != 0 {
+            dnd_action::MOVE
+        } else if (actions & dnd_action::ASK) != 0 {
+            dnd_action::ASK
+        } else {
+            dnd_action::NONE
+        };
+        self.action
+    }
+
+    /// Forgets everything related to current target.
+    pub fn reset_target(&mut self) {
+        self.target_sid = SurfaceId::invalid();
+        self.accepted = false;
+        self.negotiated = false;
+        self.action = dnd_action::NONE;
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// `Mediator` stores information about which surface was created by which client and sockets of
+/// clients used to request data transfers from them.
+///
+/// It also keeps selection and drag-and-drop state as there is only one seat and they are shared
+/// between all clients.
 ///
 /// For information about its place among other structures see crate-level documentation.
 pub struct Mediator {
     sid_to_cid_dictionary: HashMap<SurfaceId, dharma::EventHandlerId>,
-    screenshoter_cid: Option<dharma::EventHandlerId>,
+    cid_to_socket_dictionary: HashMap<dharma::EventHandlerId, wl::Socket>,
+    screenshot_id_to_cid_dictionary: HashMap<ScreenshotId, dharma::EventHandlerId>,
+    last_screenshot_id: ScreenshotId,
+    selections: HashMap<SelectionKind, Selection>,
+    changed_selections: HashSet<SelectionKind>,
+    drag: Option<Drag>,
 }
 
 define_ref!(struct Mediator as MediatorRef);
@@ -29,7 +201,12 @@ impl Mediator {
     pub fn new() -> Self {
         Mediator {
             sid_to_cid_dictionary: HashMap::new(),
-            screenshoter_cid: None,
+            cid_to_socket_dictionary: HashMap::new(),
+            screenshot_id_to_cid_dictionary: HashMap::new(),
+            last_screenshot_id: ScreenshotId::initial(),
+            selections: HashMap::new(),
+            changed_selections: HashSet::new(),
+            drag: None,
         }
     }
 }
@@ -49,12 +226,378 @@ impl Mediator {
         self.sid_to_cid_dictionary.remove(&sid);
     }
 
-    pub fn register_screenshoter(&mut self, cid: Option<dharma::EventHandlerId>) {
-        self.screenshoter_cid = cid;
+    /// Checks if both surfaces were created by the same client. Surfaces not related to any client
+    /// are treated as created by the same (none) client.
+    pub fn is_same_client(&self, sid1: SurfaceId, sid2: SurfaceId) -> bool {
+        self.get_client_for_sid(sid1) == self.get_client_for_sid(sid2)
+    }
+
+    pub fn register_client(&mut self, cid: dharma::EventHandlerId, socket: wl::Socket) {
+        self.cid_to_socket_dictionary.insert(cid, socket);
+    }
+
+    pub fn unregister_client(&mut self, cid: dharma::EventHandlerId) {
+        self.cid_to_socket_dictionary.remove(&cid);
+    }
+
+    pub fn get_socket(&self, cid: dharma::EventHandlerId) -> Option<wl::Socket> {
+        self.cid_to_socket_dictionary.get(&cid).cloned()
+    }
+
+    /// Generates ID for new screenshot request and relates it with requesting client.
+    pub fn register_screenshooter(&mut self, cid: dharma::EventHandlerId) -> ScreenshotId {
+        let id = self.last_screenshot_id.increment();
+        self.screenshot_id_to_cid_dictionary.insert(id, cid);
+        id
+    }
+
+    /// Returns and forgets client which requested given screenshot.
+    pub fn take_screenshooter(&mut self, id: ScreenshotId) -> Option<dharma::EventHandlerId> {
+        self.screenshot_id_to_cid_dictionary.remove(&id)
+    }
+
+    /// Sets new selection of given kind. Returns previous selection if it was replaced by
+    /// selection coming from other data source (or cleared) so its owner can be informed.
+    pub fn set_selection(&mut self,
+                         kind: SelectionKind,
+                         selection: Option<Selection>)
+                         -> Option<Selection> {
+        self.changed_selections.insert(kind);
+        let source = selection.as_ref().map(|selection| (selection.cid, selection.source_oid));
+        let previous = if let Some(selection) = selection {
+            self.selections.insert(kind, selection)
+        } else {
+            self.selections.remove(&kind)
+        };
+
+        let is_replaced = match previous {
+            Some(ref previous) => Some((previous.cid, previous.source_oid)) != source,
+            None => false,
+        };
+        if is_replaced { previous } else { None }
+    }
+
+    pub fn get_selection(&self, kind: SelectionKind) -> Option<Selection> {
+        self.selections.get(&kind).cloned()
+    }
+
+    /// Removes selections owned by given client and coming from given data source or from any
+    /// source if `source_oid` is `None`.
+    pub fn remove_selection(&mut self,
+                            cid: dharma::EventHandlerId,
+                            source_oid: Option<wl::ObjectId>) {
+        let kinds: Vec<SelectionKind> = self.selections
+            .iter()
+            .filter(|&(_, selection)| {
+                (selection.cid == cid) &&
+                source_oid.map_or(true, |oid| oid == selection.source_oid)
+            })
+            .map(|(kind, _)| *kind)
+            .collect();
+
+        for kind in kinds {
+            self.set_selection(kind, None);
+        }
     }
 
-    pub fn get_screenshooter(&self) -> Option<dharma::EventHandlerId> {
-        self.screenshoter_cid
+    /// Checks if selection of given kind changed since last call and resets the flag.
+    pub fn take_selection_change(&mut self, kind: SelectionKind) -> bool {
+        self.changed_selections.remove(&kind)
+    }
+
+    /// Registers drag requested by client. Returns `false` if other drag is still in progress.
+    pub fn start_drag(&mut self, drag: Drag) -> bool {
+        let is_busy = match self.get_drag_state() {
+            Some(DragState::Requested) | Some(DragState::Ongoing) => true,
+            _ => false,
+        };
+
+        if !is_busy {
+            self.drag = Some(drag);
+        }
+        !is_busy
+    }
+
+    pub fn get_drag(&self) -> Option<&Drag> {
+        self.drag.as_ref()
+    }
+
+    pub fn get_drag_mut(&mut self) -> Option<&mut Drag> {
+        self.drag.as_mut()
+    }
+
+    pub fn get_drag_state(&self) -> Option<DragState> {
+        self.drag.as_ref().map(|drag| drag.state)
+    }
+
+    /// Returns socket of client which started the drag and object ID of dragged data source.
+    /// Returns `None` if there is no drag or the drag is internal to client.
+    pub fn get_drag_source(&self) -> Option<(wl::Socket, wl::ObjectId)> {
+        if let Some(ref drag) = self.drag {
+            if let Some(source_oid) = drag.source_oid {
+                return self.get_socket(drag.cid).map(|socket| (socket, source_oid));
+            }
+        }
+        None
+    }
+
+    /// Forgets current drag.
+    pub fn end_drag(&mut self) -> Option<Drag> {
+        self.drag.take()
+    }
+
+    /// Cancels drag if it was started by given client from given data source or from any source if
+    /// `source_oid` is `None`. Dropped drags are forgotten, the other ones have to be finished by
+    /// `Engine`.
+    pub fn cancel_drag(&mut self,
+                       cid: dharma::EventHandlerId,
+                       source_oid: Option<wl::ObjectId>) {
+        let state = if let Some(ref drag) = self.drag {
+            if (drag.cid == cid) && source_oid.map_or(true, |oid| Some(oid) == drag.source_oid) {
+                Some(drag.state)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        match state {
+            Some(DragState::Dropped) => self.drag = None,
+            Some(_) => {
+                if let Some(ref mut drag) = self.drag {
+                    drag.state = DragState::Cancelled;
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test_selection {
+    use skylane::server as wl;
+    use qualia::SurfaceId;
+
+    use facade::SelectionKind;
+    use super::{Mediator, Selection};
+
+    /// Constructs selection set by client `cid` from data source `source`.
+    fn make_selection(cid: u64, source: u32) -> Selection {
+        Selection {
+            cid: cid,
+            source_oid: wl::ObjectId::new(source),
+            mime_types: vec!["text/plain".to_owned()],
+        }
+    }
+
+    /// Returns IDs of client and data source of given selection.
+    fn get_owner(selection: Option<Selection>) -> Option<(u64, u32)> {
+        selection.map(|selection| (selection.cid, selection.source_oid.get_value()))
+    }
+
+    /// Check if previous selection is returned when it is replaced by selection from other source
+    /// or cleared, so that its owner can be informed about cancellation.
+    #[test]
+    fn test_replacing_selection() {
+        let mut m = Mediator::new();
+        let kind = SelectionKind::Clipboard;
+
+        assert_eq!(get_owner(m.set_selection(kind, Some(make_selection(1, 10)))), None);
+        assert_eq!(get_owner(m.set_selection(kind, Some(make_selection(1, 10)))), None);
+        assert_eq!(get_owner(m.set_selection(kind, Some(make_selection(1, 11)))), Some((1, 10)));
+        assert_eq!(get_owner(m.set_selection(kind, Some(make_selection(2, 11)))), Some((1, 11)));
+        assert_eq!(get_owner(m.get_selection(kind)), Some((2, 11)));
+
+        assert_eq!(get_owner(m.set_selection(kind, None)), Some((2, 11)));
+        assert_eq!(get_owner(m.get_selection(kind)), None);
+    }
+
+    /// Check if selections are removed only by their owner and only if they come from given data
+    /// source. Clipboard and primary selection are independent.
+    #[test]
+    fn test_removing_selection_by_owner() {
+        let mut m = Mediator::new();
+        m.set_selection(SelectionKind::Clipboard, Some(make_selection(1, 10)));
+        m.set_selection(SelectionKind::Primary, Some(make_selection(1, 11)));
+
+        m.remove_selection(2, None);
+        assert_eq!(get_owner(m.get_selection(SelectionKind::Clipboard)), Some((1, 10)));
+        assert_eq!(get_owner(m.get_selection(SelectionKind::Primary)), Some((1, 11)));
+
+        m.remove_selection(1, Some(wl::ObjectId::new(11)));
+        assert_eq!(get_owner(m.get_selection(SelectionKind::Clipboard)), Some((1, 10)));
+        assert_eq!(get_owner(m.get_selection(SelectionKind::Primary)), None);
+
+        m.remove_selection(1, None);
+        assert_eq!(get_owner(m.get_selection(SelectionKind::Clipboard)), None);
+    }
+
+    /// Check if every change of selection is reported once so that the selection is offered to
+    /// focused client.
+    #[test]
+    fn test_reporting_selection_changes() {
+        let mut m = Mediator::new();
+        assert!(!m.take_selection_change(SelectionKind::Clipboard));
+
+        m.set_selection(SelectionKind::Clipboard, Some(make_selection(1, 10)));
+        assert!(m.take_selection_change(SelectionKind::Clipboard));
+        assert!(!m.take_selection_change(SelectionKind::Clipboard));
+        assert!(!m.take_selection_change(SelectionKind::Primary));
+
+        m.remove_selection(2, None);
+        assert!(!m.take_selection_change(SelectionKind::Clipboard));
+
+        m.remove_selection(1, None);
+        assert!(m.take_selection_change(SelectionKind::Clipboard));
+    }
+
+    /// Check if keyboard focus change between surfaces of different clients is detected so that
+    /// selection is offered to client receiving focus.
+    #[test]
+    fn test_detecting_focus_change_between_clients() {
+        let mut m = Mediator::new();
+        m.relate_sid_to_client(SurfaceId::new(1), 1);
+        m.relate_sid_to_client(SurfaceId::new(2), 1);
+        m.relate_sid_to_client(SurfaceId::new(3), 2);
+
+        assert!(m.is_same_client(SurfaceId::new(1), SurfaceId::new(2)));
+        assert!(!m.is_same_client(SurfaceId::new(2), SurfaceId::new(3)));
+        assert!(!m.is_same_client(SurfaceId::invalid(), SurfaceId::new(1)));
+        assert!(m.is_same_client(SurfaceId::invalid(), SurfaceId::invalid()));
+
+        m.remove(SurfaceId::new(3));
+        assert!(!m.is_same_client(SurfaceId::new(1), SurfaceId::new(3)));
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test_drag {
+    use skylane::server as wl;
+    use skylane_protocols::server::wayland::wl_data_device_manager::dnd_action;
+    use qualia::SurfaceId;
+
+    use super::{Drag, DragState, Mediator};
+
+    /// Button holding drags in tests.
+    const LEFT_BUTTON: u16 = 0x110;
+
+    /// Other button pressed or released while dragging.
+    const RIGHT_BUTTON: u16 = 0x111;
+
+    /// Constructs drag of text started by client `cid` from data source `source` or internal to
+    /// the client if `source` is `None`.
+    fn make_drag(cid: u64, source: Option<u32>) -> Drag {
+        Drag::new(cid,
+                  source.map(|source| wl::ObjectId::new(source)),
+                  vec!["text/plain".to_owned()],
+                  dnd_action::COPY | dnd_action::MOVE)
+    }
+
+    /// Starts drag held by left button and makes surface `sid` of client `cid` its target.
+    fn start_and_enter(m: &mut Mediator, drag: Drag, sid: u64, cid: u64) -> bool {
+        assert!(m.start_drag(drag));
+        let drag = m.get_drag_mut().expect("drag should be started");
+        drag.start(Some(LEFT_BUTTON));
+        drag.enter(SurfaceId::new(sid), cid)
+    }
+
+    /// Check if drag from data source can enter surfaces of any client while drag internal to
+    /// client can enter only surfaces of that client. Only one drag can be in progress.
+    #[test]
+    fn test_entering_surfaces() {
+        let mut m = Mediator::new();
+        assert!(start_and_enter(&mut m, make_drag(1, Some(10)), 5, 2));
+        assert!(!m.start_drag(make_drag(2, None)));
+        assert_eq!(m.get_drag_state(), Some(DragState::Ongoing));
+        assert_eq!(m.get_drag().unwrap().target_sid, SurfaceId::new(5));
+        m.end_drag();
+
+        assert!(!start_and_enter(&mut m, make_drag(1, None), 5, 2));
+        assert_eq!(m.get_drag().unwrap().target_sid, SurfaceId::invalid());
+        assert!(m.get_drag_mut().unwrap().enter(SurfaceId::new(4), 1));
+        assert_eq!(m.get_drag().unwrap().target_sid, SurfaceId::new(4));
+        assert!(m.get_drag_source().is_none());
+    }
+
+    /// Check if acceptance and negotiated action are forgotten when dragged data moves to other
+    /// surface.
+    #[test]
+    fn test_moving_between_surfaces() {
+        let mut m = Mediator::new();
+        start_and_enter(&mut m, make_drag(1, Some(10)), 5, 2);
+        {
+            let drag = m.get_drag_mut().unwrap();
+            drag.accepted = true;
+            assert_eq!(drag.negotiate_action(dnd_action::MOVE, dnd_action::MOVE),
+                       dnd_action::MOVE);
+            assert!(drag.is_droppable());
+
+            drag.reset_target();
+            assert!(!drag.is_droppable());
+            assert!(drag.enter(SurfaceId::new(6), 3));
+            assert_eq!(drag.target_sid, SurfaceId::new(6));
+            assert!(!drag.accepted);
+            assert!(!drag.negotiated);
+            assert_eq!(drag.action, dnd_action::NONE);
+        }
+    }
+
+    /// Check if data is dropped only when target accepted it and, if target negotiated actions,
+    /// when common action was found. Only release of the button holding the drag drops the data.
+    #[test]
+    fn test_dropping() {
+        let mut m = Mediator::new();
+        start_and_enter(&mut m, make_drag(1, Some(10)), 5, 2);
+        {
+            let drag = m.get_drag_mut().unwrap();
+            assert!(drag.is_grab_button(LEFT_BUTTON));
+            assert!(!drag.is_grab_button(RIGHT_BUTTON));
+            assert!(!drag.drop_data());
+            assert_eq!(drag.state, DragState::Ongoing);
+
+            drag.accepted = true;
+            assert_eq!(drag.negotiate_action(dnd_action::ASK, dnd_action::ASK), dnd_action::NONE);
+            assert!(!drag.drop_data());
+
+            assert_eq!(drag.negotiate_action(dnd_action::COPY, dnd_action::MOVE),
+                       dnd_action::COPY);
+            assert!(drag.drop_data());
+            assert_eq!(drag.state, DragState::Dropped);
+        }
+
+        // Other drag can be started while dropped data is being transferred.
+        assert!(m.start_drag(make_drag(2, None)));
+        assert!(m.get_drag().unwrap().is_grab_button(RIGHT_BUTTON));
+    }
+
+    /// Check if drag is cancelled only by its owner and only if it comes from given data source.
+    /// Dropped drags are forgotten as there is nothing left to finish.
+    #[test]
+    fn test_cancelling() {
+        let mut m = Mediator::new();
+        start_and_enter(&mut m, make_drag(1, Some(10)), 5, 2);
+
+        m.cancel_drag(2, None);
+        assert_eq!(m.get_drag_state(), Some(DragState::Ongoing));
+        m.cancel_drag(1, Some(wl::ObjectId::new(11)));
+        assert_eq!(m.get_drag_state(), Some(DragState::Ongoing));
+        m.cancel_drag(1, Some(wl::ObjectId::new(10)));
+        assert_eq!(m.get_drag_state(), Some(DragState::Cancelled));
+        m.end_drag();
+
+        start_and_enter(&mut m, make_drag(1, Some(10)), 5, 2);
+        {
+            let drag = m.get_drag_mut().unwrap();
+            drag.accepted = true;
+            assert!(drag.drop_data());
+        }
+        m.cancel_drag(1, None);
+        assert_eq!(m.get_drag_state(), None);
     }
 }
 
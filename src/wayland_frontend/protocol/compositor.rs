@@ -11,9 +11,11 @@ use skylane_protocols::server::wayland::wl_compositor;
 use skylane_protocols::server::wayland::wl_surface;
 use skylane_protocols::server::wayland::wl_region;
 
-use qualia::{Area, SurfaceId};
+use qualia;
+use qualia::{buffer_transform, Area, SurfaceId};
 
 use global::Global;
+use protocol::post_error;
 use facade::Facade;
 use proxy::ProxyRef;
 
@@ -62,7 +64,7 @@ impl wl_compositor::Interface for Compositor {
                      _bundle: &mut Bundle,
                      new_region_id: ObjectId)
                      -> Task {
-        let region = Region::new_object(self.proxy.clone());
+        let region = Region::new_object(new_region_id, self.proxy.clone());
         Task::Create {
             id: new_region_id,
             object: region,
@@ -103,7 +105,7 @@ impl Surface {
 #[allow(unused_variables)]
 impl wl_surface::Interface for Surface {
     fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
-        let proxy = self.proxy.borrow();
+        let mut proxy = self.proxy.borrow_mut();
         proxy.destroy_surface(self.sid);
         Task::Destroy { id: this_object_id }
     }
@@ -128,6 +130,11 @@ impl wl_surface::Interface for Surface {
               width: i32,
               height: i32)
               -> Task {
+        if width > 0 && height > 0 {
+            let area = Area::create(x as isize, y as isize, width as usize, height as usize);
+            let proxy = self.proxy.borrow();
+            proxy.damage(self.sid, area);
+        }
         Task::None
     }
 
@@ -143,7 +150,7 @@ impl wl_surface::Interface for Surface {
                          region_oid: ObjectId)
                          -> Task {
         let proxy = self.proxy.borrow_mut();
-        proxy.set_input_region(self.sid, region_oid);
+        proxy.set_opaque_region(self.sid, region_oid);
         Task::None
     }
 
@@ -158,7 +165,7 @@ impl wl_surface::Interface for Surface {
     }
 
     fn commit(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
-        let proxy = self.proxy.borrow_mut();
+        let mut proxy = self.proxy.borrow_mut();
         proxy.commit(self.sid);
         Task::None
     }
@@ -168,6 +175,13 @@ impl wl_surface::Interface for Surface {
                             bundle: &mut Bundle,
                             transform: i32)
                             -> Task {
+        if !buffer_transform::is_valid(transform) {
+            let msg = format!("Invalid buffer transform: {}", transform);
+            post_error(bundle, this_object_id, wl_surface::error::INVALID_TRANSFORM, &msg);
+        } else {
+            let proxy = self.proxy.borrow();
+            proxy.set_buffer_transform(self.sid, transform);
+        }
         Task::None
     }
 
@@ -176,6 +190,13 @@ impl wl_surface::Interface for Surface {
                         bundle: &mut Bundle,
                         scale: i32)
                         -> Task {
+        if scale < 1 {
+            let msg = format!("Invalid buffer scale: {}", scale);
+            post_error(bundle, this_object_id, wl_surface::error::INVALID_SCALE, &msg);
+        } else {
+            let proxy = self.proxy.borrow();
+            proxy.set_buffer_scale(self.sid, scale);
+        }
         Task::None
     }
 
@@ -187,6 +208,11 @@ impl wl_surface::Interface for Surface {
                      width: i32,
                      height: i32)
                      -> Task {
+        if width > 0 && height > 0 {
+            let area = Area::create(x as isize, y as isize, width as usize, height as usize);
+            let proxy = self.proxy.borrow();
+            proxy.damage_buffer(self.sid, area);
+        }
         Task::None
     }
 }
@@ -196,21 +222,23 @@ impl wl_surface::Interface for Surface {
 /// Wayland `wl_region` object.
 struct Region {
     proxy: ProxyRef,
-    area: Option<Area>,
+    region: qualia::Region,
 }
 
 // -------------------------------------------------------------------------------------------------
 
 impl Region {
-    fn new(proxy_ref: ProxyRef) -> Self {
+    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        let region = qualia::Region::new();
+        proxy_ref.borrow_mut().define_region(oid, region.clone());
         Region {
             proxy: proxy_ref,
-            area: None,
+            region: region,
         }
     }
 
-    fn new_object(proxy_ref: ProxyRef) -> Box<Object> {
-        Box::new(Handler::<_, wl_region::Dispatcher>::new(Self::new(proxy_ref)))
+    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, wl_region::Dispatcher>::new(Self::new(oid, proxy_ref)))
     }
 }
 
@@ -233,16 +261,9 @@ impl wl_region::Interface for Region {
            -> Task {
         if width > 0 && height > 0 {
             let area = Area::create(x as isize, y as isize, width as usize, height as usize);
-            if let Some(ref mut region) = self.area {
-                region.inflate(&area);
-            } else {
-                self.area = Some(area);
-            }
-
-            if let Some(region) = self.area {
-                let mut proxy = self.proxy.borrow_mut();
-                proxy.define_region(this_object_id, region);
-            }
+            self.region.add(&area);
+            let mut proxy = self.proxy.borrow_mut();
+            proxy.define_region(this_object_id, self.region.clone());
         } else {
             log_wayl3!("Received region with non-positive width or height");
         }
@@ -250,14 +271,21 @@ impl wl_region::Interface for Region {
     }
 
     fn subtract(&mut self,
-                _this_object_id: ObjectId,
+                this_object_id: ObjectId,
                 _bundle: &mut Bundle,
-                _x: i32,
-                _y: i32,
-                _width: i32,
-                _height: i32)
+                x: i32,
+                y: i32,
+                width: i32,
+                height: i32)
                 -> Task {
-        // Not supported yet
+        if width > 0 && height > 0 {
+            let area = Area::create(x as isize, y as isize, width as usize, height as usize);
+            self.region.subtract(&area);
+            let mut proxy = self.proxy.borrow_mut();
+            proxy.define_region(this_object_id, self.region.clone());
+        } else {
+            log_wayl3!("Received region with non-positive width or height");
+        }
         Task::None
     }
 }
@@ -1,21 +1,28 @@
 // This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
 // the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
 
-//! Implementations of Wayland `wl_data_device_manager` object.
+//! Implementations of Wayland `wl_data_device_manager`, `wl_data_source`, `wl_data_device` and
+//! `wl_data_offer` objects.
 
 use std::rc::Rc;
 
 use skylane::server::{Bundle, Object, ObjectId, Task};
 use skylane_protocols::server::Handler;
 use skylane_protocols::server::wayland::wl_data_device_manager;
+use skylane_protocols::server::wayland::wl_data_source;
+use skylane_protocols::server::wayland::wl_data_device;
+use skylane_protocols::server::wayland::wl_data_offer;
 
 use global::Global;
+use facade::{Facade, SelectionKind};
 use proxy::ProxyRef;
 
 // -------------------------------------------------------------------------------------------------
 
 /// Wayland `wl_data_device_manager` object.
-struct DataDeviceManager {}
+struct DataDeviceManager {
+    proxy: ProxyRef,
+}
 
 // -------------------------------------------------------------------------------------------------
 
@@ -29,8 +36,8 @@ pub fn get_global() -> Global {
 
 impl DataDeviceManager {
     /// Creates new `DataDeviceManager`.
-    fn new(_oid: ObjectId, _proxy_ref: ProxyRef) -> Self {
-        DataDeviceManager {}
+    fn new(_oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        DataDeviceManager { proxy: proxy_ref }
     }
 
     fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
@@ -47,8 +54,10 @@ impl wl_data_device_manager::Interface for DataDeviceManager {
                           bundle: &mut Bundle,
                           id: ObjectId)
                           -> Task {
-        // FIXME: Finish implementation of `create_data_source`.
-        Task::None
+        Task::Create {
+            id: id,
+            object: DataSource::new_object(id, self.proxy.clone()),
+        }
     }
 
     fn get_data_device(&mut self,
@@ -57,7 +66,174 @@ impl wl_data_device_manager::Interface for DataDeviceManager {
                        id: ObjectId,
                        seat: ObjectId)
                        -> Task {
-        // FIXME: Finish implementation of `get_data_device`.
+        // There is only one seat so `seat` can be ignored.
+        Task::Create {
+            id: id,
+            object: DataDevice::new_object(id, self.proxy.clone()),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `wl_data_source` object.
+struct DataSource {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl DataSource {
+    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        proxy_ref.borrow_mut().create_data_source(oid);
+        DataSource { proxy: proxy_ref }
+    }
+
+    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, wl_data_source::Dispatcher>::new(Self::new(oid, proxy_ref)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl wl_data_source::Interface for DataSource {
+    fn offer(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, mime_type: String) -> Task {
+        self.proxy.borrow_mut().add_data_source_mime_type(this_object_id, mime_type);
+        Task::None
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().destroy_data_source(this_object_id);
+        Task::Destroy { id: this_object_id }
+    }
+
+    fn set_actions(&mut self,
+                   this_object_id: ObjectId,
+                   bundle: &mut Bundle,
+                   dnd_actions: u32)
+                   -> Task {
+        self.proxy.borrow_mut().set_data_source_actions(this_object_id, dnd_actions);
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `wl_data_device` object.
+struct DataDevice {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl DataDevice {
+    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        proxy_ref.borrow_mut().add_data_device_oid(oid);
+        DataDevice { proxy: proxy_ref }
+    }
+
+    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, wl_data_device::Dispatcher>::new(Self::new(oid, proxy_ref)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl wl_data_device::Interface for DataDevice {
+    fn start_drag(&mut self,
+                  this_object_id: ObjectId,
+                  bundle: &mut Bundle,
+                  source: ObjectId,
+                  origin: ObjectId,
+                  icon: ObjectId,
+                  serial: u32)
+                  -> Task {
+        // Drag always starts from surface under the pointer so `origin` is not needed.
+        self.proxy.borrow_mut().start_drag(source, icon);
+        Task::None
+    }
+
+    fn set_selection(&mut self,
+                     this_object_id: ObjectId,
+                     bundle: &mut Bundle,
+                     source: ObjectId,
+                     serial: u32)
+                     -> Task {
+        self.proxy.borrow_mut().set_selection(SelectionKind::Clipboard, source);
+        Task::None
+    }
+
+    fn release(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().remove_data_device_oid(this_object_id);
+        Task::Destroy { id: this_object_id }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `wl_data_offer` object.
+///
+/// Data offers are created by server when selection or dragged data is offered to client.
+pub struct DataOffer {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl DataOffer {
+    fn new(_oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        DataOffer { proxy: proxy_ref }
+    }
+
+    pub fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, wl_data_offer::Dispatcher>::new(Self::new(oid, proxy_ref)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl wl_data_offer::Interface for DataOffer {
+    fn accept(&mut self,
+              this_object_id: ObjectId,
+              bundle: &mut Bundle,
+              serial: u32,
+              mime_type: String)
+              -> Task {
+        self.proxy.borrow_mut().accept_data_offer(this_object_id, mime_type);
+        Task::None
+    }
+
+    fn receive(&mut self,
+               this_object_id: ObjectId,
+               bundle: &mut Bundle,
+               mime_type: String,
+               fd: i32)
+               -> Task {
+        self.proxy.borrow_mut().receive_data_offer(this_object_id, mime_type, fd);
+        Task::None
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().destroy_data_offer(this_object_id);
+        Task::Destroy { id: this_object_id }
+    }
+
+    fn finish(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().finish_data_offer(this_object_id);
+        Task::None
+    }
+
+    fn set_actions(&mut self,
+                   this_object_id: ObjectId,
+                   bundle: &mut Bundle,
+                   dnd_actions: u32,
+                   preferred_action: u32)
+                   -> Task {
+        let mut proxy = self.proxy.borrow_mut();
+        proxy.set_data_offer_actions(this_object_id, dnd_actions, preferred_action);
         Task::None
     }
 }
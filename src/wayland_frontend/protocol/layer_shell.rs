@@ -0,0 +1,264 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Implementations of Wayland `zwlr_layer_shell_v1` and `zwlr_layer_surface_v1` objects.
+//!
+//! Layer surfaces are used by panels, docks, wallpapers and similar clients to place surfaces
+//! along edges of output outside of workspaces.
+
+use std::rc::Rc;
+
+use skylane::server::{Bundle, Object, ObjectId, Task};
+use skylane_extra_protocols::server::Handler;
+use skylane_extra_protocols::server::wlr_layer_shell_unstable_v1 as unstable;
+use self::unstable::zwlr_layer_shell_v1 as layer_shell;
+use self::unstable::zwlr_layer_surface_v1 as layer_surface;
+
+use qualia::{layer_anchor, KeyboardInteractivity, Layer, LayerSurfaceInfo, Margins, Size};
+
+use facade::Facade;
+use global::Global;
+use protocol::post_error;
+use proxy::ProxyRef;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Converts protocol value to `Layer`.
+fn layer_from_protocol(layer: u32) -> Option<Layer> {
+    match layer {
+        layer_shell::layer::BACKGROUND => Some(Layer::Background),
+        layer_shell::layer::BOTTOM => Some(Layer::Bottom),
+        layer_shell::layer::TOP => Some(Layer::Top),
+        layer_shell::layer::OVERLAY => Some(Layer::Overlay),
+        _ => None,
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `zwlr_layer_shell_v1` object.
+struct LayerShell {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub fn get_global() -> Global {
+    Global::new(layer_shell::NAME, layer_shell::VERSION, Rc::new(LayerShell::new_object))
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl LayerShell {
+    fn new(_oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        LayerShell { proxy: proxy_ref }
+    }
+
+    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, layer_shell::Dispatcher>::new(Self::new(oid, proxy_ref)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl layer_shell::Interface for LayerShell {
+    fn get_layer_surface(&mut self,
+                         this_object_id: ObjectId,
+                         bundle: &mut Bundle,
+                         id: ObjectId,
+                         surface: ObjectId,
+                         output: ObjectId,
+                         layer: u32,
+                         namespace: String)
+                         -> Task {
+        let layer = if let Some(layer) = layer_from_protocol(layer) {
+            layer
+        } else {
+            post_error(bundle,
+                       this_object_id,
+                       layer_shell::error::INVALID_LAYER,
+                       "Invalid layer requested");
+            return Task::None;
+        };
+
+        let info = {
+            let mut proxy = self.proxy.borrow_mut();
+            let output_id = if output.is_null() { None } else { proxy.get_output_id(output) };
+            let info = LayerSurfaceInfo::new(output_id, layer);
+            proxy.set_as_layer_surface(surface, id, info);
+            info
+        };
+
+        Task::Create {
+            id: id,
+            object: LayerSurface::new_object(id, surface, info, self.proxy.clone()),
+        }
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::Destroy { id: this_object_id }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `zwlr_layer_surface_v1` object.
+///
+/// Keeps pending state of the surface which is passed to `Proxy` on every change and takes effect
+/// on commit of the surface.
+struct LayerSurface {
+    proxy: ProxyRef,
+    surface_oid: ObjectId,
+    info: LayerSurfaceInfo,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl LayerSurface {
+    fn new(_oid: ObjectId,
+           surface_oid: ObjectId,
+           info: LayerSurfaceInfo,
+           proxy_ref: ProxyRef)
+           -> Self {
+        LayerSurface {
+            proxy: proxy_ref,
+            surface_oid: surface_oid,
+            info: info,
+        }
+    }
+
+    fn new_object(oid: ObjectId,
+                  surface_oid: ObjectId,
+                  info: LayerSurfaceInfo,
+                  proxy_ref: ProxyRef)
+                  -> Box<Object> {
+        Box::new(Handler::<_, layer_surface::Dispatcher>::new(Self::new(oid,
+                                                                          surface_oid,
+                                                                          info,
+                                                                          proxy_ref)))
+    }
+
+    /// Passes pending state to `Proxy`.
+    fn update(&self) {
+        self.proxy.borrow_mut().set_layer_surface_state(self.surface_oid, self.info);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl layer_surface::Interface for LayerSurface {
+    fn set_size(&mut self,
+                this_object_id: ObjectId,
+                bundle: &mut Bundle,
+                width: u32,
+                height: u32)
+                -> Task {
+        self.info.size = Size::new(width as usize, height as usize);
+        self.update();
+        Task::None
+    }
+
+    fn set_anchor(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, anchor: u32) -> Task {
+        if let Some(anchor) = layer_anchor::LayerAnchor::from_bits(anchor) {
+            self.info.anchor = anchor;
+            self.update();
+        } else {
+            post_error(bundle,
+                       this_object_id,
+                       layer_surface::error::INVALID_ANCHOR,
+                       "Invalid anchor requested");
+        }
+        Task::None
+    }
+
+    fn set_exclusive_zone(&mut self,
+                          this_object_id: ObjectId,
+                          bundle: &mut Bundle,
+                          zone: i32)
+                          -> Task {
+        self.info.exclusive_zone = zone;
+        self.update();
+        Task::None
+    }
+
+    fn set_margin(&mut self,
+                  this_object_id: ObjectId,
+                  bundle: &mut Bundle,
+                  top: i32,
+                  right: i32,
+                  bottom: i32,
+                  left: i32)
+                  -> Task {
+        self.info.margins = Margins {
+            top: top as isize,
+            right: right as isize,
+            bottom: bottom as isize,
+            left: left as isize,
+        };
+        self.update();
+        Task::None
+    }
+
+    fn set_keyboard_interactivity(&mut self,
+                                  this_object_id: ObjectId,
+                                  bundle: &mut Bundle,
+                                  keyboard_interactivity: u32)
+                                  -> Task {
+        let interactivity = match keyboard_interactivity {
+            layer_surface::keyboard_interactivity::NONE => KeyboardInteractivity::None,
+            layer_surface::keyboard_interactivity::EXCLUSIVE => KeyboardInteractivity::Exclusive,
+            layer_surface::keyboard_interactivity::ON_DEMAND => KeyboardInteractivity::OnDemand,
+            _ => {
+                post_error(bundle,
+                           this_object_id,
+                           layer_surface::error::INVALID_KEYBOARD_INTERACTIVITY,
+                           "Invalid keyboard interactivity requested");
+                return Task::None;
+            }
+        };
+        self.info.keyboard_interactivity = interactivity;
+        self.update();
+        Task::None
+    }
+
+    fn get_popup(&mut self,
+                 this_object_id: ObjectId,
+                 bundle: &mut Bundle,
+                 popup: ObjectId)
+                 -> Task {
+        // TODO: Pop-ups of layer surfaces are not yet supported.
+        log_warn3!("Pop-ups of layer surfaces are not supported");
+        Task::None
+    }
+
+    fn ack_configure(&mut self,
+                     this_object_id: ObjectId,
+                     bundle: &mut Bundle,
+                     serial: u32)
+                     -> Task {
+        self.proxy.borrow_mut().ack_configure(self.surface_oid, serial);
+        Task::None
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().unset_as_layer_surface(self.surface_oid);
+        Task::Destroy { id: this_object_id }
+    }
+
+    fn set_layer(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, layer: u32) -> Task {
+        if let Some(layer) = layer_from_protocol(layer) {
+            self.info.layer = layer;
+            self.update();
+        } else {
+            post_error(bundle,
+                       this_object_id,
+                       layer_shell::error::INVALID_LAYER,
+                       "Invalid layer requested");
+        }
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -3,17 +3,37 @@
 
 //! Implementation of handlers for Wayland protocol.
 
+use skylane::server as wl;
+use skylane::server::{Bundle, ObjectId};
+use skylane_protocols::server::wayland::wl_display;
+
 pub mod display;
 pub mod registry;
 pub mod shm;
 
 pub mod compositor;
 pub mod shell;
+pub mod xdg_common;
 pub mod xdg_shell_v6;
+pub mod xdg_shell;
+pub mod layer_shell;
 
 pub mod data_device_manager;
+pub mod primary_selection;
 pub mod seat;
 pub mod subcompositor;
 pub mod output;
+pub mod presentation;
 
 pub mod weston_screenshooter;
+pub mod screencopy;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Logs protocol error and informs client about it.
+pub fn post_error(bundle: &mut Bundle, object_id: ObjectId, code: u32, msg: &str) {
+    log_warn2!("Protocol error: {}", msg);
+    send!(wl_display::error(&bundle.get_socket(), wl::DISPLAY_ID, object_id, code, msg));
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -44,16 +44,39 @@ impl Output {
                                       wl_output::subpixel::UNKNOWN as i32,
                                       &info.make,
                                       &info.model,
-                                      wl_output::transform::NORMAL as i32));
-
-            send!(wl_output::mode(&socket,
-                                  oid,
-                                  wl_output::mode::CURRENT as u32,
-                                  info.area.size.width as i32,
-                                  info.area.size.height as i32,
-                                  info.refresh_rate as i32));
+                                      info.transform));
+
+            // Send modes described by the display. The first one is the preferred one.
+            let mode_size = info.get_mode_size();
+            let mut current_sent = false;
+            for (i, mode) in info.modes.iter().enumerate() {
+                let mut flags = 0;
+                if i == 0 {
+                    flags |= wl_output::mode::PREFERRED as u32;
+                }
+                if !current_sent && mode.matches(mode_size, info.refresh_rate) {
+                    flags |= wl_output::mode::CURRENT as u32;
+                    current_sent = true;
+                }
+                send!(wl_output::mode(&socket,
+                                      oid,
+                                      flags,
+                                      mode.size.width as i32,
+                                      mode.size.height as i32,
+                                      mode.refresh_rate as i32));
+            }
+
+            // Current mode may be not one of modes described by the display.
+            if !current_sent {
+                send!(wl_output::mode(&socket,
+                                      oid,
+                                      wl_output::mode::CURRENT as u32,
+                                      mode_size.width as i32,
+                                      mode_size.height as i32,
+                                      info.refresh_rate as i32));
+            }
 
-            send!(wl_output::scale(&socket, oid, 1));
+            send!(wl_output::scale(&socket, oid, info.scale));
             send!(wl_output::done(&socket, oid));
 
             proxy.relate_output_oid_with_id(oid, info.id);
@@ -0,0 +1,77 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Implementations of Wayland `wp_presentation` object.
+//!
+//! `wp_presentation_feedback` objects are not backed by server-side handlers. They have no requests
+//! and are destroyed after sending `presented` or `discarded` event, similarly to `wl_callback`.
+
+use std::rc::Rc;
+
+use skylane::server::{Bundle, Object, ObjectId, Task};
+use skylane_extra_protocols::server::Handler;
+use skylane_extra_protocols::server::presentation_time::wp_presentation;
+
+use global::Global;
+use facade::Facade;
+use proxy::ProxyRef;
+
+// -------------------------------------------------------------------------------------------------
+
+/// ID of `CLOCK_MONOTONIC`. Time stamps of page flips reported by DRM are given in this clock
+/// domain.
+const CLOCK_MONOTONIC: u32 = 1;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `wp_presentation` object.
+struct Presentation {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub fn get_global() -> Global {
+    Global::new(wp_presentation::NAME,
+                wp_presentation::VERSION,
+                Rc::new(Presentation::new_object))
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl Presentation {
+    /// Creates new `Presentation` and sends clock ID to client.
+    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        {
+            let socket = proxy_ref.borrow().get_socket();
+            send!(wp_presentation::clock_id(&socket, oid, CLOCK_MONOTONIC));
+        }
+
+        Presentation { proxy: proxy_ref }
+    }
+
+    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, wp_presentation::Dispatcher>::new(Self::new(oid, proxy_ref)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl wp_presentation::Interface for Presentation {
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::Destroy { id: this_object_id }
+    }
+
+    fn feedback(&mut self,
+                this_object_id: ObjectId,
+                bundle: &mut Bundle,
+                surface: ObjectId,
+                callback: ObjectId)
+                -> Task {
+        self.proxy.borrow_mut().set_presentation_feedback(surface, callback);
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
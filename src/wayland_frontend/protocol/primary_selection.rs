@@ -0,0 +1,201 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Implementations of Wayland `zwp_primary_selection_device_manager_v1`,
+//! `zwp_primary_selection_source_v1`, `zwp_primary_selection_device_v1` and
+//! `zwp_primary_selection_offer_v1` objects.
+
+use std::rc::Rc;
+
+use skylane::server::{Bundle, Object, ObjectId, Task};
+use skylane_extra_protocols::server::Handler;
+use skylane_extra_protocols::server::primary_selection_unstable_v1 as unstable;
+use self::unstable::zwp_primary_selection_device_manager_v1 as manager;
+use self::unstable::zwp_primary_selection_source_v1 as source;
+use self::unstable::zwp_primary_selection_device_v1 as device;
+use self::unstable::zwp_primary_selection_offer_v1 as offer;
+
+use global::Global;
+use facade::{Facade, SelectionKind};
+use proxy::ProxyRef;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `zwp_primary_selection_device_manager_v1` object.
+struct PrimarySelectionDeviceManager {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub fn get_global() -> Global {
+    Global::new(manager::NAME,
+                manager::VERSION,
+                Rc::new(PrimarySelectionDeviceManager::new_object))
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl PrimarySelectionDeviceManager {
+    /// Creates new `PrimarySelectionDeviceManager`.
+    fn new(_oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        PrimarySelectionDeviceManager { proxy: proxy_ref }
+    }
+
+    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, manager::Dispatcher>::new(Self::new(oid, proxy_ref)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl manager::Interface for PrimarySelectionDeviceManager {
+    fn create_source(&mut self,
+                     this_object_id: ObjectId,
+                     bundle: &mut Bundle,
+                     id: ObjectId)
+                     -> Task {
+        Task::Create {
+            id: id,
+            object: PrimarySelectionSource::new_object(id, self.proxy.clone()),
+        }
+    }
+
+    fn get_device(&mut self,
+                  this_object_id: ObjectId,
+                  bundle: &mut Bundle,
+                  id: ObjectId,
+                  seat: ObjectId)
+                  -> Task {
+        // There is only one seat so `seat` can be ignored.
+        Task::Create {
+            id: id,
+            object: PrimarySelectionDevice::new_object(id, self.proxy.clone()),
+        }
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::Destroy { id: this_object_id }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `zwp_primary_selection_source_v1` object.
+///
+/// Primary selection sources are kept together with data sources.
+struct PrimarySelectionSource {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl PrimarySelectionSource {
+    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        proxy_ref.borrow_mut().create_data_source(oid);
+        PrimarySelectionSource { proxy: proxy_ref }
+    }
+
+    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, source::Dispatcher>::new(Self::new(oid, proxy_ref)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl source::Interface for PrimarySelectionSource {
+    fn offer(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, mime_type: String) -> Task {
+        self.proxy.borrow_mut().add_data_source_mime_type(this_object_id, mime_type);
+        Task::None
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().destroy_data_source(this_object_id);
+        Task::Destroy { id: this_object_id }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `zwp_primary_selection_device_v1` object.
+struct PrimarySelectionDevice {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl PrimarySelectionDevice {
+    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        proxy_ref.borrow_mut().add_primary_selection_device_oid(oid);
+        PrimarySelectionDevice { proxy: proxy_ref }
+    }
+
+    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, device::Dispatcher>::new(Self::new(oid, proxy_ref)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl device::Interface for PrimarySelectionDevice {
+    fn set_selection(&mut self,
+                     this_object_id: ObjectId,
+                     bundle: &mut Bundle,
+                     source: ObjectId,
+                     serial: u32)
+                     -> Task {
+        self.proxy.borrow_mut().set_selection(SelectionKind::Primary, source);
+        Task::None
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().remove_primary_selection_device_oid(this_object_id);
+        Task::Destroy { id: this_object_id }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `zwp_primary_selection_offer_v1` object.
+///
+/// Offers are created by server when primary selection is offered to client.
+pub struct PrimarySelectionOffer {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl PrimarySelectionOffer {
+    fn new(_oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        PrimarySelectionOffer { proxy: proxy_ref }
+    }
+
+    pub fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, offer::Dispatcher>::new(Self::new(oid, proxy_ref)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl offer::Interface for PrimarySelectionOffer {
+    fn receive(&mut self,
+               this_object_id: ObjectId,
+               bundle: &mut Bundle,
+               mime_type: String,
+               fd: i32)
+               -> Task {
+        self.proxy.borrow_mut().receive_data_offer(this_object_id, mime_type, fd);
+        Task::None
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().destroy_data_offer(this_object_id);
+        Task::Destroy { id: this_object_id }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -21,14 +21,16 @@ pub struct Registry {
 // -------------------------------------------------------------------------------------------------
 
 impl Registry {
-    /// Creates new `Registry` and posts current globals.
+    /// Creates new `Registry` and posts current globals. Globals added or removed later will be
+    /// announced by `Proxy`.
     fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
         {
-            let proxy = proxy_ref.borrow();
+            let mut proxy = proxy_ref.borrow_mut();
             let socket = proxy.get_socket();
             for (name, global) in proxy.get_globals() {
                 send!(wl_registry::global(&socket, oid, *name, global.interface, global.version));
             }
+            proxy.add_registry_oid(oid);
         }
 
         Registry { proxy: proxy_ref }
@@ -74,6 +76,7 @@ impl wl_registry::Interface for Registry {
             }
         } {
             Ok(global) => {
+                self.proxy.borrow_mut().set_object_version(new_object_id, version);
                 let object = global.construct(new_object_id, self.proxy.clone());
                 Task::Create {
                     id: new_object_id,
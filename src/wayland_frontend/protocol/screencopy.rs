@@ -0,0 +1,277 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Implementations of Wayland `zwlr_screencopy_manager_v1` and `zwlr_screencopy_frame_v1` objects.
+//!
+//! Contents of output (or its part) are copied to client's shared memory buffer. Copying is done
+//! while drawing next frame, or next frame changing the captured area if client requested copy
+//! with damage.
+
+use std::rc::Rc;
+
+use skylane::server::{Bundle, Object, ObjectId, Task};
+use skylane_extra_protocols::server::Handler;
+use skylane_protocols::server::wayland::wl_shm;
+use skylane_extra_protocols::server::wlr_screencopy_unstable_v1 as unstable;
+use self::unstable::zwlr_screencopy_manager_v1 as screencopy_manager;
+use self::unstable::zwlr_screencopy_frame_v1 as screencopy_frame;
+
+use qualia::{Area, Position, Size};
+
+use facade::Facade;
+use global::Global;
+use protocol::post_error;
+use proxy::ProxyRef;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Format of buffers used for copying. Renderer data are converted to it before copying.
+const FORMAT: u32 = wl_shm::format::XRGB8888;
+
+/// Number of bytes per pixel in `FORMAT`.
+const BYTES_PER_PIXEL: usize = 4;
+
+/// Version of `zwlr_screencopy_frame_v1` which introduced `buffer_done` event.
+const BUFFER_DONE_SINCE_VERSION: u32 = 3;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Parameters of capture requested by client.
+#[derive(Clone, Copy)]
+struct Capture {
+    /// ID of captured output.
+    output_id: i32,
+
+    /// Captured area in logical output coordinates.
+    area: Area,
+
+    /// Size of buffer in pixels.
+    size: Size,
+
+    /// `true` if cursor should be visible on the copy.
+    overlay_cursor: bool,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `zwlr_screencopy_manager_v1` object.
+struct ScreencopyManager {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub fn get_global() -> Global {
+    Global::new(screencopy_manager::NAME,
+                screencopy_manager::VERSION,
+                Rc::new(ScreencopyManager::new_object))
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl ScreencopyManager {
+    fn new(proxy_ref: ProxyRef) -> Self {
+        ScreencopyManager { proxy: proxy_ref }
+    }
+
+    fn new_object(_oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, screencopy_manager::Dispatcher>::new(Self::new(proxy_ref)))
+    }
+
+    /// Creates new frame object. Captured area is clipped to the output. If the output is unknown
+    /// or the area does not overlap with it, the frame will fail.
+    fn capture(&mut self,
+               this_object_id: ObjectId,
+               frame_oid: ObjectId,
+               overlay_cursor: i32,
+               output_oid: ObjectId,
+               region: Option<Area>)
+               -> Task {
+        let (version, capture) = {
+            let proxy = self.proxy.borrow();
+            let capture = proxy.get_output_info(output_oid).and_then(|info| {
+                let output_area = Area::new(Position::default(), info.area.size);
+                let area = if let Some(region) = region {
+                    region.intersection(&output_area)
+                } else {
+                    Some(output_area)
+                };
+
+                area.map(|area| {
+                    Capture {
+                        output_id: info.id,
+                        area: area,
+                        size: area.size.scaled(info.scale as f32),
+                        overlay_cursor: overlay_cursor != 0,
+                    }
+                })
+            });
+            (proxy.get_object_version(this_object_id), capture)
+        };
+
+        Task::Create {
+            id: frame_oid,
+            object: ScreencopyFrame::new_object(frame_oid, version, capture, self.proxy.clone()),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl screencopy_manager::Interface for ScreencopyManager {
+    fn capture_output(&mut self,
+                      this_object_id: ObjectId,
+                      bundle: &mut Bundle,
+                      frame: ObjectId,
+                      overlay_cursor: i32,
+                      output: ObjectId)
+                      -> Task {
+        self.capture(this_object_id, frame, overlay_cursor, output, None)
+    }
+
+    fn capture_output_region(&mut self,
+                             this_object_id: ObjectId,
+                             bundle: &mut Bundle,
+                             frame: ObjectId,
+                             overlay_cursor: i32,
+                             output: ObjectId,
+                             x: i32,
+                             y: i32,
+                             width: i32,
+                             height: i32)
+                             -> Task {
+        let region = if (width > 0) && (height > 0) {
+            Area::create(x as isize, y as isize, width as usize, height as usize)
+        } else {
+            Area::default()
+        };
+        self.capture(this_object_id, frame, overlay_cursor, output, Some(region))
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::Destroy { id: this_object_id }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Wayland `zwlr_screencopy_frame_v1` object.
+struct ScreencopyFrame {
+    proxy: ProxyRef,
+    capture: Option<Capture>,
+    used: bool,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl ScreencopyFrame {
+    /// Creates new `ScreencopyFrame` and informs client about required buffer parameters or about
+    /// failure if capture is not possible.
+    fn new(oid: ObjectId, version: u32, capture: Option<Capture>, proxy_ref: ProxyRef) -> Self {
+        {
+            let socket = proxy_ref.borrow().get_socket();
+            if let Some(capture) = capture {
+                send!(screencopy_frame::buffer(&socket,
+                                               oid,
+                                               FORMAT,
+                                               capture.size.width as u32,
+                                               capture.size.height as u32,
+                                               (BYTES_PER_PIXEL * capture.size.width) as u32));
+                if version >= BUFFER_DONE_SINCE_VERSION {
+                    send!(screencopy_frame::buffer_done(&socket, oid));
+                }
+            } else {
+                send!(screencopy_frame::failed(&socket, oid));
+            }
+        }
+
+        ScreencopyFrame {
+            proxy: proxy_ref,
+            capture: capture,
+            used: false,
+        }
+    }
+
+    fn new_object(oid: ObjectId,
+                  version: u32,
+                  capture: Option<Capture>,
+                  proxy_ref: ProxyRef)
+                  -> Box<Object> {
+        Box::new(Handler::<_, screencopy_frame::Dispatcher>::new(Self::new(oid,
+                                                                             version,
+                                                                             capture,
+                                                                             proxy_ref)))
+    }
+
+    /// Checks if given buffer matches parameters of the capture and requests the copy.
+    fn request_copy(&mut self,
+                    this_object_id: ObjectId,
+                    bundle: &mut Bundle,
+                    buffer_oid: ObjectId,
+                    with_damage: bool) {
+        if self.used {
+            post_error(bundle,
+                       this_object_id,
+                       screencopy_frame::error::ALREADY_USED,
+                       "Frame was already used for copying");
+            return;
+        }
+
+        let capture = if let Some(capture) = self.capture {
+            capture
+        } else {
+            // Client was already informed about failure.
+            return;
+        };
+
+        let mut proxy = self.proxy.borrow_mut();
+        let is_valid = if let Some(info) = proxy.get_buffer_info(buffer_oid) {
+            (info.size == capture.size) && (info.format == FORMAT) &&
+            (info.stride == BYTES_PER_PIXEL * capture.size.width)
+        } else {
+            false
+        };
+
+        if is_valid {
+            self.used = true;
+            proxy.copy_output(this_object_id,
+                              buffer_oid,
+                              capture.output_id,
+                              capture.area,
+                              capture.overlay_cursor,
+                              with_damage);
+        } else {
+            post_error(bundle,
+                       this_object_id,
+                       screencopy_frame::error::INVALID_BUFFER,
+                       "Buffer does not match parameters of the frame");
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl screencopy_frame::Interface for ScreencopyFrame {
+    fn copy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, buffer: ObjectId) -> Task {
+        self.request_copy(this_object_id, bundle, buffer, false);
+        Task::None
+    }
+
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().cancel_output_copy(this_object_id);
+        Task::Destroy { id: this_object_id }
+    }
+
+    fn copy_with_damage(&mut self,
+                        this_object_id: ObjectId,
+                        bundle: &mut Bundle,
+                        buffer: ObjectId)
+                        -> Task {
+        self.request_copy(this_object_id, bundle, buffer, true);
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
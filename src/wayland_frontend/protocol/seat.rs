@@ -1,7 +1,7 @@
 // This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
 // the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
 
-//! Implementations of Wayland `wl_seat` and `wl_keyboard` objects.
+//! Implementations of Wayland `wl_seat`, `wl_pointer`, `wl_keyboard` and `wl_touch` objects.
 
 use std::rc::Rc;
 
@@ -34,10 +34,10 @@ pub fn get_global() -> Global {
 impl Seat {
     fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
         {
-            // TODO: Add more capabilities
             let proxy = proxy_ref.borrow();
             let socket = proxy.get_socket();
-            let caps = wl_seat::capability::POINTER | wl_seat::capability::KEYBOARD;
+            let caps = wl_seat::capability::POINTER | wl_seat::capability::KEYBOARD |
+                       wl_seat::capability::TOUCH;
             send!(wl_seat::capabilities(&socket, oid, caps));
             send!(wl_seat::name(&socket, oid, "seat0"));
         }
@@ -170,7 +170,6 @@ impl wl_keyboard::Interface for Keyboard {
 // -------------------------------------------------------------------------------------------------
 
 /// Wayland `wl_touch` object.
-#[allow(dead_code)]
 struct Touch {
     proxy: ProxyRef,
 }
@@ -178,12 +177,13 @@ struct Touch {
 // -------------------------------------------------------------------------------------------------
 
 impl Touch {
-    fn new(proxy_ref: ProxyRef) -> Self {
+    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        proxy_ref.borrow_mut().add_touch_oid(oid);
         Touch { proxy: proxy_ref }
     }
 
-    fn new_object(_oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
-        Box::new(Handler::<_, wl_touch::Dispatcher>::new(Self::new(proxy_ref)))
+    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+        Box::new(Handler::<_, wl_touch::Dispatcher>::new(Self::new(oid, proxy_ref)))
     }
 }
 
@@ -191,6 +191,7 @@ impl Touch {
 
 impl wl_touch::Interface for Touch {
     fn release(&mut self, this_object_id: ObjectId, _bundle: &mut Bundle) -> Task {
+        self.proxy.borrow_mut().remove_touch_oid(this_object_id);
         Task::Destroy { id: this_object_id }
     }
 }
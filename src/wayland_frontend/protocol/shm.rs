@@ -134,7 +134,8 @@ impl wl_shm_pool::Interface for ShmPool {
                                                      offset as usize,
                                                      width as usize,
                                                      height as usize,
-                                                     stride as usize) {
+                                                     stride as usize,
+                                                     format) {
             let buffer = ShmBuffer::new_object(self.proxy.clone(), mvid);
             Task::Create {
                 id: new_buffer_id,
@@ -80,6 +80,9 @@ impl Subsurface {
         {
             let proxy = proxy_ref.borrow_mut();
             proxy.relate(surface_oid, parent_surface_oid);
+
+            // Subsurfaces are created in synchronized mode.
+            proxy.set_synchronized(surface_oid, true);
         }
         Subsurface {
             surface_oid: surface_oid,
@@ -122,6 +125,8 @@ impl wl_subsurface::Interface for Subsurface {
                    _bundle: &mut Bundle,
                    sibling: ObjectId)
                    -> Task {
+        let proxy = self.proxy.borrow_mut();
+        proxy.place_above(self.surface_oid, sibling);
         Task::None
     }
 
@@ -130,14 +135,20 @@ impl wl_subsurface::Interface for Subsurface {
                    bundle: &mut Bundle,
                    sibling: ObjectId)
                    -> Task {
+        let proxy = self.proxy.borrow_mut();
+        proxy.place_below(self.surface_oid, sibling);
         Task::None
     }
 
     fn set_sync(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        let proxy = self.proxy.borrow_mut();
+        proxy.set_synchronized(self.surface_oid, true);
         Task::None
     }
 
     fn set_desync(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        let proxy = self.proxy.borrow_mut();
+        proxy.set_synchronized(self.surface_oid, false);
         Task::None
     }
 }
@@ -0,0 +1,449 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Version independent implementation of XDG shell objects.
+//!
+//! Stable `xdg_shell` and its unstable predecessor differ only in interface names and version
+//! specific events. Handling of requests is implemented here while `xdg_shell` and `xdg_shell_v6`
+//! modules bind it to the interfaces of given version.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use skylane::server::{Bundle, ObjectId, Task};
+
+use qualia::{show_reason, Area, Size, Vector};
+
+use facade::{Facade, PositionerInfo, ShellSurfaceOid};
+use protocol::post_error;
+use proxy::ProxyRef;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Kinds of protocol errors raised by XDG shell objects. Error codes of all versions are chosen by
+/// version specific code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XdgErrorKind {
+    /// Shell was destroyed before its surfaces.
+    DefunctSurfaces,
+
+    /// Pop-up was created with incomplete positioner.
+    InvalidPositioner,
+
+    /// Positioner received invalid values.
+    InvalidInput,
+
+    /// Role object was already created for the surface.
+    AlreadyConstructed,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Protocol error raised while handling request.
+pub struct XdgError {
+    pub kind: XdgErrorKind,
+    pub object_id: ObjectId,
+    pub message: &'static str,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl XdgError {
+    /// Constructs new `XdgError`.
+    pub fn new(kind: XdgErrorKind, object_id: ObjectId, message: &'static str) -> Self {
+        XdgError {
+            kind: kind,
+            object_id: object_id,
+            message: message,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Returns task of handled request. If handling failed posts error with code chosen by `get_code`.
+pub fn finish(bundle: &mut Bundle,
+              result: Result<Task, XdgError>,
+              get_code: fn(XdgErrorKind) -> u32)
+              -> Task {
+    match result {
+        Ok(task) => task,
+        Err(error) => {
+            post_error(bundle, error.object_id, get_code(error.kind), error.message);
+            Task::None
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Common part of Wayland `xdg_wm_base` and `zxdg_shell_v6` objects.
+pub struct XdgShell {
+    pub proxy: ProxyRef,
+
+    /// Number of not destroyed surfaces created from this object.
+    surface_count: Rc<Cell<usize>>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl XdgShell {
+    /// Constructs new `XdgShell`.
+    pub fn new(proxy_ref: ProxyRef) -> Self {
+        XdgShell {
+            proxy: proxy_ref,
+            surface_count: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Handles `destroy` request. Shell can not be destroyed before its surfaces.
+    pub fn handle_destroy(&self, oid: ObjectId) -> Result<Task, XdgError> {
+        if self.surface_count.get() > 0 {
+            Err(XdgError::new(XdgErrorKind::DefunctSurfaces,
+                              oid,
+                              "XDG shell destroyed before its surfaces"))
+        } else {
+            Ok(Task::Destroy { id: oid })
+        }
+    }
+
+    /// Handles `create_positioner` request.
+    pub fn handle_create_positioner(&self, positioner_oid: ObjectId) -> XdgPositioner {
+        XdgPositioner::new(positioner_oid, self.proxy.clone())
+    }
+
+    /// Handles `get_xdg_surface` request.
+    pub fn handle_get_xdg_surface(&self,
+                                  oid: ObjectId,
+                                  shell_surface_oid: ObjectId,
+                                  surface_oid: ObjectId)
+                                  -> XdgSurface {
+        XdgSurface::new(shell_surface_oid,
+                        surface_oid,
+                        oid,
+                        self.surface_count.clone(),
+                        self.proxy.clone())
+    }
+
+    /// Handles `pong` request.
+    pub fn handle_pong(&self, serial: u32) -> Task {
+        self.proxy.borrow_mut().pong(serial);
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Common part of Wayland `xdg_positioner` and `zxdg_positioner_v6` objects.
+pub struct XdgPositioner {
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl XdgPositioner {
+    /// Constructs new `XdgPositioner`.
+    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
+        proxy_ref.borrow_mut().set_positioner(oid, PositionerInfo::new());
+        XdgPositioner { proxy: proxy_ref }
+    }
+
+    /// Handles `destroy` request.
+    pub fn handle_destroy(&self, oid: ObjectId) -> Task {
+        self.proxy.borrow_mut().remove_positioner(oid);
+        Task::Destroy { id: oid }
+    }
+
+    /// Handles `set_size` request. Size must be positive.
+    pub fn handle_set_size(&self,
+                           oid: ObjectId,
+                           width: i32,
+                           height: i32)
+                           -> Result<Task, XdgError> {
+        if (width <= 0) || (height <= 0) {
+            return Err(XdgError::new(XdgErrorKind::InvalidInput,
+                                     oid,
+                                     "XDG positioner size must be positive"));
+        }
+
+        let mut proxy = self.proxy.borrow_mut();
+        if let Some(mut positioner) = proxy.get_positioner(oid) {
+            positioner.size = Size::new(width as usize, height as usize);
+            proxy.set_positioner(oid, positioner);
+        }
+        Ok(Task::None)
+    }
+
+    /// Handles `set_anchor_rect` request. Size of anchor rectangle must not be negative.
+    pub fn handle_set_anchor_rect(&self,
+                                  oid: ObjectId,
+                                  x: i32,
+                                  y: i32,
+                                  width: i32,
+                                  height: i32)
+                                  -> Result<Task, XdgError> {
+        if (width < 0) || (height < 0) {
+            return Err(XdgError::new(XdgErrorKind::InvalidInput,
+                                     oid,
+                                     "XDG positioner anchor rectangle size must not be negative"));
+        }
+
+        let mut proxy = self.proxy.borrow_mut();
+        if let Some(mut positioner) = proxy.get_positioner(oid) {
+            positioner.anchor.pos.x = x as isize;
+            positioner.anchor.pos.y = y as isize;
+            positioner.anchor.size = Size::new(width as usize, height as usize);
+            proxy.set_positioner(oid, positioner);
+        }
+        Ok(Task::None)
+    }
+
+    /// Handles `set_offset` request.
+    pub fn handle_set_offset(&self, oid: ObjectId, x: i32, y: i32) -> Task {
+        let mut proxy = self.proxy.borrow_mut();
+        if let Some(mut positioner) = proxy.get_positioner(oid) {
+            positioner.offset.x = x as isize;
+            positioner.offset.y = y as isize;
+            proxy.set_positioner(oid, positioner);
+        }
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Common part of Wayland `xdg_surface` and `zxdg_surface_v6` objects.
+pub struct XdgSurface {
+    oid: ObjectId,
+    surface_oid: ObjectId,
+    shell_oid: ObjectId,
+    proxy: ProxyRef,
+
+    /// Tells if toplevel or pop-up was already created for this surface.
+    constructed: bool,
+
+    /// Counter of surfaces shared with parent shell.
+    surface_count: Rc<Cell<usize>>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl XdgSurface {
+    /// Constructs new `XdgSurface`.
+    fn new(oid: ObjectId,
+           surface_oid: ObjectId,
+           shell_oid: ObjectId,
+           surface_count: Rc<Cell<usize>>,
+           proxy_ref: ProxyRef)
+           -> Self {
+        surface_count.set(surface_count.get() + 1);
+        XdgSurface {
+            oid: oid,
+            surface_oid: surface_oid,
+            shell_oid: shell_oid,
+            proxy: proxy_ref,
+            constructed: false,
+            surface_count: surface_count,
+        }
+    }
+
+    /// Checks if role object was not yet constructed for this surface.
+    fn check_not_constructed(&self) -> Result<(), XdgError> {
+        if self.constructed {
+            Err(XdgError::new(XdgErrorKind::AlreadyConstructed,
+                              self.oid,
+                              "XDG surface already has a role object"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Handles `destroy` request.
+    pub fn handle_destroy(&self, oid: ObjectId) -> Task {
+        self.proxy.borrow_mut().hide(self.surface_oid, show_reason::IN_SHELL);
+        self.surface_count.set(self.surface_count.get() - 1);
+        Task::Destroy { id: oid }
+    }
+
+    /// Handles `get_toplevel` request. `make_shell_surface_oid` wraps object IDs of this surface
+    /// and the new toplevel in version specific variant of `ShellSurfaceOid`.
+    pub fn handle_get_toplevel(&mut self,
+                               toplevel_oid: ObjectId,
+                               make_shell_surface_oid: fn(ObjectId, ObjectId) -> ShellSurfaceOid)
+                               -> Result<XdgToplevel, XdgError> {
+        self.check_not_constructed()?;
+        self.constructed = true;
+
+        let version = self.proxy.borrow().get_object_version(self.shell_oid);
+        Ok(XdgToplevel::new(toplevel_oid,
+                            self.surface_oid,
+                            make_shell_surface_oid(self.oid, toplevel_oid),
+                            version,
+                            self.proxy.clone()))
+    }
+
+    /// Handles `get_popup` request. Returns new pop-up and area it was placed in. Pop-ups can be
+    /// created only with complete positioner.
+    pub fn handle_get_popup(&mut self,
+                            parent_shell_surface_oid: ObjectId,
+                            positioner_oid: ObjectId)
+                            -> Result<(XdgPopup, Area), XdgError> {
+        self.check_not_constructed()?;
+
+        let positioner = self.proxy.borrow_mut().get_positioner(positioner_oid);
+        let area = match positioner {
+            Some(ref positioner) if positioner.is_complete() => positioner.get_area(),
+            _ => {
+                return Err(XdgError::new(XdgErrorKind::InvalidPositioner,
+                                         self.shell_oid,
+                                         "XDG positioner is incomplete"));
+            }
+        };
+        self.constructed = true;
+
+        let popup = XdgPopup::new(self.surface_oid,
+                                  self.oid,
+                                  parent_shell_surface_oid,
+                                  area,
+                                  self.proxy.clone());
+        Ok((popup, area))
+    }
+
+    /// Handles `ack_configure` request.
+    pub fn handle_ack_configure(&self, serial: u32) -> Task {
+        self.proxy.borrow_mut().ack_configure(self.surface_oid, serial);
+        Task::None
+    }
+
+    /// Handles `set_window_geometry` request. Geometry is applied to the surface on next commit.
+    pub fn handle_set_window_geometry(&self, x: i32, y: i32, width: i32, height: i32) -> Task {
+        if (width > 0) && (height > 0) {
+            let proxy = self.proxy.borrow();
+            proxy.set_offset(self.surface_oid, Vector::new(x as isize, y as isize));
+            proxy.set_requested_size(self.surface_oid, Size::new(width as usize, height as usize));
+        } else {
+            log_wayl3!("Received non-positive window geometry size");
+        }
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Common part of Wayland `xdg_toplevel` and `zxdg_toplevel_v6` objects.
+pub struct XdgToplevel {
+    surface_oid: ObjectId,
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl XdgToplevel {
+    /// Constructs new `XdgToplevel` and shows its surface.
+    fn new(oid: ObjectId,
+           surface_oid: ObjectId,
+           shell_surface_oid: ShellSurfaceOid,
+           version: u32,
+           proxy_ref: ProxyRef)
+           -> Self {
+        {
+            let mut proxy = proxy_ref.borrow_mut();
+            proxy.set_object_version(oid, version);
+            proxy.show(surface_oid, shell_surface_oid, show_reason::IN_SHELL);
+        }
+
+        XdgToplevel {
+            surface_oid: surface_oid,
+            proxy: proxy_ref,
+        }
+    }
+
+    /// Handles `set_parent` request. Null parent makes the surface not transient.
+    pub fn handle_set_parent(&self, parent: ObjectId) -> Task {
+        let proxy = self.proxy.borrow();
+        if parent.is_null() {
+            proxy.set_transient_for(self.surface_oid, parent);
+        } else if let Some(parent_surface_oid) = proxy.get_surface_oid_for_toplevel(parent) {
+            proxy.set_transient_for(self.surface_oid, parent_surface_oid);
+        } else {
+            log_warn2!("Unknown parent toplevel object ID: {}", parent);
+        }
+        Task::None
+    }
+
+    /// Handles `set_max_size` request.
+    pub fn handle_set_max_size(&self, width: i32, height: i32) -> Task {
+        if (width >= 0) && (height >= 0) {
+            let proxy = self.proxy.borrow();
+            proxy.set_max_size(self.surface_oid, Size::new(width as usize, height as usize));
+        } else {
+            log_wayl3!("Received negative maximal size");
+        }
+        Task::None
+    }
+
+    /// Handles `set_min_size` request.
+    pub fn handle_set_min_size(&self, width: i32, height: i32) -> Task {
+        if (width >= 0) && (height >= 0) {
+            let proxy = self.proxy.borrow();
+            proxy.set_min_size(self.surface_oid, Size::new(width as usize, height as usize));
+        } else {
+            log_wayl3!("Received negative minimal size");
+        }
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Common part of Wayland `xdg_popup` and `zxdg_popup_v6` objects.
+pub struct XdgPopup {
+    pub shell_surface_oid: ObjectId,
+    surface_oid: ObjectId,
+    proxy: ProxyRef,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl XdgPopup {
+    /// Constructs new `XdgPopup` and places its surface relatively to parent.
+    fn new(surface_oid: ObjectId,
+           shell_surface_oid: ObjectId,
+           parent_shell_surface_oid: ObjectId,
+           area: Area,
+           proxy_ref: ProxyRef)
+           -> Self {
+        {
+            let proxy = proxy_ref.borrow();
+            let parent_surface_oid = proxy.get_surface_oid_for_shell(parent_shell_surface_oid);
+            if let Some(parent_surface_oid) = parent_surface_oid {
+                proxy.relate(surface_oid, parent_surface_oid);
+                proxy.set_relative_position(surface_oid, area.pos.x, area.pos.y);
+            }
+        }
+
+        XdgPopup {
+            shell_surface_oid: shell_surface_oid,
+            surface_oid: surface_oid,
+            proxy: proxy_ref,
+        }
+    }
+
+    /// Handles `destroy` request.
+    pub fn handle_destroy(&self, oid: ObjectId) -> Task {
+        self.proxy.borrow().unrelate(self.surface_oid);
+        Task::Destroy { id: oid }
+    }
+
+    /// Handles `reposition` request. Returns new area of the pop-up if positioner is known.
+    pub fn handle_reposition(&self, positioner_oid: ObjectId) -> Option<Area> {
+        let positioner = self.proxy.borrow_mut().get_positioner(positioner_oid);
+        positioner.map(|positioner| {
+            let area = positioner.get_area();
+            self.proxy.borrow().set_relative_position(self.surface_oid, area.pos.x, area.pos.y);
+            area
+        })
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
@@ -0,0 +1,380 @@
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You can obtain one at http://mozilla.org/MPL/2.0/
+
+//! Implementations of Wayland `xdg_wm_base`, `xdg_positioner`, `xdg_surface`, `xdg_toplevel` and
+//! `xdg_popup` objects.
+//!
+//! Requests are handled by code shared with unstable predecessor (see `xdg_common` module). This
+//! module binds it to stable interfaces and sends events specific for this version.
+
+use std::rc::Rc;
+
+use skylane::server::{Bundle, Object, ObjectId, Task};
+use skylane_extra_protocols::server::Handler;
+use skylane_extra_protocols::server::xdg_shell::xdg_wm_base;
+use skylane_extra_protocols::server::xdg_shell::xdg_positioner;
+use skylane_extra_protocols::server::xdg_shell::xdg_surface;
+use skylane_extra_protocols::server::xdg_shell::xdg_toplevel;
+use skylane_extra_protocols::server::xdg_shell::xdg_popup;
+
+use qualia::Area;
+
+use facade::{Facade, ShellSurfaceOid};
+use global::Global;
+use proxy::ProxyRef;
+use protocol::xdg_common::{finish, XdgErrorKind};
+use protocol::xdg_common::{XdgShell, XdgPositioner, XdgSurface, XdgToplevel, XdgPopup};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Returns error code of given error kind.
+fn get_error_code(kind: XdgErrorKind) -> u32 {
+    match kind {
+        XdgErrorKind::DefunctSurfaces => xdg_wm_base::error::DEFUNCT_SURFACES,
+        XdgErrorKind::InvalidPositioner => xdg_wm_base::error::INVALID_POSITIONER,
+        XdgErrorKind::InvalidInput => xdg_positioner::error::INVALID_INPUT,
+        XdgErrorKind::AlreadyConstructed => xdg_surface::error::ALREADY_CONSTRUCTED,
+    }
+}
+
+/// Sends configuration of pop-up placed in given area.
+fn configure_popup(bundle: &mut Bundle,
+                   shell_surface_oid: ObjectId,
+                   popup_oid: ObjectId,
+                   area: Area) {
+    let serial = bundle.get_socket().get_next_serial();
+    send!(xdg_popup::configure(&bundle.get_socket(),
+                               popup_oid,
+                               area.pos.x as i32,
+                               area.pos.y as i32,
+                               area.size.width as i32,
+                               area.size.height as i32));
+    send!(xdg_surface::configure(&bundle.get_socket(), shell_surface_oid, serial));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub fn get_global() -> Global {
+    Global::new(xdg_wm_base::NAME, xdg_wm_base::VERSION, Rc::new(new_wm_base_object))
+}
+
+/// Creates new `xdg_wm_base` object. Only this version of shell is used for pinging clients.
+fn new_wm_base_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+    proxy_ref.borrow_mut().add_xdg_wm_base_oid(oid);
+    Box::new(Handler::<_, xdg_wm_base::Dispatcher>::new(XdgShell::new(proxy_ref)))
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl xdg_wm_base::Interface for XdgShell {
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        let result = self.handle_destroy(this_object_id);
+        if result.is_ok() {
+            self.proxy.borrow_mut().remove_xdg_wm_base_oid(this_object_id);
+        }
+        finish(bundle, result, get_error_code)
+    }
+
+    fn create_positioner(&mut self,
+                         this_object_id: ObjectId,
+                         bundle: &mut Bundle,
+                         new_positioner_oid: ObjectId)
+                         -> Task {
+        let positioner = self.handle_create_positioner(new_positioner_oid);
+        Task::Create {
+            id: new_positioner_oid,
+            object: Box::new(Handler::<_, xdg_positioner::Dispatcher>::new(positioner)),
+        }
+    }
+
+    fn get_xdg_surface(&mut self,
+                       this_object_id: ObjectId,
+                       bundle: &mut Bundle,
+                       new_surface_oid: ObjectId,
+                       surface: ObjectId)
+                       -> Task {
+        let surface = self.handle_get_xdg_surface(this_object_id, new_surface_oid, surface);
+        Task::Create {
+            id: new_surface_oid,
+            object: Box::new(Handler::<_, xdg_surface::Dispatcher>::new(surface)),
+        }
+    }
+
+    fn pong(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, serial: u32) -> Task {
+        self.handle_pong(serial)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl xdg_positioner::Interface for XdgPositioner {
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.handle_destroy(this_object_id)
+    }
+
+    fn set_size(&mut self,
+                this_object_id: ObjectId,
+                bundle: &mut Bundle,
+                width: i32,
+                height: i32)
+                -> Task {
+        let result = self.handle_set_size(this_object_id, width, height);
+        finish(bundle, result, get_error_code)
+    }
+
+    fn set_anchor_rect(&mut self,
+                       this_object_id: ObjectId,
+                       bundle: &mut Bundle,
+                       x: i32,
+                       y: i32,
+                       width: i32,
+                       height: i32)
+                       -> Task {
+        let result = self.handle_set_anchor_rect(this_object_id, x, y, width, height);
+        finish(bundle, result, get_error_code)
+    }
+
+    fn set_anchor(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, anchor: u32) -> Task {
+        Task::None
+    }
+
+    fn set_gravity(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, gravity: u32) -> Task {
+        Task::None
+    }
+
+    fn set_constraint_adjustment(&mut self,
+                                 this_object_id: ObjectId,
+                                 bundle: &mut Bundle,
+                                 constraint_adjustment: u32)
+                                 -> Task {
+        Task::None
+    }
+
+    fn set_offset(&mut self,
+                  this_object_id: ObjectId,
+                  bundle: &mut Bundle,
+                  x: i32,
+                  y: i32)
+                  -> Task {
+        self.handle_set_offset(this_object_id, x, y)
+    }
+
+    fn set_reactive(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::None
+    }
+
+    fn set_parent_size(&mut self,
+                       this_object_id: ObjectId,
+                       bundle: &mut Bundle,
+                       parent_width: i32,
+                       parent_height: i32)
+                       -> Task {
+        Task::None
+    }
+
+    fn set_parent_configure(&mut self,
+                            this_object_id: ObjectId,
+                            bundle: &mut Bundle,
+                            serial: u32)
+                            -> Task {
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl xdg_surface::Interface for XdgSurface {
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.handle_destroy(this_object_id)
+    }
+
+    fn get_toplevel(&mut self,
+                    this_object_id: ObjectId,
+                    bundle: &mut Bundle,
+                    new_toplevel_id: ObjectId)
+                    -> Task {
+        let result = self.handle_get_toplevel(new_toplevel_id, ShellSurfaceOid::XdgToplevel)
+            .map(|toplevel| {
+                Task::Create {
+                    id: new_toplevel_id,
+                    object: Box::new(Handler::<_, xdg_toplevel::Dispatcher>::new(toplevel)),
+                }
+            });
+        finish(bundle, result, get_error_code)
+    }
+
+    fn get_popup(&mut self,
+                 this_object_id: ObjectId,
+                 bundle: &mut Bundle,
+                 new_popup_oid: ObjectId,
+                 parent_shell_surface_oid: ObjectId,
+                 positioner_oid: ObjectId)
+                 -> Task {
+        match self.handle_get_popup(parent_shell_surface_oid, positioner_oid) {
+            Ok((popup, area)) => {
+                configure_popup(bundle, this_object_id, new_popup_oid, area);
+                Task::Create {
+                    id: new_popup_oid,
+                    object: Box::new(Handler::<_, xdg_popup::Dispatcher>::new(popup)),
+                }
+            }
+            Err(error) => finish(bundle, Err(error), get_error_code),
+        }
+    }
+
+    fn set_window_geometry(&mut self,
+                           this_object_id: ObjectId,
+                           bundle: &mut Bundle,
+                           x: i32,
+                           y: i32,
+                           width: i32,
+                           height: i32)
+                           -> Task {
+        self.handle_set_window_geometry(x, y, width, height)
+    }
+
+    fn ack_configure(&mut self,
+                     this_object_id: ObjectId,
+                     bundle: &mut Bundle,
+                     serial: u32)
+                     -> Task {
+        self.handle_ack_configure(serial)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl xdg_toplevel::Interface for XdgToplevel {
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::Destroy { id: this_object_id }
+    }
+
+    fn set_parent(&mut self,
+                  this_object_id: ObjectId,
+                  bundle: &mut Bundle,
+                  parent: ObjectId)
+                  -> Task {
+        self.handle_set_parent(parent)
+    }
+
+    fn set_title(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, title: String) -> Task {
+        Task::None
+    }
+
+    fn set_app_id(&mut self,
+                  this_object_id: ObjectId,
+                  bundle: &mut Bundle,
+                  app_id: String)
+                  -> Task {
+        Task::None
+    }
+
+    fn show_window_menu(&mut self,
+                        this_object_id: ObjectId,
+                        bundle: &mut Bundle,
+                        seat: ObjectId,
+                        serial: u32,
+                        x: i32,
+                        y: i32)
+                        -> Task {
+        Task::None
+    }
+
+    fn move_(&mut self,
+             this_object_id: ObjectId,
+             bundle: &mut Bundle,
+             seat: ObjectId,
+             serial: u32)
+             -> Task {
+        Task::None
+    }
+
+    fn resize(&mut self,
+              this_object_id: ObjectId,
+              bundle: &mut Bundle,
+              seat: ObjectId,
+              serial: u32,
+              edges: u32)
+              -> Task {
+        Task::None
+    }
+
+    fn set_max_size(&mut self,
+                    this_object_id: ObjectId,
+                    bundle: &mut Bundle,
+                    width: i32,
+                    height: i32)
+                    -> Task {
+        self.handle_set_max_size(width, height)
+    }
+
+    fn set_min_size(&mut self,
+                    this_object_id: ObjectId,
+                    bundle: &mut Bundle,
+                    width: i32,
+                    height: i32)
+                    -> Task {
+        self.handle_set_min_size(width, height)
+    }
+
+    fn set_maximized(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::None
+    }
+
+    fn unset_maximized(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::None
+    }
+
+    fn set_fullscreen(&mut self,
+                      this_object_id: ObjectId,
+                      bundle: &mut Bundle,
+                      output: ObjectId)
+                      -> Task {
+        Task::None
+    }
+
+    fn unset_fullscreen(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::None
+    }
+
+    fn set_minimized(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(unused_variables)]
+impl xdg_popup::Interface for XdgPopup {
+    fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
+        self.handle_destroy(this_object_id)
+    }
+
+    fn grab(&mut self,
+            this_object_id: ObjectId,
+            bundle: &mut Bundle,
+            seat: ObjectId,
+            serial: u32)
+            -> Task {
+        Task::None
+    }
+
+    fn reposition(&mut self,
+                  this_object_id: ObjectId,
+                  bundle: &mut Bundle,
+                  positioner_oid: ObjectId,
+                  token: u32)
+                  -> Task {
+        if let Some(area) = self.handle_reposition(positioner_oid) {
+            send!(xdg_popup::repositioned(&bundle.get_socket(), this_object_id, token));
+            configure_popup(bundle, self.shell_surface_oid, this_object_id, area);
+        }
+        Task::None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
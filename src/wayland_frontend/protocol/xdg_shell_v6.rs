@@ -3,6 +3,9 @@
 
 //! Implementations of Wayland `zxdg_shell_v6`, `zxdg_positioner_v6`, `zxdg_surface_v6`,
 //! `zxdg_toplevel_v6` and `zxdg_popup_v6` objects.
+//!
+//! Requests are handled by code shared with stable version (see `xdg_common` module). This module
+//! binds it to unstable interfaces and sends events specific for this version.
 
 // FIXME: Finish implementation of XDG pop-up positioning.
 
@@ -16,48 +19,59 @@ use skylane_protocols::server::xdg_shell_unstable_v6::zxdg_surface_v6;
 use skylane_protocols::server::xdg_shell_unstable_v6::zxdg_toplevel_v6;
 use skylane_protocols::server::xdg_shell_unstable_v6::zxdg_popup_v6;
 
-use qualia::{show_reason, Area};
+use qualia::Area;
 
-use facade::{Facade, PositionerInfo, ShellSurfaceOid};
+use facade::ShellSurfaceOid;
 use global::Global;
 use proxy::ProxyRef;
+use protocol::xdg_common::{finish, XdgErrorKind};
+use protocol::xdg_common::{XdgShell, XdgPositioner, XdgSurface, XdgToplevel, XdgPopup};
 
 // -------------------------------------------------------------------------------------------------
 
-/// Wayland `zxdg_shell_v6` object.
-#[allow(dead_code)]
-struct ZxdgShellV6 {
-    oid: ObjectId,
-    proxy: ProxyRef,
+/// Returns error code of given error kind.
+fn get_error_code(kind: XdgErrorKind) -> u32 {
+    match kind {
+        XdgErrorKind::DefunctSurfaces => zxdg_shell_v6::error::DEFUNCT_SURFACES,
+        XdgErrorKind::InvalidPositioner => zxdg_shell_v6::error::INVALID_POSITIONER,
+        XdgErrorKind::InvalidInput => zxdg_positioner_v6::error::INVALID_INPUT,
+        XdgErrorKind::AlreadyConstructed => zxdg_surface_v6::error::ALREADY_CONSTRUCTED,
+    }
 }
 
-// -------------------------------------------------------------------------------------------------
-
-pub fn get_global() -> Global {
-    Global::new(zxdg_shell_v6::NAME, zxdg_shell_v6::VERSION, Rc::new(ZxdgShellV6::new_object))
+/// Sends configuration of pop-up placed in given area.
+fn configure_popup(bundle: &mut Bundle,
+                   shell_surface_oid: ObjectId,
+                   popup_oid: ObjectId,
+                   area: Area) {
+    let serial = bundle.get_socket().get_next_serial();
+    send!(zxdg_popup_v6::configure(&bundle.get_socket(),
+                                   popup_oid,
+                                   area.pos.x as i32,
+                                   area.pos.y as i32,
+                                   area.size.width as i32,
+                                   area.size.height as i32));
+    send!(zxdg_surface_v6::configure(&bundle.get_socket(), shell_surface_oid, serial));
 }
 
 // -------------------------------------------------------------------------------------------------
 
-impl ZxdgShellV6 {
-    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
-        ZxdgShellV6 {
-            oid: oid,
-            proxy: proxy_ref,
-        }
-    }
+pub fn get_global() -> Global {
+    Global::new(zxdg_shell_v6::NAME, zxdg_shell_v6::VERSION, Rc::new(new_shell_object))
+}
 
-    fn new_object(oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
-        Box::new(Handler::<_, zxdg_shell_v6::Dispatcher>::new(Self::new(oid, proxy_ref)))
-    }
+/// Creates new `zxdg_shell_v6` object.
+fn new_shell_object(_oid: ObjectId, proxy_ref: ProxyRef) -> Box<Object> {
+    Box::new(Handler::<_, zxdg_shell_v6::Dispatcher>::new(XdgShell::new(proxy_ref)))
 }
 
 // -------------------------------------------------------------------------------------------------
 
 #[allow(unused_variables)]
-impl zxdg_shell_v6::Interface for ZxdgShellV6 {
+impl zxdg_shell_v6::Interface for XdgShell {
     fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
-        Task::Destroy { id: this_object_id }
+        let result = self.handle_destroy(this_object_id);
+        finish(bundle, result, get_error_code)
     }
 
     fn create_positioner(&mut self,
@@ -65,10 +79,10 @@ impl zxdg_shell_v6::Interface for ZxdgShellV6 {
                          bundle: &mut Bundle,
                          new_positioner_oid: ObjectId)
                          -> Task {
-        let positioner = ZxdgPositionerV6::new_object(new_positioner_oid, self.proxy.clone());
+        let positioner = self.handle_create_positioner(new_positioner_oid);
         Task::Create {
             id: new_positioner_oid,
-            object: positioner,
+            object: Box::new(Handler::<_, zxdg_positioner_v6::Dispatcher>::new(positioner)),
         }
     }
 
@@ -78,49 +92,24 @@ impl zxdg_shell_v6::Interface for ZxdgShellV6 {
                        new_surface_oid: ObjectId,
                        surface: ObjectId)
                        -> Task {
-        let surface = ZxdgSurfaceV6::new_object(new_surface_oid, surface, self.proxy.clone());
+        let surface = self.handle_get_xdg_surface(this_object_id, new_surface_oid, surface);
         Task::Create {
             id: new_surface_oid,
-            object: surface,
+            object: Box::new(Handler::<_, zxdg_surface_v6::Dispatcher>::new(surface)),
         }
     }
 
     fn pong(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, serial: u32) -> Task {
-        Task::None
-    }
-}
-
-// -------------------------------------------------------------------------------------------------
-
-/// Wayland `zxdg_positioner_v6` object.
-struct ZxdgPositionerV6 {
-    proxy: ProxyRef,
-}
-
-// -------------------------------------------------------------------------------------------------
-
-impl ZxdgPositionerV6 {
-    fn new(oid: ObjectId, proxy_ref: ProxyRef) -> Self {
-        {
-            let mut proxy = proxy_ref.borrow_mut();
-            proxy.set_positioner(oid, PositionerInfo::new());
-        }
-        ZxdgPositionerV6 { proxy: proxy_ref }
-    }
-
-    fn new_object(oid: ObjectId, proxy: ProxyRef) -> Box<Object> {
-        Box::new(Handler::<_, zxdg_positioner_v6::Dispatcher>::new(Self::new(oid, proxy)))
+        self.handle_pong(serial)
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 
 #[allow(unused_variables)]
-impl zxdg_positioner_v6::Interface for ZxdgPositionerV6 {
+impl zxdg_positioner_v6::Interface for XdgPositioner {
     fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
-        let mut proxy = self.proxy.borrow_mut();
-        proxy.remove_positioner(this_object_id);
-        Task::Destroy { id: this_object_id }
+        self.handle_destroy(this_object_id)
     }
 
     fn set_size(&mut self,
@@ -129,13 +118,8 @@ impl zxdg_positioner_v6::Interface for ZxdgPositionerV6 {
                 width: i32,
                 height: i32)
                 -> Task {
-        let mut proxy = self.proxy.borrow_mut();
-        if let Some(mut positioner) = proxy.get_positioner(this_object_id) {
-            positioner.size.width = width as usize;
-            positioner.size.height = height as usize;
-            proxy.set_positioner(this_object_id, positioner);
-        }
-        Task::None
+        let result = self.handle_set_size(this_object_id, width, height);
+        finish(bundle, result, get_error_code)
     }
 
     fn set_anchor_rect(&mut self,
@@ -146,15 +130,8 @@ impl zxdg_positioner_v6::Interface for ZxdgPositionerV6 {
                        width: i32,
                        height: i32)
                        -> Task {
-        let mut proxy = self.proxy.borrow_mut();
-        if let Some(mut positioner) = proxy.get_positioner(this_object_id) {
-            positioner.anchor.pos.x = x as isize;
-            positioner.anchor.pos.y = y as isize;
-            positioner.anchor.size.width = width as usize;
-            positioner.anchor.size.height = height as usize;
-            proxy.set_positioner(this_object_id, positioner);
-        }
-        Task::None
+        let result = self.handle_set_anchor_rect(this_object_id, x, y, width, height);
+        finish(bundle, result, get_error_code)
     }
 
     fn set_anchor(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, anchor: u32) -> Task {
@@ -175,53 +152,20 @@ impl zxdg_positioner_v6::Interface for ZxdgPositionerV6 {
 
     fn set_offset(&mut self,
                   this_object_id: ObjectId,
-                  _bundle: &mut Bundle,
+                  bundle: &mut Bundle,
                   x: i32,
                   y: i32)
                   -> Task {
-        let mut proxy = self.proxy.borrow_mut();
-        if let Some(mut positioner) = proxy.get_positioner(this_object_id) {
-            positioner.offset.x = x as isize;
-            positioner.offset.y = y as isize;
-            proxy.set_positioner(this_object_id, positioner);
-        }
-        Task::None
-    }
-}
-
-// -------------------------------------------------------------------------------------------------
-
-/// Wayland `zxdg_surface_v6` object.
-struct ZxdgSurfaceV6 {
-    oid: ObjectId,
-    surface_oid: ObjectId,
-    proxy: ProxyRef,
-}
-
-// -------------------------------------------------------------------------------------------------
-
-impl ZxdgSurfaceV6 {
-    fn new(oid: ObjectId, surface_oid: ObjectId, proxy_ref: ProxyRef) -> Self {
-        ZxdgSurfaceV6 {
-            oid: oid,
-            surface_oid: surface_oid,
-            proxy: proxy_ref,
-        }
-    }
-
-    fn new_object(oid: ObjectId, surface_oid: ObjectId, proxy: ProxyRef) -> Box<Object> {
-        Box::new(Handler::<_, zxdg_surface_v6::Dispatcher>::new(Self::new(oid, surface_oid, proxy)))
+        self.handle_set_offset(this_object_id, x, y)
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 
 #[allow(unused_variables)]
-impl zxdg_surface_v6::Interface for ZxdgSurfaceV6 {
+impl zxdg_surface_v6::Interface for XdgSurface {
     fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
-        let mut proxy = self.proxy.borrow_mut();
-        proxy.hide(self.surface_oid, show_reason::IN_SHELL);
-        Task::Destroy { id: this_object_id }
+        self.handle_destroy(this_object_id)
     }
 
     fn get_toplevel(&mut self,
@@ -229,14 +173,14 @@ impl zxdg_surface_v6::Interface for ZxdgSurfaceV6 {
                     bundle: &mut Bundle,
                     new_toplevel_id: ObjectId)
                     -> Task {
-        let toplevel = ZxdgToplevelV6::new_object(new_toplevel_id,
-                                                  self.surface_oid,
-                                                  self.oid,
-                                                  self.proxy.clone());
-        Task::Create {
-            id: new_toplevel_id,
-            object: toplevel,
-        }
+        let result = self.handle_get_toplevel(new_toplevel_id, ShellSurfaceOid::ZxdgToplevelV6)
+            .map(|toplevel| {
+                Task::Create {
+                    id: new_toplevel_id,
+                    object: Box::new(Handler::<_, zxdg_toplevel_v6::Dispatcher>::new(toplevel)),
+                }
+            });
+        finish(bundle, result, get_error_code)
     }
 
     fn get_popup(&mut self,
@@ -246,33 +190,16 @@ impl zxdg_surface_v6::Interface for ZxdgSurfaceV6 {
                  parent_shell_surface_oid: ObjectId,
                  positioner_oid: ObjectId)
                  -> Task {
-        let area = {
-            let mut proxy = self.proxy.borrow_mut();
-            if let Some(positioner) = proxy.get_positioner(positioner_oid) {
-                positioner.get_area()
-            } else {
-                Area::default()
+        match self.handle_get_popup(parent_shell_surface_oid, positioner_oid) {
+            Ok((popup, area)) => {
+                // GTK does not map surface without configuring it.
+                configure_popup(bundle, this_object_id, new_popup_oid, area);
+                Task::Create {
+                    id: new_popup_oid,
+                    object: Box::new(Handler::<_, zxdg_popup_v6::Dispatcher>::new(popup)),
+                }
             }
-        };
-
-        let popup = ZxdgPopupV6::new_object(self.surface_oid,
-                                            parent_shell_surface_oid,
-                                            area,
-                                            self.proxy.clone());
-
-        // GTK does not map surface without configuring it.
-        let serial = bundle.get_socket().get_next_serial();
-        send!(zxdg_popup_v6::configure(&bundle.get_socket(),
-                                       new_popup_oid,
-                                       area.pos.x as i32,
-                                       area.pos.y as i32,
-                                       area.size.width as i32,
-                                       area.size.height as i32));
-        send!(zxdg_surface_v6::configure(&bundle.get_socket(), this_object_id, serial));
-
-        Task::Create {
-            id: new_popup_oid,
-            object: popup,
+            Err(error) => finish(bundle, Err(error), get_error_code),
         }
     }
 
@@ -284,7 +211,7 @@ impl zxdg_surface_v6::Interface for ZxdgSurfaceV6 {
                            width: i32,
                            height: i32)
                            -> Task {
-        Task::None
+        self.handle_set_window_geometry(x, y, width, height)
     }
 
     fn ack_configure(&mut self,
@@ -292,47 +219,14 @@ impl zxdg_surface_v6::Interface for ZxdgSurfaceV6 {
                      bundle: &mut Bundle,
                      serial: u32)
                      -> Task {
-        Task::None
-    }
-}
-
-// -------------------------------------------------------------------------------------------------
-
-/// Wayland `zxdg_toplevel_v6` object.
-struct ZxdgToplevelV6 {}
-
-// -------------------------------------------------------------------------------------------------
-
-impl ZxdgToplevelV6 {
-    fn new(oid: ObjectId,
-           surface_oid: ObjectId,
-           shell_surface_oid: ObjectId,
-           proxy_ref: ProxyRef)
-           -> Self {
-        {
-            let mut proxy = proxy_ref.borrow_mut();
-            proxy.show(surface_oid,
-                       ShellSurfaceOid::ZxdgToplevelV6(shell_surface_oid, oid),
-                       show_reason::IN_SHELL);
-        }
-
-        ZxdgToplevelV6 {}
-    }
-
-    fn new_object(oid: ObjectId,
-                  surface_oid: ObjectId,
-                  shell_surface_oid: ObjectId,
-                  proxy_ref: ProxyRef)
-                  -> Box<Object> {
-        let toplevel = Self::new(oid, surface_oid, shell_surface_oid, proxy_ref);
-        Box::new(Handler::<_, zxdg_toplevel_v6::Dispatcher>::new(toplevel))
+        self.handle_ack_configure(serial)
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 
 #[allow(unused_variables)]
-impl zxdg_toplevel_v6::Interface for ZxdgToplevelV6 {
+impl zxdg_toplevel_v6::Interface for XdgToplevel {
     fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
         Task::Destroy { id: this_object_id }
     }
@@ -342,7 +236,7 @@ impl zxdg_toplevel_v6::Interface for ZxdgToplevelV6 {
                   bundle: &mut Bundle,
                   parent: ObjectId)
                   -> Task {
-        Task::None
+        self.handle_set_parent(parent)
     }
 
     fn set_title(&mut self, this_object_id: ObjectId, bundle: &mut Bundle, title: String) -> Task {
@@ -393,7 +287,7 @@ impl zxdg_toplevel_v6::Interface for ZxdgToplevelV6 {
                     width: i32,
                     height: i32)
                     -> Task {
-        Task::None
+        self.handle_set_max_size(width, height)
     }
 
     fn set_min_size(&mut self,
@@ -402,7 +296,7 @@ impl zxdg_toplevel_v6::Interface for ZxdgToplevelV6 {
                     width: i32,
                     height: i32)
                     -> Task {
-        Task::None
+        self.handle_set_min_size(width, height)
     }
 
     fn set_maximized(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
@@ -432,53 +326,10 @@ impl zxdg_toplevel_v6::Interface for ZxdgToplevelV6 {
 
 // -------------------------------------------------------------------------------------------------
 
-/// Wayland `zxdg_popup_v6` object.
-struct ZxdgPopupV6 {
-    surface_oid: ObjectId,
-    proxy: ProxyRef,
-}
-
-// -------------------------------------------------------------------------------------------------
-
-impl ZxdgPopupV6 {
-    fn new(surface_oid: ObjectId,
-           parent_shell_surface_oid: ObjectId,
-           area: Area,
-           proxy_ref: ProxyRef)
-           -> Self {
-        {
-            let proxy = proxy_ref.borrow();
-            let parent_surface_oid = proxy.get_surface_oid_for_shell(parent_shell_surface_oid);
-            if let Some(parent_surface_oid) = parent_surface_oid {
-                proxy.relate(surface_oid, parent_surface_oid);
-                proxy.set_relative_position(surface_oid, area.pos.x, area.pos.y);
-            }
-        }
-
-        ZxdgPopupV6 {
-            surface_oid: surface_oid,
-            proxy: proxy_ref,
-        }
-    }
-
-    fn new_object(surface_oid: ObjectId,
-                  parent_shell_surface_oid: ObjectId,
-                  area: Area,
-                  proxy_ref: ProxyRef)
-                  -> Box<Object> {
-        let popup = Self::new(surface_oid, parent_shell_surface_oid, area, proxy_ref);
-        Box::new(Handler::<_, zxdg_popup_v6::Dispatcher>::new(popup))
-    }
-}
-
-// -------------------------------------------------------------------------------------------------
-
 #[allow(unused_variables)]
-impl zxdg_popup_v6::Interface for ZxdgPopupV6 {
+impl zxdg_popup_v6::Interface for XdgPopup {
     fn destroy(&mut self, this_object_id: ObjectId, bundle: &mut Bundle) -> Task {
-        let proxy = self.proxy.borrow();
-        proxy.unrelate(self.surface_oid);
-        Task::Destroy { id: this_object_id }
+        self.handle_destroy(this_object_id)
     }
 
     fn grab(&mut self,
@@ -6,19 +6,33 @@
 // -------------------------------------------------------------------------------------------------
 
 use std;
+use std::cmp::max;
 use std::collections::{BTreeMap, HashMap, HashSet};
 
+use nix;
 use dharma;
 use skylane::server as wl;
-use skylane_protocols::server::wayland::{wl_display, wl_callback, wl_buffer};
-use skylane_protocols::server::wayland::{wl_keyboard, wl_pointer};
+use skylane_protocols::server::wayland::{wl_display, wl_callback, wl_buffer, wl_registry};
+use skylane_protocols::server::wayland::{wl_data_device, wl_data_offer, wl_data_source};
+use skylane_protocols::server::wayland::wl_data_device_manager::dnd_action;
+use skylane_protocols::server::wayland::{wl_keyboard, wl_pointer, wl_touch};
 use skylane_protocols::server::wayland::wl_shell_surface;
 use skylane_protocols::server::xdg_shell_unstable_v6::{zxdg_toplevel_v6, zxdg_surface_v6};
+use skylane_extra_protocols::server::xdg_shell::{xdg_wm_base, xdg_toplevel, xdg_surface};
 use skylane_protocols::server::weston_screenshooter::weston_screenshooter;
-
-use qualia::Settings;
-use qualia::{Area, Axis, Button, Key, KeyMods, Milliseconds, OutputInfo, Position, Size, Vector};
-use qualia::{MappedMemory, MemoryPoolId, MemoryViewId};
+use skylane_extra_protocols::server::primary_selection_unstable_v1 as primary_selection;
+use self::primary_selection::{zwp_primary_selection_device_v1, zwp_primary_selection_offer_v1};
+use self::primary_selection::zwp_primary_selection_source_v1;
+use skylane_extra_protocols::server::presentation_time::wp_presentation_feedback;
+use skylane_extra_protocols::server::wlr_layer_shell_unstable_v1::zwlr_layer_surface_v1;
+use skylane_extra_protocols::server::wlr_screencopy_unstable_v1::zwlr_screencopy_frame_v1;
+
+use qualia::{KeyboardConfig, Settings};
+use qualia::{Area, Axis, AxisSource, Button, Key, KeyMods, Milliseconds, OutputInfo, Position};
+use qualia::{Size, Vector};
+use qualia::{LayerSurfaceInfo, Presentation, Region};
+use qualia::{Buffer, Illusion, MappedMemory, MemoryPoolId, MemoryViewId};
+use qualia::{Screenshot, ScreenshotId, ScreenshotRequest};
 use qualia::{show_reason, surface_state, SurfaceId};
 use qualia::{SurfaceManagement, SurfaceControl, SurfaceViewer, SurfaceFocusing};
 use qualia::{AppearanceManagement, Screenshooting, MemoryManagement};
@@ -26,10 +40,18 @@ use qualia::{AppearanceManagement, Screenshooting, MemoryManagement};
 use coordination::Coordinator;
 
 use protocol;
-use facade::{Facade, PositionerInfo, ShellSurfaceOid};
+use facade::{Facade, PositionerInfo, SelectionKind, ShellSurfaceOid};
 use gateway::Gateway;
 use global::Global;
-use mediator::MediatorRef;
+use mediator::{Drag, DragState, MediatorRef, Selection};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Object IDs from this value up are reserved for objects created by server.
+const SERVER_OBJECT_ID_START: u32 = 0xff000000;
+
+/// Version of `xdg_toplevel` which introduced `configure_bounds` event.
+const CONFIGURE_BOUNDS_SINCE_VERSION: u32 = 4;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -67,6 +89,23 @@ macro_rules! unrelate_sid_with {
 
 // -------------------------------------------------------------------------------------------------
 
+/// State committed by client which was not yet applied by coordinator.
+struct CommittedState {
+    /// Number of commits of the surface up to this one.
+    serial: u32,
+
+    /// Newly attached buffer (null object ID if buffer was detached).
+    buffer_oid: Option<wl::ObjectId>,
+
+    /// Frame callbacks requested with this commit.
+    frame_oids: Vec<wl::ObjectId>,
+
+    /// Presentation feedbacks requested with this commit.
+    feedback_oids: Vec<wl::ObjectId>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// Helper structure for aggregating information about surface.
 struct SurfaceInfo {
     // For sending keyboard `enter` and `leave`.
@@ -75,11 +114,38 @@ struct SurfaceInfo {
     // For releasing buffer in `on_surface_frame`
     buffer_oid: Option<wl::ObjectId>,
 
-    // For sending frame callback in `on_surface_frame`
-    frame_oid: Option<wl::ObjectId>,
+    // Buffer attached but not yet committed (null object ID if buffer was detached)
+    pending_buffer_oid: Option<wl::ObjectId>,
+
+    // For sending frame callbacks in `on_surface_frame`
+    frame_oids: Vec<wl::ObjectId>,
+
+    // Frame callbacks requested but not yet committed
+    pending_frame_oids: Vec<wl::ObjectId>,
+
+    // For sending presentation feedback in `on_surface_frame`
+    feedback_oids: Vec<wl::ObjectId>,
+
+    // Presentation feedbacks requested but not yet committed
+    pending_feedback_oids: Vec<wl::ObjectId>,
+
+    // Number of commits, for matching committed states with `on_surface_state_applied`
+    commit_count: u32,
+
+    // States committed but not yet made current by coordinator
+    committed_states: Vec<CommittedState>,
 
     // For send reconfiguration events in `on_surface_reconfigured`
     shell_surface_oid: Option<ShellSurfaceOid>,
+
+    // For matching client acknowledgement with the most recent configuration in `ack_configure`
+    configure_serial: Option<u32>,
+
+    // Layer state passed to the rest of application on the last commit
+    layer: Option<LayerSurfaceInfo>,
+
+    // Layer state requested but not yet committed
+    pending_layer: Option<LayerSurfaceInfo>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -90,7 +156,16 @@ impl SurfaceInfo {
             surface_oid: None,
             shell_surface_oid: None,
             buffer_oid: None,
-            frame_oid: None,
+            pending_buffer_oid: None,
+            frame_oids: Vec::new(),
+            pending_frame_oids: Vec::new(),
+            feedback_oids: Vec::new(),
+            pending_feedback_oids: Vec::new(),
+            commit_count: 0,
+            committed_states: Vec::new(),
+            configure_serial: None,
+            layer: None,
+            pending_layer: None,
         }
     }
 }
@@ -99,18 +174,69 @@ impl SurfaceInfo {
 
 /// Helper structure for aggregating information about buffers.
 #[derive(Clone, Copy)]
-struct BufferInfo {
-    mpid: MemoryPoolId,
-    mvid: MemoryViewId,
+pub struct BufferInfo {
+    pub mpid: MemoryPoolId,
+    pub mvid: MemoryViewId,
+    pub size: Size,
+    pub stride: usize,
+    pub format: u32,
 }
 
 // -------------------------------------------------------------------------------------------------
 
 impl BufferInfo {
-    pub fn new(mpid: MemoryPoolId, mvid: MemoryViewId) -> Self {
+    pub fn new(mpid: MemoryPoolId,
+               mvid: MemoryViewId,
+               size: Size,
+               stride: usize,
+               format: u32)
+               -> Self {
         BufferInfo {
             mpid: mpid,
             mvid: mvid,
+            size: size,
+            stride: stride,
+            format: format,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Object waiting for results of screenshot request.
+#[derive(Clone, Copy)]
+enum ScreenshotTarget {
+    /// Weston screenshooter copying whole output to buffer.
+    Screenshooter {
+        screenshooter_oid: wl::ObjectId,
+        buffer_oid: wl::ObjectId,
+    },
+
+    /// Screencopy frame copying output or its part to buffer.
+    ScreencopyFrame {
+        frame_oid: wl::ObjectId,
+        buffer_oid: wl::ObjectId,
+        with_damage: bool,
+    },
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Helper structure for aggregating information about data sources.
+struct DataSourceInfo {
+    mime_types: Vec<String>,
+
+    // `None` if client did not set actions (e.g. because it does not support them).
+    dnd_actions: Option<u32>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+impl DataSourceInfo {
+    pub fn new() -> Self {
+        DataSourceInfo {
+            mime_types: Vec::new(),
+            dnd_actions: None,
         }
     }
 }
@@ -135,18 +261,33 @@ pub struct Proxy {
     /// globals in wrong order may crash clients
     globals: BTreeMap<u32, Global>,
 
-    regions: HashMap<wl::ObjectId, Area>,
+    /// Names of `wl_output` globals indexed by output ID.
+    output_globals: HashMap<i32, u32>,
+
+    registry_oids: HashSet<wl::ObjectId>,
+    regions: HashMap<wl::ObjectId, Region>,
     positioners: HashMap<wl::ObjectId, PositionerInfo>,
     pointer_oids: HashSet<wl::ObjectId>,
     keyboard_oids: HashSet<wl::ObjectId>,
+    touch_oids: HashSet<wl::ObjectId>,
+    data_device_oids: HashSet<wl::ObjectId>,
+    primary_selection_device_oids: HashSet<wl::ObjectId>,
+    selection_offer_oids: HashMap<wl::ObjectId, SelectionKind>,
+    drag_offer_oids: HashSet<wl::ObjectId>,
+    data_sources: HashMap<wl::ObjectId, DataSourceInfo>,
+    xdg_wm_base_oids: HashSet<wl::ObjectId>,
+    object_versions: HashMap<wl::ObjectId, u32>,
     memory_pools: HashSet<MemoryPoolId>,
     surface_oid_to_sid_dictionary: HashMap<wl::ObjectId, SurfaceId>,
     sid_to_surface_info_dictionary: HashMap<SurfaceId, SurfaceInfo>,
     buffer_oid_to_buffer_info_dictionary: HashMap<wl::ObjectId, BufferInfo>,
     output_oid_to_id: HashMap<wl::ObjectId, i32>,
-    screenshooter_oid: Option<wl::ObjectId>,
-    screenshot_memory: Option<MappedMemory>,
+    output_infos: HashMap<i32, OutputInfo>,
+    screenshots: HashMap<ScreenshotId, ScreenshotTarget>,
+    ping_serial: Option<u32>,
+    configure_bounds: Size,
     last_global_id: u32,
+    next_server_oid: u32,
 }
 
 define_ref!(struct Proxy as ProxyRef);
@@ -161,6 +302,7 @@ impl Proxy {
                mediator: MediatorRef,
                socket: wl::Socket)
                -> Self {
+        mediator.borrow_mut().register_client(id, socket.clone());
         Proxy {
             id: id,
             coordinator: coordinator,
@@ -168,18 +310,31 @@ impl Proxy {
             settings: settings,
             socket: socket,
             globals: BTreeMap::new(),
+            output_globals: HashMap::new(),
+            registry_oids: HashSet::new(),
             regions: HashMap::new(),
             positioners: HashMap::new(),
             pointer_oids: HashSet::new(),
             keyboard_oids: HashSet::new(),
+            touch_oids: HashSet::new(),
+            data_device_oids: HashSet::new(),
+            primary_selection_device_oids: HashSet::new(),
+            selection_offer_oids: HashMap::new(),
+            drag_offer_oids: HashSet::new(),
+            data_sources: HashMap::new(),
+            xdg_wm_base_oids: HashSet::new(),
+            object_versions: HashMap::new(),
             memory_pools: HashSet::new(),
             surface_oid_to_sid_dictionary: HashMap::new(),
             sid_to_surface_info_dictionary: HashMap::new(),
             buffer_oid_to_buffer_info_dictionary: HashMap::new(),
             output_oid_to_id: HashMap::new(),
-            screenshooter_oid: None,
-            screenshot_memory: None,
+            output_infos: HashMap::new(),
+            screenshots: HashMap::new(),
+            ping_serial: None,
+            configure_bounds: Size::default(),
             last_global_id: 0,
+            next_server_oid: SERVER_OBJECT_ID_START,
         }
     }
 
@@ -193,16 +348,68 @@ impl Proxy {
         self.socket.clone()
     }
 
+    /// Returns ID of output related to given `wl_output` object ID.
+    pub fn get_output_id(&self, output_oid: wl::ObjectId) -> Option<i32> {
+        self.output_oid_to_id.get(&output_oid).cloned()
+    }
+
+    /// Returns information about output related to given `wl_output` object ID.
+    pub fn get_output_info(&self, output_oid: wl::ObjectId) -> Option<OutputInfo> {
+        self.get_output_id(output_oid).and_then(|id| self.output_infos.get(&id).cloned())
+    }
+
+    /// Returns information about shared memory buffer with given object ID.
+    pub fn get_buffer_info(&self, buffer_oid: wl::ObjectId) -> Option<BufferInfo> {
+        self.buffer_oid_to_buffer_info_dictionary.get(&buffer_oid).cloned()
+    }
+
     /// Return list of current globals.
     pub fn get_globals(&self) -> &BTreeMap<u32, Global> {
         &self.globals
     }
 
-    /// Registers new global.
-    pub fn register_global(&mut self, mut global: Global) {
+    /// Registers new global and announces it to existing registries. Returns name of the global.
+    pub fn register_global(&mut self, mut global: Global) -> u32 {
         self.last_global_id += 1;
         global.name = self.last_global_id;
+        for &registry_oid in self.registry_oids.iter() {
+            send!(wl_registry::global(&self.socket,
+                                      registry_oid,
+                                      global.name,
+                                      global.interface,
+                                      global.version));
+        }
         self.globals.insert(self.last_global_id, global);
+        self.last_global_id
+    }
+
+    /// Unregisters global with given name and announces its removal to existing registries.
+    pub fn unregister_global(&mut self, name: u32) {
+        if self.globals.remove(&name).is_some() {
+            for &registry_oid in self.registry_oids.iter() {
+                send!(wl_registry::global_remove(&self.socket, registry_oid, name));
+            }
+        }
+    }
+
+    /// Remembers registry object ID. Registries are informed about added and removed globals.
+    pub fn add_registry_oid(&mut self, oid: wl::ObjectId) {
+        self.registry_oids.insert(oid);
+    }
+
+    /// Remembers version of given object negotiated with client.
+    pub fn set_object_version(&mut self, oid: wl::ObjectId, version: u32) {
+        self.object_versions.insert(oid, version);
+    }
+
+    /// Returns version of given object negotiated with client. Versions are remembered only for
+    /// objects requiring them for deciding which events can be sent.
+    pub fn get_object_version(&self, oid: wl::ObjectId) -> u32 {
+        if let Some(version) = self.object_versions.get(&oid) {
+            *version
+        } else {
+            1
+        }
     }
 
     /// Handles termination of client by destroying its resources.
@@ -215,6 +422,119 @@ impl Proxy {
             self.mediator.borrow_mut().remove(*sid);
             self.coordinator.destroy_surface(*sid);
         }
+
+        self.mediator.borrow_mut().remove_selection(self.id, None);
+        self.mediator.borrow_mut().cancel_drag(self.id, None);
+        self.mediator.borrow_mut().unregister_client(self.id);
+    }
+
+    /// Offers current selection of given kind to client by creating new offer for every device of
+    /// that kind and announcing it as selection. Returns IDs of newly created offers.
+    /// Corresponding objects have to be added to client connection.
+    pub fn offer_selection(&mut self, kind: SelectionKind) -> Vec<wl::ObjectId> {
+        let selection = self.mediator.borrow().get_selection(kind);
+        let device_oids: Vec<wl::ObjectId> = match kind {
+            SelectionKind::Clipboard => self.data_device_oids.iter().cloned().collect(),
+            SelectionKind::Primary => self.primary_selection_device_oids.iter().cloned().collect(),
+        };
+
+        let mut offer_oids = Vec::new();
+        for device_oid in device_oids {
+            let offer_oid = if let Some(ref selection) = selection {
+                let offer_oid = wl::ObjectId::new(self.next_server_oid);
+                self.next_server_oid += 1;
+                self.send_selection_offer(kind, device_oid, offer_oid, &selection.mime_types);
+                offer_oids.push(offer_oid);
+                offer_oid
+            } else {
+                wl::ObjectId::new(0)
+            };
+            self.send_selection(kind, device_oid, offer_oid);
+        }
+
+        // Offers made earlier are outdated now.
+        self.selection_offer_oids.retain(|_, offer_kind| *offer_kind != kind);
+        for offer_oid in offer_oids.iter() {
+            self.selection_offer_oids.insert(*offer_oid, kind);
+        }
+        offer_oids
+    }
+
+    /// Informs client that data is dragged over given surface by creating new data offer for
+    /// every data device and sending `enter` event. Returns IDs of newly created data offers.
+    /// Corresponding objects have to be added to client connection.
+    pub fn on_drag_enter(&mut self, sid: SurfaceId, position: Position) -> Vec<wl::ObjectId> {
+        let surface_oid = if let Some(info) = self.sid_to_surface_info_dictionary.get(&sid) {
+            info.surface_oid
+        } else {
+            None
+        };
+
+        let mut offer_oids = Vec::new();
+        if let Some(surface_oid) = surface_oid {
+            let mediator = self.mediator.borrow();
+            if let Some(drag) = mediator.get_drag() {
+                for &data_device_oid in self.data_device_oids.iter() {
+                    // Drags internal to client do not provide data offer.
+                    let offer_oid = if drag.source_oid.is_some() {
+                        let offer_oid = wl::ObjectId::new(self.next_server_oid);
+                        self.next_server_oid += 1;
+
+                        send!(wl_data_device::data_offer(&self.socket, data_device_oid, offer_oid));
+                        for mime_type in drag.mime_types.iter() {
+                            send!(wl_data_offer::offer(&self.socket, offer_oid, mime_type));
+                        }
+                        send!(wl_data_offer::source_actions(&self.socket,
+                                                            offer_oid,
+                                                            drag.source_actions));
+                        offer_oids.push(offer_oid);
+                        offer_oid
+                    } else {
+                        wl::ObjectId::new(0)
+                    };
+
+                    let serial = self.socket.get_next_serial();
+                    send!(wl_data_device::enter(&self.socket,
+                                                data_device_oid,
+                                                serial,
+                                                surface_oid,
+                                                position.x as f32,
+                                                position.y as f32,
+                                                offer_oid));
+                }
+            }
+        }
+
+        self.drag_offer_oids = offer_oids.iter().cloned().collect();
+        offer_oids
+    }
+
+    /// Informs client that dragged data left its surface.
+    pub fn on_drag_leave(&mut self) {
+        for data_device_oid in self.data_device_oids.iter() {
+            send!(wl_data_device::leave(&self.socket, *data_device_oid));
+        }
+        self.drag_offer_oids.clear();
+    }
+
+    /// Informs client about motion of dragged data over its surface.
+    pub fn on_drag_motion(&self, position: Position, milliseconds: Milliseconds) {
+        for data_device_oid in self.data_device_oids.iter() {
+            send!(wl_data_device::motion(&self.socket,
+                                         *data_device_oid,
+                                         milliseconds.get_value() as u32,
+                                         position.x as f32,
+                                         position.y as f32));
+        }
+    }
+
+    /// Informs client that data was dropped on its surface. Data offers stay valid until client
+    /// finishes the transfer.
+    pub fn on_drop(&self) {
+        for data_device_oid in self.data_device_oids.iter() {
+            send!(wl_data_device::drop(&self.socket, *data_device_oid));
+            send!(wl_data_device::leave(&self.socket, *data_device_oid));
+        }
     }
 }
 
@@ -231,24 +551,183 @@ impl Proxy {
     fn relate_sid_with_shell_surface(&mut self, sid: SurfaceId, oid: ShellSurfaceOid) {
         relate_sid_with!(shell_surface_oid, self.sid_to_surface_info_dictionary, sid, oid);
     }
+}
+
+// -------------------------------------------------------------------------------------------------
 
-    /// Helper method for setting buffer information for surface.
-    fn relate_sid_with_buffer(&mut self, sid: SurfaceId, oid: wl::ObjectId) {
-        relate_sid_with!(buffer_oid, self.sid_to_surface_info_dictionary, sid, oid);
+impl Proxy {
+    /// Helper method for unsetting shell information for surface.
+    fn unrelate_sid_with_shell_surface(&mut self, sid: SurfaceId) {
+        unrelate_sid_with!(shell_surface_oid, self.sid_to_surface_info_dictionary, sid);
     }
+}
+
+// -------------------------------------------------------------------------------------------------
 
-    /// Helper method for setting frame callback ID information for surface.
-    fn relate_sid_with_frame(&mut self, sid: SurfaceId, oid: wl::ObjectId) {
-        relate_sid_with!(frame_oid, self.sid_to_surface_info_dictionary, sid, oid);
+// Helper methods for screenshots.
+impl Proxy {
+    /// Copies given image data to shared memory buffer of client.
+    fn fill_buffer(&mut self, buffer_oid: wl::ObjectId, buffer: &Buffer) -> Result<(), Illusion> {
+        if let Some(info) = self.get_buffer_info(buffer_oid) {
+            self.coordinator.fill_memory_view(info.mvid, buffer)
+        } else {
+            Err(Illusion::General(format!("Unknown buffer object ID: {}", buffer_oid)))
+        }
+    }
+
+    /// Copies screenshot to client's buffer and informs screencopy frame about the result.
+    fn finish_output_copy(&mut self,
+                          frame_oid: wl::ObjectId,
+                          buffer_oid: wl::ObjectId,
+                          with_damage: bool,
+                          screenshot: Option<Screenshot>) {
+        if let Some(mut screenshot) = screenshot {
+            // Renderer returns RGBA data while `XRGB8888` pixels are stored as BGRX.
+            screenshot.buffer.swap_red_and_blue();
+            match self.fill_buffer(buffer_oid, &screenshot.buffer) {
+                Ok(()) => {
+                    send!(zwlr_screencopy_frame_v1::flags(&self.socket, frame_oid, 0));
+                    if with_damage {
+                        for rect in screenshot.damage.get_rects() {
+                            send!(zwlr_screencopy_frame_v1::damage(&self.socket,
+                                                                   frame_oid,
+                                                                   rect.pos.x as u32,
+                                                                   rect.pos.y as u32,
+                                                                   rect.size.width as u32,
+                                                                   rect.size.height as u32));
+                        }
+                    }
+                    let time = screenshot.time;
+                    send!(zwlr_screencopy_frame_v1::ready(&self.socket,
+                                                          frame_oid,
+                                                          (time.seconds >> 32) as u32,
+                                                          time.seconds as u32,
+                                                          time.nanoseconds));
+                    return;
+                }
+                Err(err) => {
+                    log_warn1!("Screencopy: {:?}", err);
+                }
+            }
+        }
+        send!(zwlr_screencopy_frame_v1::failed(&self.socket, frame_oid));
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 
+// Helper methods for sending events related to shells.
 impl Proxy {
-    /// Helper method for unsetting shell information for surface.
-    fn unrelate_sid_with_shell_surface(&mut self, sid: SurfaceId) {
-        unrelate_sid_with!(shell_surface_oid, self.sid_to_surface_info_dictionary, sid);
+    /// Pings the client to check if it is responsive. If client did not respond to previous ping
+    /// it is reported as not responding.
+    fn ping(&mut self) {
+        if let Some(serial) = self.ping_serial {
+            log_warn2!("Client {} did not respond to ping {}", self.id, serial);
+        } else if let Some(&wm_base_oid) = self.xdg_wm_base_oids.iter().next() {
+            let serial = self.socket.get_next_serial();
+            send!(xdg_wm_base::ping(&self.socket, wm_base_oid, serial));
+            self.ping_serial = Some(serial);
+        }
+    }
+
+    /// Prepares list of toplevel states for configuration event. Values of states in `xdg_shell`
+    /// and its unstable predecessor are the same.
+    fn get_toplevel_states(&self,
+                           sid: SurfaceId,
+                           state_flags: surface_state::SurfaceState)
+                           -> Vec<u32> {
+        let mut states = Vec::with_capacity(2);
+        if state_flags.intersects(surface_state::MAXIMIZED) {
+            states.push(xdg_toplevel::state::MAXIMIZED);
+        }
+        if sid == self.coordinator.get_keyboard_focused_sid() {
+            states.push(xdg_toplevel::state::ACTIVATED);
+        }
+        states
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Helper methods for sending events related to selections.
+impl Proxy {
+    /// Introduces new selection offer with its mime types to client.
+    fn send_selection_offer(&self,
+                            kind: SelectionKind,
+                            device_oid: wl::ObjectId,
+                            offer_oid: wl::ObjectId,
+                            mime_types: &Vec<String>) {
+        match kind {
+            SelectionKind::Clipboard => {
+                send!(wl_data_device::data_offer(&self.socket, device_oid, offer_oid));
+                for mime_type in mime_types.iter() {
+                    send!(wl_data_offer::offer(&self.socket, offer_oid, mime_type));
+                }
+            }
+            SelectionKind::Primary => {
+                send!(zwp_primary_selection_device_v1::data_offer(&self.socket,
+                                                                  device_oid,
+                                                                  offer_oid));
+                for mime_type in mime_types.iter() {
+                    send!(zwp_primary_selection_offer_v1::offer(&self.socket,
+                                                                offer_oid,
+                                                                mime_type));
+                }
+            }
+        }
+    }
+
+    /// Announces given offer as current selection. Null offer means there is no selection.
+    fn send_selection(&self,
+                      kind: SelectionKind,
+                      device_oid: wl::ObjectId,
+                      offer_oid: wl::ObjectId) {
+        match kind {
+            SelectionKind::Clipboard => {
+                send!(wl_data_device::selection(&self.socket, device_oid, offer_oid));
+            }
+            SelectionKind::Primary => {
+                send!(zwp_primary_selection_device_v1::selection(&self.socket,
+                                                                 device_oid,
+                                                                 offer_oid));
+            }
+        }
+    }
+
+    /// Requests selection owner to write data in given mime type to given file descriptor.
+    fn send_source_request(&self,
+                           kind: SelectionKind,
+                           selection: &Selection,
+                           mime_type: &str,
+                           fd: i32) {
+        if let Some(socket) = self.mediator.borrow().get_socket(selection.cid) {
+            match kind {
+                SelectionKind::Clipboard => {
+                    send!(wl_data_source::send(&socket, selection.source_oid, mime_type, fd));
+                }
+                SelectionKind::Primary => {
+                    send!(zwp_primary_selection_source_v1::send(&socket,
+                                                                selection.source_oid,
+                                                                mime_type,
+                                                                fd));
+                }
+            }
+        }
+    }
+
+    /// Informs selection owner that its data source was replaced.
+    fn send_source_cancelled(&self, kind: SelectionKind, selection: &Selection) {
+        if let Some(socket) = self.mediator.borrow().get_socket(selection.cid) {
+            match kind {
+                SelectionKind::Clipboard => {
+                    send!(wl_data_source::cancelled(&socket, selection.source_oid));
+                }
+                SelectionKind::Primary => {
+                    send!(zwp_primary_selection_source_v1::cancelled(&socket,
+                                                                     selection.source_oid));
+                }
+            }
+        }
     }
 }
 
@@ -262,7 +741,8 @@ impl Proxy {
         for info in self.sid_to_surface_info_dictionary.values() {
             if let Some(shell_surface_oid) = info.shell_surface_oid {
                 match shell_surface_oid {
-                    ShellSurfaceOid::ZxdgToplevelV6(shell_surface_oid, _) => {
+                    ShellSurfaceOid::ZxdgToplevelV6(shell_surface_oid, _) |
+                    ShellSurfaceOid::XdgToplevel(shell_surface_oid, _) => {
                         if shell_surface_oid == parent_shell_surface_oid {
                             return info.surface_oid;
                         }
@@ -273,6 +753,25 @@ impl Proxy {
         }
         None
     }
+
+    pub fn get_surface_oid_for_toplevel(&self,
+                                        toplevel_oid: wl::ObjectId)
+                                        -> Option<wl::ObjectId> {
+        for info in self.sid_to_surface_info_dictionary.values() {
+            if let Some(shell_surface_oid) = info.shell_surface_oid {
+                match shell_surface_oid {
+                    ShellSurfaceOid::ZxdgToplevelV6(_, shell_toplevel_oid) |
+                    ShellSurfaceOid::XdgToplevel(_, shell_toplevel_oid) => {
+                        if shell_toplevel_oid == toplevel_oid {
+                            return info.surface_oid;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -300,11 +799,12 @@ impl Facade for Proxy {
                           offset: usize,
                           width: usize,
                           height: usize,
-                          stride: usize)
+                          stride: usize,
+                          format: u32)
                           -> Option<MemoryViewId> {
         let result = self.coordinator.create_memory_view(mpid, offset, width, height, stride);
         if let Some(mvid) = result {
-            let info = BufferInfo::new(mpid, mvid);
+            let info = BufferInfo::new(mpid, mvid, Size::new(width, height), stride, format);
             self.buffer_oid_to_buffer_info_dictionary.insert(buffer_oid, info);
         }
         result
@@ -314,7 +814,7 @@ impl Facade for Proxy {
         self.coordinator.destroy_memory_view(mvid);
     }
 
-    fn define_region(&mut self, region_oid: wl::ObjectId, region: Area) {
+    fn define_region(&mut self, region_oid: wl::ObjectId, region: Region) {
         self.regions.insert(region_oid, region);
     }
 
@@ -338,6 +838,14 @@ impl Facade for Proxy {
         self.keyboard_oids.remove(&keyboard_oid);
     }
 
+    fn add_touch_oid(&mut self, touch_oid: wl::ObjectId) {
+        self.touch_oids.insert(touch_oid);
+    }
+
+    fn remove_touch_oid(&mut self, touch_oid: wl::ObjectId) {
+        self.touch_oids.remove(&touch_oid);
+    }
+
     fn set_positioner(&mut self, oid: wl::ObjectId, positioner: PositionerInfo) {
         self.positioners.insert(oid, positioner);
     }
@@ -354,12 +862,205 @@ impl Facade for Proxy {
         self.positioners.remove(&oid);
     }
 
+    fn add_xdg_wm_base_oid(&mut self, wm_base_oid: wl::ObjectId) {
+        self.xdg_wm_base_oids.insert(wm_base_oid);
+    }
+
+    fn remove_xdg_wm_base_oid(&mut self, wm_base_oid: wl::ObjectId) {
+        self.xdg_wm_base_oids.remove(&wm_base_oid);
+        if self.xdg_wm_base_oids.is_empty() {
+            self.ping_serial = None;
+        }
+    }
+
+    fn pong(&mut self, serial: u32) {
+        if self.ping_serial == Some(serial) {
+            self.ping_serial = None;
+        }
+    }
+
+    fn create_data_source(&mut self, source_oid: wl::ObjectId) {
+        self.data_sources.insert(source_oid, DataSourceInfo::new());
+    }
+
+    fn add_data_source_mime_type(&mut self, source_oid: wl::ObjectId, mime_type: String) {
+        if let Some(info) = self.data_sources.get_mut(&source_oid) {
+            info.mime_types.push(mime_type);
+        }
+    }
+
+    fn set_data_source_actions(&mut self, source_oid: wl::ObjectId, dnd_actions: u32) {
+        if let Some(info) = self.data_sources.get_mut(&source_oid) {
+            info.dnd_actions = Some(dnd_actions);
+        }
+    }
+
+    fn destroy_data_source(&mut self, source_oid: wl::ObjectId) {
+        self.data_sources.remove(&source_oid);
+        self.mediator.borrow_mut().remove_selection(self.id, Some(source_oid));
+        self.mediator.borrow_mut().cancel_drag(self.id, Some(source_oid));
+    }
+
+    fn add_data_device_oid(&mut self, data_device_oid: wl::ObjectId) {
+        self.data_device_oids.insert(data_device_oid);
+    }
+
+    fn remove_data_device_oid(&mut self, data_device_oid: wl::ObjectId) {
+        self.data_device_oids.remove(&data_device_oid);
+    }
+
+    fn add_primary_selection_device_oid(&mut self, device_oid: wl::ObjectId) {
+        self.primary_selection_device_oids.insert(device_oid);
+    }
+
+    fn remove_primary_selection_device_oid(&mut self, device_oid: wl::ObjectId) {
+        self.primary_selection_device_oids.remove(&device_oid);
+    }
+
+    fn set_selection(&mut self, kind: SelectionKind, source_oid: wl::ObjectId) {
+        let selection = if source_oid.is_null() {
+            None
+        } else if let Some(info) = self.data_sources.get(&source_oid) {
+            Some(Selection {
+                cid: self.id,
+                source_oid: source_oid,
+                mime_types: info.mime_types.clone(),
+            })
+        } else {
+            log_warn3!("Unknown data source: {}", source_oid);
+            return;
+        };
+
+        let previous = self.mediator.borrow_mut().set_selection(kind, selection);
+        if let Some(previous) = previous {
+            self.send_source_cancelled(kind, &previous);
+        }
+    }
+
+    fn start_drag(&mut self, source_oid: wl::ObjectId, icon_surface_oid: wl::ObjectId) {
+        let drag = if source_oid.is_null() {
+            Drag::new(self.id, None, Vec::new(), dnd_action::NONE)
+        } else if let Some(info) = self.data_sources.get(&source_oid) {
+            // Clients not supporting actions expect data to be copied.
+            let actions = info.dnd_actions.unwrap_or(dnd_action::COPY);
+            Drag::new(self.id, Some(source_oid), info.mime_types.clone(), actions)
+        } else {
+            log_warn3!("Unknown data source: {}", source_oid);
+            return;
+        };
+
+        if self.mediator.borrow_mut().start_drag(drag) {
+            if let Some(&icon_sid) = self.surface_oid_to_sid_dictionary.get(&icon_surface_oid) {
+                self.coordinator.set_surface_as_drag_icon(icon_sid);
+            }
+        } else {
+            log_wayl3!("Drag requested while other drag is in progress");
+            if !source_oid.is_null() {
+                send!(wl_data_source::cancelled(&self.socket, source_oid));
+            }
+        }
+    }
+
+    fn accept_data_offer(&mut self, offer_oid: wl::ObjectId, mime_type: String) {
+        if self.drag_offer_oids.contains(&offer_oid) {
+            let mut mediator = self.mediator.borrow_mut();
+            if let Some(drag) = mediator.get_drag_mut() {
+                drag.accepted = !mime_type.is_empty();
+            }
+            if let Some((socket, source_oid)) = mediator.get_drag_source() {
+                send!(wl_data_source::target(&socket, source_oid, &mime_type));
+            }
+        }
+    }
+
+    fn set_data_offer_actions(&mut self,
+                              offer_oid: wl::ObjectId,
+                              dnd_actions: u32,
+                              preferred_action: u32) {
+        if self.drag_offer_oids.contains(&offer_oid) {
+            let mut mediator = self.mediator.borrow_mut();
+            let action = if let Some(drag) = mediator.get_drag_mut() {
+                drag.negotiate_action(dnd_actions, preferred_action)
+            } else {
+                return;
+            };
+
+            send!(wl_data_offer::action(&self.socket, offer_oid, action));
+            if let Some((socket, source_oid)) = mediator.get_drag_source() {
+                send!(wl_data_source::action(&socket, source_oid, action));
+            }
+        }
+    }
+
+    fn finish_data_offer(&mut self, offer_oid: wl::ObjectId) {
+        if self.drag_offer_oids.contains(&offer_oid) {
+            let mut mediator = self.mediator.borrow_mut();
+            if mediator.get_drag_state() == Some(DragState::Dropped) {
+                if let Some((socket, source_oid)) = mediator.get_drag_source() {
+                    send!(wl_data_source::dnd_finished(&socket, source_oid));
+                }
+                mediator.end_drag();
+            }
+            self.drag_offer_oids.clear();
+        }
+    }
+
+    fn receive_data_offer(&mut self, offer_oid: wl::ObjectId, mime_type: String, fd: i32) {
+        if let Some(&kind) = self.selection_offer_oids.get(&offer_oid) {
+            if let Some(selection) = self.mediator.borrow().get_selection(kind) {
+                self.send_source_request(kind, &selection, &mime_type, fd);
+            }
+        } else if self.drag_offer_oids.contains(&offer_oid) {
+            if let Some((socket, source_oid)) = self.mediator.borrow().get_drag_source() {
+                send!(wl_data_source::send(&socket, source_oid, &mime_type, fd));
+            }
+        } else {
+            log_wayl3!("Received request for outdated data offer {}", offer_oid);
+        }
+
+        // File descriptor was passed to data source owner and is not needed anymore.
+        if let Err(err) = nix::unistd::close(fd) {
+            log_warn3!("Failed to close data offer file descriptor: {:?}", err);
+        }
+    }
+
+    fn destroy_data_offer(&mut self, offer_oid: wl::ObjectId) {
+        self.selection_offer_oids.remove(&offer_oid);
+        if self.drag_offer_oids.remove(&offer_oid) && self.drag_offer_oids.is_empty() {
+            // Destination destroyed offer of dropped data without finishing the transfer. Clients
+            // not supporting actions never finish so for them the transfer is assumed successful.
+            let mut mediator = self.mediator.borrow_mut();
+            if mediator.get_drag_state() == Some(DragState::Dropped) {
+                let source = mediator.get_drag_source();
+                if let (Some(drag), Some((socket, source_oid))) = (mediator.end_drag(), source) {
+                    if drag.negotiated {
+                        send!(wl_data_source::cancelled(&socket, source_oid));
+                    } else {
+                        send!(wl_data_source::dnd_finished(&socket, source_oid));
+                    }
+                }
+            }
+        }
+    }
+
     fn set_input_region(&self, sid: SurfaceId, region_oid: wl::ObjectId) {
         if let Some(region) = self.regions.get(&region_oid) {
-            self.coordinator.set_surface_offset(sid, region.pos);
-            self.coordinator.set_surface_requested_size(sid, region.size);
+            if !region.is_empty() {
+                let extents = region.get_extents();
+                self.coordinator.set_surface_offset(sid, extents.pos);
+                self.coordinator.set_surface_requested_size(sid, extents.size);
+            }
+            self.coordinator.set_surface_input_region(sid, Some(region.clone()));
+        } else {
+            self.coordinator.set_surface_input_region(sid, None);
+        }
+    }
+
+    fn set_opaque_region(&self, sid: SurfaceId, region_oid: wl::ObjectId) {
+        if let Some(region) = self.regions.get(&region_oid) {
+            self.coordinator.set_surface_opaque_region(sid, region.clone());
         } else {
-            // TODO: Implement reseting input region.
+            self.coordinator.set_surface_opaque_region(sid, Region::new());
         }
     }
 
@@ -370,30 +1071,104 @@ impl Facade for Proxy {
         sid
     }
 
-    fn destroy_surface(&self, sid: SurfaceId) {
+    fn destroy_surface(&mut self, sid: SurfaceId) {
+        if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
+            for feedback_oid in info.feedback_oids.drain(..) {
+                discard_presentation_feedback(&self.socket, feedback_oid);
+            }
+            for feedback_oid in info.pending_feedback_oids.drain(..) {
+                discard_presentation_feedback(&self.socket, feedback_oid);
+            }
+            for state in info.committed_states.drain(..) {
+                for feedback_oid in state.feedback_oids {
+                    discard_presentation_feedback(&self.socket, feedback_oid);
+                }
+            }
+        }
         self.coordinator.destroy_surface(sid)
     }
 
     fn attach(&mut self, buffer_oid: wl::ObjectId, sid: SurfaceId, x: i32, y: i32) {
         if buffer_oid.is_null() {
             // Client wants to unmap this surface
-            // TODO: This should be done on commit
-            self.coordinator.unrelate_surface(sid);
-            self.coordinator.detach_surface(sid)
+            self.coordinator.detach_surface(sid);
         } else if let Some(&info) = self.buffer_oid_to_buffer_info_dictionary.get(&buffer_oid) {
-            self.relate_sid_with_buffer(sid, buffer_oid);
             self.coordinator.attach_surface(info.mvid, sid);
         } else {
             log_error!("Unknown buffer object ID: {}", buffer_oid);
+            return;
+        }
+
+        if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
+            info.pending_buffer_oid = Some(buffer_oid);
         }
     }
 
-    fn commit(&self, sid: SurfaceId) {
+    fn commit(&mut self, sid: SurfaceId) {
+        if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
+            if info.pending_layer != info.layer {
+                if let (Some(layer), Some(ShellSurfaceOid::Layer(layer_surface_oid))) =
+                    (info.pending_layer, info.shell_surface_oid) {
+                    if !layer.is_size_valid() {
+                        let msg = "Surface with zero size must be anchored to opposite edges";
+                        log_warn2!("Protocol error: {}", msg);
+                        send!(wl_display::error(&self.socket,
+                                                wl::DISPLAY_ID,
+                                                layer_surface_oid,
+                                                zwlr_layer_surface_v1::error::INVALID_SIZE,
+                                                msg));
+                        return;
+                    }
+                }
+                info.layer = info.pending_layer;
+                self.coordinator.set_surface_as_layer(sid, info.layer);
+            }
+
+            // Buffer and callbacks are handled when coordinator reports the state became current
+            info.commit_count = info.commit_count.wrapping_add(1);
+            info.committed_states.push(CommittedState {
+                serial: info.commit_count,
+                buffer_oid: info.pending_buffer_oid.take(),
+                frame_oids: info.pending_frame_oids.drain(..).collect(),
+                feedback_oids: info.pending_feedback_oids.drain(..).collect(),
+            });
+        }
         self.coordinator.commit_surface(sid);
     }
 
     fn set_frame(&mut self, sid: SurfaceId, frame_oid: wl::ObjectId) {
-        self.relate_sid_with_frame(sid, frame_oid);
+        if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
+            info.pending_frame_oids.push(frame_oid);
+        }
+    }
+
+    fn set_presentation_feedback(&mut self,
+                                 surface_oid: wl::ObjectId,
+                                 feedback_oid: wl::ObjectId) {
+        if let Some(sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(sid) {
+                info.pending_feedback_oids.push(feedback_oid);
+                return;
+            }
+        }
+        log_warn2!("Requested presentation feedback for unknown surface: {}", surface_oid);
+        discard_presentation_feedback(&self.socket, feedback_oid);
+    }
+
+    fn damage(&self, sid: SurfaceId, area: Area) {
+        self.coordinator.damage_surface(sid, area);
+    }
+
+    fn damage_buffer(&self, sid: SurfaceId, area: Area) {
+        self.coordinator.damage_surface_buffer(sid, area);
+    }
+
+    fn set_buffer_scale(&self, sid: SurfaceId, scale: i32) {
+        self.coordinator.set_surface_buffer_scale(sid, scale);
+    }
+
+    fn set_buffer_transform(&self, sid: SurfaceId, transform: i32) {
+        self.coordinator.set_surface_buffer_transform(sid, transform);
     }
 
     fn show(&mut self,
@@ -417,12 +1192,72 @@ impl Facade for Proxy {
         }
     }
 
-    fn set_offset(&self, sid: SurfaceId, offset: Vector) {
-        self.coordinator.set_surface_offset(sid, offset);
+    fn set_as_layer_surface(&mut self,
+                            surface_oid: wl::ObjectId,
+                            layer_surface_oid: wl::ObjectId,
+                            info: LayerSurfaceInfo) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            self.relate_sid_with_shell_surface(sid, ShellSurfaceOid::Layer(layer_surface_oid));
+            self.set_layer_surface_state(surface_oid, info);
+        } else {
+            log_error!("Unknown surface object ID: {}", surface_oid);
+        }
+    }
+
+    fn set_layer_surface_state(&mut self, surface_oid: wl::ObjectId, info: LayerSurfaceInfo) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            if let Some(surface_info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
+                surface_info.pending_layer = Some(info);
+            }
+        }
+    }
+
+    fn unset_as_layer_surface(&mut self, surface_oid: wl::ObjectId) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
+                info.pending_layer = None;
+                if info.layer.take().is_some() {
+                    self.coordinator.set_surface_as_layer(sid, None);
+                }
+            }
+            self.unrelate_sid_with_shell_surface(sid);
+        }
+    }
+
+    fn set_offset(&self, surface_oid: wl::ObjectId, offset: Vector) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            self.coordinator.set_surface_offset(sid, offset);
+        }
+    }
+
+    fn set_requested_size(&self, surface_oid: wl::ObjectId, size: Size) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            self.coordinator.set_surface_requested_size(sid, size);
+        }
+    }
+
+    fn set_min_size(&self, surface_oid: wl::ObjectId, size: Size) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            self.coordinator.set_surface_min_size(sid, size);
+        }
     }
 
-    fn set_requested_size(&self, sid: SurfaceId, size: Size) {
-        self.coordinator.set_surface_requested_size(sid, size);
+    fn set_max_size(&self, surface_oid: wl::ObjectId, size: Size) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            self.coordinator.set_surface_max_size(sid, size);
+        }
+    }
+
+    fn ack_configure(&mut self, surface_oid: wl::ObjectId, serial: u32) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
+                // Only acknowledgement of the most recent configuration concludes it.
+                if info.configure_serial == Some(serial) {
+                    info.configure_serial = None;
+                    self.coordinator.acknowledge_surface_configuration(sid);
+                }
+            }
+        }
     }
 
     fn set_relative_position(&self, surface_oid: wl::ObjectId, x: isize, y: isize) {
@@ -447,6 +1282,47 @@ impl Facade for Proxy {
         }
     }
 
+    fn set_synchronized(&self, surface_oid: wl::ObjectId, synchronized: bool) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            self.coordinator.set_surface_synchronized(sid, synchronized);
+        }
+    }
+
+    fn place_above(&self, surface_oid: wl::ObjectId, sibling_surface_oid: wl::ObjectId) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            if let Some(&sibling_sid) = self.surface_oid_to_sid_dictionary
+                .get(&sibling_surface_oid) {
+                self.coordinator.place_surface_above(sid, sibling_sid);
+            }
+        }
+    }
+
+    fn place_below(&self, surface_oid: wl::ObjectId, sibling_surface_oid: wl::ObjectId) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            if let Some(&sibling_sid) = self.surface_oid_to_sid_dictionary
+                .get(&sibling_surface_oid) {
+                self.coordinator.place_surface_below(sid, sibling_sid);
+            }
+        }
+    }
+
+    fn set_transient_for(&self, surface_oid: wl::ObjectId, parent_surface_oid: wl::ObjectId) {
+        if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
+            let parent_sid = if parent_surface_oid.is_null() {
+                SurfaceId::invalid()
+            } else {
+                match self.surface_oid_to_sid_dictionary.get(&parent_surface_oid) {
+                    Some(&parent_sid) => parent_sid,
+                    None => {
+                        log_error!("Unknown parent surface object ID: {}", parent_surface_oid);
+                        return;
+                    }
+                }
+            };
+            self.coordinator.set_surface_transient_for(sid, parent_sid);
+        }
+    }
+
     fn set_as_cursor(&self, surface_oid: wl::ObjectId, hotspot_x: isize, hotspot_y: isize) {
         if let Some(&sid) = self.surface_oid_to_sid_dictionary.get(&surface_oid) {
             self.coordinator.set_surface_offset(sid, Position { x: hotspot_x, y: hotspot_y });
@@ -462,29 +1338,52 @@ impl Facade for Proxy {
                        screenshooter_oid: wl::ObjectId,
                        output_oid: wl::ObjectId,
                        buffer_oid: wl::ObjectId) {
-        // Destroy memory pool to be used to transfer screenshot.
-        if let Some(&info) = self.buffer_oid_to_buffer_info_dictionary.get(&buffer_oid) {
-            self.screenshot_memory = self.coordinator.destroy_memory_pool(info.mpid);
+        if let Some(output_id) = self.get_output_id(output_oid) {
+            // Request to take screenshot asynchronously. After data is ready method
+            // `on_screenshot_done` will be called.
+            let id = self.mediator.borrow_mut().register_screenshooter(self.id);
+            let target = ScreenshotTarget::Screenshooter {
+                screenshooter_oid: screenshooter_oid,
+                buffer_oid: buffer_oid,
+            };
+            self.screenshots.insert(id, target);
+            self.coordinator.take_screenshot(ScreenshotRequest::new(id, output_id));
+        } else {
+            log_warn1!("No matching output for screenshot");
         }
+    }
 
-        // If the memory was not in use the mapped memory will be returned.
-        if self.screenshot_memory.is_some() {
-            if let Some(output_id) = self.output_oid_to_id.get(&output_oid) {
-                // Request to take screenshot asynchronously. After data is ready method
-                // `on_screenshot_done` will be called.
-                self.coordinator.take_screenshot(*output_id);
-
-                // Save ID of client requesting screenshot for later use.
-                self.mediator.borrow_mut().register_screenshoter(Some(self.id));
+    fn copy_output(&mut self,
+                   frame_oid: wl::ObjectId,
+                   buffer_oid: wl::ObjectId,
+                   output_id: i32,
+                   area: Area,
+                   overlay_cursor: bool,
+                   with_damage: bool) {
+        let id = self.mediator.borrow_mut().register_screenshooter(self.id);
+        let target = ScreenshotTarget::ScreencopyFrame {
+            frame_oid: frame_oid,
+            buffer_oid: buffer_oid,
+            with_damage: with_damage,
+        };
+        self.screenshots.insert(id, target);
+        self.coordinator.take_screenshot(ScreenshotRequest {
+            id: id,
+            output_id: output_id,
+            area: Some(area),
+            overlay_cursor: overlay_cursor,
+            wait_for_damage: with_damage,
+        });
+    }
 
-                // Save screenshooter object ID for later use.
-                self.screenshooter_oid = Some(screenshooter_oid);
+    fn cancel_output_copy(&mut self, frame_oid: wl::ObjectId) {
+        self.screenshots.retain(|_, target| {
+            if let ScreenshotTarget::ScreencopyFrame { frame_oid: oid, .. } = *target {
+                oid != frame_oid
             } else {
-                log_warn1!("No matching output for screenshot");
+                true
             }
-        } else {
-            log_warn1!("Could not set buffer up for screenshot");
-        }
+        });
     }
 }
 
@@ -493,7 +1392,28 @@ impl Facade for Proxy {
 #[allow(unused_variables)]
 impl Gateway for Proxy {
     fn on_display_created(&mut self, output_info: OutputInfo) {
-        self.register_global(protocol::output::get_global(output_info));
+        // Toplevels are bounded by the biggest output.
+        let size = output_info.area.size;
+        self.configure_bounds.width = max(self.configure_bounds.width, size.width);
+        self.configure_bounds.height = max(self.configure_bounds.height, size.height);
+        self.output_infos.insert(output_info.id, output_info.clone());
+        let name = self.register_global(protocol::output::get_global(output_info.clone()));
+        self.output_globals.insert(output_info.id, name);
+    }
+
+    fn on_display_destroyed(&mut self, id: i32) {
+        if let Some(name) = self.output_globals.remove(&id) {
+            self.unregister_global(name);
+        }
+        self.output_infos.remove(&id);
+
+        // Toplevels are bounded by the biggest of remaining outputs.
+        self.configure_bounds = Size::default();
+        for info in self.output_infos.values() {
+            let size = info.area.size;
+            self.configure_bounds.width = max(self.configure_bounds.width, size.width);
+            self.configure_bounds.height = max(self.configure_bounds.height, size.height);
+        }
     }
 
     fn on_keyboard_input(&mut self, key: Key, mods: Option<KeyMods>) {
@@ -519,13 +1439,52 @@ impl Gateway for Proxy {
         }
     }
 
-    fn on_surface_frame(&mut self, sid: SurfaceId, milliseconds: Milliseconds) {
+    fn on_keymap_changed(&mut self, _config: KeyboardConfig) {
+        let keymap = self.settings.get_keymap();
+        for &keyboard_oid in self.keyboard_oids.iter() {
+            send!(wl_keyboard::keymap(&self.socket,
+                                      keyboard_oid,
+                                      keymap.format,
+                                      keymap.fd,
+                                      keymap.size as u32));
+        }
+    }
+
+    fn on_surface_frame(&mut self, sid: SurfaceId, presentation: Presentation) {
         if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
-            if let Some(frame_oid) = info.frame_oid {
-                send!(wl_callback::done(&self.socket, frame_oid, milliseconds.get_value() as u32));
+            let milliseconds = presentation.get_milliseconds().get_value() as u32;
+            for frame_oid in info.frame_oids.drain(..) {
+                send!(wl_callback::done(&self.socket, frame_oid, milliseconds));
                 send!(wl_display::delete_id(&self.socket, wl::DISPLAY_ID, frame_oid.get_value()));
             }
-            info.frame_oid = None;
+
+            let flags = if presentation.vsync {
+                wp_presentation_feedback::kind::VSYNC | wp_presentation_feedback::kind::HW_CLOCK |
+                wp_presentation_feedback::kind::HW_COMPLETION
+            } else {
+                0
+            };
+
+            for feedback_oid in info.feedback_oids.drain(..) {
+                for (&output_oid, &output_id) in self.output_oid_to_id.iter() {
+                    if output_id == presentation.output_id {
+                        send!(wp_presentation_feedback::sync_output(&self.socket,
+                                                                    feedback_oid,
+                                                                    output_oid));
+                    }
+                }
+                send!(wp_presentation_feedback::presented(&self.socket,
+                                                          feedback_oid,
+                                                          (presentation.seconds >> 32) as u32,
+                                                          presentation.seconds as u32,
+                                                          presentation.nanoseconds,
+                                                          presentation.refresh,
+                                                          (presentation.sequence >> 32) as u32,
+                                                          presentation.sequence as u32,
+                                                          flags));
+                let id = feedback_oid.get_value();
+                send!(wl_display::delete_id(&self.socket, wl::DISPLAY_ID, id));
+            }
 
             if let Some(buffer_oid) = info.buffer_oid {
                 send!(wl_buffer::release(&self.socket, buffer_oid));
@@ -534,7 +1493,10 @@ impl Gateway for Proxy {
         }
     }
 
-    fn on_pointer_focus_changed(&self, old_sid: SurfaceId, new_sid: SurfaceId, position: Position) {
+    fn on_pointer_focus_changed(&mut self,
+                                old_sid: SurfaceId,
+                                new_sid: SurfaceId,
+                                position: Position) {
         if old_sid != SurfaceId::invalid() {
             if let Some(surface_info) = self.sid_to_surface_info_dictionary.get(&old_sid) {
                 if let Some(surface_oid) = surface_info.surface_oid {
@@ -563,7 +1525,7 @@ impl Gateway for Proxy {
         }
     }
 
-    fn on_pointer_relative_motion(&self,
+    fn on_pointer_relative_motion(&mut self,
                                   sid: SurfaceId,
                                   position: Position,
                                   milliseconds: Milliseconds) {
@@ -576,7 +1538,7 @@ impl Gateway for Proxy {
         }
     }
 
-    fn on_pointer_button(&self, btn: Button) {
+    fn on_pointer_button(&mut self, btn: Button) {
         let serial = self.socket.get_next_serial();
         let state = if btn.value == 0 {
             wl_pointer::button_state::RELEASED
@@ -598,51 +1560,60 @@ impl Gateway for Proxy {
         axis.discrete.y = -1 * axis.discrete.y;
         axis.continuous.y = -1.0 * axis.continuous.y;
 
-        for pointer_oid in self.pointer_oids.iter() {
-            // vertical scroll
-            let axis_type = wl_pointer::axis::VERTICAL_SCROLL;
+        let source = match axis.source {
+            AxisSource::Wheel => wl_pointer::axis_source::WHEEL,
+            AxisSource::Finger => wl_pointer::axis_source::FINGER,
+        };
 
-            if axis.discrete.y != 0 {
-                send!(wl_pointer::axis_discrete(&self.socket,
+        for pointer_oid in self.pointer_oids.iter() {
+            send!(wl_pointer::axis_source(&self.socket, *pointer_oid, source));
+
+            if axis.is_stop() {
+                // end of scrolling
+                for axis_type in [wl_pointer::axis::VERTICAL_SCROLL,
+                                  wl_pointer::axis::HORIZONTAL_SCROLL]
+                    .iter() {
+                    send!(wl_pointer::axis_stop(&self.socket,
                                                 *pointer_oid,
-                                                axis_type,
-                                                axis.discrete.y as i32));
-            }
-
-            if axis.continuous.y != 0.0 {
-                send!(wl_pointer::axis(&self.socket,
-                                       *pointer_oid,
-                                       axis.time.get_value() as u32,
-                                       axis_type,
-                                       axis.continuous.y));
+                                                axis.time.get_value() as u32,
+                                                *axis_type));
+                }
             } else {
-                send!(wl_pointer::axis_stop(&self.socket,
-                                            *pointer_oid,
-                                            axis.time.get_value() as u32,
-                                            axis_type));
-            }
+                // vertical scroll
+                let axis_type = wl_pointer::axis::VERTICAL_SCROLL;
+
+                if axis.discrete.y != 0 {
+                    send!(wl_pointer::axis_discrete(&self.socket,
+                                                    *pointer_oid,
+                                                    axis_type,
+                                                    axis.discrete.y as i32));
+                }
 
-            // horizontal scroll
-            let axis_type = wl_pointer::axis::HORIZONTAL_SCROLL;
+                if axis.continuous.y != 0.0 {
+                    send!(wl_pointer::axis(&self.socket,
+                                           *pointer_oid,
+                                           axis.time.get_value() as u32,
+                                           axis_type,
+                                           axis.continuous.y));
+                }
 
-            if axis.discrete.x != 0 {
-                send!(wl_pointer::axis_discrete(&self.socket,
-                                                *pointer_oid,
-                                                axis_type,
-                                                axis.discrete.x as i32));
-            }
+                // horizontal scroll
+                let axis_type = wl_pointer::axis::HORIZONTAL_SCROLL;
 
-            if axis.continuous.x != 0.0 {
-                send!(wl_pointer::axis(&self.socket,
-                                       *pointer_oid,
-                                       axis.time.get_value() as u32,
-                                       axis_type,
-                                       axis.continuous.x));
-            } else {
-                send!(wl_pointer::axis_stop(&self.socket,
-                                            *pointer_oid,
-                                            axis.time.get_value() as u32,
-                                            axis_type));
+                if axis.discrete.x != 0 {
+                    send!(wl_pointer::axis_discrete(&self.socket,
+                                                    *pointer_oid,
+                                                    axis_type,
+                                                    axis.discrete.x as i32));
+                }
+
+                if axis.continuous.x != 0.0 {
+                    send!(wl_pointer::axis(&self.socket,
+                                           *pointer_oid,
+                                           axis.time.get_value() as u32,
+                                           axis_type,
+                                           axis.continuous.x));
+                }
             }
 
             // send frame
@@ -652,53 +1623,156 @@ impl Gateway for Proxy {
 
     fn on_keyboard_focus_changed(&mut self, old_sid: SurfaceId, new_sid: SurfaceId) {
         if old_sid != SurfaceId::invalid() {
-            if let Some(surface_info) = self.sid_to_surface_info_dictionary.get(&old_sid) {
-                if let Some(surface_oid) = surface_info.surface_oid {
-                    for keyboard_oid in self.keyboard_oids.iter() {
-                        let serial = self.socket.get_next_serial();
-                        send!(wl_keyboard::leave(&self.socket, *keyboard_oid, serial, surface_oid));
+            let surface_oid = if let Some(surface_info) = self.sid_to_surface_info_dictionary
+                .get(&old_sid) {
+                surface_info.surface_oid
+            } else {
+                None
+            };
 
-                        if let Some(window_info) = self.coordinator.get_surface(old_sid) {
-                            self.on_surface_reconfigured(old_sid,
-                                                         window_info.desired_size,
-                                                         window_info.state_flags);
-                        }
-                    }
+            if let Some(surface_oid) = surface_oid {
+                for keyboard_oid in self.keyboard_oids.iter() {
+                    let serial = self.socket.get_next_serial();
+                    send!(wl_keyboard::leave(&self.socket, *keyboard_oid, serial, surface_oid));
+                }
+
+                if let Some(window_info) = self.coordinator.get_surface(old_sid) {
+                    self.on_surface_reconfigured(old_sid,
+                                                 window_info.desired_size,
+                                                 window_info.state_flags);
                 }
             }
         }
 
         if new_sid != SurfaceId::invalid() {
-            if let Some(surface_info) = self.sid_to_surface_info_dictionary.get(&new_sid) {
-                if let Some(surface_oid) = surface_info.surface_oid {
-                    for keyboard_oid in self.keyboard_oids.iter() {
-                        let serial = self.socket.get_next_serial();
+            let surface_oid = if let Some(surface_info) = self.sid_to_surface_info_dictionary
+                .get(&new_sid) {
+                surface_info.surface_oid
+            } else {
+                None
+            };
+
+            if let Some(surface_oid) = surface_oid {
+                for keyboard_oid in self.keyboard_oids.iter() {
+                    let serial = self.socket.get_next_serial();
+
+                    // TODO: Pass correct keys on keyboard enter.
+                    let keys: [u32; 0] = [0; 0];
+
+                    send!(wl_keyboard::enter(&self.socket,
+                                             *keyboard_oid,
+                                             serial,
+                                             surface_oid,
+                                             &keys[..]));
+                }
 
-                        // TODO: Pass correct keys on keyboard enter.
-                        let keys: [u32; 0] = [0; 0];
+                // Focus change is a good occasion to check if client is still responsive.
+                self.ping();
 
-                        send!(wl_keyboard::enter(&self.socket,
-                                                 *keyboard_oid,
-                                                 serial,
-                                                 surface_oid,
-                                                 &keys[..]));
+                if let Some(window_info) = self.coordinator.get_surface(new_sid) {
+                    self.on_surface_reconfigured(new_sid,
+                                                 window_info.desired_size,
+                                                 window_info.state_flags);
+                }
+            }
+        }
+    }
 
-                        if let Some(window_info) = self.coordinator.get_surface(new_sid) {
-                            self.on_surface_reconfigured(new_sid,
-                                                         window_info.desired_size,
-                                                         window_info.state_flags);
+    fn on_touch_down(&mut self,
+                     sid: SurfaceId,
+                     id: i32,
+                     position: Position,
+                     milliseconds: Milliseconds) {
+        let surface_oid = if let Some(surface_info) = self.sid_to_surface_info_dictionary
+            .get(&sid) {
+            surface_info.surface_oid
+        } else {
+            None
+        };
+
+        if let Some(surface_oid) = surface_oid {
+            let serial = self.socket.get_next_serial();
+            for touch_oid in self.touch_oids.iter() {
+                send!(wl_touch::down(&self.socket,
+                                     *touch_oid,
+                                     serial,
+                                     milliseconds.get_value() as u32,
+                                     surface_oid,
+                                     id,
+                                     position.x as f32,
+                                     position.y as f32));
+            }
+        }
+    }
+
+    fn on_touch_motion(&mut self,
+                       _sid: SurfaceId,
+                       id: i32,
+                       position: Position,
+                       milliseconds: Milliseconds) {
+        for touch_oid in self.touch_oids.iter() {
+            send!(wl_touch::motion(&self.socket,
+                                   *touch_oid,
+                                   milliseconds.get_value() as u32,
+                                   id,
+                                   position.x as f32,
+                                   position.y as f32));
+        }
+    }
+
+    fn on_touch_up(&mut self, _sid: SurfaceId, id: i32, milliseconds: Milliseconds) {
+        let serial = self.socket.get_next_serial();
+        for touch_oid in self.touch_oids.iter() {
+            send!(wl_touch::up(&self.socket,
+                               *touch_oid,
+                               serial,
+                               milliseconds.get_value() as u32,
+                               id));
+        }
+    }
+
+    fn on_touch_frame(&mut self, _sid: SurfaceId) {
+        for touch_oid in self.touch_oids.iter() {
+            send!(wl_touch::frame(&self.socket, *touch_oid));
+        }
+    }
+
+    fn on_surface_state_applied(&mut self, sid: SurfaceId, serial: u32) {
+        if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
+            // States committed before the applied one were replaced without being used
+            let count = match info.committed_states.iter().position(|s| s.serial == serial) {
+                Some(position) => position + 1,
+                None => return,
+            };
+
+            let states: Vec<CommittedState> = info.committed_states.drain(..count).collect();
+            for state in states {
+                if let Some(buffer_oid) = state.buffer_oid {
+                    // Buffer replaced before it was displayed will not be used any more
+                    if let Some(old_buffer_oid) = info.buffer_oid {
+                        if old_buffer_oid != buffer_oid {
+                            send!(wl_buffer::release(&self.socket, old_buffer_oid));
                         }
                     }
+                    info.buffer_oid = if buffer_oid.is_null() { None } else { Some(buffer_oid) };
+
+                    // Contents replaced before they were presented will never be presented
+                    for feedback_oid in info.feedback_oids.drain(..) {
+                        discard_presentation_feedback(&self.socket, feedback_oid);
+                    }
                 }
+                info.frame_oids.extend(state.frame_oids);
+                info.feedback_oids.extend(state.feedback_oids);
             }
         }
     }
 
-    fn on_surface_reconfigured(&self,
+    fn on_surface_reconfigured(&mut self,
                                sid: SurfaceId,
                                size: Size,
                                state_flags: surface_state::SurfaceState) {
-        if let Some(info) = self.sid_to_surface_info_dictionary.get(&sid) {
+        let states = self.get_toplevel_states(sid, state_flags);
+        if let Some(info) = self.sid_to_surface_info_dictionary.get_mut(&sid) {
             if let Some(shell_surface) = info.shell_surface_oid {
                 match shell_surface {
                     ShellSurfaceOid::Shell(shell_surface_oid) => {
@@ -707,25 +1781,46 @@ impl Gateway for Proxy {
                                                           wl_shell_surface::resize::NONE,
                                                           size.width as i32,
                                                           size.height as i32));
+
+                        // `wl_shell` does not provide means to acknowledge configuration.
+                        self.coordinator.acknowledge_surface_configuration(sid);
                     }
                     ShellSurfaceOid::ZxdgToplevelV6(shell_surface_oid, shell_toplevel_oid) => {
-                        let mut pos = 0;
-                        let mut states: [u32; 2] = [0; 2];
-                        if state_flags.intersects(surface_state::MAXIMIZED) {
-                            states[pos] = zxdg_toplevel_v6::state::MAXIMIZED;
-                            pos += 1;
-                        }
-                        if sid == self.coordinator.get_keyboard_focused_sid() {
-                            states[pos] = zxdg_toplevel_v6::state::ACTIVATED;
-                            pos += 1;
-                        }
                         send!(zxdg_toplevel_v6::configure(&self.socket,
                                                           shell_toplevel_oid,
                                                           size.width as i32,
                                                           size.height as i32,
-                                                          &states[0..pos]));
+                                                          &states));
                         let serial = self.socket.get_next_serial();
                         send!(zxdg_surface_v6::configure(&self.socket, shell_surface_oid, serial));
+                        info.configure_serial = Some(serial);
+                    }
+                    ShellSurfaceOid::XdgToplevel(shell_surface_oid, shell_toplevel_oid) => {
+                        let version = self.object_versions.get(&shell_toplevel_oid).cloned();
+                        if version.unwrap_or(1) >= CONFIGURE_BOUNDS_SINCE_VERSION {
+                            let bounds = self.configure_bounds;
+                            send!(xdg_toplevel::configure_bounds(&self.socket,
+                                                                 shell_toplevel_oid,
+                                                                 bounds.width as i32,
+                                                                 bounds.height as i32));
+                        }
+                        send!(xdg_toplevel::configure(&self.socket,
+                                                      shell_toplevel_oid,
+                                                      size.width as i32,
+                                                      size.height as i32,
+                                                      &states));
+                        let serial = self.socket.get_next_serial();
+                        send!(xdg_surface::configure(&self.socket, shell_surface_oid, serial));
+                        info.configure_serial = Some(serial);
+                    }
+                    ShellSurfaceOid::Layer(layer_surface_oid) => {
+                        let serial = self.socket.get_next_serial();
+                        send!(zwlr_layer_surface_v1::configure(&self.socket,
+                                                               layer_surface_oid,
+                                                               serial,
+                                                               size.width as u32,
+                                                               size.height as u32));
+                        info.configure_serial = Some(serial);
                     }
                 }
             } else {
@@ -736,20 +1831,25 @@ impl Gateway for Proxy {
         }
     }
 
-    fn on_screenshot_done(&mut self) {
-        if let Some(screenshooter_oid) = self.screenshooter_oid {
-            if let Some(ref mut screenshot_memory) = self.screenshot_memory {
-                let screenshot = self.coordinator.take_screenshot_buffer();
+    fn on_screenshot_done(&mut self, id: ScreenshotId) {
+        let screenshot = self.coordinator.take_screenshot_result(id);
+        match self.screenshots.remove(&id) {
+            Some(ScreenshotTarget::Screenshooter { screenshooter_oid, buffer_oid }) => {
                 if let Some(ref screenshot) = screenshot {
-                    if let Err(err) = unsafe { screenshot_memory.absorb(screenshot) } {
+                    if let Err(err) = self.fill_buffer(buffer_oid, &screenshot.buffer) {
                         log_warn1!("Screenshot: {:?}", err);
                     }
                 } else {
                     log_warn1!("Screenshot: buffer not found");
                 }
+                send!(weston_screenshooter::done(&self.socket, screenshooter_oid));
+            }
+            Some(ScreenshotTarget::ScreencopyFrame { frame_oid, buffer_oid, with_damage }) => {
+                self.finish_output_copy(frame_oid, buffer_oid, with_damage, screenshot);
+            }
+            None => {
+                // Requesting object was destroyed in the meantime.
             }
-            send!(weston_screenshooter::done(&self.get_socket(), screenshooter_oid));
-            self.mediator.borrow_mut().register_screenshoter(None);
         }
     }
 }
@@ -763,3 +1863,11 @@ impl Drop for Proxy {
 }
 
 // -------------------------------------------------------------------------------------------------
+
+/// Sends `discarded` event to given presentation feedback object and lets client reuse its ID.
+fn discard_presentation_feedback(socket: &wl::Socket, feedback_oid: wl::ObjectId) {
+    send!(wp_presentation_feedback::discarded(socket, feedback_oid));
+    send!(wl_display::delete_id(socket, wl::DISPLAY_ID, feedback_oid.get_value()));
+}
+
+// -------------------------------------------------------------------------------------------------